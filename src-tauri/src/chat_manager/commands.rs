@@ -8,17 +8,24 @@ use rusqlite::{params, OptionalExtension};
 use crate::api::{api_request, ApiRequest};
 use crate::chat_manager::storage::{get_base_prompt, PromptType};
 use crate::embedding_model;
+use crate::memory_jobs::{cancel_queued_memory_job, enqueue_memory_job};
 use crate::storage_manager::db::open_db;
 use crate::utils::{emit_toast, log_error, log_info, log_warn, now_millis};
 
 use super::dynamic_memory::{
-    apply_memory_decay, calculate_hot_memory_tokens, context_enrichment_enabled, cosine_similarity,
-    dynamic_cold_threshold, dynamic_decay_rate, dynamic_hot_memory_token_budget,
-    dynamic_max_entries, dynamic_min_similarity, dynamic_retrieval_limit,
-    dynamic_retrieval_strategy, dynamic_window_size, enforce_hot_memory_budget, ensure_pinned_hot,
-    generate_memory_id, mark_memories_accessed, normalize_query_text, promote_cold_memories,
-    search_cold_memory_indices_by_keyword, select_relevant_memory_indices,
-    select_top_cosine_memory_indices, trim_memories_to_max,
+    apply_configured_memory_decay, apply_memory_merge, calculate_hot_memory_tokens,
+    cluster_similar_memory_indices, context_enrichment_enabled, cosine_similarity,
+    dynamic_character_memory_enabled, dynamic_cold_threshold, dynamic_consolidation_threshold,
+    dynamic_hot_memory_token_budget, dynamic_knowledge_graph_enabled, dynamic_max_entries,
+    dynamic_min_similarity, dynamic_reranker_enabled, dynamic_retrieval_limit,
+    dynamic_retrieval_strategy, dynamic_window_size, effective_dynamic_memory_settings,
+    enforce_hot_memory_budget, ensure_pinned_hot, generate_memory_id, mark_memories_accessed,
+    memory_cycle_provenance, memory_merge_action, memory_merge_request, memory_merge_tool_config,
+    normalize_query_text, promote_cold_memories, rerank_memory_hits, same_embedding_space,
+    search_cold_memory_indices_by_keyword, select_hybrid_memory_indices,
+    select_memories_from_store, simulate_memory_decay, trim_memories_to_max, MemoryDecayTrajectory,
+    MEMORY_MERGE_INSTRUCTIONS, SIMULATION_CYCLES_PER_DAY, SIMULATION_HORIZON_HOURS,
+    SIMULATION_STEP_HOURS,
 };
 use super::prompt_engine;
 use super::prompts;
@@ -35,11 +42,17 @@ use crate::usage::tracking::UsageOperationType;
 use super::storage::{default_character_rules, recent_messages, save_session};
 use super::tooling::{parse_tool_calls, ToolCall, ToolChoice, ToolConfig, ToolDefinition};
 use super::types::{
-    Character, ChatAddMessageAttachmentArgs, ChatCompletionArgs, ChatContinueArgs,
-    ChatRegenerateArgs, ChatTurnResult, ContinueResult, MemoryEmbedding, MemoryRetrievalStrategy,
-    Model, Persona, PromptEntryPosition, PromptScope, ProviderCredential, RegenerateResult,
-    Session, Settings, StoredMessage, SystemPromptEntry, SystemPromptTemplate,
+    llama_request_fields, AdvancedModelSettings, Character, ChatAddMessageAttachmentArgs,
+    ChatCompletionArgs, ChatContinueArgs, ChatRegenerateArgs, ChatTurnResult, ContinueResult,
+    DynamicMemorySettings, MemoryEmbedding, MemoryRetrievalStrategy, Model, Persona,
+    PromptEntryPosition, PromptScope, ProviderCredential, RegenerateResult, Session, Settings,
+    StoredMessage, SystemPromptEntry, SystemPromptTemplate,
 };
+use crate::storage_manager::knowledge_graph::{
+    format_graph, mentioned_entity_ids, read_graph, record_relation, subgraph, upsert_entity,
+    ENTITY_KINDS,
+};
+use crate::storage_manager::memories::{add_character_memory, MemoryActor, MemoryOwner};
 use crate::storage_manager::sessions::{
     messages_upsert_batch, session_conversation_count, session_upsert_meta,
 };
@@ -48,6 +61,8 @@ use crate::utils::emit_debug;
 const FALLBACK_TEMPERATURE: f64 = 0.7;
 const FALLBACK_TOP_P: f64 = 1.0;
 const FALLBACK_MAX_OUTPUT_TOKENS: u32 = 4096;
+/// How many relations away from a mentioned entity the injected subgraph reaches
+const KNOWLEDGE_GRAPH_HOPS: usize = 1;
 
 fn resolve_persona_id<'a>(session: &'a Session, explicit: Option<&'a str>) -> Option<&'a str> {
     if explicit.is_some() {
@@ -180,6 +195,16 @@ fn manual_window_size(settings: &Settings) -> usize {
         .unwrap_or(50) as usize
 }
 
+/// Chat model the character replies with; memory and summary budgets are spent against it.
+fn memory_budget_model<'a>(
+    settings: &'a Settings,
+    character: &super::types::Character,
+) -> Option<&'a Model> {
+    super::storage::select_model(settings, character)
+        .ok()
+        .map(|(model, _)| model)
+}
+
 /// Calculate total tokens used by hot (non-cold) memories
 fn conversation_window(messages: &[StoredMessage], limit: usize) -> Vec<StoredMessage> {
     let mut convo: Vec<StoredMessage> = messages
@@ -447,6 +472,36 @@ fn resolve_context_length(session: &Session, model: &Model, settings: &Settings)
         .filter(|v| *v > 0)
 }
 
+/// Drops the oldest chat turns when the prompt would overflow the configured context length,
+/// leaving room for the reply. Counted with the model's own tokenizer.
+fn fit_messages_to_context(
+    app: &AppHandle,
+    session: &Session,
+    model: &Model,
+    settings: &Settings,
+    system_role: &str,
+    messages: &mut Vec<Value>,
+) {
+    let Some(context_length) = resolve_context_length(session, model, settings) else {
+        return;
+    };
+    let budget = context_length.saturating_sub(resolve_max_tokens(session, model, settings));
+    let dropped =
+        super::messages::trim_api_messages_to_budget(messages, system_role, budget, |text| {
+            crate::tokenizer::count_model_tokens(app, Some(model), text)
+        });
+    if dropped > 0 {
+        log_info(
+            app,
+            "chat_context",
+            format!(
+                "dropped {} oldest messages to fit context_length={}",
+                dropped, context_length
+            ),
+        );
+    }
+}
+
 fn resolve_frequency_penalty(
     session: &Session,
     model: &Model,
@@ -608,8 +663,9 @@ fn build_llama_extra_fields(
     session: &Session,
     model: &Model,
     settings: &Settings,
-) -> Option<HashMap<String, Value>> {
+) -> HashMap<String, Value> {
     let mut extra = HashMap::new();
+    extra.insert("llamaSessionId".to_string(), json!(session.id));
     if let Some(v) = resolve_llama_gpu_layers(session, model, settings) {
         extra.insert("llamaGpuLayers".to_string(), json!(v));
     }
@@ -631,12 +687,30 @@ fn build_llama_extra_fields(
     if let Some(v) = resolve_llama_offload_kqv(session, model, settings) {
         extra.insert("llamaOffloadKqv".to_string(), json!(v));
     }
-
-    if extra.is_empty() {
-        None
-    } else {
-        Some(extra)
+    let layers: Vec<&AdvancedModelSettings> = [
+        session.advanced_model_settings.as_ref(),
+        model.advanced_model_settings.as_ref(),
+        Some(&settings.advanced_model_settings),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    for (key, value) in llama_request_fields(&layers) {
+        extra.insert(key.to_string(), value);
     }
+    extra
+}
+
+/// [`build_llama_extra_fields`] for a chat turn, whose context state is kept on disk so the
+/// next turn can resume from it.
+fn build_llama_chat_extra_fields(
+    session: &Session,
+    model: &Model,
+    settings: &Settings,
+) -> HashMap<String, Value> {
+    let mut extra = build_llama_extra_fields(session, model, settings);
+    extra.insert("llamaSaveState".to_string(), json!(true));
+    extra
 }
 
 fn build_ollama_extra_fields(
@@ -952,8 +1026,10 @@ async fn select_relevant_memories(
     limit: usize,
     min_similarity: f32,
     strategy: MemoryRetrievalStrategy,
+    rerank: bool,
+    character_memory: bool,
 ) -> Vec<MemoryEmbedding> {
-    if query.is_empty() || session.memory_embeddings.is_empty() {
+    if query.is_empty() || (session.memory_embeddings.is_empty() && !character_memory) {
         return Vec::new();
     }
 
@@ -970,103 +1046,230 @@ async fn select_relevant_memories(
             }
         };
 
-    if matches!(strategy, MemoryRetrievalStrategy::Cosine) {
-        let cosine_indices = select_top_cosine_memory_indices(
+    let mut results = select_session_memories(
+        app,
+        session,
+        query,
+        &query_embedding,
+        limit,
+        min_similarity,
+        strategy,
+        rerank,
+    )
+    .await;
+    if character_memory {
+        let shared = select_character_memories(
+            app,
+            &session.character_id,
             &query_embedding,
-            &session.memory_embeddings,
             limit,
             min_similarity,
+            &results,
+        );
+        results.extend(shared);
+    }
+    results
+}
+
+/// Long-term memories of the session's character, up to half of `limit`. Memories whose text
+/// the session already recalled are skipped.
+fn select_character_memories(
+    app: &AppHandle,
+    character_id: &str,
+    query_embedding: &[f32],
+    limit: usize,
+    min_similarity: f32,
+    already_selected: &[MemoryEmbedding],
+) -> Vec<MemoryEmbedding> {
+    let conn = match open_db(app) {
+        Ok(conn) => conn,
+        Err(err) => {
+            log_warn(
+                app,
+                "memory_retrieval",
+                format!("database unavailable: {}", err),
+            );
+            return Vec::new();
+        }
+    };
+    let hits: Vec<(MemoryEmbedding, f32)> = match select_memories_from_store(
+        &conn,
+        MemoryOwner::Character(character_id),
+        query_embedding,
+        (limit / 2).max(1),
+        min_similarity,
+        &MemoryRetrievalStrategy::Cosine,
+    ) {
+        Ok(hits) => hits
+            .into_iter()
+            .map(|(memory, score)| (memory.into(), score))
+            .collect(),
+        Err(err) => {
+            log_warn(
+                app,
+                "memory_retrieval",
+                format!("character memories unavailable: {}", err),
+            );
+            return Vec::new();
+        }
+    };
+
+    let seen: HashSet<String> = already_selected
+        .iter()
+        .map(|m| m.text.trim().to_lowercase())
+        .collect();
+    let selected: Vec<MemoryEmbedding> = hits
+        .into_iter()
+        .map(|(mut mem, score)| {
+            mem.match_score = Some(score);
+            mem
+        })
+        .filter(|m| !seen.contains(&m.text.trim().to_lowercase()))
+        .collect();
+    if !selected.is_empty() {
+        log_info(
+            app,
+            "memory_retrieval",
+            format!("Recalled {} character memories", selected.len()),
         );
-        if cosine_indices.is_empty() {
+    }
+    selected
+}
+
+async fn select_session_memories(
+    app: &AppHandle,
+    session: &Session,
+    query: &str,
+    query_embedding: &[f32],
+    limit: usize,
+    min_similarity: f32,
+    strategy: MemoryRetrievalStrategy,
+    rerank: bool,
+) -> Vec<MemoryEmbedding> {
+    if session.memory_embeddings.is_empty() {
+        return Vec::new();
+    }
+
+    let conn = match open_db(app) {
+        Ok(conn) => conn,
+        Err(err) => {
+            log_warn(
+                app,
+                "memory_retrieval",
+                format!("database unavailable: {}", err),
+            );
             return Vec::new();
         }
-        return cosine_indices
+    };
+    let owner = MemoryOwner::Session(&session.id);
+
+    if matches!(strategy, MemoryRetrievalStrategy::Hybrid) {
+        let pool = if rerank { limit * 3 } else { limit };
+        let mut hits = select_hybrid_memory_indices(
+            &conn,
+            owner,
+            query,
+            query_embedding,
+            &session.memory_embeddings,
+            pool,
+            min_similarity,
+        );
+        drop(conn);
+        if rerank {
+            if let Err(err) =
+                rerank_memory_hits(app, query, &session.memory_embeddings, &mut hits).await
+            {
+                log_warn(app, "memory_retrieval", format!("rerank skipped: {}", err));
+            }
+        }
+        return hits
             .into_iter()
-            .filter_map(|(idx, score)| {
+            .take(limit)
+            .filter_map(|(idx, breakdown)| {
                 session.memory_embeddings.get(idx).map(|mem| {
                     let mut cloned = mem.clone();
-                    cloned.match_score = Some(score);
+                    cloned.match_score = Some(breakdown.final_score());
+                    cloned.score_breakdown = Some(breakdown);
                     cloned
                 })
             })
             .collect();
     }
 
-    // Smart mode: blend semantic match + recency/frequency + fallback fill.
-    let cosine_limit = (limit.saturating_sub(2)).max(1);
-    let cosine_indices = select_relevant_memory_indices(
-        &query_embedding,
-        &session.memory_embeddings,
-        cosine_limit,
+    let ranked: Vec<(MemoryEmbedding, f32)> = match select_memories_from_store(
+        &conn,
+        owner,
+        query_embedding,
+        limit,
         min_similarity,
-    );
+        &strategy,
+    ) {
+        Ok(ranked) => ranked
+            .into_iter()
+            .map(|(memory, score)| (memory.into(), score))
+            .collect(),
+        Err(err) => {
+            log_warn(
+                app,
+                "memory_retrieval",
+                format!("memory store unavailable: {}", err),
+            );
+            Vec::new()
+        }
+    };
+    let mut scored = ranked.into_iter().map(|(mut mem, score)| {
+        mem.match_score = Some(score);
+        mem
+    });
 
-    let mut selected: HashSet<usize> = HashSet::new();
+    if matches!(strategy, MemoryRetrievalStrategy::Cosine) {
+        return scored.collect();
+    }
+
+    // Smart mode: blend semantic match + recency/frequency + fallback fill.
+    let cosine_limit = (limit.saturating_sub(2)).max(1);
+    let mut selected: HashSet<String> = HashSet::new();
     let mut results: Vec<MemoryEmbedding> = Vec::new();
 
-    for (idx, score) in &cosine_indices {
-        if let Some(mem) = session.memory_embeddings.get(*idx) {
-            let mut cloned = mem.clone();
-            cloned.match_score = Some(*score);
-            results.push(cloned);
-            selected.insert(*idx);
-        }
+    while results.len() < cosine_limit {
+        let Some(mem) = scored.next() else { break };
+        selected.insert(mem.id.clone());
+        results.push(mem);
     }
 
     // 2. Add 1 most recently created hot memory (if not already selected)
     if results.len() < limit {
-        if let Some(recent_idx) = session
+        if let Some(mem) = session
             .memory_embeddings
             .iter()
-            .enumerate()
-            .filter(|(i, m)| !m.is_cold && !selected.contains(i))
-            .max_by_key(|(_, m)| m.created_at)
-            .map(|(i, _)| i)
+            .filter(|m| !m.is_cold && !selected.contains(&m.id))
+            .max_by_key(|m| m.created_at)
         {
-            if let Some(mem) = session.memory_embeddings.get(recent_idx) {
-                results.push(mem.clone());
-                selected.insert(recent_idx);
-            }
+            selected.insert(mem.id.clone());
+            results.push(mem.clone());
         }
     }
 
     // 3. Add 1 most frequently accessed hot memory (if not already selected, access_count > 0)
     if results.len() < limit {
-        if let Some(freq_idx) = session
+        if let Some(mem) = session
             .memory_embeddings
             .iter()
-            .enumerate()
-            .filter(|(i, m)| !m.is_cold && !selected.contains(i) && m.access_count > 0)
-            .max_by_key(|(_, m)| m.access_count)
-            .map(|(i, _)| i)
+            .filter(|m| !m.is_cold && !selected.contains(&m.id) && m.access_count > 0)
+            .max_by_key(|m| m.access_count)
         {
-            if let Some(mem) = session.memory_embeddings.get(freq_idx) {
-                results.push(mem.clone());
-                selected.insert(freq_idx);
-            }
+            selected.insert(mem.id.clone());
+            results.push(mem.clone());
         }
     }
 
     // 4. Fill remaining slots with next best cosine results
-    if results.len() < limit {
-        let extra_indices = select_relevant_memory_indices(
-            &query_embedding,
-            &session.memory_embeddings,
-            limit,
-            min_similarity,
-        );
-        for (idx, score) in extra_indices {
-            if results.len() >= limit {
-                break;
-            }
-            if !selected.contains(&idx) {
-                if let Some(mem) = session.memory_embeddings.get(idx) {
-                    let mut cloned = mem.clone();
-                    cloned.match_score = Some(score);
-                    results.push(cloned);
-                    selected.insert(idx);
-                }
-            }
+    for mem in scored {
+        if results.len() >= limit {
+            break;
+        }
+        if selected.insert(mem.id.clone()) {
+            results.push(mem);
         }
     }
 
@@ -1417,7 +1620,9 @@ pub async fn chat_completion(
     // Retrieve top-k relevant memories for this turn.
     // - Dynamic memory: use semantic search over memory embeddings
     // - Manual memory: memories are injected via system prompt (see below)
-    let relevant_memories = if dynamic_memory_enabled && !session.memory_embeddings.is_empty() {
+    let relevant_memories = if dynamic_memory_enabled
+        && (!session.memory_embeddings.is_empty() || dynamic_character_memory_enabled(settings))
+    {
         let fixed = ensure_pinned_hot(&mut session.memory_embeddings);
         if fixed > 0 {
             log_info(
@@ -1451,6 +1656,8 @@ pub async fn chat_completion(
             dynamic_retrieval_limit(settings),
             dynamic_min_similarity(settings),
             dynamic_retrieval_strategy(settings),
+            dynamic_reranker_enabled(settings),
+            dynamic_character_memory_enabled(settings),
         )
         .await
     } else {
@@ -1538,6 +1745,16 @@ pub async fn chat_completion(
             Some(format!("Relevant memories:\n{}", block)),
         );
     }
+    if dynamic_memory_enabled && dynamic_knowledge_graph_enabled(settings) {
+        let query = build_enriched_query(&session.messages);
+        if let Some(block) = knowledge_graph_block(&app, &session.id, &query) {
+            crate::chat_manager::messages::push_system_message(
+                &mut messages_for_api,
+                &system_role,
+                Some(format!("Known entities and relations:\n{}", block)),
+            );
+        }
+    }
 
     let char_name = if swap_places {
         persona.map(|p| p.title.as_str()).unwrap_or("User")
@@ -1590,6 +1807,14 @@ pub async fn chat_completion(
         char_name,
         persona_name,
     );
+    fit_messages_to_context(
+        &app,
+        &session,
+        model,
+        &settings,
+        &system_role,
+        &mut messages_for_api,
+    );
 
     let should_stream = stream.unwrap_or(true);
     let request_id = if should_stream {
@@ -1690,7 +1915,11 @@ pub async fn chat_completion(
             reasoning_effort.as_deref(),
         );
         let extra_body_fields = if attempt_provider_cred.provider_id == "llamacpp" {
-            build_llama_extra_fields(&session, attempt_model, &settings)
+            Some(build_llama_chat_extra_fields(
+                &session,
+                attempt_model,
+                &settings,
+            ))
         } else if attempt_provider_cred.provider_id == "ollama" {
             build_ollama_extra_fields(
                 &session,
@@ -2114,13 +2343,11 @@ pub async fn chat_completion(
     .await;
 
     if dynamic_memory_enabled {
-        if let Err(err) =
-            process_dynamic_memory_cycle(&app, &mut session, settings, &character).await
-        {
+        if let Err(err) = enqueue_memory_job(&app, MemoryOwner::Session(&session.id)) {
             log_error(
                 &app,
                 "chat_completion",
-                format!("dynamic memory cycle failed: {}", err),
+                format!("failed to queue dynamic memory cycle: {}", err),
             );
         }
     }
@@ -2278,7 +2505,10 @@ pub async fn chat_regenerate(
     let dynamic_memory_enabled = is_dynamic_memory_active(settings, &character);
     let dynamic_window = dynamic_window_size(settings);
 
-    let relevant_memories = if dynamic_memory_enabled && !session.memory_embeddings.is_empty() {
+    let relevant_memories = if dynamic_memory_enabled
+        && (!session.memory_embeddings.is_empty()
+            || dynamic_character_memory_enabled(&context.settings))
+    {
         let fixed = ensure_pinned_hot(&mut session.memory_embeddings);
         if fixed > 0 {
             log_info(
@@ -2312,6 +2542,8 @@ pub async fn chat_regenerate(
             dynamic_retrieval_limit(&context.settings),
             dynamic_min_similarity(&context.settings),
             dynamic_retrieval_strategy(&context.settings),
+            dynamic_reranker_enabled(&context.settings),
+            dynamic_character_memory_enabled(&context.settings),
         )
         .await
     } else {
@@ -2468,6 +2700,7 @@ pub async fn chat_regenerate(
             char_name,
             persona_name,
         );
+        fit_messages_to_context(&app, &session, model, &settings, &system_role, &mut out);
         out
     };
 
@@ -2537,7 +2770,11 @@ pub async fn chat_regenerate(
             reasoning_effort.as_deref(),
         );
         let extra_body_fields = if attempt_provider_cred.provider_id == "llamacpp" {
-            build_llama_extra_fields(&session, attempt_model, &settings)
+            Some(build_llama_chat_extra_fields(
+                &session,
+                attempt_model,
+                &settings,
+            ))
         } else if attempt_provider_cred.provider_id == "ollama" {
             build_ollama_extra_fields(
                 &session,
@@ -2955,7 +3192,10 @@ pub async fn chat_continue(
     let dynamic_memory_enabled = is_dynamic_memory_active(settings, &character);
     let dynamic_window = dynamic_window_size(settings);
 
-    let relevant_memories = if dynamic_memory_enabled && !session.memory_embeddings.is_empty() {
+    let relevant_memories = if dynamic_memory_enabled
+        && (!session.memory_embeddings.is_empty()
+            || dynamic_character_memory_enabled(&context.settings))
+    {
         let fixed = ensure_pinned_hot(&mut session.memory_embeddings);
         if fixed > 0 {
             log_info(
@@ -2984,6 +3224,8 @@ pub async fn chat_continue(
             dynamic_retrieval_limit(&context.settings),
             dynamic_min_similarity(&context.settings),
             dynamic_retrieval_strategy(&context.settings),
+            dynamic_reranker_enabled(&context.settings),
+            dynamic_character_memory_enabled(&context.settings),
         )
         .await
     } else {
@@ -3121,6 +3363,14 @@ pub async fn chat_continue(
         "role": "user",
         "content": "[CONTINUE] You were in the middle of a response. Continue writing from exactly where you left off. Do NOT restart, regenerate, or rewrite what you already said. Simply pick up the narrative thread and continue the scene forward with new content."
     }));
+    fit_messages_to_context(
+        &app,
+        &session,
+        model,
+        &settings,
+        &system_role,
+        &mut messages_for_api,
+    );
 
     let should_stream = stream.unwrap_or(true);
     let request_id = if should_stream {
@@ -3178,7 +3428,11 @@ pub async fn chat_continue(
             reasoning_effort.as_deref(),
         );
         let extra_body_fields = if attempt_provider_cred.provider_id == "llamacpp" {
-            build_llama_extra_fields(&session, attempt_model, &settings)
+            Some(build_llama_chat_extra_fields(
+                &session,
+                attempt_model,
+                &settings,
+            ))
         } else if attempt_provider_cred.provider_id == "ollama" {
             build_ollama_extra_fields(
                 &session,
@@ -3499,13 +3753,11 @@ pub async fn chat_continue(
     .await;
 
     if dynamic_memory_enabled {
-        if let Err(err) =
-            process_dynamic_memory_cycle(&app, &mut session, settings, &character).await
-        {
+        if let Err(err) = enqueue_memory_job(&app, MemoryOwner::Session(&session.id)) {
             log_error(
                 &app,
                 "chat_continue",
-                format!("dynamic memory cycle failed: {}", err),
+                format!("failed to queue dynamic memory cycle: {}", err),
             );
         }
     }
@@ -3738,6 +3990,7 @@ pub async fn retry_dynamic_memory(
         .ok_or_else(|| "Session not found".to_string())?;
 
     let character = context.find_character(&session.character_id)?;
+    cancel_queued_memory_job(&app, MemoryOwner::Session(&session_id))?;
 
     // Run the memory cycle with optional model override
     process_dynamic_memory_cycle_with_model(
@@ -3765,6 +4018,7 @@ pub async fn trigger_dynamic_memory(app: AppHandle, session_id: String) -> Resul
         .ok_or_else(|| "Session not found".to_string())?;
 
     let character = context.find_character(&session.character_id)?;
+    cancel_queued_memory_job(&app, MemoryOwner::Session(&session_id))?;
 
     // Run the memory cycle with default settings, but force=true
     process_dynamic_memory_cycle_with_model(
@@ -3779,6 +4033,47 @@ pub async fn trigger_dynamic_memory(app: AppHandle, session_id: String) -> Resul
     .await
 }
 
+/// Projects how the session's hot memories would decay if never retrieved again, under the
+/// current settings or `overrides` (to preview a decay model before switching to it).
+#[tauri::command]
+pub fn simulate_dynamic_memory_decay(
+    app: AppHandle,
+    session_id: String,
+    overrides: Option<DynamicMemorySettings>,
+    horizon_hours: Option<f32>,
+    step_hours: Option<f32>,
+    cycles_per_day: Option<f32>,
+) -> Result<Vec<MemoryDecayTrajectory>, String> {
+    let context = ChatContext::initialize(app.clone())?;
+    let session = context
+        .load_session(&session_id)?
+        .ok_or_else(|| "Session not found".to_string())?;
+    let dynamic_settings = effective_dynamic_memory_settings(&context.settings, overrides.as_ref());
+
+    Ok(simulate_memory_decay(
+        &session.memory_embeddings,
+        &dynamic_settings,
+        horizon_hours.unwrap_or(SIMULATION_HORIZON_HOURS),
+        step_hours.unwrap_or(SIMULATION_STEP_HOURS),
+        cycles_per_day.unwrap_or(SIMULATION_CYCLES_PER_DAY),
+        now_millis().unwrap_or_default(),
+    ))
+}
+
+/// Runs the memory cycle for a job from the memory job queue.
+pub(crate) async fn run_queued_dynamic_memory_cycle(
+    app: &AppHandle,
+    session_id: &str,
+) -> Result<(), String> {
+    let context = ChatContext::initialize(app.clone())?;
+    let mut session = context
+        .load_session(session_id)?
+        .ok_or_else(|| "Session not found".to_string())?;
+    let character = context.find_character(&session.character_id)?;
+
+    process_dynamic_memory_cycle(app, &mut session, &context.settings, &character).await
+}
+
 async fn process_dynamic_memory_cycle(
     app: &AppHandle,
     session: &mut Session,
@@ -3966,8 +4261,6 @@ async fn process_dynamic_memory_cycle_with_model(
     let window_message_ids: Vec<String> = convo_window.iter().map(|m| m.id.clone()).collect();
 
     // Apply importance decay to all hot, unpinned memories
-    let decay_rate = dynamic_decay_rate(settings);
-    let cold_threshold = dynamic_cold_threshold(settings);
     let pinned_fixed = ensure_pinned_hot(&mut session.memory_embeddings);
     if pinned_fixed > 0 {
         log_info(
@@ -3977,8 +4270,11 @@ async fn process_dynamic_memory_cycle_with_model(
         );
     }
 
-    let (decayed, demoted) =
-        apply_memory_decay(&mut session.memory_embeddings, decay_rate, cold_threshold);
+    let (decayed, demoted) = apply_configured_memory_decay(
+        &mut session.memory_embeddings,
+        &effective_dynamic_memory_settings(settings, None),
+        now_millis().unwrap_or_default(),
+    );
     if decayed > 0 || !demoted.is_empty() {
         log_info(
             app,
@@ -4083,7 +4379,11 @@ async fn process_dynamic_memory_cycle_with_model(
         format!(
             "summary generated: length={} chars tokens={}",
             summary.len(),
-            crate::tokenizer::count_tokens(app, &summary).unwrap_or(0)
+            crate::tokenizer::count_model_tokens(
+                app,
+                memory_budget_model(settings, character),
+                &summary
+            )
         ),
     );
 
@@ -4128,8 +4428,11 @@ async fn process_dynamic_memory_cycle_with_model(
                 "createdAt": now_millis().unwrap_or_default(),
             });
             session.memory_summary = Some(summary.clone());
-            session.memory_summary_token_count =
-                crate::tokenizer::count_tokens(app, &summary).unwrap_or(0);
+            session.memory_summary_token_count = crate::tokenizer::count_model_tokens(
+                app,
+                memory_budget_model(settings, character),
+                &summary,
+            );
             session.memory_tool_events.push(event);
             if session.memory_tool_events.len() > 50 {
                 let excess = session.memory_tool_events.len() - 50;
@@ -4140,18 +4443,23 @@ async fn process_dynamic_memory_cycle_with_model(
             session.updated_at = now_millis()?;
             if let Err(save_err) = save_session(app, session) {
                 record_dynamic_memory_error(app, session, &save_err, "save_session");
-                return Ok(());
+                return Err(err);
             }
             let _ = app.emit(
                 "dynamic-memory:error",
                 json!({ "sessionId": session.id, "error": err, "stage": "memory_tools" }),
             );
-            return Ok(());
+            // Fail the cycle so a queued job gets retried.
+            return Err(err);
         }
     };
 
     session.memory_summary = Some(summary.clone());
-    session.memory_summary_token_count = crate::tokenizer::count_tokens(app, &summary).unwrap_or(0);
+    session.memory_summary_token_count = crate::tokenizer::count_model_tokens(
+        app,
+        memory_budget_model(settings, character),
+        &summary,
+    );
     let event = json!({
         "id": Uuid::new_v4().to_string(),
         "windowStart": window_start,
@@ -4302,7 +4610,9 @@ async fn run_memory_tool_update(
     convo_window: &[StoredMessage],
     character: &super::types::Character,
 ) -> Result<Vec<Value>, String> {
-    let tool_config = build_memory_tool_config();
+    let character_memory = dynamic_character_memory_enabled(settings);
+    let knowledge_graph = dynamic_knowledge_graph_enabled(settings);
+    let tool_config = build_memory_tool_config(character_memory, knowledge_graph);
     let max_entries = dynamic_max_entries(settings);
 
     let mut messages_for_api = Vec::new();
@@ -4340,20 +4650,50 @@ async fn run_memory_tool_update(
         Some(rendered),
     );
     let memory_lines = format_memories_with_ids(session);
-    messages_for_api.push(json!({
-        "role": "user",
-        "content": format!(
-            "Conversation summary:\n{}\n\nRecent messages:\n{}\n\nCurrent memories (with IDs):\n{}",
-            summary,
-            convo_window.iter().map(|m| format!("{}: {}", m.role, m.content)).collect::<Vec<_>>().join("\n"),
-            if memory_lines.is_empty() { "none".to_string() } else { memory_lines.join("\n") }
-        )
-    }));
+    let mut content = format!(
+        "Conversation summary:\n{}\n\nRecent messages:\n{}\n\nCurrent memories (with IDs):\n{}",
+        summary,
+        convo_window
+            .iter()
+            .map(|m| if knowledge_graph {
+                format!("[{}] {}: {}", m.id, m.role, m.content)
+            } else {
+                format!("{}: {}", m.role, m.content)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        if memory_lines.is_empty() {
+            "none".to_string()
+        } else {
+            memory_lines.join("\n")
+        }
+    );
+    if knowledge_graph {
+        let known = open_db(app)
+            .and_then(|conn| read_graph(&conn, &session.id))
+            .map(|graph| {
+                graph
+                    .entities
+                    .iter()
+                    .map(|e| format!("{} ({})", e.name, e.kind))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        content.push_str(&format!(
+            "\n\nKnown entities (reuse these names):\n{}",
+            if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            }
+        ));
+    }
+    messages_for_api.push(json!({ "role": "user", "content": content }));
 
     let context_length = resolve_context_length(session, model, settings);
     let max_tokens = resolve_max_tokens(session, model, settings);
     let extra_body_fields = if provider_cred.provider_id == "llamacpp" {
-        build_llama_extra_fields(session, model, settings)
+        Some(build_llama_extra_fields(session, model, settings))
     } else if provider_cred.provider_id == "ollama" {
         build_ollama_extra_fields(
             session,
@@ -4460,9 +4800,16 @@ async fn run_memory_tool_update(
                                 None
                             }
                         };
+                    let embedding_model_id = embedding
+                        .as_ref()
+                        .and_then(|_| embedding_model::active_embedding_backend_id(app));
                     if let Some(ref new_emb) = embedding {
                         let is_duplicate = session.memory_embeddings.iter().any(|existing| {
                             !existing.embedding.is_empty()
+                                && same_embedding_space(
+                                    existing.embedding_model.as_deref(),
+                                    embedding_model_id.as_deref(),
+                                )
                                 && cosine_similarity(new_emb, &existing.embedding) > 0.85
                         });
                         if is_duplicate {
@@ -4481,7 +4828,11 @@ async fn run_memory_tool_update(
                             continue;
                         }
                     }
-                    let token_count = crate::tokenizer::count_tokens(app, &text).unwrap_or(0);
+                    let token_count = crate::tokenizer::count_model_tokens(
+                        app,
+                        memory_budget_model(settings, character),
+                        &text,
+                    );
                     // Check if memory should be pinned
                     let is_pinned = call
                         .arguments
@@ -4497,6 +4848,7 @@ async fn run_memory_tool_update(
                         id: mem_id.clone(),
                         text,
                         embedding: embedding.unwrap_or_default(),
+                        embedding_model: embedding_model_id,
                         created_at: now_millis().unwrap_or_default(),
                         token_count,
                         is_cold: false,
@@ -4506,6 +4858,14 @@ async fn run_memory_tool_update(
                         access_count: 0,
                         match_score: None,
                         category,
+                        score_breakdown: None,
+                        provenance: Some(memory_cycle_provenance(
+                            &convo_window
+                                .iter()
+                                .map(|m| m.id.as_str())
+                                .collect::<Vec<_>>(),
+                            &model.id,
+                        )),
                     });
                     actions_log.push(json!({
                         "name": "create_memory",
@@ -4618,6 +4978,51 @@ async fn run_memory_tool_update(
                     }
                 }
             }
+            "share_memory" if character_memory => {
+                if let Some(raw_id) = call.arguments.get("id").and_then(|v| v.as_str()) {
+                    let id = sanitize_memory_id(raw_id);
+                    let Some(mem) = session.memory_embeddings.iter().find(|m| m.id == id) else {
+                        log_warn(
+                            app,
+                            "dynamic_memory",
+                            format!("share_memory could not find: {}", id),
+                        );
+                        continue;
+                    };
+                    match share_with_character(app, &session.id, &session.character_id, mem) {
+                        Ok(shared_id) => {
+                            actions_log.push(json!({
+                                "name": "share_memory",
+                                "arguments": call.arguments,
+                                "memoryId": id,
+                                "characterMemoryId": shared_id,
+                                "timestamp": now_millis().unwrap_or_default(),
+                            }));
+                            log_info(
+                                app,
+                                "dynamic_memory",
+                                format!("Shared memory {} with the character", id),
+                            );
+                        }
+                        Err(err) => log_warn(
+                            app,
+                            "dynamic_memory",
+                            format!("share_memory failed for {}: {}", id, err),
+                        ),
+                    }
+                }
+            }
+            "record_entity" | "record_relation" if knowledge_graph => {
+                let window_ids: Vec<String> = convo_window.iter().map(|m| m.id.clone()).collect();
+                match apply_knowledge_graph_call(app, &session.id, &call, &window_ids) {
+                    Ok(action) => actions_log.push(action),
+                    Err(err) => log_warn(
+                        app,
+                        "dynamic_memory",
+                        format!("{} failed: {}", call.name, err),
+                    ),
+                }
+            }
             "done" => {
                 actions_log.push(json!({
                     "name": "done",
@@ -4630,6 +5035,21 @@ async fn run_memory_tool_update(
         }
     }
 
+    if let Some(threshold) = dynamic_consolidation_threshold(settings) {
+        let merges = consolidate_memories(
+            app,
+            provider_cred,
+            model,
+            api_key,
+            session,
+            settings,
+            character,
+            threshold,
+        )
+        .await;
+        actions_log.extend(merges);
+    }
+
     let trimmed = trim_memories_to_max(&mut session.memory_embeddings, max_entries);
     if trimmed > 0 {
         log_info(
@@ -4679,7 +5099,227 @@ async fn run_memory_tool_update(
     Ok(actions_log)
 }
 
-fn extract_text_argument(call: &ToolCall) -> Option<String> {
+/// Merges clusters of near-duplicate hot memories with the summarisation model. Returns the
+/// `merge_memories` actions for this cycle's tool event; clusters that fail to merge stay as is.
+async fn consolidate_memories(
+    app: &AppHandle,
+    provider_cred: &ProviderCredential,
+    model: &Model,
+    api_key: &str,
+    session: &mut Session,
+    settings: &Settings,
+    character: &super::types::Character,
+    threshold: f32,
+) -> Vec<Value> {
+    let clusters = cluster_similar_memory_indices(&session.memory_embeddings, threshold);
+    if clusters.is_empty() {
+        return Vec::new();
+    }
+    log_info(
+        app,
+        "dynamic_memory",
+        format!(
+            "consolidating {} clusters of similar memories (threshold={})",
+            clusters.len(),
+            threshold
+        ),
+    );
+
+    let mut actions = Vec::new();
+    for cluster in clusters {
+        // Earlier merges shift indices, so track members by id.
+        let ids: Vec<String> = cluster
+            .iter()
+            .map(|&idx| session.memory_embeddings[idx].id.clone())
+            .collect();
+        let request = memory_merge_request(&session.memory_embeddings, &cluster);
+        let text = match merge_memory_texts(
+            app,
+            provider_cred,
+            model,
+            api_key,
+            session,
+            settings,
+            character,
+            &request,
+        )
+        .await
+        {
+            Ok(text) => text,
+            Err(err) => {
+                log_warn(
+                    app,
+                    "dynamic_memory",
+                    format!("memory merge failed for {:?}: {}", ids, err),
+                );
+                continue;
+            }
+        };
+        let embedding = match embedding_model::compute_embedding(app.clone(), text.clone()).await {
+            Ok(vec) => vec,
+            Err(err) => {
+                log_warn(
+                    app,
+                    "dynamic_memory",
+                    format!("failed to embed merged memory: {}", err),
+                );
+                continue;
+            }
+        };
+        let token_count = crate::tokenizer::count_model_tokens(
+            app,
+            memory_budget_model(settings, character),
+            &text,
+        );
+
+        let current: Vec<usize> = ids
+            .iter()
+            .filter_map(|id| session.memory_embeddings.iter().position(|m| &m.id == id))
+            .collect();
+        if current.len() < 2 {
+            continue;
+        }
+        let (merged_id, originals) = apply_memory_merge(
+            &mut session.memory_embeddings,
+            &current,
+            text.clone(),
+            embedding,
+            embedding_model::active_embedding_backend_id(app),
+            token_count,
+            &model.id,
+        );
+        log_info(
+            app,
+            "dynamic_memory",
+            format!("Merged memories {:?} into {}", ids, merged_id),
+        );
+        actions.push(memory_merge_action(
+            &merged_id,
+            &text,
+            &originals,
+            now_millis().unwrap_or_default(),
+        ));
+    }
+    actions
+}
+
+async fn merge_memory_texts(
+    app: &AppHandle,
+    provider_cred: &ProviderCredential,
+    model: &Model,
+    api_key: &str,
+    session: &Session,
+    settings: &Settings,
+    character: &super::types::Character,
+    request: &str,
+) -> Result<String, String> {
+    let mut messages_for_api = Vec::new();
+    let system_role = super::request_builder::system_role_for(provider_cred);
+    crate::chat_manager::messages::push_system_message(
+        &mut messages_for_api,
+        &system_role,
+        Some(MEMORY_MERGE_INSTRUCTIONS.to_string()),
+    );
+    messages_for_api.push(json!({ "role": "user", "content": request }));
+
+    let context_length = resolve_context_length(session, model, settings);
+    let max_tokens = resolve_max_tokens(session, model, settings);
+    let extra_body_fields = if provider_cred.provider_id == "llamacpp" {
+        Some(build_llama_extra_fields(session, model, settings))
+    } else if provider_cred.provider_id == "ollama" {
+        build_ollama_extra_fields(
+            session,
+            model,
+            settings,
+            context_length,
+            max_tokens,
+            0.2,
+            1.0,
+            None,
+            None,
+            None,
+        )
+    } else {
+        None
+    };
+    let built = super::request_builder::build_chat_request(
+        provider_cred,
+        api_key,
+        &model.name,
+        &messages_for_api,
+        None,
+        0.2,
+        1.0,
+        max_tokens,
+        context_length,
+        false,
+        None,
+        None,
+        None,
+        None,
+        Some(&memory_merge_tool_config()),
+        false,
+        None,
+        None,
+        extra_body_fields,
+    );
+
+    let api_request_payload = ApiRequest {
+        url: built.url,
+        method: Some("POST".into()),
+        headers: Some(built.headers),
+        query: None,
+        body: Some(built.body),
+        timeout_ms: Some(60_000),
+        stream: Some(false),
+        request_id: built.request_id.clone(),
+        provider_id: Some(provider_cred.provider_id.clone()),
+    };
+
+    let api_response = api_request(app.clone(), api_request_payload).await?;
+
+    let usage = extract_usage(api_response.data());
+    let context = ChatContext::initialize(app.clone())?;
+    record_usage_if_available(
+        &context,
+        &usage,
+        session,
+        character,
+        model,
+        provider_cred,
+        api_key,
+        now_millis().unwrap_or(0),
+        UsageOperationType::MemoryManager,
+        "memory_consolidation",
+    )
+    .await;
+
+    if !api_response.ok {
+        let fallback = format!("Provider returned status {}", api_response.status);
+        let err_message = extract_error_message(api_response.data()).unwrap_or(fallback.clone());
+        return Err(if err_message == fallback {
+            err_message
+        } else {
+            format!("{} (status {})", err_message, api_response.status)
+        });
+    }
+
+    let calls = parse_tool_calls(&provider_cred.provider_id, api_response.data());
+    for call in calls {
+        if call.name == "write_memory" {
+            if let Some(text) = extract_text_argument(&call) {
+                return Ok(text);
+            }
+        }
+    }
+
+    extract_text(api_response.data(), Some(&provider_cred.provider_id))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Failed to merge memories".to_string())
+}
+
+fn extract_text_argument(call: &ToolCall) -> Option<String> {
     if let Some(text) = call
         .arguments
         .get("text")
@@ -4691,81 +5331,247 @@ fn extract_text_argument(call: &ToolCall) -> Option<String> {
     call.raw_arguments.clone()
 }
 
-fn build_memory_tool_config() -> ToolConfig {
-    ToolConfig {
-        tools: vec![
-            ToolDefinition {
-                name: "create_memory".to_string(),
-                description: Some(
-                    "Create a concise memory entry capturing important facts.".to_string(),
-                ),
-                parameters: json!({
-                    "type": "object",
-                    "properties": {
-                        "text": { "type": "string", "description": "Concise memory to store" },
-                        "important": { "type": "boolean", "description": "If true, memory will be pinned (never decays)" },
-                        "category": {
-                            "type": "string",
-                            "enum": ["character_trait", "relationship", "plot_event", "world_detail", "preference", "other"],
-                            "description": "Category of this memory for organization"
-                        }
-                    },
-                    "required": ["text"]
-                }),
-            },
+/// Applies a `record_entity` / `record_relation` tool call to the session's knowledge graph.
+/// Relations without valid supporting ids are attributed to the whole window.
+fn apply_knowledge_graph_call(
+    app: &AppHandle,
+    session_id: &str,
+    call: &ToolCall,
+    window_ids: &[String],
+) -> Result<Value, String> {
+    let arg = |key: &str| call.arguments.get(key).and_then(|v| v.as_str());
+    let missing = |key: &str| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("missing argument '{}'", key),
+        )
+    };
+    let conn = open_db(app)?;
+
+    if call.name == "record_entity" {
+        let name = arg("name").ok_or_else(|| missing("name"))?;
+        let entity = upsert_entity(&conn, session_id, name, arg("kind"), arg("description"))?;
+        return Ok(json!({
+            "name": "record_entity",
+            "arguments": call.arguments,
+            "entityId": entity.id,
+            "timestamp": now_millis().unwrap_or_default(),
+        }));
+    }
+
+    let source = arg("source").ok_or_else(|| missing("source"))?;
+    let relation = arg("relation").ok_or_else(|| missing("relation"))?;
+    let target = arg("target").ok_or_else(|| missing("target"))?;
+    let mut message_ids: Vec<String> = call
+        .arguments
+        .get("messageIds")
+        .and_then(|v| v.as_array())
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .filter(|id| window_ids.iter().any(|w| w == id))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    if message_ids.is_empty() {
+        message_ids = window_ids.to_vec();
+    }
+    let recorded = record_relation(&conn, session_id, source, relation, target, &message_ids)?;
+    Ok(json!({
+        "name": "record_relation",
+        "arguments": call.arguments,
+        "relationId": recorded.id,
+        "messageIds": recorded.message_ids,
+        "timestamp": now_millis().unwrap_or_default(),
+    }))
+}
+
+/// Renders the part of the session's knowledge graph around entities named in `query`.
+fn knowledge_graph_block(app: &AppHandle, session_id: &str, query: &str) -> Option<String> {
+    let graph = match open_db(app).and_then(|conn| read_graph(&conn, session_id)) {
+        Ok(graph) => graph,
+        Err(err) => {
+            log_warn(
+                app,
+                "memory_retrieval",
+                format!("knowledge graph unavailable: {}", err),
+            );
+            return None;
+        }
+    };
+    let seeds = mentioned_entity_ids(&graph, query);
+    if seeds.is_empty() {
+        return None;
+    }
+    let around = subgraph(&graph, &seeds, KNOWLEDGE_GRAPH_HOPS);
+    log_info(
+        app,
+        "memory_retrieval",
+        format!(
+            "Knowledge graph: {} entities, {} relations around {} mentioned",
+            around.entities.len(),
+            around.relations.len(),
+            seeds.len()
+        ),
+    );
+    Some(format_graph(&around))
+}
+
+/// Copies a session memory into the character's long-term store; returns its id there.
+fn share_with_character(
+    app: &AppHandle,
+    session_id: &str,
+    character_id: &str,
+    memory: &MemoryEmbedding,
+) -> Result<String, String> {
+    let stored = serde_json::to_value(memory)
+        .and_then(serde_json::from_value)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let conn = open_db(app)?;
+    add_character_memory(
+        &conn,
+        character_id,
+        MemoryOwner::Session(session_id),
+        stored,
+        MemoryActor::MemoryManager,
+    )
+    .map(|m| m.id)
+}
+
+fn build_memory_tool_config(character_memory: bool, knowledge_graph: bool) -> ToolConfig {
+    let mut tools = vec![
+        ToolDefinition {
+            name: "create_memory".to_string(),
+            description: Some(
+                "Create a concise memory entry capturing important facts.".to_string(),
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Concise memory to store" },
+                    "important": { "type": "boolean", "description": "If true, memory will be pinned (never decays)" },
+                    "category": {
+                        "type": "string",
+                        "enum": ["character_trait", "relationship", "plot_event", "world_detail", "preference", "other"],
+                        "description": "Category of this memory for organization"
+                    }
+                },
+                "required": ["text"]
+            }),
+        },
+        ToolDefinition {
+            name: "delete_memory".to_string(),
+            description: Some(
+                "Delete an outdated or redundant memory. Low confidence (< 0.7) triggers soft-delete to cold storage.".to_string(),
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Memory ID (preferred) or exact text to remove" },
+                    "confidence": { "type": "number", "description": "Confidence that this memory should be deleted (0.0-1.0). Below 0.7 triggers soft-delete to cold storage." }
+                },
+                "required": ["text"]
+            }),
+        },
+        ToolDefinition {
+            name: "pin_memory".to_string(),
+            description: Some(
+                "Pin a critical memory so it never decays. Use for character-defining facts."
+                    .to_string(),
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "6-digit memory ID to pin" }
+                },
+                "required": ["id"]
+            }),
+        },
+        ToolDefinition {
+            name: "unpin_memory".to_string(),
+            description: Some("Unpin a memory, allowing it to decay normally.".to_string()),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "6-digit memory ID to unpin" }
+                },
+                "required": ["id"]
+            }),
+        },
+    ];
+    if character_memory {
+        tools.push(ToolDefinition {
+            name: "share_memory".to_string(),
+            description: Some(
+                "Share a memory with the character's long-term memory, recalled in all future chats. Use only for lasting facts about the user, the character or their relationship, not for scene details.".to_string(),
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "6-digit memory ID to share" }
+                },
+                "required": ["id"]
+            }),
+        });
+    }
+    if knowledge_graph {
+        tools.extend([
             ToolDefinition {
-                name: "delete_memory".to_string(),
+                name: "record_entity".to_string(),
                 description: Some(
-                    "Delete an outdated or redundant memory. Low confidence (< 0.7) triggers soft-delete to cold storage.".to_string(),
+                    "Record a person, place, item or organization that matters to the story."
+                        .to_string(),
                 ),
                 parameters: json!({
                     "type": "object",
                     "properties": {
-                        "text": { "type": "string", "description": "Memory ID (preferred) or exact text to remove" },
-                        "confidence": { "type": "number", "description": "Confidence that this memory should be deleted (0.0-1.0). Below 0.7 triggers soft-delete to cold storage." }
+                        "name": { "type": "string", "description": "Canonical name; reuse known entity names" },
+                        "kind": { "type": "string", "enum": ENTITY_KINDS },
+                        "description": { "type": "string", "description": "Optional one-line description" }
                     },
-                    "required": ["text"]
+                    "required": ["name", "kind"]
                 }),
             },
             ToolDefinition {
-                name: "pin_memory".to_string(),
+                name: "record_relation".to_string(),
                 description: Some(
-                    "Pin a critical memory so it never decays. Use for character-defining facts."
+                    "Record a typed relation between two entities stated in the messages."
                         .to_string(),
                 ),
                 parameters: json!({
                     "type": "object",
                     "properties": {
-                        "id": { "type": "string", "description": "6-digit memory ID to pin" }
-                    },
-                    "required": ["id"]
-                }),
-            },
-            ToolDefinition {
-                name: "unpin_memory".to_string(),
-                description: Some("Unpin a memory, allowing it to decay normally.".to_string()),
-                parameters: json!({
-                    "type": "object",
-                    "properties": {
-                        "id": { "type": "string", "description": "6-digit memory ID to unpin" }
+                        "source": { "type": "string", "description": "Name of the subject entity" },
+                        "relation": { "type": "string", "description": "Relation type, e.g. sister_of, lives_in, owns" },
+                        "target": { "type": "string", "description": "Name of the object entity" },
+                        "messageIds": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "IDs of the messages that support this relation"
+                        }
                     },
-                    "required": ["id"]
+                    "required": ["source", "relation", "target"]
                 }),
             },
-            ToolDefinition {
-                name: "done".to_string(),
-                description: Some(
-                    "Call this when you have finished adding or deleting memories.".to_string(),
-                ),
-                parameters: json!({
-                    "type": "object",
-                    "properties": {
-                        "summary": { "type": "string", "description": "Optional short note of changes made" }
-                    },
-                    "required": []
-                }),
+        ]);
+    }
+    tools.push(ToolDefinition {
+        name: "done".to_string(),
+        description: Some(
+            "Call this when you have finished adding or deleting memories.".to_string(),
+        ),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "summary": { "type": "string", "description": "Optional short note of changes made" }
             },
-        ],
+            "required": []
+        }),
+    });
+    ToolConfig {
+        tools,
         choice: Some(ToolChoice::Any),
     }
 }
@@ -4844,7 +5650,7 @@ async fn summarize_messages(
     let context_length = resolve_context_length(session, model, settings);
     let max_tokens = resolve_max_tokens(session, model, settings);
     let extra_body_fields = if provider_cred.provider_id == "llamacpp" {
-        build_llama_extra_fields(session, model, settings)
+        Some(build_llama_extra_fields(session, model, settings))
     } else if provider_cred.provider_id == "ollama" {
         build_ollama_extra_fields(
             session,
@@ -5342,7 +6148,7 @@ pub async fn chat_generate_user_reply(
 
     let context_length = resolve_context_length(&session, model, settings);
     let extra_body_fields = if provider_cred.provider_id == "llamacpp" {
-        build_llama_extra_fields(&session, model, settings)
+        Some(build_llama_extra_fields(&session, model, settings))
     } else if provider_cred.provider_id == "ollama" {
         build_ollama_extra_fields(
             &session,
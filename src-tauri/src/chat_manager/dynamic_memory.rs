@@ -5,7 +5,19 @@
 
 use std::collections::{HashMap, HashSet};
 
-use super::types::{DynamicMemorySettings, MemoryRetrievalStrategy, Settings};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use super::prompts::APP_DYNAMIC_MEMORY_TEMPLATE_ID;
+use super::tooling::{ToolChoice, ToolConfig, ToolDefinition};
+
+use super::types::{
+    DynamicMemorySettings, MemoryDecayModel, MemoryProvenance, MemoryRetrievalStrategy,
+    MemoryScoreBreakdown, Settings,
+};
+use crate::storage_manager::memories::{
+    bm25_rank_memories, read_retrievable_memories, MemoryOwner,
+};
 
 // ============================================================================
 // Shared Memory Entry Trait
@@ -13,12 +25,18 @@ use super::types::{DynamicMemorySettings, MemoryRetrievalStrategy, Settings};
 
 pub trait MemoryEntry {
     fn id(&self) -> &str;
+    fn set_id(&mut self, value: String);
     fn text(&self) -> &str;
+    fn set_text(&mut self, value: String);
     fn embedding(&self) -> &[f32];
+    fn embedding_model(&self) -> Option<&str>;
+    fn set_embedding(&mut self, value: Vec<f32>, model: Option<String>);
     fn token_count(&self) -> u32;
+    fn set_token_count(&mut self, value: u32);
     fn is_cold(&self) -> bool;
     fn set_is_cold(&mut self, value: bool);
     fn is_pinned(&self) -> bool;
+    fn set_is_pinned(&mut self, value: bool);
     fn importance_score(&self) -> f32;
     fn set_importance_score(&mut self, value: f32);
     fn last_accessed_at(&self) -> u64;
@@ -26,21 +44,39 @@ pub trait MemoryEntry {
     fn access_count(&self) -> u32;
     fn set_access_count(&mut self, value: u32);
     fn category(&self) -> Option<&str>;
+    fn provenance(&self) -> Option<&MemoryProvenance>;
+    fn set_provenance(&mut self, value: Option<MemoryProvenance>);
 }
 
 impl MemoryEntry for crate::chat_manager::types::MemoryEmbedding {
     fn id(&self) -> &str {
         &self.id
     }
+    fn set_id(&mut self, value: String) {
+        self.id = value;
+    }
     fn text(&self) -> &str {
         &self.text
     }
+    fn set_text(&mut self, value: String) {
+        self.text = value;
+    }
     fn embedding(&self) -> &[f32] {
         &self.embedding
     }
+    fn embedding_model(&self) -> Option<&str> {
+        self.embedding_model.as_deref()
+    }
+    fn set_embedding(&mut self, value: Vec<f32>, model: Option<String>) {
+        self.embedding = value;
+        self.embedding_model = model;
+    }
     fn token_count(&self) -> u32 {
         self.token_count
     }
+    fn set_token_count(&mut self, value: u32) {
+        self.token_count = value;
+    }
     fn is_cold(&self) -> bool {
         self.is_cold
     }
@@ -50,6 +86,9 @@ impl MemoryEntry for crate::chat_manager::types::MemoryEmbedding {
     fn is_pinned(&self) -> bool {
         self.is_pinned
     }
+    fn set_is_pinned(&mut self, value: bool) {
+        self.is_pinned = value;
+    }
     fn importance_score(&self) -> f32 {
         self.importance_score
     }
@@ -71,21 +110,43 @@ impl MemoryEntry for crate::chat_manager::types::MemoryEmbedding {
     fn category(&self) -> Option<&str> {
         self.category.as_deref()
     }
+    fn provenance(&self) -> Option<&MemoryProvenance> {
+        self.provenance.as_ref()
+    }
+    fn set_provenance(&mut self, value: Option<MemoryProvenance>) {
+        self.provenance = value;
+    }
 }
 
 impl MemoryEntry for crate::storage_manager::group_sessions::MemoryEmbedding {
     fn id(&self) -> &str {
         &self.id
     }
+    fn set_id(&mut self, value: String) {
+        self.id = value;
+    }
     fn text(&self) -> &str {
         &self.text
     }
+    fn set_text(&mut self, value: String) {
+        self.text = value;
+    }
     fn embedding(&self) -> &[f32] {
         &self.embedding
     }
+    fn embedding_model(&self) -> Option<&str> {
+        self.embedding_model.as_deref()
+    }
+    fn set_embedding(&mut self, value: Vec<f32>, model: Option<String>) {
+        self.embedding = value;
+        self.embedding_model = model;
+    }
     fn token_count(&self) -> u32 {
         self.token_count.max(0) as u32
     }
+    fn set_token_count(&mut self, value: u32) {
+        self.token_count = value as i32;
+    }
     fn is_cold(&self) -> bool {
         self.is_cold
     }
@@ -95,6 +156,9 @@ impl MemoryEntry for crate::storage_manager::group_sessions::MemoryEmbedding {
     fn is_pinned(&self) -> bool {
         self.is_pinned
     }
+    fn set_is_pinned(&mut self, value: bool) {
+        self.is_pinned = value;
+    }
     fn importance_score(&self) -> f32 {
         self.importance_score
     }
@@ -116,6 +180,12 @@ impl MemoryEntry for crate::storage_manager::group_sessions::MemoryEmbedding {
     fn category(&self) -> Option<&str> {
         self.category.as_deref()
     }
+    fn provenance(&self) -> Option<&MemoryProvenance> {
+        self.provenance.as_ref()
+    }
+    fn set_provenance(&mut self, value: Option<MemoryProvenance>) {
+        self.provenance = value;
+    }
 }
 
 // ============================================================================
@@ -129,8 +199,21 @@ pub const FALLBACK_RETRIEVAL_LIMIT: u32 = 5;
 pub const FALLBACK_RETRIEVAL_STRATEGY: MemoryRetrievalStrategy = MemoryRetrievalStrategy::Smart;
 pub const FALLBACK_HOT_MEMORY_TOKEN_BUDGET: u32 = 2000;
 pub const FALLBACK_DECAY_RATE: f32 = 0.08;
+pub const FALLBACK_DECAY_MODEL: MemoryDecayModel = MemoryDecayModel::PerCycle;
+pub const FALLBACK_DECAY_HALF_LIFE_HOURS: f32 = 72.0;
+/// Decay simulation defaults: 30 days sampled daily, assuming 8 memory cycles a day
+pub const SIMULATION_HORIZON_HOURS: f32 = 720.0;
+pub const SIMULATION_STEP_HOURS: f32 = 24.0;
+pub const SIMULATION_CYCLES_PER_DAY: f32 = 8.0;
 pub const FALLBACK_COLD_THRESHOLD: f32 = 0.3;
+pub const FALLBACK_CONSOLIDATION_THRESHOLD: f32 = 0.8;
 pub const MEMORY_ID_SPACE: u64 = 1_000_000;
+/// Largest group of memories merged in one summarisation call
+pub const MAX_CONSOLIDATION_CLUSTER: usize = 5;
+/// Damping constant for reciprocal rank fusion (the value from the original RRF paper)
+pub const RRF_K: f32 = 60.0;
+/// How many candidates each ranking contributes before fusion
+pub const HYBRID_CANDIDATE_POOL: usize = 50;
 
 // ============================================================================
 // Settings Helper Functions
@@ -207,24 +290,56 @@ pub fn dynamic_retrieval_strategy(settings: &Settings) -> MemoryRetrievalStrateg
         .unwrap_or_else(|| FALLBACK_RETRIEVAL_STRATEGY.clone())
 }
 
-/// Get the decay rate for memory importance scores
-pub fn dynamic_decay_rate(settings: &Settings) -> f32 {
+/// Get the threshold below which memories are demoted to cold storage
+pub fn dynamic_cold_threshold(settings: &Settings) -> f32 {
     settings
         .advanced_settings
         .as_ref()
         .and_then(|a| a.dynamic_memory.as_ref())
-        .map(|dm| dm.decay_rate)
-        .unwrap_or(FALLBACK_DECAY_RATE)
+        .map(|dm| dm.cold_threshold)
+        .unwrap_or(FALLBACK_COLD_THRESHOLD)
 }
 
-/// Get the threshold below which memories are demoted to cold storage
-pub fn dynamic_cold_threshold(settings: &Settings) -> f32 {
+/// Check if hybrid retrieval should rescore candidates with the cross-encoder
+pub fn dynamic_reranker_enabled(settings: &Settings) -> bool {
     settings
         .advanced_settings
         .as_ref()
         .and_then(|a| a.dynamic_memory.as_ref())
-        .map(|dm| dm.cold_threshold)
-        .unwrap_or(FALLBACK_COLD_THRESHOLD)
+        .map(|dm| dm.reranker_enabled)
+        .unwrap_or(false)
+}
+
+/// Check if character-level long-term memory is enabled
+pub fn dynamic_character_memory_enabled(settings: &Settings) -> bool {
+    settings
+        .advanced_settings
+        .as_ref()
+        .and_then(|a| a.dynamic_memory.as_ref())
+        .map(|dm| dm.character_memory_enabled)
+        .unwrap_or(false)
+}
+
+/// Check if knowledge-graph extraction and retrieval is enabled
+pub fn dynamic_knowledge_graph_enabled(settings: &Settings) -> bool {
+    settings
+        .advanced_settings
+        .as_ref()
+        .and_then(|a| a.dynamic_memory.as_ref())
+        .map(|dm| dm.knowledge_graph_enabled)
+        .unwrap_or(false)
+}
+
+/// Get the similarity threshold for merging near-duplicates; `None` when consolidation is off
+pub fn dynamic_consolidation_threshold(settings: &Settings) -> Option<f32> {
+    match settings
+        .advanced_settings
+        .as_ref()
+        .and_then(|a| a.dynamic_memory.as_ref())
+    {
+        Some(dm) if dm.consolidation_enabled => Some(dm.consolidation_threshold),
+        _ => None,
+    }
 }
 
 /// Check if context enrichment (semantic search) is enabled
@@ -263,8 +378,15 @@ pub fn effective_dynamic_memory_settings(
         retrieval_strategy: FALLBACK_RETRIEVAL_STRATEGY,
         hot_memory_token_budget: FALLBACK_HOT_MEMORY_TOKEN_BUDGET,
         decay_rate: FALLBACK_DECAY_RATE,
+        decay_model: FALLBACK_DECAY_MODEL,
+        decay_half_life_hours: FALLBACK_DECAY_HALF_LIFE_HOURS,
         cold_threshold: FALLBACK_COLD_THRESHOLD,
         context_enrichment_enabled: true,
+        reranker_enabled: false,
+        consolidation_enabled: false,
+        consolidation_threshold: FALLBACK_CONSOLIDATION_THRESHOLD,
+        character_memory_enabled: false,
+        knowledge_graph_enabled: false,
     }
 }
 
@@ -408,6 +530,152 @@ pub fn apply_memory_decay<E: MemoryEntry>(
     (decayed, demoted)
 }
 
+/// Wall-clock decay: importance halves every `half_life_hours` since the memory was last
+/// retrieved, and every retrieval stretches the half-life the way access count slows the
+/// per-cycle rate. Scores only ever go down here; `mark_memories_accessed` restores them.
+/// Returns (decayed_count, demoted_ids).
+pub fn apply_half_life_decay<E: MemoryEntry>(
+    memories: &mut [E],
+    half_life_hours: f32,
+    cold_threshold: f32,
+    now: u64,
+) -> (usize, Vec<String>) {
+    let half_life_ms = half_life_hours.max(0.1) as f64 * 3_600_000.0;
+    let mut decayed = 0;
+    let mut demoted = Vec::new();
+
+    for mem in memories.iter_mut() {
+        if mem.is_cold() || mem.is_pinned() {
+            continue;
+        }
+        // Legacy rows without an access time start their clock now.
+        if mem.last_accessed_at() == 0 {
+            mem.set_last_accessed_at(now);
+            continue;
+        }
+
+        let elapsed = now.saturating_sub(mem.last_accessed_at()) as f64;
+        let effective_half_life = half_life_ms * (1.0 + (mem.access_count() as f64).sqrt());
+        let retention = 0.5f64.powf(elapsed / effective_half_life) as f32;
+        if retention < mem.importance_score() {
+            mem.set_importance_score(retention);
+            decayed += 1;
+        }
+
+        if mem.importance_score() < cold_threshold {
+            mem.set_is_cold(true);
+            demoted.push(mem.id().to_string());
+        }
+    }
+
+    (decayed, demoted)
+}
+
+/// Apply whichever decay model `settings` selects. Returns (decayed_count, demoted_ids).
+pub fn apply_configured_memory_decay<E: MemoryEntry>(
+    memories: &mut [E],
+    settings: &DynamicMemorySettings,
+    now: u64,
+) -> (usize, Vec<String>) {
+    match settings.decay_model {
+        MemoryDecayModel::PerCycle => {
+            apply_memory_decay(memories, settings.decay_rate, settings.cold_threshold)
+        }
+        MemoryDecayModel::HalfLife => apply_half_life_decay(
+            memories,
+            settings.decay_half_life_hours,
+            settings.cold_threshold,
+            now,
+        ),
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDecayPoint {
+    pub hours: f32,
+    pub importance_score: f32,
+    pub is_cold: bool,
+}
+
+/// Projected importance of one memory if it is never retrieved again.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDecayTrajectory {
+    pub memory_id: String,
+    pub text: String,
+    pub points: Vec<MemoryDecayPoint>,
+    /// First sampled hour at which the memory is cold, if within the horizon
+    pub cold_after_hours: Option<f32>,
+}
+
+/// Runs the configured decay over copies of the hot, unpinned memories, sampling every
+/// `step_hours` up to `horizon_hours` from `now`. Per-cycle decay is applied
+/// `cycles_per_day` times a day, since it doesn't depend on the clock.
+pub fn simulate_memory_decay<E: MemoryEntry + Clone>(
+    memories: &[E],
+    settings: &DynamicMemorySettings,
+    horizon_hours: f32,
+    step_hours: f32,
+    cycles_per_day: f32,
+    now: u64,
+) -> Vec<MemoryDecayTrajectory> {
+    let step_hours = step_hours.max(0.1);
+    let mut working: Vec<E> = memories
+        .iter()
+        .filter(|m| !m.is_cold() && !m.is_pinned())
+        .cloned()
+        .collect();
+    let mut trajectories: Vec<MemoryDecayTrajectory> = working
+        .iter()
+        .map(|m| MemoryDecayTrajectory {
+            memory_id: m.id().to_string(),
+            text: m.text().to_string(),
+            points: vec![MemoryDecayPoint {
+                hours: 0.0,
+                importance_score: m.importance_score(),
+                is_cold: false,
+            }],
+            cold_after_hours: None,
+        })
+        .collect();
+
+    let mut cycles_run = 0u64;
+    let mut hours = 0.0f32;
+    while hours + step_hours <= horizon_hours + f32::EPSILON {
+        hours += step_hours;
+        match settings.decay_model {
+            MemoryDecayModel::PerCycle => {
+                let due = (hours / 24.0 * cycles_per_day).floor() as u64;
+                for _ in cycles_run..due {
+                    apply_memory_decay(&mut working, settings.decay_rate, settings.cold_threshold);
+                }
+                cycles_run = cycles_run.max(due);
+            }
+            MemoryDecayModel::HalfLife => {
+                let at = now + (hours as f64 * 3_600_000.0) as u64;
+                apply_half_life_decay(
+                    &mut working,
+                    settings.decay_half_life_hours,
+                    settings.cold_threshold,
+                    at,
+                );
+            }
+        }
+        for (mem, trajectory) in working.iter().zip(trajectories.iter_mut()) {
+            if mem.is_cold() && trajectory.cold_after_hours.is_none() {
+                trajectory.cold_after_hours = Some(hours);
+            }
+            trajectory.points.push(MemoryDecayPoint {
+                hours,
+                importance_score: mem.importance_score(),
+                is_cold: mem.is_cold(),
+            });
+        }
+    }
+    trajectories
+}
+
 /// Promote cold memories by ID. Returns count promoted.
 pub fn promote_cold_memories<E: MemoryEntry>(
     memories: &mut [E],
@@ -459,6 +727,12 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     dot / denom
 }
 
+/// Whether two vectors tagged with these embedding backends can be compared. An untagged
+/// vector's backend is unknown, so it matches nothing until re-embedded.
+pub fn same_embedding_space(a: Option<&str>, b: Option<&str>) -> bool {
+    a.is_some() && a == b
+}
+
 /// Select hot (or pinned) memories by similarity. Returns (index, score).
 pub fn select_relevant_memory_indices<E: MemoryEntry>(
     query_embedding: &[f32],
@@ -530,6 +804,151 @@ pub fn select_top_cosine_memory_indices<E: MemoryEntry>(
         .collect()
 }
 
+/// Ranks the owner's retrievable rows straight from the `memories` table. Returns the hits
+/// best first with their scores.
+pub fn select_memories_from_store(
+    conn: &rusqlite::Connection,
+    owner: MemoryOwner<'_>,
+    query_embedding: &[f32],
+    limit: usize,
+    min_similarity: f32,
+    strategy: &MemoryRetrievalStrategy,
+) -> Result<Vec<(crate::storage_manager::group_sessions::MemoryEmbedding, f32)>, String> {
+    let candidates = read_retrievable_memories(conn, owner)?;
+    let hits = if matches!(strategy, MemoryRetrievalStrategy::Cosine) {
+        select_top_cosine_memory_indices(query_embedding, &candidates, limit, min_similarity)
+    } else {
+        select_relevant_memory_indices(query_embedding, &candidates, limit, min_similarity)
+    };
+    let mut candidates: Vec<Option<_>> = candidates.into_iter().map(Some).collect();
+    Ok(hits
+        .into_iter()
+        .filter_map(|(idx, score)| candidates[idx].take().map(|memory| (memory, score)))
+        .collect())
+}
+
+/// Fuses a cosine ranking and a BM25 ranking (each `(id, score)`, best first) with
+/// reciprocal rank fusion. Returns ids best first with their score breakdown.
+pub fn reciprocal_rank_fusion(
+    cosine: &[(String, f32)],
+    bm25: &[(String, f32)],
+) -> Vec<(String, MemoryScoreBreakdown)> {
+    let mut fused: Vec<(String, MemoryScoreBreakdown)> = Vec::new();
+    let mut slot_by_id: HashMap<&str, usize> = HashMap::new();
+
+    for (rank, (id, score)) in cosine.iter().enumerate() {
+        let slot = *slot_by_id.entry(id.as_str()).or_insert_with(|| {
+            fused.push((id.clone(), MemoryScoreBreakdown::default()));
+            fused.len() - 1
+        });
+        let breakdown = &mut fused[slot].1;
+        if breakdown.cosine_rank.is_none() {
+            breakdown.cosine = Some(*score);
+            breakdown.cosine_rank = Some(rank + 1);
+            breakdown.fused += 1.0 / (RRF_K + (rank + 1) as f32);
+        }
+    }
+    for (rank, (id, score)) in bm25.iter().enumerate() {
+        let slot = *slot_by_id.entry(id.as_str()).or_insert_with(|| {
+            fused.push((id.clone(), MemoryScoreBreakdown::default()));
+            fused.len() - 1
+        });
+        let breakdown = &mut fused[slot].1;
+        if breakdown.bm25_rank.is_none() {
+            breakdown.bm25 = Some(*score);
+            breakdown.bm25_rank = Some(rank + 1);
+            breakdown.fused += 1.0 / (RRF_K + (rank + 1) as f32);
+        }
+    }
+
+    fused.sort_by(|a, b| {
+        b.1.fused
+            .partial_cmp(&a.1.fused)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    fused
+}
+
+/// Hybrid retrieval: cosine rank over retrievable memories fused with BM25 rank over the
+/// owner's whole `memories` table (cold rows included). Returns up to `limit` indices into
+/// `memories` with their score breakdown, best first.
+pub fn select_hybrid_memory_indices<E: MemoryEntry>(
+    conn: &rusqlite::Connection,
+    owner: MemoryOwner<'_>,
+    query: &str,
+    query_embedding: &[f32],
+    memories: &[E],
+    limit: usize,
+    min_similarity: f32,
+) -> Vec<(usize, MemoryScoreBreakdown)> {
+    let mut cosine: Vec<(String, f32)> = match read_retrievable_memories(conn, owner) {
+        Ok(rows) if !rows.is_empty() => rows
+            .iter()
+            .map(|m| {
+                (
+                    m.id.clone(),
+                    cosine_similarity(query_embedding, &m.embedding),
+                )
+            })
+            .collect(),
+        _ => memories
+            .iter()
+            .filter(|m| !m.embedding().is_empty() && (!m.is_cold() || m.is_pinned()))
+            .map(|m| {
+                (
+                    m.id().to_string(),
+                    cosine_similarity(query_embedding, m.embedding()),
+                )
+            })
+            .collect(),
+    };
+    cosine.retain(|(_, score)| *score >= min_similarity);
+    cosine.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    cosine.truncate(HYBRID_CANDIDATE_POOL);
+
+    let bm25 = bm25_rank_memories(conn, owner, query, HYBRID_CANDIDATE_POOL).unwrap_or_default();
+
+    let index_by_id: HashMap<&str, usize> = memories
+        .iter()
+        .enumerate()
+        .map(|(idx, m)| (m.id(), idx))
+        .collect();
+    reciprocal_rank_fusion(&cosine, &bm25)
+        .into_iter()
+        .filter_map(|(id, breakdown)| index_by_id.get(id.as_str()).map(|&idx| (idx, breakdown)))
+        .take(limit)
+        .collect()
+}
+
+/// Rescores hybrid hits with the local cross-encoder and re-sorts them by the new score.
+/// Leaves `hits` untouched when no reranker model is installed.
+pub async fn rerank_memory_hits<E: MemoryEntry>(
+    app: &AppHandle,
+    query: &str,
+    memories: &[E],
+    hits: &mut [(usize, MemoryScoreBreakdown)],
+) -> Result<(), String> {
+    if hits.is_empty() || !crate::embedding_model::check_reranker_model(app.clone())? {
+        return Ok(());
+    }
+
+    let passages: Vec<String> = hits
+        .iter()
+        .map(|(idx, _)| memories[*idx].text().to_string())
+        .collect();
+    let scores =
+        crate::embedding_model::rerank_passages(app.clone(), query.to_string(), passages).await?;
+    for ((_, breakdown), score) in hits.iter_mut().zip(scores) {
+        breakdown.rerank = Some(score);
+    }
+    hits.sort_by(|a, b| {
+        b.1.final_score()
+            .partial_cmp(&a.1.final_score())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(())
+}
+
 /// Keyword search over cold memories. Returns indices.
 pub fn search_cold_memory_indices_by_keyword<E: MemoryEntry>(
     memories: &[E],
@@ -569,6 +988,184 @@ pub fn search_cold_memory_indices_by_keyword<E: MemoryEntry>(
         .collect()
 }
 
+// ============================================================================
+// Consolidation
+// ============================================================================
+
+pub const MEMORY_MERGE_INSTRUCTIONS: &str = "You merge near-duplicate long-term memories. Combine the numbered memories into ONE concise factual memory that keeps every distinct detail and drops repetition. Do not invent anything. Use the write_memory tool.";
+
+pub fn memory_merge_tool_config() -> ToolConfig {
+    ToolConfig {
+        tools: vec![ToolDefinition {
+            name: "write_memory".to_string(),
+            description: Some("Return the single merged memory.".to_string()),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Merged memory text" }
+                },
+                "required": ["text"]
+            }),
+        }],
+        choice: Some(ToolChoice::Required),
+    }
+}
+
+/// User message listing the memories to merge.
+pub fn memory_merge_request<E: MemoryEntry>(memories: &[E], cluster: &[usize]) -> String {
+    let lines: Vec<String> = cluster
+        .iter()
+        .enumerate()
+        .map(|(n, &idx)| format!("{}. {}", n + 1, memories[idx].text()))
+        .collect();
+    format!("Memories to merge:\n{}", lines.join("\n"))
+}
+
+/// Provenance for a memory created by the memory cycle over `window_ids` (oldest first).
+pub fn memory_cycle_provenance(window_ids: &[&str], model_id: &str) -> MemoryProvenance {
+    MemoryProvenance {
+        origin: "memory_cycle".to_string(),
+        start_message_id: window_ids.first().map(|id| id.to_string()),
+        end_message_id: window_ids.last().map(|id| id.to_string()),
+        model_id: Some(model_id.to_string()),
+        prompt_template_id: Some(APP_DYNAMIC_MEMORY_TEMPLATE_ID.to_string()),
+        ..Default::default()
+    }
+}
+
+/// The `memory_tool_events` action for a merge. `originals` keeps each merged memory's id
+/// and text so the merge can be undone (see `storage_manager::memories::undo_memory_merge`).
+pub fn memory_merge_action<E: MemoryEntry>(
+    merged_id: &str,
+    text: &str,
+    originals: &[E],
+    now: u64,
+) -> Value {
+    let ids: Vec<&str> = originals.iter().map(|m| m.id()).collect();
+    let originals: Vec<Value> = originals
+        .iter()
+        .map(|m| json!({ "id": m.id(), "text": m.text() }))
+        .collect();
+    json!({
+        "name": "merge_memories",
+        "arguments": { "ids": ids, "text": text },
+        "memoryId": merged_id,
+        "originals": originals,
+        "timestamp": now,
+    })
+}
+
+/// Groups hot memories whose embeddings are at least `threshold` similar. Each cluster is
+/// seeded by its earliest member and only takes memories close to that seed, so chains of
+/// loosely related memories don't collapse together. Only clusters of 2+ are returned.
+/// Vectors from different embedding backends are never compared.
+pub fn cluster_similar_memory_indices<E: MemoryEntry>(
+    memories: &[E],
+    threshold: f32,
+) -> Vec<Vec<usize>> {
+    let candidates: Vec<usize> = memories
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| !m.is_cold() && !m.embedding().is_empty())
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut assigned: HashSet<usize> = HashSet::new();
+    let mut clusters = Vec::new();
+    for (pos, &seed) in candidates.iter().enumerate() {
+        if assigned.contains(&seed) {
+            continue;
+        }
+        let mut cluster = vec![seed];
+        for &other in &candidates[pos + 1..] {
+            if cluster.len() >= MAX_CONSOLIDATION_CLUSTER {
+                break;
+            }
+            if !assigned.contains(&other)
+                && same_embedding_space(
+                    memories[seed].embedding_model(),
+                    memories[other].embedding_model(),
+                )
+                && cosine_similarity(memories[seed].embedding(), memories[other].embedding())
+                    >= threshold
+            {
+                cluster.push(other);
+            }
+        }
+        if cluster.len() > 1 {
+            assigned.extend(cluster.iter().copied());
+            clusters.push(cluster);
+        }
+    }
+    clusters
+}
+
+/// Replaces the memories at `cluster` with a single merged entry placed where the first
+/// member was. The merged entry keeps any pin, the highest importance and the combined
+/// access history. Returns the merged entry's id and the removed originals, in order.
+pub fn apply_memory_merge<E: MemoryEntry + Clone>(
+    memories: &mut Vec<E>,
+    cluster: &[usize],
+    text: String,
+    embedding: Vec<f32>,
+    embedding_model: Option<String>,
+    token_count: u32,
+    model_id: &str,
+) -> (String, Vec<E>) {
+    let mut sorted = cluster.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let originals: Vec<E> = sorted.iter().map(|&idx| memories[idx].clone()).collect();
+    let mut merged = originals[0].clone();
+    merged.set_id(generate_memory_id());
+    merged.set_text(text);
+    merged.set_embedding(embedding, embedding_model);
+    merged.set_token_count(token_count);
+    merged.set_is_cold(false);
+    merged.set_is_pinned(originals.iter().any(|m| m.is_pinned()));
+    merged.set_importance_score(
+        originals
+            .iter()
+            .map(|m| m.importance_score())
+            .fold(0.0, f32::max),
+    );
+    merged.set_access_count(
+        originals
+            .iter()
+            .fold(0u32, |acc, m| acc.saturating_add(m.access_count())),
+    );
+    merged.set_last_accessed_at(
+        originals
+            .iter()
+            .map(|m| m.last_accessed_at())
+            .max()
+            .unwrap_or(0),
+    );
+    let sources: Vec<&MemoryProvenance> = originals.iter().filter_map(|m| m.provenance()).collect();
+    merged.set_provenance(Some(MemoryProvenance {
+        origin: "merge".to_string(),
+        start_message_id: sources.iter().find_map(|p| p.start_message_id.clone()),
+        end_message_id: sources.iter().rev().find_map(|p| p.end_message_id.clone()),
+        model_id: Some(model_id.to_string()),
+        merged_from: originals.iter().map(|m| m.id().to_string()).collect(),
+        ..Default::default()
+    }));
+
+    // Timestamp ids can collide with a survivor; keep the merged id unique.
+    let mut merged_id = merged.id().to_string();
+    while memories.iter().any(|m| m.id() == merged_id) {
+        merged_id = format!("{}-m", merged_id);
+    }
+    merged.set_id(merged_id.clone());
+
+    for &idx in sorted.iter().rev() {
+        memories.remove(idx);
+    }
+    memories.insert(sorted[0], merged);
+    (merged_id, originals)
+}
+
 pub fn trim_memories_to_max<E: MemoryEntry>(memories: &mut Vec<E>, max_entries: usize) -> usize {
     if memories.len() <= max_entries {
         return 0;
@@ -598,3 +1195,133 @@ pub fn trim_memories_to_max<E: MemoryEntry>(memories: &mut Vec<E>, max_entries:
     memories.retain(|m| !remove_ids.contains(m.id()));
     before.saturating_sub(memories.len())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat_manager::types::MemoryEmbedding;
+
+    fn memory(id: &str, embedding: Vec<f32>, pinned: bool, importance: f32) -> MemoryEmbedding {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": id,
+            "embedding": embedding,
+            "embeddingModel": "v3",
+            "isPinned": pinned,
+            "importanceScore": importance,
+            "accessCount": 2,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn merges_similar_cluster_keeping_pin_and_importance() {
+        let mut memories = vec![
+            memory("a", vec![1.0, 0.0], false, 0.4),
+            memory("b", vec![0.0, 1.0], false, 0.5),
+            memory("c", vec![0.99, 0.05], true, 0.9),
+        ];
+        let clusters = cluster_similar_memory_indices(&memories, 0.9);
+        assert_eq!(clusters, vec![vec![0, 2]]);
+        memories[2].embedding_model = None;
+        assert!(cluster_similar_memory_indices(&memories, 0.9).is_empty());
+        memories[2].embedding_model = Some("v3".to_string());
+
+        let (merged_id, originals) = apply_memory_merge(
+            &mut memories,
+            &clusters[0],
+            "a and c".to_string(),
+            vec![1.0, 0.0],
+            Some("v3".to_string()),
+            3,
+            "model",
+        );
+        assert_eq!(originals.len(), 2);
+        assert_eq!(memories.len(), 2);
+        assert_eq!(memories[0].id, merged_id);
+        assert_eq!(memories[0].text, "a and c");
+        assert_eq!(memories[0].embedding_model.as_deref(), Some("v3"));
+        assert!(memories[0].is_pinned);
+        assert_eq!(memories[0].importance_score, 0.9);
+        assert_eq!(memories[0].access_count, 4);
+        let provenance = memories[0].provenance.as_ref().unwrap();
+        assert_eq!(provenance.origin, "merge");
+        assert_eq!(provenance.merged_from, vec!["a", "c"]);
+        assert_eq!(memories[1].id, "b");
+    }
+
+    #[test]
+    fn rrf_rewards_agreement_between_rankings() {
+        let cosine = vec![
+            ("a".to_string(), 0.9),
+            ("b".to_string(), 0.8),
+            ("c".to_string(), 0.7),
+        ];
+        let bm25 = vec![("c".to_string(), 4.0), ("d".to_string(), 2.0)];
+
+        let fused = reciprocal_rank_fusion(&cosine, &bm25);
+        let ids: Vec<&str> = fused.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b", "d"]);
+
+        let top = &fused[0].1;
+        assert_eq!(top.cosine_rank, Some(3));
+        assert_eq!(top.bm25_rank, Some(1));
+        assert_eq!(top.bm25, Some(4.0));
+        assert!((top.fused - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-6);
+        assert_eq!(fused[3].1.cosine, None);
+        assert_eq!(fused[3].1.bm25_rank, Some(2));
+    }
+
+    #[test]
+    fn half_life_decay_follows_the_clock_and_access_count() {
+        const HOUR: u64 = 3_600_000;
+        let mut memories = vec![
+            memory("fresh", vec![], false, 1.0),
+            memory("stale", vec![], false, 1.0),
+            memory("pinned", vec![], true, 1.0),
+        ];
+        memories[0].access_count = 0;
+        memories[0].last_accessed_at = 100 * HOUR;
+        memories[1].access_count = 0;
+        memories[1].last_accessed_at = HOUR;
+        memories[2].last_accessed_at = HOUR;
+
+        let (decayed, demoted) = apply_half_life_decay(&mut memories, 24.0, 0.3, 100 * HOUR);
+        assert_eq!(decayed, 1);
+        assert_eq!(demoted, vec!["stale"]);
+        assert_eq!(memories[0].importance_score, 1.0);
+        assert!(memories[1].importance_score < 0.3);
+        assert_eq!(memories[2].importance_score, 1.0);
+
+        // One retrieval doubles the half-life, so a plain half-life leaves ~0.71.
+        let mut reinforced = vec![memory("r", vec![], false, 1.0)];
+        reinforced[0].access_count = 1;
+        reinforced[0].last_accessed_at = HOUR;
+        apply_half_life_decay(&mut reinforced, 24.0, 0.3, 25 * HOUR);
+        assert!((reinforced[0].importance_score - 0.5f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn decay_simulation_reports_when_memories_go_cold() {
+        let mut settings: DynamicMemorySettings =
+            serde_json::from_value(json!({ "enabled": true, "coldThreshold": 0.3 })).unwrap();
+        let mut memories = vec![memory("a", vec![], false, 1.0)];
+        memories[0].access_count = 0;
+        memories[0].last_accessed_at = 1;
+
+        settings.decay_model = MemoryDecayModel::HalfLife;
+        settings.decay_half_life_hours = 24.0;
+        let half_life = simulate_memory_decay(&memories, &settings, 96.0, 24.0, 8.0, 1);
+        assert_eq!(half_life.len(), 1);
+        assert_eq!(half_life[0].points.len(), 5);
+        assert!((half_life[0].points[1].importance_score - 0.5).abs() < 1e-4);
+        assert_eq!(half_life[0].cold_after_hours, Some(48.0));
+
+        settings.decay_model = MemoryDecayModel::PerCycle;
+        settings.decay_rate = 0.1;
+        let per_cycle = simulate_memory_decay(&memories, &settings, 24.0, 12.0, 4.0, 1);
+        assert!((per_cycle[0].points[1].importance_score - 0.8).abs() < 1e-4);
+        assert!((per_cycle[0].points[2].importance_score - 0.6).abs() < 1e-4);
+        assert_eq!(per_cycle[0].cold_after_hours, None);
+    }
+}
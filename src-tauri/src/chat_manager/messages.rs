@@ -140,3 +140,89 @@ pub fn sanitize_placeholders_in_api_messages(
         }
     }
 }
+
+/// Framing tokens (role markers, separators) chat templates add around each message.
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+fn message_token_cost(message: &Value, count_tokens: &impl Fn(&str) -> u32) -> u32 {
+    let text_tokens = match message.get("content") {
+        Some(Value::String(text)) => count_tokens(text),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .map(count_tokens)
+            .sum(),
+        _ => 0,
+    };
+    text_tokens + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Drops the oldest chat turns until the prompt fits in `budget` tokens. System messages and
+/// the final message are always kept. Returns how many messages were dropped.
+pub fn trim_api_messages_to_budget(
+    messages: &mut Vec<Value>,
+    system_role: &str,
+    budget: u32,
+    count_tokens: impl Fn(&str) -> u32,
+) -> usize {
+    let costs: Vec<u32> = messages
+        .iter()
+        .map(|message| message_token_cost(message, &count_tokens))
+        .collect();
+    let mut total: u32 = costs.iter().sum();
+    let mut dropped = vec![false; messages.len()];
+    for idx in 0..messages.len().saturating_sub(1) {
+        if total <= budget {
+            break;
+        }
+        if messages[idx].get("role").and_then(|r| r.as_str()) == Some(system_role) {
+            continue;
+        }
+        dropped[idx] = true;
+        total -= costs[idx];
+    }
+
+    let mut flags = dropped.iter();
+    messages.retain(|_| !flags.next().copied().unwrap_or(false));
+    dropped.iter().filter(|d| **d).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trimming_drops_oldest_turns_and_keeps_system_and_last() {
+        let mut messages = vec![
+            json!({ "role": "system", "content": "aaaa aaaa" }),
+            json!({ "role": "user", "content": "bbbb bbbb" }),
+            json!({ "role": "assistant", "content": [{ "type": "text", "text": "cccc cccc" }] }),
+            json!({ "role": "user", "content": "dddd dddd" }),
+        ];
+        // Two words per message plus overhead: 6 tokens each.
+        let count = |text: &str| text.split_whitespace().count() as u32;
+
+        assert_eq!(
+            trim_api_messages_to_budget(&mut messages, "system", 24, count),
+            0
+        );
+        assert_eq!(messages.len(), 4);
+
+        assert_eq!(
+            trim_api_messages_to_budget(&mut messages, "system", 13, count),
+            2
+        );
+        let roles: Vec<&str> = messages
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["system", "user"]);
+        assert_eq!(messages[1]["content"], "dddd dddd");
+
+        assert_eq!(
+            trim_api_messages_to_budget(&mut messages, "system", 0, count),
+            0
+        );
+        assert_eq!(messages.len(), 2);
+    }
+}
@@ -23,14 +23,16 @@ pub use commands::{
     __cmd__reset_app_default_template, __cmd__reset_dynamic_memory_template,
     __cmd__reset_dynamic_summary_template, __cmd__reset_help_me_reply_conversational_template,
     __cmd__reset_help_me_reply_template, __cmd__retry_dynamic_memory, __cmd__search_messages,
-    __cmd__trigger_dynamic_memory, __cmd__update_prompt_template,
-    __cmd__validate_template_variables, chat_add_message_attachment, chat_completion,
-    chat_continue, chat_generate_user_reply, chat_regenerate, create_prompt_template,
-    delete_prompt_template, get_app_default_template_id, get_default_character_rules,
-    get_default_system_prompt_template, get_prompt_template, get_required_template_variables,
-    is_app_default_template, list_prompt_templates, render_prompt_preview,
-    reset_app_default_template, reset_dynamic_memory_template, reset_dynamic_summary_template,
-    reset_help_me_reply_conversational_template, reset_help_me_reply_template,
-    retry_dynamic_memory, search_messages, trigger_dynamic_memory, update_prompt_template,
+    __cmd__simulate_dynamic_memory_decay, __cmd__trigger_dynamic_memory,
+    __cmd__update_prompt_template, __cmd__validate_template_variables, chat_add_message_attachment,
+    chat_completion, chat_continue, chat_generate_user_reply, chat_regenerate,
+    create_prompt_template, delete_prompt_template, get_app_default_template_id,
+    get_default_character_rules, get_default_system_prompt_template, get_prompt_template,
+    get_required_template_variables, is_app_default_template, list_prompt_templates,
+    render_prompt_preview, reset_app_default_template, reset_dynamic_memory_template,
+    reset_dynamic_summary_template, reset_help_me_reply_conversational_template,
+    reset_help_me_reply_template, retry_dynamic_memory, search_messages,
+    simulate_dynamic_memory_decay, trigger_dynamic_memory, update_prompt_template,
     validate_template_variables,
 };
+pub(crate) use commands::run_queued_dynamic_memory_cycle;
@@ -379,6 +379,12 @@ fn usage_from_map(map: &Map<String, Value>) -> Option<UsageSummary> {
                     .and_then(|details| take_first(details, &["image_tokens", "imageTokens"]))
             })
     });
+    let draft_details = map
+        .get("completion_tokens_details")
+        .and_then(|v| v.as_object());
+    let draft_tokens = draft_details.and_then(|details| take_first(details, &["draft_tokens"]));
+    let accepted_draft_tokens =
+        draft_details.and_then(|details| take_first(details, &["accepted_draft_tokens"]));
     let total_tokens = take_first(map, &["total_tokens", "totalTokens"]).or_else(|| {
         match (prompt_tokens, completion_tokens) {
             (Some(p), Some(c)) => Some(p + c),
@@ -402,6 +408,8 @@ fn usage_from_map(map: &Map<String, Value>) -> Option<UsageSummary> {
             reasoning_tokens,
             image_tokens,
             finish_reason,
+            draft_tokens,
+            accepted_draft_tokens,
         })
     }
 }
@@ -170,7 +170,7 @@ pub async fn record_usage_if_available(
         cost: None,
         success: true,
         error_message: None,
-        metadata: Default::default(),
+        metadata: usage_info.draft_metadata(),
     };
 
     // Calculate memory and summary token counts only when dynamic memory is active.
@@ -298,7 +298,7 @@ pub fn record_failed_usage(
         cost: None,
         success: false,
         error_message: Some(error_message.to_string()),
-        metadata: Default::default(),
+        metadata: usage_info.draft_metadata(),
     };
 
     log_info(
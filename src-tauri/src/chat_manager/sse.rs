@@ -574,6 +574,8 @@ pub fn usage_from_value(v: &Value) -> Option<UsageSummary> {
             reasoning_tokens,
             image_tokens,
             finish_reason,
+            draft_tokens: None,
+            accepted_draft_tokens: None,
         })
     }
 }
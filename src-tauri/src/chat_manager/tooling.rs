@@ -323,3 +323,192 @@ fn extract_openai_calls(node: &Value, out: &mut Vec<ToolCall>) {
         }
     }
 }
+
+/// Tool calling emulated through a sampling grammar, for local engines without native support.
+pub struct EmulatedTools {
+    /// Appended to the system prompt; describes the tools and the reply format.
+    pub instructions: String,
+    /// GBNF grammar that only admits a tool-call object (or, unless a call is required, plain
+    /// text that doesn't start like one).
+    pub grammar: String,
+}
+
+/// Builds the emulation for OpenAI-style `tools` and `tool_choice` request fields. Returns
+/// `None` when no tool may be called.
+pub fn emulated_tools(
+    tools: &[Value],
+    tool_choice: Option<&Value>,
+) -> Result<Option<EmulatedTools>, String> {
+    use crate::llama_cpp::grammar::{json_string_literal, GbnfBuilder};
+
+    let forced = tool_choice
+        .and_then(|c| c.get("function"))
+        .and_then(|f| f.get("name"))
+        .and_then(|n| n.as_str());
+    let mode = tool_choice.and_then(|c| c.as_str()).unwrap_or("auto");
+    if mode == "none" {
+        return Ok(None);
+    }
+    let required = forced.is_some() || mode == "required";
+
+    let mut builder = GbnfBuilder::new();
+    let mut calls = Vec::new();
+    let mut listing = String::new();
+    for function in tools.iter().filter_map(|tool| tool.get("function")) {
+        let Some(name) = function
+            .get("name")
+            .and_then(|n| n.as_str())
+            .filter(|n| !n.is_empty())
+        else {
+            continue;
+        };
+        if forced.is_some_and(|forced| forced != name) {
+            continue;
+        }
+        let parameters = function
+            .get("parameters")
+            .filter(|p| p.is_object())
+            .cloned()
+            .unwrap_or_else(|| json!({ "type": "object" }));
+        let arguments = builder.add_schema(&format!("{}-arguments", name), &parameters)?;
+        calls.push(builder.add_rule(
+            &format!("call-{}", name),
+            format!(
+                "\"{{\" ws {} ws \":\" ws {} ws \",\" ws {} ws \":\" ws {} \"}}\" ws",
+                json_string_literal("name"),
+                json_string_literal(name),
+                json_string_literal("arguments"),
+                arguments
+            ),
+        ));
+
+        listing.push_str(&format!("- {}", name));
+        if let Some(description) = function.get("description").and_then(|d| d.as_str()) {
+            listing.push_str(&format!(": {}", description.trim()));
+        }
+        listing.push_str(&format!("\n  Arguments schema: {}\n", parameters));
+    }
+    if calls.is_empty() {
+        return Ok(None);
+    }
+
+    let call = builder.add_rule("call", calls.join(" | "));
+    let tool_calls = builder.add_rule(
+        "tool-calls",
+        format!(
+            "\"{{\" ws {} ws \":\" ws \"[\" ws {} (\",\" ws {})* \"]\" ws \"}}\" ws",
+            json_string_literal("tool_calls"),
+            call,
+            call
+        ),
+    );
+    let root = if required {
+        tool_calls
+    } else {
+        let free_text = builder.add_rule("free-text", r#"[^{ \t\n] [^\x00]*"#);
+        format!("{} | {}", tool_calls, free_text)
+    };
+
+    let instructions = format!(
+        "# Tools\nYou can call these tools:\n{}\nTo call tools, reply with only a JSON object of the form {{\"tool_calls\": [{{\"name\": \"<tool name>\", \"arguments\": {{...}}}}]}}. {}",
+        listing,
+        if required {
+            "You must call a tool."
+        } else {
+            "If no tool is needed, reply normally instead."
+        }
+    );
+
+    Ok(Some(EmulatedTools {
+        instructions,
+        grammar: builder.build(&root),
+    }))
+}
+
+/// Converts a reply produced under [`emulated_tools`] into OpenAI-style `tool_calls`. Returns
+/// `None` for plain-text replies.
+pub fn parse_emulated_tool_calls(text: &str) -> Option<Vec<Value>> {
+    let value: Value = serde_json::from_str(text.trim()).ok()?;
+    let calls: Vec<Value> = value
+        .get("tool_calls")?
+        .as_array()?
+        .iter()
+        .filter_map(|call| {
+            let name = call.get("name")?.as_str()?;
+            let arguments = call.get("arguments").cloned().unwrap_or_else(|| json!({}));
+            Some((name, arguments))
+        })
+        .enumerate()
+        .map(|(idx, (name, arguments))| {
+            json!({
+                "id": format!("call_{}", idx + 1),
+                "type": "function",
+                "function": { "name": name, "arguments": arguments.to_string() }
+            })
+        })
+        .collect();
+    (!calls.is_empty()).then_some(calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_tools() -> Vec<Value> {
+        vec![
+            json!({
+                "type": "function",
+                "function": {
+                    "name": "create_memory",
+                    "description": "Store a new memory.",
+                    "parameters": {
+                        "type": "object",
+                        "properties": { "text": { "type": "string" } },
+                        "required": ["text"]
+                    }
+                }
+            }),
+            json!({
+                "type": "function",
+                "function": { "name": "done", "parameters": { "type": "object" } }
+            }),
+        ]
+    }
+
+    #[test]
+    fn emulation_respects_tool_choice() {
+        let tools = memory_tools();
+        assert!(emulated_tools(&tools, Some(&json!("none")))
+            .unwrap()
+            .is_none());
+
+        let auto = emulated_tools(&tools, None).unwrap().unwrap();
+        assert!(auto
+            .grammar
+            .starts_with("root ::= tool-calls | free-text\n"));
+        assert!(auto
+            .instructions
+            .contains("- create_memory: Store a new memory."));
+
+        let forced = json!({ "type": "function", "function": { "name": "done" } });
+        let forced = emulated_tools(&tools, Some(&forced)).unwrap().unwrap();
+        assert!(forced.grammar.starts_with("root ::= tool-calls\n"));
+        assert!(!forced.grammar.contains("create_memory"));
+    }
+
+    #[test]
+    fn parses_emulated_tool_calls_into_openai_shape() {
+        let reply =
+            r#"{"tool_calls": [{"name": "create_memory", "arguments": {"text": "Likes tea"}}]}"#;
+        let calls = parse_emulated_tool_calls(reply).unwrap();
+        assert_eq!(calls[0]["id"], "call_1");
+        assert_eq!(calls[0]["function"]["name"], "create_memory");
+        assert_eq!(calls[0]["function"]["arguments"], r#"{"text":"Likes tea"}"#);
+
+        let payload = json!({ "choices": [{ "message": { "tool_calls": calls } }] });
+        let parsed = parse_tool_calls("llamacpp", &payload);
+        assert_eq!(parsed[0].arguments, json!({ "text": "Likes tea" }));
+
+        assert!(parse_emulated_tool_calls("Sure, I'll remember that.").is_none());
+    }
+}
@@ -200,6 +200,17 @@ pub struct AccessibilitySoundSettings {
 pub enum MemoryRetrievalStrategy {
     Smart,
     Cosine,
+    /// BM25 keyword rank fused with cosine rank (reciprocal rank fusion)
+    Hybrid,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryDecayModel {
+    /// Subtract `decay_rate` every memory cycle
+    PerCycle,
+    /// Halve importance every `decay_half_life_hours` since last access
+    HalfLife,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -221,12 +232,35 @@ pub struct DynamicMemorySettings {
     /// Score reduction per memory cycle (0.05-0.15 recommended)
     #[serde(default = "default_decay_rate")]
     pub decay_rate: f32,
+    #[serde(default = "default_decay_model")]
+    pub decay_model: MemoryDecayModel,
+    /// Half-life model only: hours for an unretrieved memory to lose half its importance
+    #[serde(default = "default_decay_half_life_hours")]
+    pub decay_half_life_hours: f32,
     /// Score below which memories are demoted to cold (0.2-0.4 recommended)
     #[serde(default = "default_cold_threshold")]
     pub cold_threshold: f32,
     /// v2 exclusive: Use last 2 messages for better memory retrieval
     #[serde(default = "default_context_enrichment")]
     pub context_enrichment_enabled: bool,
+    /// Hybrid only: rescore fused candidates with the local cross-encoder, if installed
+    #[serde(default)]
+    pub reranker_enabled: bool,
+    /// Merge near-duplicate memories with the summarisation model each cycle (opt-in; each
+    /// merge is an extra model request)
+    #[serde(default)]
+    pub consolidation_enabled: bool,
+    /// Cosine similarity at which two memories count as near-duplicates (0.75-0.9 recommended)
+    #[serde(default = "default_consolidation_threshold")]
+    pub consolidation_threshold: f32,
+    /// Recall the character's long-term memories (shared across its chats) and let the
+    /// memory manager share session memories into them
+    #[serde(default)]
+    pub character_memory_enabled: bool,
+    /// Extract entities and relations into the session's knowledge graph each cycle and
+    /// inject the part around entities mentioned in the latest turns
+    #[serde(default)]
+    pub knowledge_graph_enabled: bool,
 }
 
 fn default_min_similarity() -> f32 {
@@ -249,6 +283,14 @@ fn default_decay_rate() -> f32 {
     0.1 // Score reduction per memory cycle
 }
 
+fn default_decay_model() -> MemoryDecayModel {
+    MemoryDecayModel::PerCycle
+}
+
+fn default_decay_half_life_hours() -> f32 {
+    72.0 // Three days
+}
+
 fn default_cold_threshold() -> f32 {
     0.4 // Memories below this score are demoted to cold
 }
@@ -257,6 +299,10 @@ fn default_context_enrichment() -> bool {
     true // v2 exclusive: Use last 2 messages for better retrieval
 }
 
+fn default_consolidation_threshold() -> f32 {
+    0.8 // Below the 0.85 create-time duplicate check, so paraphrases still get merged
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AdvancedModelSettings {
@@ -274,6 +320,32 @@ pub struct AdvancedModelSettings {
     pub llama_rope_freq_base: Option<f64>,
     pub llama_rope_freq_scale: Option<f64>,
     pub llama_offload_kqv: Option<bool>,
+    pub llama_min_p: Option<f64>,
+    pub llama_typical_p: Option<f64>,
+    pub llama_mirostat: Option<u32>,
+    pub llama_mirostat_tau: Option<f64>,
+    pub llama_mirostat_eta: Option<f64>,
+    pub llama_repeat_penalty: Option<f64>,
+    pub llama_repeat_last_n: Option<i32>,
+    pub llama_dry_multiplier: Option<f64>,
+    pub llama_dry_base: Option<f64>,
+    pub llama_dry_allowed_length: Option<u32>,
+    pub llama_dry_penalty_last_n: Option<i32>,
+    pub llama_xtc_probability: Option<f64>,
+    pub llama_xtc_threshold: Option<f64>,
+    /// Sampler stage names in application order, e.g. `["penalties", "min_p", "temperature"]`.
+    pub llama_sampler_order: Option<Vec<String>>,
+    /// LoRA adapters applied on top of the base GGUF, in order.
+    pub llama_lora_adapters: Option<Vec<LlamaLoraAdapter>>,
+    /// Disk space for saved llama.cpp context states, in MiB; 0 turns saving off.
+    pub llama_state_budget_mb: Option<u32>,
+    /// GGUF path or library id of a draft model for speculative decoding.
+    pub llama_draft_model: Option<String>,
+    /// Most tokens the draft model proposes per step; 0 turns speculative decoding off.
+    pub llama_draft_max: Option<u32>,
+    /// GGUF path or library id of the multimodal projector (mmproj) that lets the model see
+    /// attached images.
+    pub llama_mmproj_path: Option<String>,
     pub ollama_num_ctx: Option<u32>,
     pub ollama_num_predict: Option<u32>,
     pub ollama_num_keep: Option<u32>,
@@ -298,6 +370,18 @@ pub struct AdvancedModelSettings {
     pub reasoning_budget_tokens: Option<u32>,
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LlamaLoraAdapter {
+    pub path: String,
+    #[serde(default = "default_lora_scale")]
+    pub scale: f64,
+}
+
+fn default_lora_scale() -> f64 {
+    1.0
+}
+
 impl Default for AdvancedModelSettings {
     fn default() -> Self {
         Self {
@@ -315,6 +399,25 @@ impl Default for AdvancedModelSettings {
             llama_rope_freq_base: None,
             llama_rope_freq_scale: None,
             llama_offload_kqv: None,
+            llama_min_p: None,
+            llama_typical_p: None,
+            llama_mirostat: None,
+            llama_mirostat_tau: None,
+            llama_mirostat_eta: None,
+            llama_repeat_penalty: None,
+            llama_repeat_last_n: None,
+            llama_dry_multiplier: None,
+            llama_dry_base: None,
+            llama_dry_allowed_length: None,
+            llama_dry_penalty_last_n: None,
+            llama_xtc_probability: None,
+            llama_xtc_threshold: None,
+            llama_sampler_order: None,
+            llama_lora_adapters: None,
+            llama_state_budget_mb: None,
+            llama_draft_model: None,
+            llama_draft_max: None,
+            llama_mmproj_path: None,
             ollama_num_ctx: None,
             ollama_num_predict: None,
             ollama_num_keep: None,
@@ -337,6 +440,78 @@ impl Default for AdvancedModelSettings {
     }
 }
 
+/// llama.cpp sampler, LoRA, saved state, draft model and projector settings as request body
+/// fields. Each takes its value from the first of `layers` (most specific first) that sets it.
+pub fn llama_request_fields(layers: &[&AdvancedModelSettings]) -> Vec<(&'static str, Value)> {
+    fn first<T: Serialize>(
+        layers: &[&AdvancedModelSettings],
+        pick: impl Fn(&AdvancedModelSettings) -> Option<T>,
+    ) -> Option<Value> {
+        layers
+            .iter()
+            .find_map(|layer| pick(layer))
+            .and_then(|value| serde_json::to_value(value).ok())
+    }
+
+    [
+        ("llamaMinP", first(layers, |s| s.llama_min_p)),
+        ("llamaTypicalP", first(layers, |s| s.llama_typical_p)),
+        ("llamaMirostat", first(layers, |s| s.llama_mirostat)),
+        ("llamaMirostatTau", first(layers, |s| s.llama_mirostat_tau)),
+        ("llamaMirostatEta", first(layers, |s| s.llama_mirostat_eta)),
+        (
+            "llamaRepeatPenalty",
+            first(layers, |s| s.llama_repeat_penalty),
+        ),
+        ("llamaRepeatLastN", first(layers, |s| s.llama_repeat_last_n)),
+        (
+            "llamaDryMultiplier",
+            first(layers, |s| s.llama_dry_multiplier),
+        ),
+        ("llamaDryBase", first(layers, |s| s.llama_dry_base)),
+        (
+            "llamaDryAllowedLength",
+            first(layers, |s| s.llama_dry_allowed_length),
+        ),
+        (
+            "llamaDryPenaltyLastN",
+            first(layers, |s| s.llama_dry_penalty_last_n),
+        ),
+        (
+            "llamaXtcProbability",
+            first(layers, |s| s.llama_xtc_probability),
+        ),
+        (
+            "llamaXtcThreshold",
+            first(layers, |s| s.llama_xtc_threshold),
+        ),
+        (
+            "llamaSamplerOrder",
+            first(layers, |s| s.llama_sampler_order.clone()),
+        ),
+        (
+            "llamaLoraAdapters",
+            first(layers, |s| s.llama_lora_adapters.clone()),
+        ),
+        (
+            "llamaStateBudgetMb",
+            first(layers, |s| s.llama_state_budget_mb),
+        ),
+        (
+            "llamaDraftModel",
+            first(layers, |s| s.llama_draft_model.clone()),
+        ),
+        ("llamaDraftMax", first(layers, |s| s.llama_draft_max)),
+        (
+            "llamaMmprojPath",
+            first(layers, |s| s.llama_mmproj_path.clone()),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some((key, value?)))
+    .collect()
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ImageAttachment {
@@ -406,6 +581,10 @@ pub struct MemoryEmbedding {
     pub id: String,
     pub text: String,
     pub embedding: Vec<f32>,
+    /// Embedding backend id that computed `embedding` (e.g. "v3"); vectors from another
+    /// backend are skipped by retrieval until re-embedded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
     #[serde(default)]
     pub created_at: u64,
     #[serde(default)]
@@ -431,6 +610,78 @@ pub struct MemoryEmbedding {
     /// Category tag for clustering (e.g. character_trait, relationship, plot_event)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
+    /// Ephemeral per-signal scores behind `match_score` (hybrid retrieval only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_breakdown: Option<MemoryScoreBreakdown>,
+    /// Where this memory came from (messages, model, prompt)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<MemoryProvenance>,
+}
+
+impl From<crate::storage_manager::group_sessions::MemoryEmbedding> for MemoryEmbedding {
+    fn from(stored: crate::storage_manager::group_sessions::MemoryEmbedding) -> Self {
+        Self {
+            id: stored.id,
+            text: stored.text,
+            embedding: stored.embedding,
+            embedding_model: stored.embedding_model,
+            created_at: stored.created_at.max(0) as u64,
+            token_count: stored.token_count.max(0) as u32,
+            is_cold: stored.is_cold,
+            last_accessed_at: stored.last_accessed_at.max(0) as u64,
+            importance_score: stored.importance_score,
+            is_pinned: stored.is_pinned,
+            access_count: stored.access_count.max(0) as u32,
+            match_score: stored.match_score,
+            category: stored.category,
+            score_breakdown: stored.score_breakdown,
+            provenance: stored.provenance,
+        }
+    }
+}
+
+/// What produced a memory. `origin` is "memory_cycle", "user", "merge" or "shared";
+/// the message range is inclusive and refers to the session the memory was derived in.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryProvenance {
+    pub origin: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_template_id: Option<String>,
+    /// Merge only: ids of the memories combined into this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_from: Vec<String>,
+    /// Shared only: the session memory this character memory was copied from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_memory_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_session_id: Option<String>,
+}
+
+/// How a hybrid retrieval hit was scored. Ranks are 1-based; `None` means the memory
+/// did not appear in that ranking.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryScoreBreakdown {
+    pub cosine: Option<f32>,
+    pub cosine_rank: Option<usize>,
+    pub bm25: Option<f32>,
+    pub bm25_rank: Option<usize>,
+    pub fused: f32,
+    pub rerank: Option<f32>,
+}
+
+impl MemoryScoreBreakdown {
+    /// The score surfaced as `match_score`: the reranker's when it ran, else the fused one.
+    pub fn final_score(&self) -> f32 {
+        self.rerank.unwrap_or(self.fused)
+    }
 }
 
 fn default_importance_score() -> f32 {
@@ -567,6 +818,30 @@ pub struct UsageSummary {
     pub image_tokens: Option<u64>,
     #[serde(default)]
     pub finish_reason: Option<String>,
+    /// Tokens proposed by a draft model during speculative decoding (local llama.cpp only).
+    #[serde(default)]
+    pub draft_tokens: Option<u64>,
+    /// Drafted tokens the main model accepted.
+    #[serde(default)]
+    pub accepted_draft_tokens: Option<u64>,
+}
+
+impl UsageSummary {
+    /// Speculative decoding statistics for a usage record's metadata.
+    pub fn draft_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        if let (Some(drafted), Some(accepted)) = (self.draft_tokens, self.accepted_draft_tokens) {
+            metadata.insert("draftTokens".to_string(), drafted.to_string());
+            metadata.insert("acceptedDraftTokens".to_string(), accepted.to_string());
+            if drafted > 0 {
+                metadata.insert(
+                    "draftAcceptanceRate".to_string(),
+                    format!("{:.3}", accepted as f64 / drafted as f64),
+                );
+            }
+        }
+        metadata
+    }
 }
 
 #[derive(Serialize)]
@@ -701,6 +976,10 @@ pub enum NormalizedEvent {
     Usage { usage: UsageSummary },
     #[serde(rename = "toolCall")]
     ToolCall { calls: Vec<ToolCall> },
+    /// A local model ran out of context and dropped `discarded` of its oldest tokens, keeping
+    /// the first `pinned` (the system prompt).
+    #[serde(rename = "contextShift")]
+    ContextShift { discarded: u32, pinned: u32 },
     #[serde(rename = "done")]
     Done,
     #[serde(rename = "error")]
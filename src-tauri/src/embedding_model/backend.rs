@@ -0,0 +1,379 @@
+use rusqlite::{params, OptionalExtension};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use super::custom::{find_custom_model, CustomEmbeddingModel};
+use super::settings::{read_embedding_preferences, EmbeddingPreferences};
+use crate::storage_manager::db::open_db;
+
+const REMOTE_EMBEDDING_TIMEOUT_SECS: u64 = 60;
+/// Inputs per remote request; OpenAI accepts up to 2048, local servers are usually slower.
+const REMOTE_EMBEDDING_BATCH_SIZE: usize = 64;
+const DIMENSION_META_PREFIX: &str = "embedding_dimension:";
+
+/// An OpenAI-compatible embeddings endpoint: OpenAI itself, or a local Ollama / llama.cpp
+/// server.
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEmbeddingConfig {
+    pub base_url: String,
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Requested output size, for models that can shorten their vectors (text-embedding-3-*).
+    #[serde(default)]
+    pub dimensions: Option<usize>,
+}
+
+/// Where embeddings come from. Vectors from different backends (or different models of one
+/// backend) live in different spaces and can't be compared.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EmbeddingBackend {
+    /// The bundled lettuce-emb ONNX models, run on-device.
+    Onnx,
+    Remote(RemoteEmbeddingConfig),
+    /// A user-supplied ONNX model that passed validation.
+    Custom(CustomEmbeddingModel),
+}
+
+impl EmbeddingBackend {
+    pub(crate) fn from_preferences(prefs: &EmbeddingPreferences) -> Self {
+        match (prefs.backend.as_deref(), &prefs.remote) {
+            (Some("remote"), Some(remote))
+                if !remote.base_url.trim().is_empty() && !remote.model.trim().is_empty() =>
+            {
+                EmbeddingBackend::Remote(remote.clone())
+            }
+            _ => EmbeddingBackend::Onnx,
+        }
+    }
+
+    /// Falls back to the bundled models when the selected custom model is gone or no longer
+    /// validated.
+    pub(crate) fn configured(app: &AppHandle) -> Self {
+        let prefs = read_embedding_preferences(app);
+        if prefs.backend.as_deref() == Some("custom") {
+            let custom = prefs
+                .custom_model_id
+                .as_deref()
+                .and_then(|id| find_custom_model(app, id))
+                .filter(|model| model.validated_at.is_some());
+            if let Some(model) = custom {
+                return EmbeddingBackend::Custom(model);
+            }
+        }
+        Self::from_preferences(&prefs)
+    }
+
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            EmbeddingBackend::Onnx => "onnx",
+            EmbeddingBackend::Remote(_) => "remote",
+            EmbeddingBackend::Custom(_) => "custom",
+        }
+    }
+
+    /// Names the vector space of this backend: the ONNX source version ("v3"), the remote
+    /// model and endpoint, or the custom model and its settings. `None` when the ONNX backend
+    /// has no model installed.
+    pub(crate) fn id(&self, app: &AppHandle) -> Option<String> {
+        match self {
+            EmbeddingBackend::Onnx => {
+                super::installed_onnx_source(app).map(|v| v.as_str().to_string())
+            }
+            EmbeddingBackend::Remote(config) => Some(remote_backend_id(config)),
+            EmbeddingBackend::Custom(model) => Some(model.backend_id()),
+        }
+    }
+
+    /// Key for the embedding cache. The ONNX id leaves out the sequence length the vectors
+    /// were computed with, so it is added here: long texts truncate differently under another
+    /// max tokens setting.
+    pub(crate) fn cache_key(&self, app: &AppHandle) -> Option<String> {
+        match self {
+            EmbeddingBackend::Onnx => super::installed_onnx_source(app).map(|source| {
+                format!(
+                    "{}@{}",
+                    source.as_str(),
+                    super::onnx_max_seq_length(app, source)
+                )
+            }),
+            _ => self.id(app),
+        }
+    }
+
+    pub(crate) async fn embed(&self, app: &AppHandle, text: String) -> Result<Vec<f32>, String> {
+        match self {
+            EmbeddingBackend::Onnx => super::inference::compute_embedding(app.clone(), text).await,
+            EmbeddingBackend::Remote(config) => embed_remote(config, &text).await,
+            EmbeddingBackend::Custom(model) => {
+                super::inference::compute_custom_embeddings(app.clone(), model, vec![text])
+                    .await?
+                    .pop()
+                    .ok_or_else(|| {
+                        crate::utils::err_msg(module_path!(), line!(), "No embedding produced")
+                    })
+            }
+        }
+    }
+
+    /// Embeds several texts in as few model runs / requests as the backend allows.
+    pub(crate) async fn embed_batch(
+        &self,
+        app: &AppHandle,
+        texts: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, String> {
+        match self {
+            EmbeddingBackend::Onnx => {
+                super::inference::compute_embeddings(app.clone(), texts).await
+            }
+            EmbeddingBackend::Remote(config) => {
+                let mut embeddings = Vec::with_capacity(texts.len());
+                for chunk in texts.chunks(REMOTE_EMBEDDING_BATCH_SIZE) {
+                    embeddings.extend(embed_remote_batch(config, chunk).await?);
+                }
+                Ok(embeddings)
+            }
+            EmbeddingBackend::Custom(model) => {
+                super::inference::compute_custom_embeddings(app.clone(), model, texts).await
+            }
+        }
+    }
+}
+
+fn normalized_base_url(base_url: &str) -> &str {
+    base_url.trim().trim_end_matches('/')
+}
+
+fn remote_backend_id(config: &RemoteEmbeddingConfig) -> String {
+    let mut id = format!(
+        "remote:{}@{}",
+        config.model.trim(),
+        normalized_base_url(&config.base_url)
+    );
+    if let Some(dimensions) = config.dimensions {
+        id.push_str(&format!(":{}", dimensions));
+    }
+    id
+}
+
+/// Accepts a server root (`http://localhost:11434`), an API root (`.../v1`) or the full
+/// embeddings URL.
+fn remote_embeddings_url(base_url: &str) -> String {
+    let base = normalized_base_url(base_url);
+    if base.ends_with("/embeddings") {
+        base.to_string()
+    } else if base.ends_with("/v1") {
+        format!("{}/embeddings", base)
+    } else {
+        format!("{}/v1/embeddings", base)
+    }
+}
+
+fn remote_request_body(config: &RemoteEmbeddingConfig, input: Value) -> Value {
+    let mut body = json!({
+        "model": config.model.trim(),
+        "input": input,
+        "encoding_format": "float",
+    });
+    if let Some(dimensions) = config.dimensions {
+        body["dimensions"] = json!(dimensions);
+    }
+    body
+}
+
+fn parse_vector(vector: &Value) -> Result<Vec<f32>, String> {
+    let values = vector.as_array().ok_or_else(|| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Embedding response contains a non-array vector",
+        )
+    })?;
+    let embedding: Vec<f32> = values
+        .iter()
+        .filter_map(Value::as_f64)
+        .map(|v| v as f32)
+        .collect();
+    if embedding.is_empty() || embedding.len() != values.len() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Embedding response contains an empty or non-numeric vector",
+        ));
+    }
+    Ok(embedding)
+}
+
+/// Reads the vectors, in input order, from an OpenAI-style `data[].embedding` response, or
+/// from the `embeddings` / `embedding` shapes some local servers return.
+fn parse_remote_embeddings(response: &Value) -> Result<Vec<Vec<f32>>, String> {
+    let vectors: Vec<&Value> = if let Some(data) = response.get("data").and_then(Value::as_array) {
+        let mut items: Vec<&Value> = data.iter().collect();
+        items.sort_by_key(|item| item.get("index").and_then(Value::as_u64).unwrap_or(0));
+        items
+            .into_iter()
+            .filter_map(|item| item.get("embedding"))
+            .collect()
+    } else if let Some(embeddings) = response.get("embeddings").and_then(Value::as_array) {
+        embeddings.iter().collect()
+    } else {
+        response.get("embedding").into_iter().collect()
+    };
+    if vectors.is_empty() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Embedding response has no embedding vector",
+        ));
+    }
+    vectors.into_iter().map(parse_vector).collect()
+}
+
+pub(crate) async fn embed_remote(
+    config: &RemoteEmbeddingConfig,
+    text: &str,
+) -> Result<Vec<f32>, String> {
+    embed_remote_batch(config, &[text.to_string()])
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            crate::utils::err_msg(module_path!(), line!(), "Embedding response is empty")
+        })
+}
+
+async fn embed_remote_batch(
+    config: &RemoteEmbeddingConfig,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REMOTE_EMBEDDING_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    let input = match texts {
+        [text] => json!(text),
+        _ => json!(texts),
+    };
+    let mut request = client
+        .post(remote_embeddings_url(&config.base_url))
+        .json(&remote_request_body(config, input));
+    if let Some(api_key) = config.api_key.as_deref().map(str::trim) {
+        if !api_key.is_empty() {
+            request = request.bearer_auth(api_key);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Embedding endpoint returned {}: {}", status, body),
+        ));
+    }
+
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let embeddings = parse_remote_embeddings(&json)?;
+    if embeddings.len() != texts.len() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!(
+                "Embedding endpoint returned {} vectors for {} inputs",
+                embeddings.len(),
+                texts.len()
+            ),
+        ));
+    }
+    Ok(embeddings)
+}
+
+/// Last vector size seen from the backend `backend_id`.
+pub(crate) fn backend_dimension(app: &AppHandle, backend_id: &str) -> Option<usize> {
+    let conn = open_db(app).ok()?;
+    conn.query_row(
+        "SELECT value FROM meta WHERE key = ?1",
+        params![format!("{}{}", DIMENSION_META_PREFIX, backend_id)],
+        |r| r.get::<_, String>(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .and_then(|value| value.parse().ok())
+}
+
+pub(crate) fn record_backend_dimension(
+    app: &AppHandle,
+    backend_id: &str,
+    dimension: usize,
+) -> Result<(), String> {
+    if backend_dimension(app, backend_id) == Some(dimension) {
+        return Ok(());
+    }
+    let conn = open_db(app)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![
+            format!("{}{}", DIMENSION_META_PREFIX, backend_id),
+            dimension.to_string()
+        ],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_urls_and_responses() {
+        assert_eq!(
+            remote_embeddings_url("http://localhost:11434/"),
+            "http://localhost:11434/v1/embeddings"
+        );
+        assert_eq!(
+            remote_embeddings_url("https://api.openai.com/v1"),
+            "https://api.openai.com/v1/embeddings"
+        );
+        assert_eq!(
+            remote_embeddings_url("http://127.0.0.1:8080/v1/embeddings"),
+            "http://127.0.0.1:8080/v1/embeddings"
+        );
+
+        let config = RemoteEmbeddingConfig {
+            base_url: "http://localhost:11434/".to_string(),
+            model: "nomic-embed-text".to_string(),
+            api_key: None,
+            dimensions: Some(256),
+        };
+        assert_eq!(
+            remote_backend_id(&config),
+            "remote:nomic-embed-text@http://localhost:11434:256"
+        );
+        assert_eq!(remote_request_body(&config, json!("hi"))["dimensions"], 256);
+
+        let openai = json!({ "data": [
+            { "index": 1, "embedding": [2.0] },
+            { "index": 0, "embedding": [0.5, -1.0] },
+        ] });
+        assert_eq!(
+            parse_remote_embeddings(&openai).unwrap(),
+            vec![vec![0.5, -1.0], vec![2.0]]
+        );
+        let ollama = json!({ "embeddings": [[1.0, 2.0, 3.0]] });
+        assert_eq!(parse_remote_embeddings(&ollama).unwrap()[0].len(), 3);
+        assert!(parse_remote_embeddings(&json!({ "data": [] })).is_err());
+        assert!(parse_remote_embeddings(&json!({ "embedding": ["x"] })).is_err());
+    }
+}
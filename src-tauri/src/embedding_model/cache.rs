@@ -0,0 +1,142 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::AppHandle;
+
+use crate::storage_manager::db::{now_ms, open_db};
+use crate::storage_manager::memories::{blob_to_embedding, embedding_to_blob};
+
+/// Oldest-used entries beyond this are dropped after each write.
+const EMBEDDING_CACHE_MAX_ENTRIES: i64 = 50_000;
+
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingCacheStats {
+    pub entries: u64,
+    pub size_bytes: u64,
+    pub models: Vec<String>,
+    /// Lookups since app start.
+    pub hits: u64,
+    pub misses: u64,
+}
+
+fn text_hash(text: &str) -> String {
+    blake3::hash(text.as_bytes()).to_hex().to_string()
+}
+
+fn lookup(conn: &Connection, hash: &str, model: &str) -> Result<Option<Vec<f32>>, String> {
+    let blob: Option<Vec<u8>> = conn
+        .query_row(
+            "SELECT embedding FROM embedding_cache WHERE text_hash = ?1 AND model = ?2",
+            params![hash, model],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if blob.is_some() {
+        conn.execute(
+            "UPDATE embedding_cache SET last_used_at = ?1 WHERE text_hash = ?2 AND model = ?3",
+            params![now_ms() as i64, hash, model],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    Ok(blob.map(|blob| blob_to_embedding(&blob)))
+}
+
+/// Cached vectors for `texts` computed by the backend `model`, in input order; `None` marks a
+/// miss.
+pub(crate) fn cached_embeddings(
+    app: &AppHandle,
+    model: &str,
+    texts: &[String],
+) -> Result<Vec<Option<Vec<f32>>>, String> {
+    let conn = open_db(app)?;
+    let cached = texts
+        .iter()
+        .map(|text| lookup(&conn, &text_hash(text), model))
+        .collect::<Result<Vec<_>, _>>()?;
+    let hits = cached.iter().filter(|entry| entry.is_some()).count() as u64;
+    CACHE_HITS.fetch_add(hits, Ordering::Relaxed);
+    CACHE_MISSES.fetch_add(texts.len() as u64 - hits, Ordering::Relaxed);
+    Ok(cached)
+}
+
+pub(crate) fn store_embeddings(
+    app: &AppHandle,
+    model: &str,
+    entries: &[(&str, &[f32])],
+) -> Result<(), String> {
+    let mut conn = open_db(app)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let now = now_ms() as i64;
+    for (text, embedding) in entries {
+        tx.execute(
+            "INSERT OR REPLACE INTO embedding_cache
+               (text_hash, model, embedding, dim, created_at, last_used_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![
+                text_hash(text),
+                model,
+                embedding_to_blob(embedding),
+                embedding.len() as i64,
+                now
+            ],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    tx.execute(
+        "DELETE FROM embedding_cache WHERE rowid IN (
+           SELECT rowid FROM embedding_cache ORDER BY last_used_at DESC LIMIT -1 OFFSET ?1
+         )",
+        params![EMBEDDING_CACHE_MAX_ENTRIES],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    tx.commit()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn cache_models(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT model FROM embedding_cache ORDER BY model")
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let models = stmt
+        .query_map([], |r| r.get(0))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(models)
+}
+
+pub(crate) fn cache_stats(app: &AppHandle) -> Result<EmbeddingCacheStats, String> {
+    let conn = open_db(app)?;
+    let (entries, size_bytes): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(embedding)), 0) FROM embedding_cache",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(EmbeddingCacheStats {
+        entries: entries as u64,
+        size_bytes: size_bytes as u64,
+        models: cache_models(&conn)?,
+        hits: CACHE_HITS.load(Ordering::Relaxed),
+        misses: CACHE_MISSES.load(Ordering::Relaxed),
+    })
+}
+
+/// Drops every cached vector and returns how many there were.
+pub(crate) fn clear_cache(app: &AppHandle) -> Result<u64, String> {
+    let conn = open_db(app)?;
+    let removed = conn
+        .execute("DELETE FROM embedding_cache", [])
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    CACHE_HITS.store(0, Ordering::Relaxed);
+    CACHE_MISSES.store(0, Ordering::Relaxed);
+    Ok(removed as u64)
+}
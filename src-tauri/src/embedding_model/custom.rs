@@ -0,0 +1,498 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tokio::time::{timeout, Duration};
+
+use super::inference::{compute_embeddings_with_session, create_runtime, OutputSpec};
+use super::tests::SIMILARITY_TEST_CASES;
+use super::util::cosine_similarity;
+use super::{embedding_model_dir, EMBEDDING_TEST_TIMEOUT_SECS};
+use crate::storage_manager::db::now_ms;
+use crate::utils::log_info;
+
+const CUSTOM_MODELS_FILE: &str = "custom-models.json";
+const CUSTOM_MAX_SEQ_LENGTH: usize = 32_768;
+const CUSTOM_MAX_DIMENSION: usize = 8192;
+/// Similar pairs must score this much higher than unrelated ones on average.
+const SEMANTIC_MARGIN: f32 = 0.05;
+/// Batched and one-by-one vectors of the same text must agree to this cosine.
+const BATCH_CONSISTENCY_MIN: f32 = 0.999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Pooling {
+    #[default]
+    Mean,
+    Cls,
+    LastToken,
+}
+
+impl Pooling {
+    fn as_str(self) -> &'static str {
+        match self {
+            Pooling::Mean => "mean",
+            Pooling::Cls => "cls",
+            Pooling::LastToken => "lastToken",
+        }
+    }
+}
+
+fn default_model_file() -> String {
+    "model.onnx".to_string()
+}
+
+fn default_tokenizer_file() -> String {
+    "tokenizer.json".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomEmbeddingModelConfig {
+    pub name: String,
+    /// Directory holding the ONNX model and its tokenizer.json.
+    pub dir: String,
+    #[serde(default = "default_model_file")]
+    pub model_file: String,
+    #[serde(default = "default_tokenizer_file")]
+    pub tokenizer_file: String,
+    /// Output tensor to read; the model's first output when unset.
+    #[serde(default)]
+    pub output_name: Option<String>,
+    #[serde(default)]
+    pub pooling: Pooling,
+    #[serde(default)]
+    pub normalize: bool,
+    pub max_seq_length: usize,
+    pub dimension: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomModelCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomModelValidation {
+    pub passed: bool,
+    pub checks: Vec<CustomModelCheck>,
+    pub checked_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomEmbeddingModel {
+    pub id: String,
+    #[serde(flatten)]
+    pub config: CustomEmbeddingModelConfig,
+    pub created_at: i64,
+    /// Set when the last validation passed. Only validated models can be the active backend;
+    /// changing the config clears it.
+    #[serde(default)]
+    pub validated_at: Option<i64>,
+    #[serde(default)]
+    pub validation: Option<CustomModelValidation>,
+}
+
+impl CustomEmbeddingModel {
+    pub(crate) fn model_path(&self) -> PathBuf {
+        Path::new(&self.config.dir).join(&self.config.model_file)
+    }
+
+    pub(crate) fn tokenizer_path(&self) -> PathBuf {
+        Path::new(&self.config.dir).join(&self.config.tokenizer_file)
+    }
+
+    pub(crate) fn output_spec(&self) -> OutputSpec {
+        OutputSpec {
+            name: self.config.output_name.clone(),
+            pooling: Some(self.config.pooling),
+            normalize: self.config.normalize,
+            dimension: self.config.dimension,
+        }
+    }
+
+    /// Changes whenever a setting that affects the vectors changes, or the model or tokenizer
+    /// file is replaced, so stored memories are re-embedded.
+    pub(crate) fn backend_id(&self) -> String {
+        let fingerprint = format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.config.dir,
+            self.config.model_file,
+            file_stamp(&self.model_path()),
+            self.config.tokenizer_file,
+            file_stamp(&self.tokenizer_path()),
+            self.config.output_name.as_deref().unwrap_or(""),
+            self.config.pooling.as_str(),
+            self.config.normalize,
+            self.config.max_seq_length,
+            self.config.dimension
+        );
+        let hash = blake3::hash(fingerprint.as_bytes()).to_hex();
+        format!("custom:{}:{}", self.id, &hash[..8])
+    }
+}
+
+/// Size and modification time of `path`, enough to notice a file swapped in place.
+fn file_stamp(path: &Path) -> String {
+    let Ok(meta) = fs::metadata(path) else {
+        return String::new();
+    };
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    format!("{}@{}", meta.len(), modified)
+}
+
+fn registry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(embedding_model_dir(app)?.join(CUSTOM_MODELS_FILE))
+}
+
+pub(crate) fn list_custom_models(app: &AppHandle) -> Result<Vec<CustomEmbeddingModel>, String> {
+    let path = registry_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    serde_json::from_str(&raw).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+fn save_custom_models(app: &AppHandle, models: &[CustomEmbeddingModel]) -> Result<(), String> {
+    let path = registry_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    let json = serde_json::to_string_pretty(models)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    fs::write(&path, json).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+pub(crate) fn find_custom_model(app: &AppHandle, id: &str) -> Option<CustomEmbeddingModel> {
+    list_custom_models(app)
+        .ok()?
+        .into_iter()
+        .find(|model| model.id == id)
+}
+
+fn normalize_config(
+    mut config: CustomEmbeddingModelConfig,
+) -> Result<CustomEmbeddingModelConfig, String> {
+    config.name = config.name.trim().to_string();
+    config.output_name = config
+        .output_name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    let invalid = |msg: String| Err(crate::utils::err_msg(module_path!(), line!(), msg));
+
+    if config.name.is_empty() {
+        return invalid("Custom model name is required".to_string());
+    }
+    if !(1..=CUSTOM_MAX_SEQ_LENGTH).contains(&config.max_seq_length) {
+        return invalid(format!(
+            "Max sequence length must be between 1 and {}",
+            CUSTOM_MAX_SEQ_LENGTH
+        ));
+    }
+    if !(1..=CUSTOM_MAX_DIMENSION).contains(&config.dimension) {
+        return invalid(format!(
+            "Dimension must be between 1 and {}",
+            CUSTOM_MAX_DIMENSION
+        ));
+    }
+    let dir = Path::new(&config.dir);
+    if !dir.is_dir() {
+        return invalid(format!("Model directory not found: {}", config.dir));
+    }
+    for file in [&config.model_file, &config.tokenizer_file] {
+        if !dir.join(file).is_file() {
+            return invalid(format!("{} not found in {}", file, config.dir));
+        }
+    }
+    Ok(config)
+}
+
+pub(crate) fn register_custom_model(
+    app: &AppHandle,
+    config: CustomEmbeddingModelConfig,
+) -> Result<CustomEmbeddingModel, String> {
+    let model = CustomEmbeddingModel {
+        id: uuid::Uuid::new_v4().to_string(),
+        config: normalize_config(config)?,
+        created_at: now_ms() as i64,
+        validated_at: None,
+        validation: None,
+    };
+    let mut models = list_custom_models(app)?;
+    models.push(model.clone());
+    save_custom_models(app, &models)?;
+    log_info(
+        app,
+        "embedding_custom",
+        format!(
+            "registered custom model id={} dir={}",
+            model.id, model.config.dir
+        ),
+    );
+    Ok(model)
+}
+
+pub(crate) fn update_custom_model(
+    app: &AppHandle,
+    id: &str,
+    config: CustomEmbeddingModelConfig,
+) -> Result<CustomEmbeddingModel, String> {
+    let config = normalize_config(config)?;
+    let mut models = list_custom_models(app)?;
+    let model = models
+        .iter_mut()
+        .find(|model| model.id == id)
+        .ok_or_else(|| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Custom model {} not found", id),
+            )
+        })?;
+    if model.config != config {
+        model.config = config;
+        model.validated_at = None;
+        model.validation = None;
+    }
+    let updated = model.clone();
+    save_custom_models(app, &models)?;
+    Ok(updated)
+}
+
+pub(crate) fn delete_custom_model(app: &AppHandle, id: &str) -> Result<(), String> {
+    let mut models = list_custom_models(app)?;
+    models.retain(|model| model.id != id);
+    save_custom_models(app, &models)
+}
+
+fn check(name: &str, passed: bool, detail: String) -> CustomModelCheck {
+    CustomModelCheck {
+        name: name.to_string(),
+        passed,
+        detail,
+    }
+}
+
+/// The embedding self-test, adapted to models of unknown scale: the model must load, produce
+/// finite vectors of the configured size, agree between batched and single runs, and rank
+/// similar pairs above unrelated ones.
+fn run_validation_checks(model: &CustomEmbeddingModel) -> Vec<CustomModelCheck> {
+    let (mut session, tokenizer) =
+        match create_runtime(&model.model_path(), &model.tokenizer_path()) {
+            Ok(runtime) => runtime,
+            Err(err) => return vec![check("load", false, err)],
+        };
+    let mut checks = vec![check(
+        "load",
+        true,
+        "model and tokenizer loaded".to_string(),
+    )];
+
+    let spec = model.output_spec();
+    let max_seq_length = model.config.max_seq_length;
+    let texts: Vec<&str> = SIMILARITY_TEST_CASES
+        .iter()
+        .flat_map(|(_, text_a, text_b, ..)| [*text_a, *text_b])
+        .collect();
+    let batched = match compute_embeddings_with_session(
+        &mut session,
+        &tokenizer,
+        &texts,
+        max_seq_length,
+        &spec,
+    ) {
+        Ok(embeddings) => embeddings,
+        Err(err) => {
+            checks.push(check("output", false, err));
+            return checks;
+        }
+    };
+    checks.push(check(
+        "output",
+        true,
+        format!("{} dimensions from {} texts", spec.dimension, texts.len()),
+    ));
+
+    let degenerate = batched
+        .iter()
+        .filter(|embedding| {
+            embedding.iter().any(|v| !v.is_finite()) || embedding.iter().all(|v| *v == 0.0)
+        })
+        .count();
+    checks.push(check(
+        "finite",
+        degenerate == 0,
+        format!(
+            "{} of {} vectors are NaN, infinite or zero",
+            degenerate,
+            batched.len()
+        ),
+    ));
+
+    let mut worst_agreement = 1.0f32;
+    for (text, batched_embedding) in texts.iter().zip(&batched).take(4) {
+        match compute_embeddings_with_session(
+            &mut session,
+            &tokenizer,
+            &[*text],
+            max_seq_length,
+            &spec,
+        ) {
+            Ok(single) => {
+                worst_agreement =
+                    worst_agreement.min(cosine_similarity(&single[0], batched_embedding));
+            }
+            Err(err) => {
+                checks.push(check("batchConsistency", false, err));
+                return checks;
+            }
+        }
+    }
+    checks.push(check(
+        "batchConsistency",
+        worst_agreement >= BATCH_CONSISTENCY_MIN,
+        format!("lowest batched/single cosine {:.4}", worst_agreement),
+    ));
+
+    let (mut similar, mut dissimilar) = (Vec::new(), Vec::new());
+    for (idx, (_, _, _, category, ..)) in SIMILARITY_TEST_CASES.iter().enumerate() {
+        let score = cosine_similarity(&batched[idx * 2], &batched[idx * 2 + 1]);
+        if *category == "dissimilar" {
+            dissimilar.push(score);
+        } else {
+            similar.push(score);
+        }
+    }
+    let mean = |scores: &[f32]| scores.iter().sum::<f32>() / scores.len().max(1) as f32;
+    let (similar_mean, dissimilar_mean) = (mean(&similar), mean(&dissimilar));
+    checks.push(check(
+        "semantic",
+        similar_mean >= dissimilar_mean + SEMANTIC_MARGIN,
+        format!(
+            "similar pairs {:.3} vs unrelated pairs {:.3}",
+            similar_mean, dissimilar_mean
+        ),
+    ));
+    checks
+}
+
+/// Runs the validation checks for a registered model and stores the result. A passing run
+/// marks the model as activatable.
+pub(crate) async fn validate_custom_model(
+    app: &AppHandle,
+    id: &str,
+) -> Result<CustomEmbeddingModel, String> {
+    let model = find_custom_model(app, id).ok_or_else(|| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Custom model {} not found", id),
+        )
+    })?;
+    super::ort_runtime::ensure_ort_init(app).await?;
+
+    let model_for_checks = model.clone();
+    let checks = timeout(
+        Duration::from_secs(EMBEDDING_TEST_TIMEOUT_SECS),
+        tokio::task::spawn_blocking(move || run_validation_checks(&model_for_checks)),
+    )
+    .await
+    .map_err(|_| "Custom model validation timed out".to_string())?
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    let now = now_ms() as i64;
+    let passed = checks.iter().all(|check| check.passed);
+    let mut models = list_custom_models(app)?;
+    let stored = models
+        .iter_mut()
+        .find(|stored| stored.id == id && stored.config == model.config)
+        .ok_or_else(|| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "Custom model changed during validation",
+            )
+        })?;
+    stored.validated_at = passed.then_some(now);
+    stored.validation = Some(CustomModelValidation {
+        passed,
+        checks,
+        checked_at: now,
+    });
+    let validated = stored.clone();
+    save_custom_models(app, &models)?;
+    log_info(
+        app,
+        "embedding_custom",
+        format!("validated custom model id={} passed={}", id, passed),
+    );
+    Ok(validated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_defaults_and_backend_id() {
+        let model: CustomEmbeddingModel = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "name": "bge-small",
+            "dir": "/models/bge",
+            "maxSeqLength": 512,
+            "dimension": 384,
+            "createdAt": 0,
+        }))
+        .unwrap();
+        assert_eq!(model.config.model_file, "model.onnx");
+        assert_eq!(model.config.pooling, Pooling::Mean);
+        assert_eq!(model.validated_at, None);
+        assert_eq!(model.model_path(), Path::new("/models/bge/model.onnx"));
+
+        let id = model.backend_id();
+        assert!(id.starts_with("custom:m1:"));
+        let mut cls = model.clone();
+        cls.config.pooling = Pooling::Cls;
+        assert_ne!(cls.backend_id(), id);
+        let mut renamed = model.clone();
+        renamed.config.name = "other".to_string();
+        assert_eq!(renamed.backend_id(), id);
+        let mut moved = model.clone();
+        moved.config.dir = "/models/bge-v2".to_string();
+        assert_ne!(moved.backend_id(), id);
+    }
+
+    #[test]
+    fn backend_id_follows_replaced_files() {
+        let dir = std::env::temp_dir().join(format!("custom-emb-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let model: CustomEmbeddingModel = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "name": "bge-small",
+            "dir": dir.to_string_lossy(),
+            "maxSeqLength": 512,
+            "dimension": 384,
+            "createdAt": 0,
+        }))
+        .unwrap();
+        fs::write(model.model_path(), b"weights").unwrap();
+        let id = model.backend_id();
+        assert_eq!(model.backend_id(), id);
+        fs::write(model.model_path(), b"other weights").unwrap();
+        assert_ne!(model.backend_id(), id);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,18 @@
+use super::manifest::{
+    check_file, fetch_remote_manifest, hash_file_into, hex_digest, manifest_path,
+    read_installed_manifest, write_installed_manifest, InstalledManifest, ManifestEntry,
+    ModelVerification,
+};
 use super::*;
 use crate::chat_manager::prompts;
+use crate::transport::download::{part_path, remove_part_files, DownloadError, ResumableDownload};
 use crate::utils::{log_error, log_info, log_warn};
-use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::Emitter;
-use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex as TokioMutex;
 
 pub async fn reset_download_state() {
@@ -23,6 +29,14 @@ pub async fn reset_download_state() {
     };
 }
 
+fn all_local_model_files() -> Vec<&'static str> {
+    let mut all_files = MODEL_FILES_V1.to_vec();
+    all_files.extend(MODEL_FILES_V2_LOCAL.iter().copied());
+    all_files.extend(MODEL_FILES_V2_LOCAL_LEGACY.iter().copied());
+    all_files.extend(MODEL_FILES_V3_LOCAL.iter().copied());
+    all_files
+}
+
 fn cleanup_partial_files(
     model_dir: &Path,
     version: Option<&EmbeddingModelVersion>,
@@ -35,13 +49,7 @@ fn cleanup_partial_files(
             v
         }
         Some(EmbeddingModelVersion::V3) => MODEL_FILES_V3_LOCAL.to_vec(),
-        None => {
-            let mut all_files = MODEL_FILES_V1.to_vec();
-            all_files.extend(MODEL_FILES_V2_LOCAL.iter().copied());
-            all_files.extend(MODEL_FILES_V2_LOCAL_LEGACY.iter().copied());
-            all_files.extend(MODEL_FILES_V3_LOCAL.iter().copied());
-            all_files
-        }
+        None => all_local_model_files(),
     };
 
     for filename in files.iter() {
@@ -56,6 +64,16 @@ fn cleanup_partial_files(
             })?;
         }
     }
+
+    let sources: &[&str] = match version {
+        Some(EmbeddingModelVersion::V1) => &["v1"],
+        Some(EmbeddingModelVersion::V2) => &["v2"],
+        Some(EmbeddingModelVersion::V3) => &["v3"],
+        None => &["v1", "v2", "v3"],
+    };
+    for source in sources {
+        let _ = fs::remove_file(manifest_path(model_dir, source));
+    }
     Ok(())
 }
 
@@ -99,100 +117,140 @@ fn log_model_file_status(app: &AppHandle, component: &str, model_dir: &PathBuf)
     }
 }
 
+/// Attempts per file before giving up; the `.part` file is kept between attempts and runs.
+const DOWNLOAD_MAX_ATTEMPTS: u32 = 4;
+const DOWNLOAD_RETRY_BASE_SECS: u64 = 2;
+const DOWNLOAD_RETRY_MAX_SECS: u64 = 30;
+
+enum FileDownloadError {
+    Cancelled(String),
+    /// Network errors, 5xx / 408 / 429 responses and checksum mismatches.
+    Retryable(String),
+    Fatal(String),
+}
+
+impl FileDownloadError {
+    fn into_message(self) -> String {
+        match self {
+            FileDownloadError::Cancelled(msg)
+            | FileDownloadError::Retryable(msg)
+            | FileDownloadError::Fatal(msg) => msg,
+        }
+    }
+}
+
+impl From<DownloadError> for FileDownloadError {
+    fn from(err: DownloadError) -> Self {
+        match err {
+            DownloadError::Retryable(msg) => FileDownloadError::Retryable(msg),
+            DownloadError::Fatal(msg) => FileDownloadError::Fatal(msg),
+        }
+    }
+}
+
+fn retry_delay(attempt: u32) -> std::time::Duration {
+    let secs = DOWNLOAD_RETRY_BASE_SECS.saturating_mul(1 << attempt.saturating_sub(1).min(8));
+    std::time::Duration::from_secs(secs.min(DOWNLOAD_RETRY_MAX_SECS))
+}
+
+fn remove_temp_files(model_dir: &Path, files: &[&str]) {
+    for filename in files {
+        let dest_path = model_dir.join(filename);
+        let _ = fs::remove_file(dest_path.with_extension("tmp"));
+        remove_part_files(&dest_path);
+    }
+}
+
+/// Downloads `url` to `dest_path` with [`ResumableDownload`], continuing a partial file left
+/// by an interrupted attempt. The finished file is checked against `expected`; returns its
+/// size and SHA-256.
 async fn download_file(
     app: &AppHandle,
+    client: &reqwest::Client,
     url: &str,
-    dest_path: &PathBuf,
+    dest_path: &Path,
+    expected: Option<&ManifestEntry>,
     state: Arc<TokioMutex<DownloadState>>,
-) -> Result<(), String> {
+) -> Result<ManifestEntry, FileDownloadError> {
+    let temp_path = part_path(dest_path, url);
+    let existing = fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
+    if expected
+        .and_then(|entry| entry.size)
+        .is_some_and(|size| existing > size)
+    {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    let mut hasher = Sha256::new();
+    if temp_path.exists() {
+        hasher = {
+            let temp_path = temp_path.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut hasher = Sha256::new();
+                hash_file_into(&temp_path, &mut hasher).map(|_| hasher)
+            })
+            .await
+            .map_err(|e| {
+                FileDownloadError::Fatal(crate::utils::err_to_string(module_path!(), line!(), e))
+            })?
+            .map_err(FileDownloadError::Fatal)?
+        };
+    }
+
+    let mut download = ResumableDownload::start(client, url, dest_path).await?;
+    if download.resumed_from == 0 {
+        // Started over; the hashed bytes are gone.
+        hasher = Sha256::new();
+    }
+    let existing = download.resumed_from;
+    let remaining = download.remaining;
     log_info(
         app,
         "embedding_download",
         format!(
-            "download start url={} dest={} temp={}",
+            "download response ok url={} dest={} temp={} status={} existing={} remaining={}",
             url,
             dest_path.display(),
-            dest_path.with_extension("tmp").display()
+            temp_path.display(),
+            download.status,
+            existing,
+            remaining
         ),
     );
-    let client = reqwest::Client::new();
-    let response = client.get(url).send().await.map_err(|e| {
-        crate::utils::err_msg(
-            module_path!(),
-            line!(),
-            format!("Failed to start download: {}", e),
-        )
-    })?;
-
-    if !response.status().is_success() {
-        log_error(
-            app,
-            "embedding_download",
-            format!("download failed status={} url={}", response.status(), url),
-        );
-        return Err(format!(
-            "Download failed with status: {}",
-            response.status()
-        ));
-    }
-
-    let total_size = response.content_length().unwrap_or(0);
-    log_info(
-        app,
-        "embedding_download",
-        format!("download response ok url={} total_size={}", url, total_size),
-    );
 
     {
         let mut state_lock = state.lock().await;
-        state_lock.progress.total += total_size;
+        state_lock.progress.total += existing + remaining;
+        state_lock.progress.downloaded += existing;
         let _ = app.emit("embedding_download_progress", &state_lock.progress);
     }
 
-    let temp_path = dest_path.with_extension("tmp");
-    let mut file = tokio::fs::File::create(&temp_path).await.map_err(|e| {
-        crate::utils::err_msg(
-            module_path!(),
-            line!(),
-            format!("Failed to create file: {}", e),
-        )
-    })?;
-
-    let mut stream = response.bytes_stream();
-    let mut _downloaded: u64 = 0;
+    let mut size = existing;
     let mut last_emit = std::time::Instant::now();
-
-    while let Some(chunk_result) = stream.next().await {
+    loop {
         {
             let state_lock = state.lock().await;
             if state_lock.cancel_requested {
-                drop(file);
-                let _ = tokio::fs::remove_file(&temp_path).await;
+                download.discard().await;
                 let _ = app.emit("embedding_download_progress", &state_lock.progress);
-                return Err(crate::utils::err_msg(
+                return Err(FileDownloadError::Cancelled(crate::utils::err_msg(
                     module_path!(),
                     line!(),
                     "Download cancelled",
-                ));
+                )));
             }
         }
 
-        let chunk = chunk_result.map_err(|e| {
-            crate::utils::err_msg(
-                module_path!(),
-                line!(),
-                format!("Error reading chunk: {}", e),
-            )
-        })?;
-        file.write_all(&chunk).await.map_err(|e| {
-            crate::utils::err_msg(
-                module_path!(),
-                line!(),
-                format!("Error writing to file: {}", e),
-            )
-        })?;
-
-        _downloaded += chunk.len() as u64;
+        let chunk = match download.next_chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(err) => {
+                let _ = download.flush().await;
+                return Err(err.into());
+            }
+        };
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
 
         {
             let mut state_lock = state.lock().await;
@@ -205,37 +263,101 @@ async fn download_file(
         }
     }
 
-    file.flush().await.map_err(|e| {
-        crate::utils::err_msg(
+    let sha256 = hex_digest(hasher);
+    let size_mismatch = expected
+        .and_then(|entry| entry.size)
+        .is_some_and(|expected_size| expected_size != size);
+    let checksum_mismatch = expected
+        .and_then(|entry| entry.sha256.as_deref())
+        .is_some_and(|expected_sha| !expected_sha.eq_ignore_ascii_case(&sha256));
+    if size_mismatch || checksum_mismatch {
+        download.discard().await;
+        return Err(FileDownloadError::Retryable(crate::utils::err_msg(
             module_path!(),
             line!(),
-            format!("Error flushing file: {}", e),
-        )
-    })?;
-    drop(file);
+            format!(
+                "Downloaded file failed verification (size={} sha256={})",
+                size, sha256
+            ),
+        )));
+    }
 
-    tokio::fs::rename(&temp_path, dest_path)
-        .await
-        .map_err(|e| {
-            crate::utils::err_msg(
-                module_path!(),
-                line!(),
-                format!("Failed to rename file: {}", e),
-            )
-        })?;
+    download.finish().await?;
 
     let file_status = describe_path(dest_path);
     log_info(
         app,
         "embedding_download",
         format!(
-            "download complete dest={} {}",
+            "download complete dest={} {} sha256={}",
             dest_path.display(),
-            file_status
+            file_status,
+            sha256
         ),
     );
 
-    Ok(())
+    Ok(ManifestEntry {
+        size: Some(size),
+        sha256: Some(sha256),
+    })
+}
+
+/// Runs [`download_file`] with retries and exponential backoff. Progress counted by a failed
+/// attempt is rolled back before the next one.
+async fn download_file_with_retry(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    url: &str,
+    dest_path: &Path,
+    expected: Option<&ManifestEntry>,
+    state: Arc<TokioMutex<DownloadState>>,
+) -> Result<ManifestEntry, String> {
+    let mut attempt = 1;
+    loop {
+        let (base_total, base_downloaded) = {
+            let state_lock = state.lock().await;
+            (state_lock.progress.total, state_lock.progress.downloaded)
+        };
+        match download_file(app, client, url, dest_path, expected, state.clone()).await {
+            Ok(entry) => return Ok(entry),
+            Err(FileDownloadError::Retryable(err)) if attempt < DOWNLOAD_MAX_ATTEMPTS => {
+                let delay = retry_delay(attempt);
+                log_warn(
+                    app,
+                    "embedding_download",
+                    format!(
+                        "download attempt {}/{} failed url={} error={} retry_in={:?}",
+                        attempt, DOWNLOAD_MAX_ATTEMPTS, url, err, delay
+                    ),
+                );
+                {
+                    let mut state_lock = state.lock().await;
+                    state_lock.progress.total = base_total;
+                    state_lock.progress.downloaded = base_downloaded;
+                    state_lock.progress.status = format!(
+                        "Retrying {} ({}/{})",
+                        state_lock.progress.current_file_name,
+                        attempt + 1,
+                        DOWNLOAD_MAX_ATTEMPTS
+                    );
+                    let _ = app.emit("embedding_download_progress", &state_lock.progress);
+                }
+                tokio::time::sleep(delay).await;
+                {
+                    let state_lock = state.lock().await;
+                    if state_lock.cancel_requested || !state_lock.is_downloading {
+                        return Err(crate::utils::err_msg(
+                            module_path!(),
+                            line!(),
+                            "Download cancelled",
+                        ));
+                    }
+                }
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into_message()),
+        }
+    }
 }
 
 pub async fn start_embedding_download(
@@ -309,6 +431,22 @@ pub async fn start_embedding_download(
     );
 
     let state = DOWNLOAD_STATE.clone();
+    let client = reqwest::Client::new();
+    let remote_manifest = match fetch_remote_manifest(&client, &source_spec).await {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            log_warn(
+                &app,
+                "embedding_download",
+                format!("manifest unavailable, checksums not verified: {}", err),
+            );
+            Default::default()
+        }
+    };
+    let mut installed_manifest = InstalledManifest {
+        source: source_label.to_string(),
+        files: Default::default(),
+    };
 
     for (file_index, (remote_filename, local_filename)) in
         remote_files.iter().zip(local_files.iter()).enumerate()
@@ -339,8 +477,21 @@ pub async fn start_embedding_download(
                 local_filename
             ),
         );
-        match download_file(&app, &url, &dest_path, state.clone()).await {
-            Ok(_) => {}
+        match download_file_with_retry(
+            &app,
+            &client,
+            &url,
+            &dest_path,
+            remote_manifest.get(*remote_filename),
+            state.clone(),
+        )
+        .await
+        {
+            Ok(entry) => {
+                installed_manifest
+                    .files
+                    .insert(local_filename.to_string(), entry);
+            }
             Err(e) => {
                 log_error(
                     &app,
@@ -354,6 +505,7 @@ pub async fn start_embedding_download(
                             let _ = fs::remove_file(path);
                         }
                     }
+                    let _ = fs::remove_file(manifest_path(&model_dir, "v3"));
                 } else {
                     let _ = cleanup_partial_files(&model_dir, Some(&target_version));
                 }
@@ -373,6 +525,14 @@ pub async fn start_embedding_download(
         let _ = app.emit("embedding_download_progress", &state_lock.progress);
     }
 
+    if let Err(err) = write_installed_manifest(&model_dir, &installed_manifest) {
+        log_warn(
+            &app,
+            "embedding_download",
+            format!("Failed to write model manifest: {}", err),
+        );
+    }
+
     log_model_file_status(&app, "embedding_download", &model_dir);
 
     if let Err(err) = prompts::ensure_dynamic_memory_templates(&app) {
@@ -386,6 +546,80 @@ pub async fn start_embedding_download(
     Ok(())
 }
 
+/// Checks the installed files of `version` (or the selected source) against the manifest
+/// written when they were downloaded, falling back to the Hugging Face listing for models
+/// installed before manifests existed.
+pub async fn verify_embedding_model(
+    app: AppHandle,
+    version: Option<String>,
+) -> Result<ModelVerification, String> {
+    let model_dir = embedding_model_dir(&app)?;
+    layout::migrate_legacy_layout(&model_dir)?;
+    let requested = match version {
+        Some(version) => Some(version.to_lowercase()),
+        None => super::installed_onnx_source(&app).map(|v| v.as_str().to_string()),
+    };
+    let spec = download_source_spec(requested.as_deref());
+
+    let (checked_against, expected): (&str, BTreeMap<String, ManifestEntry>) =
+        match read_installed_manifest(&model_dir, spec.source_label) {
+            Some(manifest) => ("installed", manifest.files),
+            None => match fetch_remote_manifest(&reqwest::Client::new(), &spec).await {
+                Ok(remote) => (
+                    "remote",
+                    spec.remote_files
+                        .iter()
+                        .zip(spec.local_files.iter())
+                        .filter_map(|(remote_name, local_name)| {
+                            remote
+                                .get(*remote_name)
+                                .map(|entry| (local_name.to_string(), entry.clone()))
+                        })
+                        .collect(),
+                ),
+                Err(err) => {
+                    log_warn(
+                        &app,
+                        "embedding_verify",
+                        format!("manifest unavailable, checking presence only: {}", err),
+                    );
+                    ("none", BTreeMap::new())
+                }
+            },
+        };
+
+    let local_files: Vec<String> = spec.local_files.iter().map(|f| f.to_string()).collect();
+    let files = {
+        let model_dir = model_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            local_files
+                .iter()
+                .map(|name| check_file(name, &model_dir.join(name), expected.get(name)))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))??
+    };
+    let ok = files
+        .iter()
+        .all(|file| file.status == "ok" || file.status == "unverified");
+    log_info(
+        &app,
+        "embedding_verify",
+        format!(
+            "verified source={} against={} ok={}",
+            spec.source_label, checked_against, ok
+        ),
+    );
+
+    Ok(ModelVerification {
+        source: spec.source_label.to_string(),
+        checked_against: checked_against.to_string(),
+        ok,
+        files,
+    })
+}
+
 pub async fn get_embedding_download_progress() -> Result<DownloadProgress, String> {
     let state = DOWNLOAD_STATE.lock().await;
     Ok(state.progress.clone())
@@ -408,6 +642,7 @@ pub async fn cancel_embedding_download(app: AppHandle) -> Result<(), String> {
 
     let model_dir = embedding_model_dir(&app)?;
     cleanup_partial_files(&model_dir, None)?;
+    remove_temp_files(&model_dir, &all_local_model_files());
 
     {
         let mut state = DOWNLOAD_STATE.lock().await;
@@ -437,6 +672,7 @@ pub async fn delete_embedding_model(app: AppHandle) -> Result<(), String> {
         format!("delete embedding model files in {}", model_dir.display()),
     );
     cleanup_partial_files(&model_dir, None)?;
+    remove_temp_files(&model_dir, &all_local_model_files());
 
     Ok(())
 }
@@ -531,5 +767,9 @@ pub async fn delete_embedding_model_version(app: AppHandle, version: String) ->
         }
     }
 
+    let spec = download_source_spec(Some(version_lower.as_str()));
+    remove_temp_files(&model_dir, spec.local_files);
+    let _ = fs::remove_file(manifest_path(&model_dir, spec.source_label));
+
     Ok(())
 }
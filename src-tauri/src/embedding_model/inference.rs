@@ -1,9 +1,10 @@
+use super::custom::{CustomEmbeddingModel, Pooling};
 use super::*;
 use crate::utils::log_info;
 use ort::{
     inputs,
     session::{builder::GraphOptimizationLevel, Session},
-    value::Value,
+    value::{Tensor, Value},
 };
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -67,12 +68,21 @@ fn log_model_file_status(app: &AppHandle, component: &str, model_dir: &PathBuf)
     }
 }
 
-pub(super) fn compute_embedding_with_session(
-    session: &mut Session,
+/// Texts per ONNX run. Texts are grouped by token length and each batch is padded only to its
+/// own longest text.
+const EMBEDDING_BATCH_SIZE: usize = 16;
+
+struct EncodedText {
+    input_ids: Vec<i64>,
+    attention_mask: Vec<i64>,
+    token_type_ids: Vec<i64>,
+}
+
+fn encode_text(
     tokenizer: &Tokenizer,
     text: &str,
     max_seq_length: usize,
-) -> Result<Vec<f32>, String> {
+) -> Result<EncodedText, String> {
     let encoding = tokenizer.encode(text, true).map_err(|e| {
         crate::utils::err_msg(
             module_path!(),
@@ -81,105 +91,233 @@ pub(super) fn compute_embedding_with_session(
         )
     })?;
 
-    let input_ids = encoding.get_ids();
-    let attention_mask = encoding.get_attention_mask();
+    let seq_len = encoding.get_ids().len().min(max_seq_length);
+    let type_ids = encoding.get_type_ids();
+    Ok(EncodedText {
+        input_ids: encoding.get_ids()[..seq_len]
+            .iter()
+            .map(|&x| x as i64)
+            .collect(),
+        attention_mask: encoding.get_attention_mask()[..seq_len]
+            .iter()
+            .map(|&x| x as i64)
+            .collect(),
+        token_type_ids: if type_ids.len() >= seq_len {
+            type_ids[..seq_len].iter().map(|&x| x as i64).collect()
+        } else {
+            vec![0; seq_len]
+        },
+    })
+}
 
-    let seq_len = input_ids.len().min(max_seq_length);
-    let input_ids = &input_ids[..seq_len];
-    let attention_mask = &attention_mask[..seq_len];
+/// Splits text indices into batches of similar token length.
+fn plan_batches(lengths: &[usize], batch_size: usize) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    order.sort_by_key(|&idx| lengths[idx]);
+    order
+        .chunks(batch_size.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
 
-    let input_ids_i64: Vec<i64> = input_ids.iter().map(|&x| x as i64).collect();
-    let attention_mask_i64: Vec<i64> = attention_mask.iter().map(|&x| x as i64).collect();
-    let type_ids = encoding.get_type_ids();
-    let token_type_ids_i64: Vec<i64> = if type_ids.len() >= seq_len {
-        type_ids[..seq_len].iter().map(|&x| x as i64).collect()
-    } else {
-        vec![0; seq_len]
-    };
+/// Right-pads every row to the longest one (masked out), returning the padded length and the
+/// row-major input_ids, attention_mask and token_type_ids.
+fn pad_batch(batch: &[&EncodedText], pad_id: i64) -> (usize, Vec<i64>, Vec<i64>, Vec<i64>) {
+    let seq_len = batch
+        .iter()
+        .map(|encoded| encoded.input_ids.len())
+        .max()
+        .unwrap_or(0);
+    let mut input_ids = Vec::with_capacity(batch.len() * seq_len);
+    let mut attention_mask = Vec::with_capacity(batch.len() * seq_len);
+    let mut token_type_ids = Vec::with_capacity(batch.len() * seq_len);
+    for (row, encoded) in batch.iter().enumerate() {
+        let row_end = (row + 1) * seq_len;
+        input_ids.extend_from_slice(&encoded.input_ids);
+        input_ids.resize(row_end, pad_id);
+        attention_mask.extend_from_slice(&encoded.attention_mask);
+        attention_mask.resize(row_end, 0);
+        token_type_ids.extend_from_slice(&encoded.token_type_ids);
+        token_type_ids.resize(row_end, 0);
+    }
+    (seq_len, input_ids, attention_mask, token_type_ids)
+}
 
-    let run_without_token_type_ids = |session: &mut Session| -> Result<Vec<f32>, String> {
-        let input_ids_value =
-            Value::from_array(([1, seq_len], input_ids_i64.clone())).map_err(|e| {
-                crate::utils::err_msg(
-                    module_path!(),
-                    line!(),
-                    format!("Failed to create input_ids tensor: {}", e),
-                )
-            })?;
-        let attention_mask_value = Value::from_array(([1, seq_len], attention_mask_i64.clone()))
-            .map_err(|e| {
+/// Cuts a `[batch, ...]` output into one vector per row. Rows longer than EMBEDDING_DIM are
+/// token states; the first token's vector is used.
+fn split_batch_output(output: &[f32], rows: usize) -> Result<Vec<Vec<f32>>, String> {
+    let row_len = output.len().checked_div(rows).unwrap_or(0);
+    if row_len == 0 || row_len * rows != output.len() {
+        return Err(format!(
+            "Unexpected embedding output size: {} values for {} texts",
+            output.len(),
+            rows
+        ));
+    }
+    match row_len {
+        len if len == EMBEDDING_DIM || (len > EMBEDDING_DIM && len % EMBEDDING_DIM == 0) => {
+            Ok(output
+                .chunks(row_len)
+                .map(|row| row[..EMBEDDING_DIM].to_vec())
+                .collect())
+        }
+        len => Err(format!(
+            "Unexpected embedding dimension: {} (expected {} or multiple thereof)",
+            len, EMBEDDING_DIM
+        )),
+    }
+}
+
+/// How a model's output becomes one vector per text.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct OutputSpec {
+    /// Output tensor name; the first output when unset.
+    pub(super) name: Option<String>,
+    /// `None` keeps the built-in rule of [`split_batch_output`].
+    pub(super) pooling: Option<Pooling>,
+    pub(super) normalize: bool,
+    pub(super) dimension: usize,
+}
+
+impl OutputSpec {
+    pub(super) fn builtin() -> Self {
+        OutputSpec {
+            name: None,
+            pooling: None,
+            normalize: false,
+            dimension: EMBEDDING_DIM,
+        }
+    }
+}
+
+/// Pools a `[batch, seq, hidden]` output over the unmasked tokens of each row. A
+/// `[batch, hidden]` output is already pooled and is passed through.
+fn pool_output(
+    output: &[f32],
+    shape: &[i64],
+    attention_mask: &[i64],
+    pooling: Pooling,
+    dimension: usize,
+) -> Result<Vec<Vec<f32>>, String> {
+    let dims: Vec<usize> = shape.iter().map(|&d| d.max(0) as usize).collect();
+    let hidden = dims.last().copied().unwrap_or(0);
+    if hidden != dimension || dims.iter().product::<usize>() != output.len() {
+        return Err(format!(
+            "Unexpected embedding output shape {:?} (expected hidden size {})",
+            shape, dimension
+        ));
+    }
+    match dims.as_slice() {
+        [_, _] => Ok(output.chunks(hidden).map(<[f32]>::to_vec).collect()),
+        [rows, seq_len, _] if attention_mask.len() == rows * seq_len => Ok((0..*rows)
+            .map(|row| {
+                let mask = &attention_mask[row * seq_len..(row + 1) * seq_len];
+                let token = |t: usize| {
+                    let start = (row * seq_len + t) * hidden;
+                    &output[start..start + hidden]
+                };
+                let attended: Vec<usize> = (0..*seq_len).filter(|&t| mask[t] != 0).collect();
+                match pooling {
+                    Pooling::Cls => token(0).to_vec(),
+                    Pooling::LastToken => token(attended.last().copied().unwrap_or(0)).to_vec(),
+                    Pooling::Mean => {
+                        let mut sum = vec![0.0f32; hidden];
+                        for &t in &attended {
+                            for (acc, value) in sum.iter_mut().zip(token(t)) {
+                                *acc += value;
+                            }
+                        }
+                        let count = attended.len().max(1) as f32;
+                        sum.into_iter().map(|v| v / count).collect()
+                    }
+                }
+            })
+            .collect()),
+        _ => Err(format!(
+            "Unsupported embedding output shape {:?} for pooling",
+            shape
+        )),
+    }
+}
+
+fn l2_normalize(mut embedding: Vec<f32>) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        embedding.iter_mut().for_each(|v| *v /= norm);
+    }
+    embedding
+}
+
+fn batch_tensor(
+    rows: usize,
+    seq_len: usize,
+    data: &[i64],
+    name: &str,
+) -> Result<Tensor<i64>, String> {
+    Value::from_array(([rows, seq_len], data.to_vec())).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Failed to create {} tensor: {}", name, e),
+        )
+    })
+}
+
+fn run_batch(
+    session: &mut Session,
+    batch: &[&EncodedText],
+    pad_id: i64,
+    spec: &OutputSpec,
+) -> Result<Vec<Vec<f32>>, String> {
+    let rows = batch.len();
+    let (seq_len, input_ids, attention_mask, token_type_ids) = pad_batch(batch, pad_id);
+    let output_index = match spec.name.as_deref() {
+        Some(name) => session
+            .outputs
+            .iter()
+            .position(|output| output.name == name)
+            .ok_or_else(|| {
                 crate::utils::err_msg(
                     module_path!(),
                     line!(),
-                    format!("Failed to create attention_mask tensor: {}", e),
+                    format!("Model has no output named '{}'", name),
                 )
-            })?;
+            })?,
+        None => 0,
+    };
 
-        let outputs = session
-            .run(inputs![
+    type RawOutput = (Vec<i64>, Vec<f32>);
+    let run = |session: &mut Session, with_token_type_ids: bool| -> Result<RawOutput, String> {
+        let input_ids_value = batch_tensor(rows, seq_len, &input_ids, "input_ids")?;
+        let attention_mask_value = batch_tensor(rows, seq_len, &attention_mask, "attention_mask")?;
+        let outputs = if with_token_type_ids {
+            let token_type_ids_value =
+                batch_tensor(rows, seq_len, &token_type_ids, "token_type_ids")?;
+            session.run(inputs![
+                "input_ids" => input_ids_value,
+                "attention_mask" => attention_mask_value,
+                "token_type_ids" => token_type_ids_value
+            ])
+        } else {
+            session.run(inputs![
                 "input_ids" => input_ids_value,
                 "attention_mask" => attention_mask_value
             ])
-            .map_err(|e| {
-                crate::utils::err_msg(module_path!(), line!(), format!("Inference failed: {}", e))
-            })?;
-
-        let embedding_value = &outputs[0];
-        let (_, embedding_slice) = embedding_value.try_extract_tensor::<f32>().map_err(|e| {
-            crate::utils::err_msg(
-                module_path!(),
-                line!(),
-                format!("Failed to extract embedding: {}", e),
-            )
+        }
+        .map_err(|e| {
+            crate::utils::err_msg(module_path!(), line!(), format!("Inference failed: {}", e))
         })?;
-        Ok(embedding_slice.to_vec())
-    };
 
-    let run_with_token_type_ids = |session: &mut Session| -> Result<Vec<f32>, String> {
-        let input_ids_value =
-            Value::from_array(([1, seq_len], input_ids_i64.clone())).map_err(|e| {
+        let embedding_value = &outputs[output_index];
+        let (shape, embedding_slice) =
+            embedding_value.try_extract_tensor::<f32>().map_err(|e| {
                 crate::utils::err_msg(
                     module_path!(),
                     line!(),
-                    format!("Failed to create input_ids tensor: {}", e),
+                    format!("Failed to extract embedding: {}", e),
                 )
             })?;
-        let attention_mask_value = Value::from_array(([1, seq_len], attention_mask_i64.clone()))
-            .map_err(|e| {
-                crate::utils::err_msg(
-                    module_path!(),
-                    line!(),
-                    format!("Failed to create attention_mask tensor: {}", e),
-                )
-            })?;
-        let token_type_ids_value = Value::from_array(([1, seq_len], token_type_ids_i64.clone()))
-            .map_err(|e| {
-                crate::utils::err_msg(
-                    module_path!(),
-                    line!(),
-                    format!("Failed to create token_type_ids tensor: {}", e),
-                )
-            })?;
-
-        let outputs = session
-            .run(inputs![
-                "input_ids" => input_ids_value,
-                "attention_mask" => attention_mask_value,
-                "token_type_ids" => token_type_ids_value
-            ])
-            .map_err(|e| {
-                crate::utils::err_msg(module_path!(), line!(), format!("Inference failed: {}", e))
-            })?;
-
-        let embedding_value = &outputs[0];
-        let (_, embedding_slice) = embedding_value.try_extract_tensor::<f32>().map_err(|e| {
-            crate::utils::err_msg(
-                module_path!(),
-                line!(),
-                format!("Failed to extract embedding: {}", e),
-            )
-        })?;
-        Ok(embedding_slice.to_vec())
+        Ok((shape.iter().copied().collect(), embedding_slice.to_vec()))
     };
 
     let expects_token_type_ids = session
@@ -187,13 +325,13 @@ pub(super) fn compute_embedding_with_session(
         .iter()
         .any(|input| input.name.contains("token_type_ids"));
 
-    let embedding_vec = if expects_token_type_ids {
-        run_with_token_type_ids(session)?
+    let (shape, output) = if expects_token_type_ids {
+        run(session, true)?
     } else {
-        match run_without_token_type_ids(session) {
+        match run(session, false) {
             Ok(vec) => vec,
-            Err(err) if err.contains("Missing Input: token_type_ids") => {
-                run_with_token_type_ids(session).map_err(|fallback_err| {
+            Err(err) if err.contains("Missing Input: token_type_ids") => run(session, true)
+                .map_err(|fallback_err| {
                     crate::utils::err_msg(
                         module_path!(),
                         line!(),
@@ -202,25 +340,71 @@ pub(super) fn compute_embedding_with_session(
                         fallback_err, err
                     ),
                     )
-                })?
-            }
+                })?,
             Err(err) => return Err(err),
         }
     };
 
-    match embedding_vec.len() {
-        len if len == EMBEDDING_DIM => Ok(embedding_vec),
-        len if len > EMBEDDING_DIM && len % EMBEDDING_DIM == 0 => {
-            Ok(embedding_vec[..EMBEDDING_DIM].to_vec())
+    let embeddings = match spec.pooling {
+        Some(pooling) => pool_output(&output, &shape, &attention_mask, pooling, spec.dimension)?,
+        None => split_batch_output(&output, rows)?,
+    };
+    Ok(if spec.normalize {
+        embeddings.into_iter().map(l2_normalize).collect()
+    } else {
+        embeddings
+    })
+}
+
+/// Embeds `texts` in padded batches; the result is in input order.
+pub(super) fn compute_embeddings_with_session(
+    session: &mut Session,
+    tokenizer: &Tokenizer,
+    texts: &[&str],
+    max_seq_length: usize,
+    spec: &OutputSpec,
+) -> Result<Vec<Vec<f32>>, String> {
+    let encoded = texts
+        .iter()
+        .map(|text| encode_text(tokenizer, text, max_seq_length))
+        .collect::<Result<Vec<_>, _>>()?;
+    let pad_id = tokenizer
+        .get_padding()
+        .map(|padding| padding.pad_id as i64)
+        .unwrap_or(0);
+    let lengths: Vec<usize> = encoded.iter().map(|e| e.input_ids.len()).collect();
+
+    let mut embeddings = vec![Vec::new(); texts.len()];
+    for indices in plan_batches(&lengths, EMBEDDING_BATCH_SIZE) {
+        let batch: Vec<&EncodedText> = indices.iter().map(|&idx| &encoded[idx]).collect();
+        for (idx, embedding) in indices
+            .into_iter()
+            .zip(run_batch(session, &batch, pad_id, spec)?)
+        {
+            embeddings[idx] = embedding;
         }
-        len => Err(format!(
-            "Unexpected embedding dimension: {} (expected {} or multiple thereof)",
-            len, EMBEDDING_DIM
-        )),
     }
+    Ok(embeddings)
+}
+
+pub(super) fn compute_embedding_with_session(
+    session: &mut Session,
+    tokenizer: &Tokenizer,
+    text: &str,
+    max_seq_length: usize,
+) -> Result<Vec<f32>, String> {
+    compute_embeddings_with_session(
+        session,
+        tokenizer,
+        &[text],
+        max_seq_length,
+        &OutputSpec::builtin(),
+    )?
+    .pop()
+    .ok_or_else(|| crate::utils::err_msg(module_path!(), line!(), "No embedding produced"))
 }
 
-fn create_runtime(
+pub(super) fn create_runtime(
     model_path: &Path,
     tokenizer_path: &Path,
 ) -> Result<(Session, Tokenizer), String> {
@@ -259,13 +443,24 @@ fn create_runtime(
 }
 
 pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>, String> {
-    let text_len = text.len();
+    compute_embeddings(app, vec![text])
+        .await?
+        .pop()
+        .ok_or_else(|| crate::utils::err_msg(module_path!(), line!(), "No embedding produced"))
+}
+
+pub async fn compute_embeddings(
+    app: AppHandle,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, String> {
+    let text_bytes: usize = texts.iter().map(String::len).sum();
     crate::utils::log_info(
         &app,
         "embedding_debug",
         format!(
-            "computing embedding for text_len_bytes={} text='{}'",
-            text_len, text
+            "computing embeddings for texts={} text_len_bytes={}",
+            texts.len(),
+            text_bytes
         ),
     );
 
@@ -312,8 +507,57 @@ pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>,
     super::ort_runtime::ensure_ort_init(&app).await?;
     log_info(&app, "embedding_debug", "ort initialized");
 
-    let keep_model_loaded = settings::read_embedding_preferences(&app).keep_model_loaded;
-    let embedding_vec = if keep_model_loaded {
+    embed_with_runtime(
+        &app,
+        model_path,
+        tokenizer_path,
+        max_seq_length,
+        &OutputSpec::builtin(),
+        &texts,
+    )
+    .await
+}
+
+/// Embeds with a user-registered model; see [`super::custom`].
+pub async fn compute_custom_embeddings(
+    app: AppHandle,
+    model: &CustomEmbeddingModel,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, String> {
+    log_info(
+        &app,
+        "embedding_debug",
+        format!(
+            "computing custom embeddings model={} texts={}",
+            model.id,
+            texts.len()
+        ),
+    );
+    super::ort_runtime::ensure_ort_init(&app).await?;
+    embed_with_runtime(
+        &app,
+        model.model_path(),
+        model.tokenizer_path(),
+        model.config.max_seq_length,
+        &model.output_spec(),
+        &texts,
+    )
+    .await
+}
+
+/// Runs `texts` through the model at `model_path`, reusing the persistent runtime when
+/// "keep model loaded" is on.
+async fn embed_with_runtime(
+    app: &AppHandle,
+    model_path: PathBuf,
+    tokenizer_path: PathBuf,
+    max_seq_length: usize,
+    spec: &OutputSpec,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let text_refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let keep_model_loaded = settings::read_embedding_preferences(app).keep_model_loaded;
+    let embeddings = if keep_model_loaded {
         let mut cache = LOADED_EMBEDDING_RUNTIME.lock().await;
         let reuse = cache.as_ref().is_some_and(|loaded| {
             loaded.model_path == model_path
@@ -331,7 +575,7 @@ pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>,
                 tokenizer,
             });
             log_info(
-                &app,
+                app,
                 "embedding_debug",
                 format!(
                     "created persistent embedding runtime model={}",
@@ -348,15 +592,16 @@ pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>,
             )
         })?;
         log_info(
-            &app,
+            app,
             "embedding_debug",
             "running embedding inference (persistent runtime)",
         );
-        compute_embedding_with_session(
+        compute_embeddings_with_session(
             &mut loaded.session,
             &loaded.tokenizer,
-            &text,
+            &text_refs,
             loaded.max_seq_length,
+            spec,
         )?
     } else {
         {
@@ -364,7 +609,7 @@ pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>,
             if cache.is_some() {
                 *cache = None;
                 log_info(
-                    &app,
+                    app,
                     "embedding_debug",
                     "cleared persistent embedding runtime cache",
                 );
@@ -373,20 +618,20 @@ pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>,
 
         let (mut session, tokenizer) = create_runtime(&model_path, &tokenizer_path)?;
         log_info(
-            &app,
+            app,
             "embedding_debug",
             format!("onnx session ready model_path={}", model_path.display()),
         );
-        log_info(&app, "embedding_debug", "tokenizer loaded");
-        log_info(&app, "embedding_debug", "running embedding inference");
-        compute_embedding_with_session(&mut session, &tokenizer, &text, max_seq_length)?
+        log_info(app, "embedding_debug", "tokenizer loaded");
+        log_info(app, "embedding_debug", "running embedding inference");
+        compute_embeddings_with_session(&mut session, &tokenizer, &text_refs, max_seq_length, spec)?
     };
     log_info(
-        &app,
+        app,
         "embedding_debug",
-        format!("embedding extracted len={}", embedding_vec.len()),
+        format!("embeddings extracted count={}", embeddings.len()),
     );
-    Ok(embedding_vec)
+    Ok(embeddings)
 }
 
 pub async fn initialize_embedding_model(app: AppHandle) -> Result<(), String> {
@@ -507,3 +752,56 @@ pub async fn initialize_embedding_model(app: AppHandle) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_pad_to_their_longest_text() {
+        let batches = plan_batches(&[5, 1, 9, 2, 3], 2);
+        assert_eq!(batches, vec![vec![1, 3], vec![4, 0], vec![2]]);
+
+        let short = EncodedText {
+            input_ids: vec![101, 7],
+            attention_mask: vec![1, 1],
+            token_type_ids: vec![0, 0],
+        };
+        let long = EncodedText {
+            input_ids: vec![101, 8, 9, 102],
+            attention_mask: vec![1, 1, 1, 1],
+            token_type_ids: vec![0, 0, 0, 0],
+        };
+        let (seq_len, ids, mask, _) = pad_batch(&[&short, &long], 0);
+        assert_eq!(seq_len, 4);
+        assert_eq!(ids, vec![101, 7, 0, 0, 101, 8, 9, 102]);
+        assert_eq!(mask, vec![1, 1, 0, 0, 1, 1, 1, 1]);
+
+        let pooled = vec![0.5; EMBEDDING_DIM * 2];
+        assert_eq!(split_batch_output(&pooled, 2).unwrap().len(), 2);
+        let mut token_states = vec![0.0; EMBEDDING_DIM * 3 * 2];
+        token_states[EMBEDDING_DIM * 3] = 1.0;
+        let rows = split_batch_output(&token_states, 2).unwrap();
+        assert_eq!(rows[1][0], 1.0);
+        assert_eq!(rows[1].len(), EMBEDDING_DIM);
+        assert!(split_batch_output(&[0.0; 10], 3).is_err());
+    }
+
+    #[test]
+    fn pooling_uses_unmasked_tokens() {
+        // 2 rows x 3 tokens x 2 hidden; the second row's last token is padding.
+        let output = [1.0, 1.0, 3.0, 3.0, 5.0, 5.0, 2.0, 0.0, 4.0, 0.0, 9.0, 9.0];
+        let shape = [2, 3, 2];
+        let mask = [1, 1, 1, 1, 1, 0];
+        let mean = pool_output(&output, &shape, &mask, Pooling::Mean, 2).unwrap();
+        assert_eq!(mean, vec![vec![3.0, 3.0], vec![3.0, 0.0]]);
+        let cls = pool_output(&output, &shape, &mask, Pooling::Cls, 2).unwrap();
+        assert_eq!(cls[1], vec![2.0, 0.0]);
+        let last = pool_output(&output, &shape, &mask, Pooling::LastToken, 2).unwrap();
+        assert_eq!(last, vec![vec![5.0, 5.0], vec![4.0, 0.0]]);
+
+        let pooled = pool_output(&[3.0, 4.0], &[1, 2], &[1], Pooling::Mean, 2).unwrap();
+        assert_eq!(l2_normalize(pooled[0].clone()), vec![0.6, 0.8]);
+        assert!(pool_output(&output, &shape, &mask, Pooling::Mean, 4).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::specs::DownloadSourceSpec;
+
+const MANIFEST_TIMEOUT_SECS: u64 = 20;
+
+/// Expected size and SHA-256 of one model file. Either may be unknown: Hugging Face only
+/// publishes hashes for LFS files.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ManifestEntry {
+    #[serde(default)]
+    pub(crate) size: Option<u64>,
+    #[serde(default)]
+    pub(crate) sha256: Option<String>,
+}
+
+/// Written next to the model files after a verified download, keyed by local file name.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InstalledManifest {
+    pub(crate) source: String,
+    pub(crate) files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFileCheck {
+    pub name: String,
+    /// "ok", "missing", "sizeMismatch", "checksumMismatch" or "unverified".
+    pub status: String,
+    pub expected_size: Option<u64>,
+    pub actual_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVerification {
+    pub source: String,
+    /// "installed" (local manifest), "remote" (Hugging Face listing) or "none".
+    pub checked_against: String,
+    pub ok: bool,
+    pub files: Vec<ModelFileCheck>,
+}
+
+pub(crate) fn manifest_path(model_dir: &Path, source_label: &str) -> PathBuf {
+    model_dir.join(format!("{}-manifest.json", source_label))
+}
+
+pub(crate) fn read_installed_manifest(
+    model_dir: &Path,
+    source_label: &str,
+) -> Option<InstalledManifest> {
+    let raw = fs::read_to_string(manifest_path(model_dir, source_label)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub(crate) fn write_installed_manifest(
+    model_dir: &Path,
+    manifest: &InstalledManifest,
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    fs::write(manifest_path(model_dir, &manifest.source), json)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Reads a Hugging Face `tree` listing into entries keyed by remote file name.
+pub(crate) fn parse_hf_tree_manifest(listing: &Value) -> BTreeMap<String, ManifestEntry> {
+    listing
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter(|item| item.get("type").and_then(Value::as_str) == Some("file"))
+                .filter_map(|item| {
+                    let path = item.get("path")?.as_str()?.to_string();
+                    let lfs = item.get("lfs");
+                    let size = lfs
+                        .and_then(|lfs| lfs.get("size"))
+                        .or_else(|| item.get("size"))
+                        .and_then(Value::as_u64);
+                    let sha256 = lfs
+                        .and_then(|lfs| lfs.get("oid"))
+                        .and_then(Value::as_str)
+                        .map(str::to_lowercase);
+                    Some((path, ManifestEntry { size, sha256 }))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) async fn fetch_remote_manifest(
+    client: &reqwest::Client,
+    spec: &DownloadSourceSpec,
+) -> Result<BTreeMap<String, ManifestEntry>, String> {
+    let response = client
+        .get(spec.manifest_url)
+        .timeout(Duration::from_secs(MANIFEST_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if !response.status().is_success() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Manifest request failed with status: {}", response.status()),
+        ));
+    }
+    let listing: Value = response
+        .json()
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(parse_hf_tree_manifest(&listing))
+}
+
+/// Feeds the contents of `path` into `hasher`, returning the number of bytes read.
+pub(crate) fn hash_file_into(path: &Path, hasher: &mut Sha256) -> Result<u64, String> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut total = 0u64;
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        if read == 0 {
+            return Ok(total);
+        }
+        hasher.update(&buffer[..read]);
+        total += read as u64;
+    }
+}
+
+pub(crate) fn hex_digest(hasher: Sha256) -> String {
+    format!("{:x}", hasher.finalize())
+}
+
+/// Compares a file on disk against its expected entry. Hashing is skipped when the size is
+/// already wrong.
+pub(crate) fn check_file(
+    name: &str,
+    path: &Path,
+    expected: Option<&ManifestEntry>,
+) -> Result<ModelFileCheck, String> {
+    let actual_size = fs::metadata(path).ok().map(|meta| meta.len());
+    let expected_size = expected.and_then(|entry| entry.size);
+    let status = match (actual_size, expected) {
+        (None, _) => "missing",
+        (Some(_), None) => "unverified",
+        (Some(actual), Some(entry)) => {
+            if entry.size.is_some_and(|size| size != actual) {
+                "sizeMismatch"
+            } else if let Some(sha256) = entry.sha256.as_deref() {
+                let mut hasher = Sha256::new();
+                hash_file_into(path, &mut hasher)?;
+                if hex_digest(hasher).eq_ignore_ascii_case(sha256) {
+                    "ok"
+                } else {
+                    "checksumMismatch"
+                }
+            } else if entry.size.is_some() {
+                "ok"
+            } else {
+                "unverified"
+            }
+        }
+    };
+    Ok(ModelFileCheck {
+        name: name.to_string(),
+        status: status.to_string(),
+        expected_size,
+        actual_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn hf_listing_and_file_checks() {
+        let listing = json!([
+            { "type": "directory", "path": "onnx" },
+            { "type": "file", "path": "tokenizer.json", "size": 11 },
+            { "type": "file", "path": "model.int8.onnx", "size": 134,
+              "lfs": { "oid": "ABC123", "size": 5000, "pointerSize": 134 } },
+        ]);
+        let manifest = parse_hf_tree_manifest(&listing);
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest["tokenizer.json"].size, Some(11));
+        assert_eq!(manifest["tokenizer.json"].sha256, None);
+        assert_eq!(manifest["model.int8.onnx"].size, Some(5000));
+        assert_eq!(
+            manifest["model.int8.onnx"].sha256.as_deref(),
+            Some("abc123")
+        );
+
+        let dir = std::env::temp_dir().join(format!("emb-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file.bin");
+        fs::write(&path, b"hello world").unwrap();
+        let good = ManifestEntry {
+            size: Some(11),
+            sha256: Some(
+                "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".to_string(),
+            ),
+        };
+        let check = |entry: Option<&ManifestEntry>| check_file("file.bin", &path, entry).unwrap();
+        assert_eq!(check(Some(&good)).status, "ok");
+        assert_eq!(check(None).status, "unverified");
+        let corrupted = ManifestEntry {
+            sha256: Some("00".repeat(32)),
+            ..good.clone()
+        };
+        assert_eq!(check(Some(&corrupted)).status, "checksumMismatch");
+        let truncated = ManifestEntry {
+            size: Some(12),
+            ..good
+        };
+        assert_eq!(check(Some(&truncated)).status, "sizeMismatch");
+        let missing = check_file("gone", &dir.join("gone"), None).unwrap();
+        assert_eq!(missing.status, "missing");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,16 +4,23 @@ use std::sync::Arc;
 use tauri::AppHandle;
 use tokio::sync::Mutex as TokioMutex;
 
+mod backend;
 mod benchmark;
+mod cache;
+mod custom;
 mod download;
 mod inference;
 mod layout;
+mod manifest;
 mod ort_runtime;
+mod reembed;
+mod reranker;
 mod settings;
 mod specs;
 mod tests;
 mod util;
 
+use backend::{EmbeddingBackend, RemoteEmbeddingConfig};
 use specs::*;
 
 const MAX_SEQ_LENGTH_V1: usize = 512;
@@ -51,12 +58,18 @@ impl EmbeddingSourceVersion {
 #[derive(Clone, Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EmbeddingModelInfo {
+    /// Whether an on-device ONNX model is installed.
     pub installed: bool,
     pub version: Option<String>,
     pub source_version: Option<String>,
     pub selected_source_version: Option<String>,
     pub available_versions: Vec<String>,
     pub max_tokens: u32,
+    /// "onnx" or "remote".
+    pub backend: String,
+    pub backend_id: Option<String>,
+    /// Vector size last produced by the active backend.
+    pub dimension: Option<u32>,
 }
 
 #[derive(Clone, Debug, serde::Serialize)]
@@ -134,6 +147,30 @@ fn resolve_selected_source_version(
     }
 }
 
+/// The ONNX source compute_embedding runs when the ONNX backend is active.
+fn installed_onnx_source(app: &AppHandle) -> Option<EmbeddingSourceVersion> {
+    let model_dir = embedding_model_dir(app).ok()?;
+    layout::migrate_legacy_layout(&model_dir).ok()?;
+    let installed = layout::detect_installed_sources(&model_dir);
+    resolve_selected_source_version(app, installed.has_v1, installed.has_v2, installed.has_v3)
+}
+
+/// Identifies the vector space new embeddings are computed in; stored vectors tagged with a
+/// different id need re-embedding.
+pub fn active_embedding_backend_id(app: &AppHandle) -> Option<String> {
+    EmbeddingBackend::configured(app).id(app)
+}
+
+/// Re-embeds stored memories in the background if the backend changed since they were embedded.
+pub fn ensure_embedding_backend_current(app: &AppHandle) {
+    reembed::ensure_backend_current(app, &EmbeddingBackend::configured(app));
+}
+
+/// Embeds, in the background, stored memories that have no vector from the current backend.
+pub fn embed_pending_memories(app: &AppHandle) {
+    reembed::embed_pending_memories(app, &EmbeddingBackend::configured(app));
+}
+
 fn resolve_model_paths(
     model_dir: &Path,
     source: EmbeddingSourceVersion,
@@ -195,16 +232,28 @@ fn resolve_runtime_model(
     let (model_path, tokenizer_path, mut max_seq_length, label) =
         resolve_model_paths(&model_dir, source);
     if source != EmbeddingSourceVersion::V1 {
-        let settings_max_tokens = settings::read_embedding_preferences(app).max_tokens;
-        let resolved_max_tokens = settings_max_tokens.unwrap_or(MAX_SEQ_LENGTH_V2);
-        max_seq_length = resolved_max_tokens.clamp(512, MAX_SEQ_LENGTH_V2);
+        max_seq_length = onnx_max_seq_length(app, source);
     }
 
     Ok((source, model_path, tokenizer_path, max_seq_length, label))
 }
 
+/// Sequence length the ONNX `source` runs with: fixed for v1, the max tokens setting otherwise.
+fn onnx_max_seq_length(app: &AppHandle, source: EmbeddingSourceVersion) -> usize {
+    if source == EmbeddingSourceVersion::V1 {
+        return MAX_SEQ_LENGTH_V1;
+    }
+    let settings_max_tokens = settings::read_embedding_preferences(app).max_tokens;
+    settings_max_tokens
+        .unwrap_or(MAX_SEQ_LENGTH_V2)
+        .clamp(512, MAX_SEQ_LENGTH_V2)
+}
+
 #[tauri::command]
 pub fn check_embedding_model(app: AppHandle) -> Result<bool, String> {
+    if !matches!(EmbeddingBackend::configured(&app), EmbeddingBackend::Onnx) {
+        return Ok(true);
+    }
     let model_dir = embedding_model_dir(&app)?;
     layout::migrate_legacy_layout(&model_dir)?;
     let installed = layout::detect_installed_sources(&model_dir);
@@ -255,6 +304,14 @@ pub fn get_embedding_model_info(app: AppHandle) -> Result<EmbeddingModelInfo, St
         0
     };
 
+    let backend = EmbeddingBackend::configured(&app);
+    let backend_kind = backend.kind().to_string();
+    let backend_id = backend.id(&app);
+    let dimension = backend_id
+        .as_deref()
+        .and_then(|id| backend::backend_dimension(&app, id))
+        .map(|d| d as u32);
+
     match detect_model_version(&app)? {
         Some(EmbeddingModelVersion::V2) => Ok(EmbeddingModelInfo {
             installed: true,
@@ -263,6 +320,9 @@ pub fn get_embedding_model_info(app: AppHandle) -> Result<EmbeddingModelInfo, St
             selected_source_version: source_version,
             available_versions,
             max_tokens,
+            backend: backend_kind,
+            backend_id,
+            dimension,
         }),
         Some(EmbeddingModelVersion::V3) => Ok(EmbeddingModelInfo {
             installed: true,
@@ -271,6 +331,9 @@ pub fn get_embedding_model_info(app: AppHandle) -> Result<EmbeddingModelInfo, St
             selected_source_version: source_version,
             available_versions,
             max_tokens,
+            backend: backend_kind,
+            backend_id,
+            dimension,
         }),
         Some(EmbeddingModelVersion::V1) => Ok(EmbeddingModelInfo {
             installed: true,
@@ -279,6 +342,9 @@ pub fn get_embedding_model_info(app: AppHandle) -> Result<EmbeddingModelInfo, St
             selected_source_version: source_version,
             available_versions,
             max_tokens,
+            backend: backend_kind,
+            backend_id,
+            dimension,
         }),
         None => Ok(EmbeddingModelInfo {
             installed: false,
@@ -287,11 +353,90 @@ pub fn get_embedding_model_info(app: AppHandle) -> Result<EmbeddingModelInfo, St
             selected_source_version: None,
             available_versions: vec![],
             max_tokens: 0,
+            backend: backend_kind,
+            backend_id,
+            dimension,
         }),
     }
 }
 
 pub use download::reset_download_state;
+pub use reranker::rerank_passages;
+
+/// Starts re-embedding stored memories if the backend changed since they were embedded, and
+/// reports how far it has got.
+#[tauri::command]
+pub fn get_embedding_reembed_status(app: AppHandle) -> Result<reembed::ReembedStatus, String> {
+    ensure_embedding_backend_current(&app);
+    reembed::reembed_status(&app)
+}
+
+#[tauri::command]
+pub fn embedding_cache_stats(app: AppHandle) -> Result<cache::EmbeddingCacheStats, String> {
+    cache::cache_stats(&app)
+}
+
+#[tauri::command]
+pub fn embedding_cache_clear(app: AppHandle) -> Result<u64, String> {
+    cache::clear_cache(&app)
+}
+
+#[tauri::command]
+pub fn list_custom_embedding_models(
+    app: AppHandle,
+) -> Result<Vec<custom::CustomEmbeddingModel>, String> {
+    custom::list_custom_models(&app)
+}
+
+#[tauri::command]
+pub fn register_custom_embedding_model(
+    app: AppHandle,
+    config: custom::CustomEmbeddingModelConfig,
+) -> Result<custom::CustomEmbeddingModel, String> {
+    custom::register_custom_model(&app, config)
+}
+
+/// Changing any setting clears the model's validation; it must be validated again before use.
+#[tauri::command]
+pub fn update_custom_embedding_model(
+    app: AppHandle,
+    id: String,
+    config: custom::CustomEmbeddingModelConfig,
+) -> Result<custom::CustomEmbeddingModel, String> {
+    custom::update_custom_model(&app, &id, config)
+}
+
+#[tauri::command]
+pub fn delete_custom_embedding_model(app: AppHandle, id: String) -> Result<(), String> {
+    custom::delete_custom_model(&app, &id)
+}
+
+#[tauri::command]
+pub async fn validate_custom_embedding_model(
+    app: AppHandle,
+    id: String,
+) -> Result<custom::CustomEmbeddingModel, String> {
+    custom::validate_custom_model(&app, &id).await
+}
+
+#[tauri::command]
+pub fn check_reranker_model(app: AppHandle) -> Result<bool, String> {
+    reranker::is_reranker_installed(&app)
+}
+
+#[tauri::command]
+pub async fn download_reranker_model(app: AppHandle) -> Result<(), String> {
+    reranker::download_reranker_model(app).await
+}
+
+#[tauri::command]
+pub async fn rerank_texts(
+    app: AppHandle,
+    query: String,
+    passages: Vec<String>,
+) -> Result<Vec<f32>, String> {
+    reranker::rerank_passages(app, query, passages).await
+}
 
 #[tauri::command]
 pub async fn start_embedding_download(
@@ -311,6 +456,14 @@ pub async fn cancel_embedding_download(app: AppHandle) -> Result<(), String> {
     download::cancel_embedding_download(app).await
 }
 
+#[tauri::command]
+pub async fn verify_embedding_model(
+    app: AppHandle,
+    version: Option<String>,
+) -> Result<manifest::ModelVerification, String> {
+    download::verify_embedding_model(app, version).await
+}
+
 #[tauri::command]
 pub async fn delete_embedding_model(app: AppHandle) -> Result<(), String> {
     download::delete_embedding_model(app).await
@@ -323,7 +476,90 @@ pub async fn delete_embedding_model_version(app: AppHandle, version: String) ->
 
 #[tauri::command]
 pub async fn compute_embedding(app: AppHandle, text: String) -> Result<Vec<f32>, String> {
-    inference::compute_embedding(app, text).await
+    compute_embeddings(app, vec![text])
+        .await?
+        .pop()
+        .ok_or_else(|| crate::utils::err_msg(module_path!(), line!(), "No embedding produced"))
+}
+
+/// Embeds `texts` in input order. Vectors already computed by the active backend come from
+/// the embedding cache; the rest are embedded in batches and cached.
+#[tauri::command]
+pub async fn compute_embeddings(
+    app: AppHandle,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, String> {
+    let backend = EmbeddingBackend::configured(&app);
+    reembed::ensure_backend_current(&app, &backend);
+    embed_with_backend(&app, &backend, &texts).await
+}
+
+/// [`compute_embeddings`] with an explicit backend; re-embedding uses the backend it switches to.
+async fn embed_with_backend(
+    app: &AppHandle,
+    backend: &EmbeddingBackend,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let backend_id = backend.id(app);
+    let cache_key = backend.cache_key(app);
+
+    let mut embeddings = match cache_key.as_deref() {
+        Some(id) => cache::cached_embeddings(app, id, texts).unwrap_or_else(|err| {
+            crate::utils::log_warn(app, "embedding_cache", err);
+            vec![None; texts.len()]
+        }),
+        None => vec![None; texts.len()],
+    };
+    let missing: Vec<usize> = (0..texts.len())
+        .filter(|&idx| embeddings[idx].is_none())
+        .collect();
+
+    if !missing.is_empty() {
+        let missing_texts: Vec<String> = missing.iter().map(|&idx| texts[idx].clone()).collect();
+        let computed = backend.embed_batch(app, missing_texts).await?;
+        if let Some(backend_id) = backend_id.as_deref() {
+            if let Some(first) = computed.first() {
+                if let Err(err) = backend::record_backend_dimension(app, backend_id, first.len()) {
+                    crate::utils::log_warn(app, "embedding", err);
+                }
+            }
+        }
+        if let Some(cache_key) = cache_key.as_deref() {
+            let entries: Vec<(&str, &[f32])> = missing
+                .iter()
+                .zip(&computed)
+                .map(|(&idx, embedding)| (texts[idx].as_str(), embedding.as_slice()))
+                .collect();
+            if let Err(err) = cache::store_embeddings(app, cache_key, &entries) {
+                crate::utils::log_warn(app, "embedding_cache", err);
+            }
+        }
+        for (idx, embedding) in missing.into_iter().zip(computed) {
+            embeddings[idx] = Some(embedding);
+        }
+    }
+
+    Ok(embeddings
+        .into_iter()
+        .map(Option::unwrap_or_default)
+        .collect())
+}
+
+/// Embeds a probe text with `config` (or the configured backend) and returns the vector size.
+#[tauri::command]
+pub async fn test_embedding_backend(
+    app: AppHandle,
+    config: Option<RemoteEmbeddingConfig>,
+) -> Result<usize, String> {
+    let embedding = match config {
+        Some(config) => backend::embed_remote(&config, "embedding backend test").await?,
+        None => {
+            EmbeddingBackend::configured(&app)
+                .embed(&app, "embedding backend test".to_string())
+                .await?
+        }
+    };
+    Ok(embedding.len())
 }
 
 #[tauri::command]
@@ -334,6 +570,7 @@ pub async fn initialize_embedding_model(app: AppHandle) -> Result<(), String> {
 #[tauri::command]
 pub async fn clear_embedding_runtime_cache() -> Result<(), String> {
     inference::clear_loaded_runtime_cache().await;
+    reranker::clear_loaded_reranker_cache().await;
     Ok(())
 }
 
@@ -0,0 +1,233 @@
+use rusqlite::{params, OptionalExtension};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+
+use super::backend::EmbeddingBackend;
+use crate::storage_manager::db::open_db;
+use crate::storage_manager::memories::{
+    embedding_to_blob, ACTIVE_EMBEDDING_BACKEND_META_KEY, QUERY_EMBEDDING_MODEL_META_KEY,
+};
+use crate::utils::{log_error, log_info, log_warn};
+
+pub(crate) const REEMBED_PROGRESS_EVENT: &str = "embedding-reembed:progress";
+
+static REEMBED_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReembedStatus {
+    pub running: bool,
+    /// Backend new vectors are computed with.
+    pub backend_id: Option<String>,
+    /// Memories whose vector came from another backend (or none yet).
+    pub pending: u64,
+    pub total: u64,
+}
+
+fn read_meta(app: &AppHandle, key: &str) -> Result<Option<String>, String> {
+    let conn = open_db(app)?;
+    conn.query_row("SELECT value FROM meta WHERE key = ?1", params![key], |r| {
+        r.get(0)
+    })
+    .optional()
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+fn write_meta(app: &AppHandle, key: &str, value: &str) -> Result<(), String> {
+    let conn = open_db(app)?;
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value
+         WHERE value IS NOT excluded.value",
+        params![key, value],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+/// Starts re-embedding stored memories when the configured backend differs from the one the
+/// stored vectors were migrated to. The job only touches rows tagged with another backend, so
+/// an interrupted run resumes where it stopped the next time this is called (at startup or
+/// before the next embedding).
+pub(crate) fn ensure_backend_current(app: &AppHandle, backend: &EmbeddingBackend) {
+    let Some(backend_id) = backend.id(app) else {
+        return;
+    };
+    if let Err(err) = write_meta(app, QUERY_EMBEDDING_MODEL_META_KEY, &backend_id) {
+        log_warn(app, "embedding_reembed", err);
+    }
+    match read_meta(app, ACTIVE_EMBEDDING_BACKEND_META_KEY) {
+        Ok(Some(stored)) if stored == backend_id => {}
+        Ok(Some(stored)) => {
+            log_info(
+                app,
+                "embedding_reembed",
+                format!("embedding backend changed {} -> {}", stored, backend_id),
+            );
+            spawn_reembed(app, backend, backend_id);
+        }
+        // No backend recorded yet: embed whatever isn't tagged with this one, untagged
+        // vectors included, then record it.
+        Ok(None) => spawn_reembed(app, backend, backend_id),
+        Err(err) => log_warn(app, "embedding_reembed", err),
+    }
+}
+
+/// Embeds, in the background, stored memories that have no vector from the current backend
+/// yet, such as the originals an undone merge brought back.
+pub(crate) fn embed_pending_memories(app: &AppHandle, backend: &EmbeddingBackend) {
+    if let Some(backend_id) = backend.id(app) {
+        spawn_reembed(app, backend, backend_id);
+    }
+}
+
+fn spawn_reembed(app: &AppHandle, backend: &EmbeddingBackend, backend_id: String) {
+    if REEMBED_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    let backend = backend.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = reembed_memories(&app, &backend, &backend_id).await {
+            log_error(&app, "embedding_reembed", err);
+        }
+        REEMBED_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Rows not yet embedded by `backend_id`, oldest first.
+fn stale_memory_texts(app: &AppHandle, backend_id: &str) -> Result<Vec<(i64, String)>, String> {
+    let conn = open_db(app)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT row_id, text FROM memories WHERE embedding_model IS NOT ?1 ORDER BY row_id",
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map(params![backend_id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(rows)
+}
+
+fn memory_count(app: &AppHandle) -> Result<usize, String> {
+    let conn = open_db(app)?;
+    conn.query_row("SELECT COUNT(*) FROM memories", [], |r| r.get::<_, i64>(0))
+        .map(|count| count as usize)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+pub(crate) fn reembed_status(app: &AppHandle) -> Result<ReembedStatus, String> {
+    let backend_id = EmbeddingBackend::configured(app).id(app);
+    let pending = match backend_id.as_deref() {
+        Some(id) => stale_memory_texts(app, id)?.len(),
+        None => 0,
+    };
+    Ok(ReembedStatus {
+        running: REEMBED_RUNNING.load(Ordering::SeqCst),
+        backend_id,
+        pending: pending as u64,
+        total: memory_count(app)? as u64,
+    })
+}
+
+/// Memories embedded per batch; progress is reported after each one.
+const REEMBED_BATCH_SIZE: usize = 32;
+
+/// Recomputes the vector of every memory not yet embedded by `backend`, tagging each batch as
+/// it lands. Stops at the first failure (the endpoint is likely down), or when the backend is
+/// switched again, and leaves the recorded backend alone so the next call resumes.
+async fn reembed_memories(
+    app: &AppHandle,
+    backend: &EmbeddingBackend,
+    backend_id: &str,
+) -> Result<(), String> {
+    let mut reembedded = 0;
+    // Memories saved meanwhile with a vector from the old backend are stale again, so passes
+    // repeat until none is left; only then is the backend recorded as migrated.
+    loop {
+        let rows = stale_memory_texts(app, backend_id)?;
+        if rows.is_empty() {
+            break;
+        }
+        let total = memory_count(app)?;
+        let mut done = total.saturating_sub(rows.len());
+        let mut updated = 0;
+
+        for chunk in rows.chunks(REEMBED_BATCH_SIZE) {
+            if super::active_embedding_backend_id(app).as_deref() != Some(backend_id) {
+                log_info(
+                    app,
+                    "embedding_reembed",
+                    format!(
+                        "backend switched away from {}, re-embedding paused",
+                        backend_id
+                    ),
+                );
+                return Ok(());
+            }
+            let texts: Vec<String> = chunk.iter().map(|(_, text)| text.clone()).collect();
+            let embeddings = super::embed_with_backend(app, backend, &texts)
+                .await
+                .map_err(|err| {
+                    let _ = app.emit(
+                        REEMBED_PROGRESS_EVENT,
+                        json!({ "done": done, "total": total, "status": "failed", "error": &err }),
+                    );
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("re-embedding stopped at {}/{}: {}", done, total, err),
+                    )
+                })?;
+            let conn = open_db(app)?;
+            for ((row_id, text), embedding) in chunk.iter().zip(&embeddings) {
+                // A memory edited meanwhile is picked up again, with its new text, next pass.
+                updated += conn
+                    .execute(
+                        "UPDATE memories SET embedding = ?1, embedding_dim = ?2, embedding_model = ?3
+                         WHERE row_id = ?4 AND text = ?5",
+                        params![
+                            embedding_to_blob(embedding),
+                            embedding.len() as i64,
+                            backend_id,
+                            row_id,
+                            text
+                        ],
+                    )
+                    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+            }
+            done += chunk.len();
+            let _ = app.emit(
+                REEMBED_PROGRESS_EVENT,
+                json!({ "done": done, "total": total, "status": "running" }),
+            );
+        }
+
+        if updated == 0 {
+            // Every row changed under us; leave the rest to the next call instead of spinning.
+            log_warn(
+                app,
+                "embedding_reembed",
+                format!("{} memories kept changing, re-embedding paused", rows.len()),
+            );
+            return Ok(());
+        }
+        reembedded += updated;
+    }
+
+    write_meta(app, ACTIVE_EMBEDDING_BACKEND_META_KEY, backend_id)?;
+    let total = memory_count(app)?;
+    let _ = app.emit(
+        REEMBED_PROGRESS_EVENT,
+        json!({ "done": total, "total": total, "status": "completed" }),
+    );
+    log_info(
+        app,
+        "embedding_reembed",
+        format!("re-embedded {} memories with {}", reembedded, backend_id),
+    );
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use super::*;
+use crate::transport::download::{DownloadError, ResumableDownload};
+use crate::utils::{log_info, log_warn};
+use ort::{inputs, session::Session, value::Value};
+use std::sync::atomic::Ordering;
+use tauri::Emitter;
+use tokenizers::Tokenizer;
+
+const RERANKER_DIR: &str = "reranker";
+const RERANKER_MODEL_FILE: &str = "model.onnx";
+const RERANKER_TOKENIZER_FILE: &str = "tokenizer.json";
+const RERANKER_MAX_SEQ_LENGTH: usize = 512;
+
+struct LoadedRerankerRuntime {
+    model_path: PathBuf,
+    session: Session,
+    tokenizer: Tokenizer,
+}
+
+lazy_static::lazy_static! {
+    static ref LOADED_RERANKER_RUNTIME: Arc<TokioMutex<Option<LoadedRerankerRuntime>>> =
+        Arc::new(TokioMutex::new(None));
+}
+
+static RERANKER_DOWNLOADING: AtomicBool = AtomicBool::new(false);
+
+pub async fn clear_loaded_reranker_cache() {
+    let mut cache = LOADED_RERANKER_RUNTIME.lock().await;
+    *cache = None;
+}
+
+fn reranker_paths(app: &AppHandle) -> Result<(PathBuf, PathBuf), String> {
+    let dir = embedding_model_dir(app)?.join(RERANKER_DIR);
+    Ok((
+        dir.join(RERANKER_MODEL_FILE),
+        dir.join(RERANKER_TOKENIZER_FILE),
+    ))
+}
+
+/// The cross-encoder is optional; it is used only when both files are present.
+pub fn is_reranker_installed(app: &AppHandle) -> Result<bool, String> {
+    let (model_path, tokenizer_path) = reranker_paths(app)?;
+    Ok(model_path.is_file() && tokenizer_path.is_file())
+}
+
+/// Downloads the cross-encoder into the reranker folder, emitting
+/// `reranker_download_progress`. Partial files are kept, so a failed download continues where
+/// it stopped when started again.
+pub async fn download_reranker_model(app: AppHandle) -> Result<(), String> {
+    if RERANKER_DOWNLOADING.swap(true, Ordering::SeqCst) {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Reranker download already in progress",
+        ));
+    }
+    let result = download_reranker_files(&app).await;
+    RERANKER_DOWNLOADING.store(false, Ordering::SeqCst);
+    if let Err(err) = &result {
+        log_warn(
+            &app,
+            "reranker_download",
+            format!("download failed error={}", err),
+        );
+        let _ = app.emit(
+            "reranker_download_progress",
+            DownloadProgress {
+                downloaded: 0,
+                total: 0,
+                status: "failed".to_string(),
+                current_file_index: 0,
+                total_files: RERANKER_FILES_REMOTE.len(),
+                current_file_name: String::new(),
+            },
+        );
+    }
+    result
+}
+
+async fn download_reranker_files(app: &AppHandle) -> Result<(), String> {
+    let (model_path, tokenizer_path) = reranker_paths(app)?;
+    if let Some(dir) = model_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Failed to create reranker directory: {}", e),
+            )
+        })?;
+    }
+
+    let client = reqwest::Client::new();
+    let targets = [model_path, tokenizer_path];
+    let mut progress = DownloadProgress {
+        downloaded: 0,
+        total: 0,
+        status: "downloading".to_string(),
+        current_file_index: 0,
+        total_files: targets.len(),
+        current_file_name: String::new(),
+    };
+    for (index, (remote_file, dest_path)) in RERANKER_FILES_REMOTE.iter().zip(&targets).enumerate()
+    {
+        if dest_path.is_file() {
+            continue;
+        }
+        let url = format!("{}/{}", HUGGINGFACE_BASE_RERANKER, remote_file);
+        let mut download = ResumableDownload::start(&client, &url, dest_path)
+            .await
+            .map_err(DownloadError::into_message)?;
+        log_info(
+            app,
+            "reranker_download",
+            format!(
+                "download started url={} status={} existing={} remaining={}",
+                url, download.status, download.resumed_from, download.remaining
+            ),
+        );
+        progress.current_file_index = index + 1;
+        progress.current_file_name = remote_file.to_string();
+        progress.downloaded = download.resumed_from;
+        progress.total = download.resumed_from + download.remaining;
+        let _ = app.emit("reranker_download_progress", &progress);
+
+        let mut last_emit = std::time::Instant::now();
+        loop {
+            let chunk = match download.next_chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(err) => {
+                    let _ = download.flush().await;
+                    return Err(err.into_message());
+                }
+            };
+            progress.downloaded += chunk.len() as u64;
+            if last_emit.elapsed().as_millis() > 100 {
+                let _ = app.emit("reranker_download_progress", &progress);
+                last_emit = std::time::Instant::now();
+            }
+        }
+        download
+            .finish()
+            .await
+            .map_err(DownloadError::into_message)?;
+    }
+
+    clear_loaded_reranker_cache().await;
+    progress.status = "completed".to_string();
+    let _ = app.emit("reranker_download_progress", &progress);
+    log_info(app, "reranker_download", "reranker model installed");
+    Ok(())
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Scores one (query, passage) pair. Single-logit heads go through a sigmoid; two-class
+/// heads use the softmax probability of the "relevant" class.
+fn score_pair(
+    session: &mut Session,
+    tokenizer: &Tokenizer,
+    query: &str,
+    passage: &str,
+) -> Result<f32, String> {
+    let encoding = tokenizer.encode((query, passage), true).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Tokenization failed: {}", e),
+        )
+    })?;
+
+    let seq_len = encoding.get_ids().len().min(RERANKER_MAX_SEQ_LENGTH);
+    let to_i64 = |values: &[u32]| -> Vec<i64> {
+        let mut out: Vec<i64> = values.iter().take(seq_len).map(|&x| x as i64).collect();
+        out.resize(seq_len, 0);
+        out
+    };
+    let tensor = |values: Vec<i64>, name: &str| {
+        Value::from_array(([1, seq_len], values)).map_err(|e| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Failed to create {} tensor: {}", name, e),
+            )
+        })
+    };
+
+    let input_ids = tensor(to_i64(encoding.get_ids()), "input_ids")?;
+    let attention_mask = tensor(to_i64(encoding.get_attention_mask()), "attention_mask")?;
+    let expects_token_type_ids = session
+        .inputs
+        .iter()
+        .any(|input| input.name.contains("token_type_ids"));
+
+    let outputs = if expects_token_type_ids {
+        let token_type_ids = tensor(to_i64(encoding.get_type_ids()), "token_type_ids")?;
+        session.run(inputs![
+            "input_ids" => input_ids,
+            "attention_mask" => attention_mask,
+            "token_type_ids" => token_type_ids
+        ])
+    } else {
+        session.run(inputs![
+            "input_ids" => input_ids,
+            "attention_mask" => attention_mask
+        ])
+    }
+    .map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Reranker inference failed: {}", e),
+        )
+    })?;
+
+    let (_, logits) = outputs[0].try_extract_tensor::<f32>().map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Failed to extract reranker logits: {}", e),
+        )
+    })?;
+
+    match logits {
+        [logit] => Ok(sigmoid(*logit)),
+        [negative, positive] => Ok(sigmoid(positive - negative)),
+        other => Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Unexpected reranker output size: {}", other.len()),
+        )),
+    }
+}
+
+/// Scores each passage against `query` with the cross-encoder. Scores are in 0.0-1.0 and
+/// returned in input order.
+pub async fn rerank_passages(
+    app: AppHandle,
+    query: String,
+    passages: Vec<String>,
+) -> Result<Vec<f32>, String> {
+    if passages.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !is_reranker_installed(&app)? {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Reranker model not found. Download it from the Dynamic Memory settings.",
+        ));
+    }
+    let (model_path, tokenizer_path) = reranker_paths(&app)?;
+
+    super::ort_runtime::ensure_ort_init(&app).await?;
+
+    let keep_model_loaded = settings::read_embedding_preferences(&app).keep_model_loaded;
+    let mut cache = LOADED_RERANKER_RUNTIME.lock().await;
+    let reuse = cache
+        .as_ref()
+        .is_some_and(|loaded| loaded.model_path == model_path);
+    if !reuse {
+        let (session, tokenizer) = super::inference::create_runtime(&model_path, &tokenizer_path)?;
+        *cache = Some(LoadedRerankerRuntime {
+            model_path: model_path.clone(),
+            session,
+            tokenizer,
+        });
+        log_info(
+            &app,
+            "memory_rerank",
+            format!("loaded reranker model={}", model_path.display()),
+        );
+    }
+
+    let loaded = cache.as_mut().ok_or_else(|| {
+        crate::utils::err_msg(module_path!(), line!(), "Reranker runtime unavailable")
+    })?;
+    let mut scores = Vec::with_capacity(passages.len());
+    for passage in &passages {
+        scores.push(score_pair(
+            &mut loaded.session,
+            &loaded.tokenizer,
+            &query,
+            passage,
+        )?);
+    }
+
+    if !keep_model_loaded {
+        *cache = None;
+    }
+    Ok(scores)
+}
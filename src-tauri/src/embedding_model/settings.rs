@@ -1,10 +1,15 @@
 use tauri::AppHandle;
 
+use super::backend::RemoteEmbeddingConfig;
+
 #[derive(Debug, Clone, Default)]
 pub(crate) struct EmbeddingPreferences {
     pub(crate) preferred_source_version: Option<String>,
     pub(crate) max_tokens: Option<usize>,
     pub(crate) keep_model_loaded: bool,
+    pub(crate) backend: Option<String>,
+    pub(crate) remote: Option<RemoteEmbeddingConfig>,
+    pub(crate) custom_model_id: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, Default)]
@@ -13,6 +18,9 @@ struct AdvancedSettingsSnapshot {
     embedding_model_version: Option<String>,
     embedding_max_tokens: Option<u64>,
     embedding_keep_model_loaded: Option<bool>,
+    embedding_backend: Option<String>,
+    embedding_remote: Option<RemoteEmbeddingConfig>,
+    embedding_custom_model_id: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, Default)]
@@ -33,5 +41,8 @@ pub(crate) fn read_embedding_preferences(app: &AppHandle) -> EmbeddingPreference
         preferred_source_version: advanced.embedding_model_version,
         max_tokens: advanced.embedding_max_tokens.map(|v| v as usize),
         keep_model_loaded: advanced.embedding_keep_model_loaded.unwrap_or(false),
+        backend: advanced.embedding_backend,
+        remote: advanced.embedding_remote,
+        custom_model_id: advanced.embedding_custom_model_id,
     }
 }
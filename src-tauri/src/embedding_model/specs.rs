@@ -23,12 +23,26 @@ pub(crate) const HUGGINGFACE_BASE_V2: &str =
 pub(crate) const HUGGINGFACE_BASE_V3: &str =
     "https://huggingface.co/Zeolit/lettuce-emb-512d-v3/resolve/main";
 
+/// Cross-encoder used to rerank hybrid retrieval hits (MiniLM-L6 trained on MS MARCO, int8).
+pub(crate) const RERANKER_FILES_REMOTE: [&str; 2] = ["onnx/model_quantized.onnx", "tokenizer.json"];
+pub(crate) const HUGGINGFACE_BASE_RERANKER: &str =
+    "https://huggingface.co/Xenova/ms-marco-MiniLM-L-6-v2/resolve/main";
+
+/// Hugging Face file listings; LFS entries carry the SHA-256 and size of each file.
+pub(crate) const HUGGINGFACE_MANIFEST_V1: &str =
+    "https://huggingface.co/api/models/Zeolit/lettuce-emb-512d-v1/tree/main";
+pub(crate) const HUGGINGFACE_MANIFEST_V2: &str =
+    "https://huggingface.co/api/models/Zeolit/lettuce-emb-512d-v2/tree/main";
+pub(crate) const HUGGINGFACE_MANIFEST_V3: &str =
+    "https://huggingface.co/api/models/Zeolit/lettuce-emb-512d-v3/tree/main";
+
 pub(crate) struct DownloadSourceSpec {
     pub(crate) target_version: EmbeddingModelVersion,
     pub(crate) source_label: &'static str,
     pub(crate) remote_files: &'static [&'static str],
     pub(crate) local_files: &'static [&'static str],
     pub(crate) base_url: &'static str,
+    pub(crate) manifest_url: &'static str,
 }
 
 pub(crate) fn download_source_spec(requested: Option<&str>) -> DownloadSourceSpec {
@@ -39,6 +53,7 @@ pub(crate) fn download_source_spec(requested: Option<&str>) -> DownloadSourceSpe
             remote_files: &MODEL_FILES_V1,
             local_files: &MODEL_FILES_V1,
             base_url: HUGGINGFACE_BASE_V1,
+            manifest_url: HUGGINGFACE_MANIFEST_V1,
         },
         Some("v2") => DownloadSourceSpec {
             target_version: EmbeddingModelVersion::V2,
@@ -46,6 +61,7 @@ pub(crate) fn download_source_spec(requested: Option<&str>) -> DownloadSourceSpe
             remote_files: &MODEL_FILES_V2_REMOTE,
             local_files: &MODEL_FILES_V2_LOCAL,
             base_url: HUGGINGFACE_BASE_V2,
+            manifest_url: HUGGINGFACE_MANIFEST_V2,
         },
         _ => DownloadSourceSpec {
             target_version: EmbeddingModelVersion::V3,
@@ -53,6 +69,7 @@ pub(crate) fn download_source_spec(requested: Option<&str>) -> DownloadSourceSpe
             remote_files: &MODEL_FILES_V3_REMOTE,
             local_files: &MODEL_FILES_V3_LOCAL,
             base_url: HUGGINGFACE_BASE_V3,
+            manifest_url: HUGGINGFACE_MANIFEST_V3,
         },
     }
 }
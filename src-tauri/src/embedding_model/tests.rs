@@ -34,76 +34,80 @@ struct ScoreComparison {
     category: String,
 }
 
+/// Text pairs shared by the embedding self-test and custom model validation:
+/// (name, text_a, text_b, category, threshold, expectation).
+pub(super) const SIMILARITY_TEST_CASES: [(&str, &str, &str, &str, f32, &str); 8] = [
+    (
+        "Semantic: Animal Description",
+        "The quick brown fox jumps over the lazy dog",
+        "A fast fox leaps over a sleepy canine",
+        "semantic",
+        0.6,
+        "High similarity expected - same meaning, different words",
+    ),
+    (
+        "Semantic: Weather",
+        "It's raining heavily outside today",
+        "There's a big storm with lots of precipitation",
+        "semantic",
+        0.5,
+        "High similarity expected - related weather concepts",
+    ),
+    (
+        "Semantic: Greeting",
+        "Hello, how are you doing today?",
+        "Hi there, how's it going?",
+        "semantic",
+        0.6,
+        "High similarity expected - same intent",
+    ),
+    (
+        "Dissimilar: Fox vs Physics",
+        "The quick brown fox jumps over the lazy dog",
+        "Quantum mechanics describes subatomic particle behavior",
+        "dissimilar",
+        0.5,
+        "Low similarity expected - unrelated topics",
+    ),
+    (
+        "Dissimilar: Food vs Technology",
+        "I love eating pizza with extra cheese",
+        "The computer crashed and lost all my files",
+        "dissimilar",
+        0.5,
+        "Low similarity expected - unrelated topics",
+    ),
+    (
+        "Roleplay: Emotional State",
+        "She felt a wave of sadness wash over her",
+        "Her heart ached with sorrow and grief",
+        "roleplay",
+        0.55,
+        "High similarity expected - same emotional content",
+    ),
+    (
+        "Roleplay: Action Description",
+        "He drew his sword and charged at the enemy",
+        "The warrior unsheathed his blade and rushed forward to attack",
+        "roleplay",
+        0.55,
+        "High similarity expected - same action described differently",
+    ),
+    (
+        "Roleplay: Setting",
+        "The tavern was dimly lit with flickering candles",
+        "Candlelight cast shadows across the dark inn",
+        "roleplay",
+        0.5,
+        "High similarity expected - similar scene description",
+    ),
+];
+
 pub async fn run_embedding_test(app: AppHandle) -> Result<TestResult, String> {
     log_info(&app, "embedding_test", "starting embedding test");
     log_info(&app, "embedding_test", "Starting embedding test...");
 
-    let test_cases: Vec<(&str, &str, &str, &str, f32, &str)> = vec![
-        (
-            "Semantic: Animal Description",
-            "The quick brown fox jumps over the lazy dog",
-            "A fast fox leaps over a sleepy canine",
-            "semantic",
-            0.6,
-            "High similarity expected - same meaning, different words",
-        ),
-        (
-            "Semantic: Weather",
-            "It's raining heavily outside today",
-            "There's a big storm with lots of precipitation",
-            "semantic",
-            0.5,
-            "High similarity expected - related weather concepts",
-        ),
-        (
-            "Semantic: Greeting",
-            "Hello, how are you doing today?",
-            "Hi there, how's it going?",
-            "semantic",
-            0.6,
-            "High similarity expected - same intent",
-        ),
-        (
-            "Dissimilar: Fox vs Physics",
-            "The quick brown fox jumps over the lazy dog",
-            "Quantum mechanics describes subatomic particle behavior",
-            "dissimilar",
-            0.5,
-            "Low similarity expected - unrelated topics",
-        ),
-        (
-            "Dissimilar: Food vs Technology",
-            "I love eating pizza with extra cheese",
-            "The computer crashed and lost all my files",
-            "dissimilar",
-            0.5,
-            "Low similarity expected - unrelated topics",
-        ),
-        (
-            "Roleplay: Emotional State",
-            "She felt a wave of sadness wash over her",
-            "Her heart ached with sorrow and grief",
-            "roleplay",
-            0.55,
-            "High similarity expected - same emotional content",
-        ),
-        (
-            "Roleplay: Action Description",
-            "He drew his sword and charged at the enemy",
-            "The warrior unsheathed his blade and rushed forward to attack",
-            "roleplay",
-            0.55,
-            "High similarity expected - same action described differently",
-        ),
-        (
-            "Roleplay: Setting",
-            "The tavern was dimly lit with flickering candles",
-            "Candlelight cast shadows across the dark inn",
-            "roleplay",
-            0.5,
-            "High similarity expected - similar scene description",
-        ),
-    ];
+    let test_cases = SIMILARITY_TEST_CASES;
 
     let total_tests = test_cases.len();
     let _ = app.emit(
@@ -24,11 +24,15 @@ use crate::usage::add_usage_record;
 use crate::usage::tracking::{RequestUsage, UsageFinishReason, UsageOperationType};
 
 use crate::chat_manager::dynamic_memory::{
-    apply_memory_decay, calculate_hot_memory_tokens, cosine_similarity,
-    effective_group_dynamic_memory_settings, enforce_hot_memory_budget, ensure_pinned_hot,
-    generate_memory_id, mark_memories_accessed, normalize_query_text, promote_cold_memories,
-    search_cold_memory_indices_by_keyword, select_relevant_memory_indices,
-    select_top_cosine_memory_indices, trim_memories_to_max,
+    apply_configured_memory_decay, apply_memory_merge, calculate_hot_memory_tokens,
+    cluster_similar_memory_indices, cosine_similarity, effective_group_dynamic_memory_settings,
+    enforce_hot_memory_budget, ensure_pinned_hot, generate_memory_id, mark_memories_accessed,
+    memory_cycle_provenance, memory_merge_action, memory_merge_request, memory_merge_tool_config,
+    normalize_query_text, promote_cold_memories, rerank_memory_hits, same_embedding_space,
+    search_cold_memory_indices_by_keyword, select_hybrid_memory_indices,
+    select_memories_from_store, simulate_memory_decay, trim_memories_to_max,
+    MemoryDecayTrajectory, MEMORY_MERGE_INSTRUCTIONS, SIMULATION_CYCLES_PER_DAY,
+    SIMULATION_HORIZON_HOURS, SIMULATION_STEP_HOURS,
 };
 use crate::chat_manager::prompts::{
     self, APP_DYNAMIC_MEMORY_TEMPLATE_ID, APP_DYNAMIC_SUMMARY_TEMPLATE_ID,
@@ -42,16 +46,19 @@ use crate::chat_manager::tooling::{
     parse_tool_calls, ToolCall, ToolChoice, ToolConfig, ToolDefinition,
 };
 use crate::chat_manager::types::{
-    Character, DynamicMemorySettings, MemoryRetrievalStrategy, Model, Persona, PromptEntryPosition,
-    PromptEntryRole, ProviderCredential, Settings, SystemPromptEntry,
+    llama_request_fields, AdvancedModelSettings, Character, DynamicMemorySettings,
+    MemoryRetrievalStrategy, Model, Persona, PromptEntryPosition, PromptEntryRole,
+    ProviderCredential, Settings, SystemPromptEntry,
 };
 use crate::embedding_model;
+use crate::memory_jobs::{cancel_queued_memory_job, enqueue_memory_job};
 use crate::models::calculate_request_cost;
-use crate::storage_manager::db::{now_ms, SwappablePool};
+use crate::storage_manager::db::{now_ms, open_db, SwappablePool};
 use crate::storage_manager::group_sessions::{
     self, group_session_update_memories_internal, GroupMessage, GroupParticipation, GroupSession,
     MemoryEmbedding, UsageSummary,
 };
+use crate::storage_manager::memories::MemoryOwner;
 use crate::utils::{log_error, log_info, log_warn, now_millis};
 
 pub use selection::parse_mentions;
@@ -186,6 +193,16 @@ fn build_llama_extra_fields(model: &Model, settings: &Settings) -> Option<HashMa
     {
         extra.insert("llamaOffloadKqv".to_string(), json!(v));
     }
+    let layers: Vec<&AdvancedModelSettings> = [
+        model.advanced_model_settings.as_ref(),
+        Some(&settings.advanced_model_settings),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    for (key, value) in llama_request_fields(&layers) {
+        extra.insert(key.to_string(), value);
+    }
 
     if extra.is_empty() {
         None
@@ -392,7 +409,7 @@ async fn record_group_usage(
         cost: None,
         success: true,
         error_message: None,
-        metadata: Default::default(),
+        metadata: usage_info.draft_metadata(),
     };
 
     // Calculate memory and summary token counts from group session
@@ -500,7 +517,7 @@ async fn record_decision_maker_usage(
         cost: None,
         success: true,
         error_message: None,
-        metadata: Default::default(),
+        metadata: usage_info.draft_metadata(),
     };
 
     // Calculate cost for OpenRouter
@@ -544,6 +561,74 @@ async fn record_decision_maker_usage(
     }
 }
 
+/// Record usage for memory manager requests (memory consolidation)
+async fn record_group_memory_usage(
+    app: &AppHandle,
+    usage: &Option<crate::chat_manager::types::UsageSummary>,
+    session: &GroupSession,
+    model: &Model,
+    provider_cred: &ProviderCredential,
+    api_key: &str,
+    log_scope: &str,
+) {
+    let Some(usage_info) = usage else {
+        return;
+    };
+
+    let mut request_usage = RequestUsage {
+        id: Uuid::new_v4().to_string(),
+        timestamp: now_millis().unwrap_or(0),
+        session_id: session.id.clone(),
+        character_id: "memory_manager".to_string(),
+        character_name: "Memory Manager".to_string(),
+        model_id: model.id.clone(),
+        model_name: model.name.clone(),
+        provider_id: provider_cred.provider_id.clone(),
+        provider_label: provider_cred.provider_id.clone(),
+        operation_type: UsageOperationType::MemoryManager,
+        finish_reason: usage_info
+            .finish_reason
+            .as_ref()
+            .and_then(|s| UsageFinishReason::from_str(s)),
+        prompt_tokens: usage_info.prompt_tokens,
+        completion_tokens: usage_info.completion_tokens,
+        total_tokens: usage_info.total_tokens,
+        memory_tokens: None,
+        summary_tokens: None,
+        reasoning_tokens: usage_info.reasoning_tokens,
+        image_tokens: usage_info.image_tokens,
+        cost: None,
+        success: true,
+        error_message: None,
+        metadata: usage_info.draft_metadata(),
+    };
+
+    if provider_cred.provider_id.eq_ignore_ascii_case("openrouter") {
+        if let Ok(Some(pricing)) = get_model_pricing(
+            app.clone(),
+            &provider_cred.provider_id,
+            &model.name,
+            Some(api_key),
+        )
+        .await
+        {
+            request_usage.cost = calculate_request_cost(
+                usage_info.prompt_tokens.map(|v| v as u64).unwrap_or(0),
+                usage_info.completion_tokens.map(|v| v as u64).unwrap_or(0),
+                &pricing,
+            );
+        }
+    }
+
+    if let Err(e) = add_usage_record(app, request_usage) {
+        log_error(
+            app,
+            log_scope,
+            format!("failed to record memory manager usage: {}", e),
+        );
+    }
+}
+
 fn format_memories_with_ids(session: &GroupSession) -> Vec<String> {
     session
         .memory_embeddings
@@ -777,6 +862,7 @@ async fn select_relevant_memories(
     limit: usize,
     min_similarity: f32,
     strategy: &MemoryRetrievalStrategy,
+    rerank: bool,
 ) -> Vec<MemoryEmbedding> {
     if query.is_empty() || session.memory_embeddings.is_empty() {
         return Vec::new();
@@ -795,93 +881,127 @@ async fn select_relevant_memories(
             }
         };
 
-    if matches!(strategy, MemoryRetrievalStrategy::Cosine) {
-        let cosine_indices = select_top_cosine_memory_indices(
+    let conn = match open_db(app) {
+        Ok(conn) => conn,
+        Err(err) => {
+            log_warn(
+                app,
+                "group_memory_retrieval",
+                format!("database unavailable: {}", err),
+            );
+            return Vec::new();
+        }
+    };
+    let owner = MemoryOwner::GroupSession(&session.id);
+
+    if matches!(strategy, MemoryRetrievalStrategy::Hybrid) {
+        let pool = if rerank { limit * 3 } else { limit };
+        let mut hits = select_hybrid_memory_indices(
+            &conn,
+            owner,
+            query,
             &query_embedding,
             &session.memory_embeddings,
-            limit,
+            pool,
             min_similarity,
         );
-        if cosine_indices.is_empty() {
-            return Vec::new();
+        drop(conn);
+        if rerank {
+            if let Err(err) =
+                rerank_memory_hits(app, query, &session.memory_embeddings, &mut hits).await
+            {
+                log_warn(
+                    app,
+                    "group_memory_retrieval",
+                    format!("rerank skipped: {}", err),
+                );
+            }
         }
-        return cosine_indices
+        return hits
             .into_iter()
-            .filter_map(|(idx, _score)| session.memory_embeddings.get(idx).cloned())
+            .take(limit)
+            .filter_map(|(idx, breakdown)| {
+                session.memory_embeddings.get(idx).map(|mem| {
+                    let mut cloned = mem.clone();
+                    cloned.match_score = Some(breakdown.final_score());
+                    cloned.score_breakdown = Some(breakdown);
+                    cloned
+                })
+            })
             .collect();
     }
 
-    // Smart mode: blend semantic match + recency/frequency + fallback fill.
-    let cosine_limit = (limit.saturating_sub(2)).max(1);
-    let cosine_indices = select_relevant_memory_indices(
+    let ranked: Vec<(MemoryEmbedding, f32)> = match select_memories_from_store(
+        &conn,
+        owner,
         &query_embedding,
-        &session.memory_embeddings,
-        cosine_limit,
+        limit,
         min_similarity,
-    );
+        strategy,
+    ) {
+        Ok(ranked) => ranked,
+        Err(err) => {
+            log_warn(
+                app,
+                "group_memory_retrieval",
+                format!("memory store unavailable: {}", err),
+            );
+            Vec::new()
+        }
+    };
+    let mut ranked = ranked.into_iter().map(|(mut mem, score)| {
+        mem.match_score = Some(score);
+        mem
+    });
 
-    let mut selected: HashSet<usize> = HashSet::new();
+    if matches!(strategy, MemoryRetrievalStrategy::Cosine) {
+        return ranked.collect();
+    }
+
+    // Smart mode: blend semantic match + recency/frequency + fallback fill.
+    let cosine_limit = (limit.saturating_sub(2)).max(1);
+    let mut selected: HashSet<String> = HashSet::new();
     let mut results: Vec<MemoryEmbedding> = Vec::new();
 
-    for (idx, _score) in &cosine_indices {
-        if let Some(mem) = session.memory_embeddings.get(*idx) {
-            results.push(mem.clone());
-            selected.insert(*idx);
-        }
+    while results.len() < cosine_limit {
+        let Some(mem) = ranked.next() else { break };
+        selected.insert(mem.id.clone());
+        results.push(mem);
     }
 
     // 2. Add 1 most recently created hot memory (if not already selected)
     if results.len() < limit {
-        if let Some(recent_idx) = session
+        if let Some(mem) = session
             .memory_embeddings
             .iter()
-            .enumerate()
-            .filter(|(i, m)| !m.is_cold && !selected.contains(i))
-            .max_by_key(|(_, m)| m.created_at)
-            .map(|(i, _)| i)
+            .filter(|m| !m.is_cold && !selected.contains(&m.id))
+            .max_by_key(|m| m.created_at)
         {
-            if let Some(mem) = session.memory_embeddings.get(recent_idx) {
-                results.push(mem.clone());
-                selected.insert(recent_idx);
-            }
+            selected.insert(mem.id.clone());
+            results.push(mem.clone());
         }
     }
 
     // 3. Add 1 most frequently accessed hot memory (if not already selected, access_count > 0)
     if results.len() < limit {
-        if let Some(freq_idx) = session
+        if let Some(mem) = session
             .memory_embeddings
             .iter()
-            .enumerate()
-            .filter(|(i, m)| !m.is_cold && !selected.contains(i) && m.access_count > 0)
-            .max_by_key(|(_, m)| m.access_count)
-            .map(|(i, _)| i)
+            .filter(|m| !m.is_cold && !selected.contains(&m.id) && m.access_count > 0)
+            .max_by_key(|m| m.access_count)
         {
-            if let Some(mem) = session.memory_embeddings.get(freq_idx) {
-                results.push(mem.clone());
-                selected.insert(freq_idx);
-            }
+            selected.insert(mem.id.clone());
+            results.push(mem.clone());
         }
     }
 
     // 4. Fill remaining slots with next best cosine results
-    if results.len() < limit {
-        let extra_indices = select_relevant_memory_indices(
-            &query_embedding,
-            &session.memory_embeddings,
-            limit,
-            min_similarity,
-        );
-        for (idx, _score) in extra_indices {
-            if results.len() >= limit {
-                break;
-            }
-            if !selected.contains(&idx) {
-                if let Some(mem) = session.memory_embeddings.get(idx) {
-                    results.push(mem.clone());
-                    selected.insert(idx);
-                }
-            }
+    for mem in ranked {
+        if results.len() >= limit {
+            break;
+        }
+        if selected.insert(mem.id.clone()) {
+            results.push(mem);
         }
     }
 
@@ -916,6 +1036,15 @@ async fn select_relevant_memories(
 // ============================================================================
 
 /// Process dynamic memory cycle for group chat after a response
+/// Group memories are shared by every participant, so their budgets are counted with the
+/// default chat model.
+fn memory_budget_model(settings: &Settings) -> Option<&Model> {
+    settings
+        .default_model_id
+        .as_ref()
+        .and_then(|id| settings.models.iter().find(|m| &m.id == id))
+}
+
 async fn process_group_dynamic_memory_cycle(
     app: &AppHandle,
     session: &mut GroupSession,
@@ -1082,10 +1211,11 @@ async fn process_group_dynamic_memory_cycle(
         );
     }
 
-    let decay_rate = dynamic_settings.decay_rate;
-    let cold_threshold = dynamic_settings.cold_threshold;
-    let (decayed, demoted) =
-        apply_memory_decay(&mut session.memory_embeddings, decay_rate, cold_threshold);
+    let (decayed, demoted) = apply_configured_memory_decay(
+        &mut session.memory_embeddings,
+        &dynamic_settings,
+        now_millis().unwrap_or_default(),
+    );
     if decayed > 0 || !demoted.is_empty() {
         log_info(
             app,
@@ -1228,7 +1358,7 @@ async fn process_group_dynamic_memory_cycle(
                 &window_message_ids,
                 prior_summary.as_deref(),
             );
-            return Ok(());
+            return Err(err);
         }
     };
 
@@ -1239,7 +1369,7 @@ async fn process_group_dynamic_memory_cycle(
         "invoking run_group_memory_tool_update",
     );
 
-    let actions = match run_group_memory_tool_update(
+    let mut actions = match run_group_memory_tool_update(
         app,
         summary_provider,
         summary_model,
@@ -1270,21 +1400,37 @@ async fn process_group_dynamic_memory_cycle(
                 &window_message_ids,
                 prior_summary.as_deref(),
             );
-            return Ok(());
+            return Err(err);
         }
     };
+    if dynamic_settings.consolidation_enabled {
+        let merges = consolidate_group_memories(
+            app,
+            summary_provider,
+            summary_model,
+            &api_key,
+            session,
+            settings,
+            dynamic_settings.consolidation_threshold,
+        )
+        .await;
+        actions.extend(merges);
+    }
     log_info(
         app,
         "group_dynamic_memory",
         format!(
             "summary generated: length={} chars tokens={}",
             summary.len(),
-            crate::tokenizer::count_tokens(app, &summary).unwrap_or(0)
+            crate::tokenizer::count_model_tokens(app, memory_budget_model(settings), &summary)
         ),
     );
     session.memory_summary = summary;
-    session.memory_summary_token_count =
-        crate::tokenizer::count_tokens(app, &session.memory_summary).unwrap_or(0) as i32;
+    session.memory_summary_token_count = crate::tokenizer::count_model_tokens(
+        app,
+        memory_budget_model(settings),
+        &session.memory_summary,
+    ) as i32;
 
     // Enforce token budget
     let pinned_fixed = ensure_pinned_hot(&mut session.memory_embeddings);
@@ -1522,6 +1668,215 @@ async fn summarize_group_messages(
         .ok_or_else(|| "Failed to summarize group messages".to_string())
 }
 
+/// Merge clusters of near-duplicate hot memories with the summarisation model.
+/// Returns the `merge_memories` actions for this cycle's tool event.
+async fn consolidate_group_memories(
+    app: &AppHandle,
+    provider_cred: &ProviderCredential,
+    model: &Model,
+    api_key: &str,
+    session: &mut GroupSession,
+    settings: &Settings,
+    threshold: f32,
+) -> Vec<Value> {
+    let clusters = cluster_similar_memory_indices(&session.memory_embeddings, threshold);
+    if clusters.is_empty() {
+        return Vec::new();
+    }
+    log_info(
+        app,
+        "group_dynamic_memory",
+        format!(
+            "consolidating {} clusters of similar memories (threshold={})",
+            clusters.len(),
+            threshold
+        ),
+    );
+
+    let mut actions = Vec::new();
+    for cluster in clusters {
+        // Earlier merges shift indices, so track members by id.
+        let ids: Vec<String> = cluster
+            .iter()
+            .map(|&idx| session.memory_embeddings[idx].id.clone())
+            .collect();
+        let request = memory_merge_request(&session.memory_embeddings, &cluster);
+        let merged = merge_group_memory_texts(
+            app,
+            provider_cred,
+            model,
+            api_key,
+            session,
+            settings,
+            &request,
+        )
+        .await;
+        let text = match merged {
+            Ok(text) => text,
+            Err(err) => {
+                log_warn(
+                    app,
+                    "group_dynamic_memory",
+                    format!("memory merge failed for {:?}: {}", ids, err),
+                );
+                continue;
+            }
+        };
+        let embedding = match embedding_model::compute_embedding(app.clone(), text.clone()).await {
+            Ok(vec) => vec,
+            Err(err) => {
+                log_warn(
+                    app,
+                    "group_dynamic_memory",
+                    format!("failed to embed merged memory: {}", err),
+                );
+                continue;
+            }
+        };
+        let token_count =
+            crate::tokenizer::count_model_tokens(app, memory_budget_model(settings), &text);
+
+        let current: Vec<usize> = ids
+            .iter()
+            .filter_map(|id| session.memory_embeddings.iter().position(|m| &m.id == id))
+            .collect();
+        if current.len() < 2 {
+            continue;
+        }
+        let (merged_id, originals) = apply_memory_merge(
+            &mut session.memory_embeddings,
+            &current,
+            text.clone(),
+            embedding,
+            embedding_model::active_embedding_backend_id(app),
+            token_count,
+            &model.id,
+        );
+        log_info(
+            app,
+            "group_dynamic_memory",
+            format!("Merged memories {:?} into {}", ids, merged_id),
+        );
+        actions.push(memory_merge_action(
+            &merged_id,
+            &text,
+            &originals,
+            now_millis().unwrap_or_default(),
+        ));
+    }
+    actions
+}
+
+async fn merge_group_memory_texts(
+    app: &AppHandle,
+    provider_cred: &ProviderCredential,
+    model: &Model,
+    api_key: &str,
+    session: &GroupSession,
+    settings: &Settings,
+    request: &str,
+) -> Result<String, String> {
+    let mut messages_for_api = Vec::new();
+    let system_role = crate::chat_manager::request_builder::system_role_for(provider_cred);
+    crate::chat_manager::messages::push_system_message(
+        &mut messages_for_api,
+        &system_role,
+        Some(MEMORY_MERGE_INSTRUCTIONS.to_string()),
+    );
+    messages_for_api.push(json!({ "role": "user", "content": request }));
+
+    let max_tokens = settings
+        .advanced_model_settings
+        .max_output_tokens
+        .unwrap_or(2048);
+
+    let context_length = resolve_context_length(model, settings);
+    let extra_body_fields = if provider_cred.provider_id == "llamacpp" {
+        build_llama_extra_fields(model, settings)
+    } else if provider_cred.provider_id == "ollama" {
+        build_ollama_extra_fields(
+            model,
+            settings,
+            context_length,
+            max_tokens,
+            0.2,
+            1.0,
+            None,
+            None,
+            None,
+        )
+    } else {
+        None
+    };
+    let built = crate::chat_manager::request_builder::build_chat_request(
+        provider_cred,
+        api_key,
+        &model.name,
+        &messages_for_api,
+        None,
+        0.2,
+        1.0,
+        max_tokens,
+        context_length,
+        false,
+        None,
+        None,
+        None,
+        None,
+        Some(&memory_merge_tool_config()),
+        false,
+        None,
+        None,
+        extra_body_fields,
+    );
+
+    let api_request_payload = ApiRequest {
+        url: built.url,
+        method: Some("POST".into()),
+        headers: Some(built.headers),
+        query: None,
+        body: Some(built.body),
+        timeout_ms: Some(60_000),
+        stream: Some(false),
+        request_id: built.request_id.clone(),
+        provider_id: Some(provider_cred.provider_id.clone()),
+    };
+
+    let api_response = api_request(app.clone(), api_request_payload).await?;
+
+    let usage = extract_usage(api_response.data());
+    record_group_memory_usage(
+        app,
+        &usage,
+        session,
+        model,
+        provider_cred,
+        api_key,
+        "group_memory_consolidation",
+    )
+    .await;
+
+    if !api_response.ok {
+        let fallback = format!("Provider returned status {}", api_response.status);
+        let err_message = extract_error_message(api_response.data()).unwrap_or(fallback.clone());
+        return Err(err_message);
+    }
+
+    let calls = parse_tool_calls(&provider_cred.provider_id, api_response.data());
+    for call in calls {
+        if call.name == "write_memory" {
+            if let Some(text) = extract_text_argument(&call) {
+                return Ok(text);
+            }
+        }
+    }
+
+    extract_text(api_response.data(), Some(&provider_cred.provider_id))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Failed to merge group memories".to_string())
+}
+
 /// Run memory tool update for group chat
 async fn run_group_memory_tool_update(
     app: &AppHandle,
@@ -1680,9 +2035,16 @@ async fn run_group_memory_tool_update(
                                 None
                             }
                         };
+                    let embedding_model_id = embedding
+                        .as_ref()
+                        .and_then(|_| embedding_model::active_embedding_backend_id(app));
                     if let Some(ref new_emb) = embedding {
                         let is_duplicate = session.memory_embeddings.iter().any(|existing| {
                             !existing.embedding.is_empty()
+                                && same_embedding_space(
+                                    existing.embedding_model.as_deref(),
+                                    embedding_model_id.as_deref(),
+                                )
                                 && cosine_similarity(new_emb, &existing.embedding) > 0.85
                         });
                         if is_duplicate {
@@ -1701,7 +2063,11 @@ async fn run_group_memory_tool_update(
                             continue;
                         }
                     }
-                    let token_count = crate::tokenizer::count_tokens(app, &text).unwrap_or(0);
+                    let token_count = crate::tokenizer::count_model_tokens(
+                        app,
+                        memory_budget_model(settings),
+                        &text,
+                    );
                     let is_pinned = call
                         .arguments
                         .get("important")
@@ -1717,6 +2083,7 @@ async fn run_group_memory_tool_update(
                         id: mem_id.clone(),
                         text,
                         embedding: embedding.unwrap_or_default(),
+                        embedding_model: embedding_model_id,
                         created_at: now_millis().unwrap_or_default() as i64,
                         token_count: token_count as i32,
                         is_cold: false,
@@ -1724,7 +2091,16 @@ async fn run_group_memory_tool_update(
                         importance_score: 1.0,
                         is_pinned,
                         access_count: 0,
+                        match_score: None,
                         category,
+                        score_breakdown: None,
+                        provenance: Some(memory_cycle_provenance(
+                            &convo_window
+                                .iter()
+                                .map(|m| m.id.as_str())
+                                .collect::<Vec<_>>(),
+                            &model.id,
+                        )),
                     });
 
                     actions_log.push(json!({
@@ -3049,6 +3425,7 @@ async fn generate_character_response(
             dynamic_settings.retrieval_limit.max(1) as usize,
             min_similarity,
             &dynamic_settings.retrieval_strategy,
+            dynamic_settings.reranker_enabled,
         )
         .await
     } else {
@@ -3592,22 +3969,16 @@ pub async fn group_chat_send(
         ),
     );
 
-    let conn = pool.get_connection()?;
-    let session_json = group_sessions::group_session_get_internal(&conn, &session_id)?;
-    let mut updated_session: GroupSession = serde_json::from_str(&session_json)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     let dynamic_settings = effective_group_dynamic_memory_settings(&settings);
     let dynamic_enabled =
         dynamic_settings.enabled && character.memory_type.eq_ignore_ascii_case("dynamic");
 
     if dynamic_enabled {
-        if let Err(e) =
-            process_group_dynamic_memory_cycle(&app, &mut updated_session, &settings, &pool).await
-        {
+        if let Err(e) = enqueue_memory_job(&app, MemoryOwner::GroupSession(&session_id)) {
             log_warn(
                 &app,
                 "group_chat_send",
-                format!("Dynamic memory cycle failed: {}", e),
+                format!("Failed to queue dynamic memory cycle: {}", e),
             );
         }
     }
@@ -3647,9 +4018,58 @@ pub async fn group_chat_retry_dynamic_memory(
         return Ok(());
     }
 
+    cancel_queued_memory_job(&app, MemoryOwner::GroupSession(&session_id))?;
     process_group_dynamic_memory_cycle(&app, &mut session, &settings, &pool).await
 }
 
+/// Runs the group memory cycle for a job from the memory job queue.
+pub(crate) async fn run_queued_group_memory_cycle(
+    app: &AppHandle,
+    session_id: &str,
+) -> Result<(), String> {
+    let settings = load_settings(app)?;
+    let pool = app.state::<SwappablePool>();
+    let session_json = {
+        let conn = pool.get_connection()?;
+        group_sessions::group_session_get_internal(&conn, session_id)?
+    };
+    let mut session: GroupSession = serde_json::from_str(&session_json)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    process_group_dynamic_memory_cycle(app, &mut session, &settings, &pool).await
+}
+
+/// Group counterpart of `simulate_dynamic_memory_decay`.
+#[tauri::command]
+pub fn group_chat_simulate_memory_decay(
+    app: AppHandle,
+    session_id: String,
+    overrides: Option<DynamicMemorySettings>,
+    horizon_hours: Option<f32>,
+    step_hours: Option<f32>,
+    cycles_per_day: Option<f32>,
+    pool: State<'_, SwappablePool>,
+) -> Result<Vec<MemoryDecayTrajectory>, String> {
+    let settings = load_settings(&app)?;
+    let conn = pool.get_connection()?;
+    let session_json = group_sessions::group_session_get_internal(&conn, &session_id)?;
+    let session: GroupSession = serde_json::from_str(&session_json)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let dynamic_settings = match overrides {
+        Some(custom) => custom,
+        None => effective_group_dynamic_memory_settings(&settings),
+    };
+
+    Ok(simulate_memory_decay(
+        &session.memory_embeddings,
+        &dynamic_settings,
+        horizon_hours.unwrap_or(SIMULATION_HORIZON_HOURS),
+        step_hours.unwrap_or(SIMULATION_STEP_HOURS),
+        cycles_per_day.unwrap_or(SIMULATION_CYCLES_PER_DAY),
+        now_millis().unwrap_or_default(),
+    ))
+}
+
 #[tauri::command]
 pub async fn group_chat_regenerate(
     app: AppHandle,
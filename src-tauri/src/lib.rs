@@ -10,6 +10,7 @@ mod group_chat_manager;
 mod image_generator;
 mod llama_cpp;
 mod logger;
+mod memory_jobs;
 pub mod migrations;
 pub mod models;
 mod pricing_cache;
@@ -196,6 +197,10 @@ pub fn run() {
                 utils::log_error(app.handle(), "bootstrap", format!("Migration error: {}", e));
             }
 
+            app.manage(memory_jobs::MemoryJobQueue::default());
+            memory_jobs::start_memory_job_worker(app.handle().clone());
+            embedding_model::ensure_embedding_backend_current(app.handle());
+
             if let Err(e) = chat_manager::prompts::ensure_app_default_template(app.handle()) {
                 utils::log_error(
                     app.handle(),
@@ -251,6 +256,10 @@ pub fn run() {
             storage_manager::characters::characters_list,
             storage_manager::characters::character_upsert,
             storage_manager::characters::character_delete,
+            storage_manager::characters::character_memories_list,
+            storage_manager::characters::character_memory_update,
+            storage_manager::characters::character_memory_delete,
+            storage_manager::characters::character_memory_timeline,
             storage_manager::lorebook::lorebooks_list,
             storage_manager::lorebook::lorebook_upsert,
             storage_manager::lorebook::lorebook_delete,
@@ -264,6 +273,8 @@ pub fn run() {
             storage_manager::lorebook::lorebook_entries_reorder,
             storage_manager::lorebook::lorebook_export,
             storage_manager::lorebook::lorebook_import,
+            storage_manager::lorebook::lorebook_import_preview,
+            storage_manager::lorebook::lorebook_detect_format,
             storage_manager::entity_transfer::character_export,
             storage_manager::entity_transfer::character_export_with_format,
             storage_manager::entity_transfer::character_import,
@@ -302,6 +313,19 @@ pub fn run() {
             storage_manager::sessions::session_update_memory,
             storage_manager::sessions::session_toggle_memory_pin,
             storage_manager::sessions::session_set_memory_cold_state,
+            storage_manager::sessions::session_undo_memory_merge,
+            storage_manager::sessions::session_share_memory,
+            storage_manager::sessions::session_memory_timeline,
+            storage_manager::sessions::session_memories_export,
+            storage_manager::sessions::session_memories_import,
+            memory_jobs::memory_jobs_list,
+            memory_jobs::memory_job_retry,
+            memory_jobs::memory_job_cancel,
+            storage_manager::knowledge_graph::session_knowledge_graph,
+            storage_manager::knowledge_graph::knowledge_graph_entity_update,
+            storage_manager::knowledge_graph::knowledge_graph_entity_delete,
+            storage_manager::knowledge_graph::knowledge_graph_relation_update,
+            storage_manager::knowledge_graph::knowledge_graph_relation_delete,
             storage_manager::usage::storage_clear_all,
             storage_manager::usage::storage_reset_database,
             storage_manager::usage::storage_usage_summary,
@@ -346,6 +370,7 @@ pub fn run() {
             chat_manager::chat_generate_user_reply,
             chat_manager::retry_dynamic_memory,
             chat_manager::trigger_dynamic_memory,
+            chat_manager::simulate_dynamic_memory_decay,
             chat_manager::list_prompt_templates,
             chat_manager::create_prompt_template,
             chat_manager::update_prompt_template,
@@ -373,15 +398,29 @@ pub fn run() {
             utils::get_app_version,
             embedding_model::check_embedding_model,
             embedding_model::get_embedding_model_info,
+            embedding_model::test_embedding_backend,
+            embedding_model::get_embedding_reembed_status,
             embedding_model::start_embedding_download,
             embedding_model::get_embedding_download_progress,
             embedding_model::cancel_embedding_download,
+            embedding_model::verify_embedding_model,
             embedding_model::compute_embedding,
+            embedding_model::compute_embeddings,
+            embedding_model::embedding_cache_stats,
+            embedding_model::embedding_cache_clear,
+            embedding_model::list_custom_embedding_models,
+            embedding_model::register_custom_embedding_model,
+            embedding_model::update_custom_embedding_model,
+            embedding_model::delete_custom_embedding_model,
+            embedding_model::validate_custom_embedding_model,
             embedding_model::initialize_embedding_model,
             embedding_model::clear_embedding_runtime_cache,
             embedding_model::run_embedding_test,
             embedding_model::run_embedding_dev_benchmark,
             embedding_model::compare_custom_texts,
+            embedding_model::check_reranker_model,
+            embedding_model::download_reranker_model,
+            embedding_model::rerank_texts,
             embedding_model::delete_embedding_model,
             embedding_model::delete_embedding_model_version,
             image_generator::commands::generate_image,
@@ -436,6 +475,13 @@ pub fn run() {
             discovery::discovery_import_character,
             llama_cpp::llamacpp_context_info,
             llama_cpp::llamacpp_unload,
+            llama_cpp::library::llamacpp_library_list,
+            llama_cpp::library::llamacpp_library_register,
+            llama_cpp::library::llamacpp_library_remove,
+            llama_cpp::library::llamacpp_library_check_fit,
+            llama_cpp::library::llamacpp_library_download,
+            llama_cpp::library::llamacpp_library_cancel_download,
+            tokenizer::count_tokens,
             content_filter::set_content_filter_level,
             content_filter::debug_content_filter,
             content_filter::get_filter_log,
@@ -473,12 +519,17 @@ pub fn run() {
             storage_manager::group_sessions::group_session_update_memory,
             storage_manager::group_sessions::group_session_toggle_memory_pin,
             storage_manager::group_sessions::group_session_set_memory_cold_state,
+            storage_manager::group_sessions::group_session_undo_memory_merge,
+            storage_manager::group_sessions::group_session_memory_timeline,
+            storage_manager::group_sessions::group_session_memories_export,
+            storage_manager::group_sessions::group_session_memories_import,
             group_chat_manager::group_chat_send,
             group_chat_manager::group_chat_regenerate,
             group_chat_manager::group_chat_continue,
             group_chat_manager::group_chat_get_selection_prompt,
             group_chat_manager::group_chat_generate_user_reply,
             group_chat_manager::group_chat_retry_dynamic_memory,
+            group_chat_manager::group_chat_simulate_memory_decay,
         ])
         .build(tauri::generate_context!())
         .expect("error while running tauri application")
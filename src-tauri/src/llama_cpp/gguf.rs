@@ -0,0 +1,397 @@
+//! Reads GGUF headers (metadata and tensor shapes) without touching the weights.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use super::KvShape;
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+/// Strings past this length are skipped instead of read; chat templates are a few KiB at most.
+const MAX_STRING_LEN: u64 = 1 << 20;
+const MAX_TENSOR_DIMS: u32 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Uint(u64),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// Arrays (token lists, per-layer values) are skipped; only their length is kept.
+    Array(u64),
+    /// A string too long to be worth reading.
+    Skipped,
+}
+
+#[derive(Debug, Default)]
+pub struct GgufHeader {
+    pub version: u32,
+    pub metadata: HashMap<String, MetadataValue>,
+    pub tensor_count: u64,
+    pub parameter_count: u64,
+}
+
+impl GgufHeader {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Failed to open {}: {}", path.display(), e),
+            )
+        })?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<Self, String> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).map_err(io_err)?;
+        if &magic != GGUF_MAGIC {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "Not a GGUF file",
+            ));
+        }
+        let version = read_u32(&mut reader)?;
+        if version < 2 {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Unsupported GGUF version {}", version),
+            ));
+        }
+        let tensor_count = read_u64(&mut reader)?;
+        let kv_count = read_u64(&mut reader)?;
+
+        let mut metadata = HashMap::new();
+        for _ in 0..kv_count {
+            let key = match read_string(&mut reader)? {
+                Some(key) => key,
+                None => {
+                    return Err(crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        "GGUF metadata key is too long",
+                    ))
+                }
+            };
+            let value_type = read_u32(&mut reader)?;
+            metadata.insert(key, read_value(&mut reader, value_type)?);
+        }
+
+        let mut parameter_count = 0u64;
+        for _ in 0..tensor_count {
+            skip_string(&mut reader)?;
+            let n_dims = read_u32(&mut reader)?;
+            if n_dims > MAX_TENSOR_DIMS {
+                return Err(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Tensor has {} dimensions", n_dims),
+                ));
+            }
+            let mut elements = 1u64;
+            for _ in 0..n_dims {
+                elements = elements.saturating_mul(read_u64(&mut reader)?);
+            }
+            // ggml type and data offset.
+            read_u32(&mut reader)?;
+            read_u64(&mut reader)?;
+            parameter_count = parameter_count.saturating_add(elements);
+        }
+
+        Ok(Self {
+            version,
+            metadata,
+            tensor_count,
+            parameter_count,
+        })
+    }
+
+    pub fn string(&self, key: &str) -> Option<&str> {
+        match self.metadata.get(key)? {
+            MetadataValue::String(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn uint(&self, key: &str) -> Option<u64> {
+        match self.metadata.get(key)? {
+            MetadataValue::Uint(value) => Some(*value),
+            MetadataValue::Int(value) => u64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn architecture(&self) -> Option<&str> {
+        self.string("general.architecture")
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.string("general.name")
+    }
+
+    pub fn chat_template(&self) -> Option<&str> {
+        self.string("tokenizer.chat_template")
+    }
+
+    pub fn quantization(&self) -> Option<&'static str> {
+        file_type_name(self.uint("general.file_type")?)
+    }
+
+    fn arch_uint(&self, suffix: &str) -> Option<u64> {
+        self.uint(&format!("{}.{}", self.architecture()?, suffix))
+    }
+
+    pub fn context_length(&self) -> Option<u32> {
+        self.arch_uint("context_length")
+            .and_then(|v| u32::try_from(v).ok())
+    }
+
+    pub fn kv_shape(&self) -> Option<KvShape> {
+        let n_head = self.arch_uint("attention.head_count")?;
+        Some(KvShape {
+            n_layer: self.arch_uint("block_count")?,
+            n_embd: self.arch_uint("embedding_length")?,
+            n_head,
+            // Some architectures store this per layer as an array; treat those as plain MHA.
+            n_head_kv: self.arch_uint("attention.head_count_kv").unwrap_or(n_head),
+        })
+    }
+}
+
+/// Name of a `general.file_type` (llama_ftype) value, as used in GGUF file names.
+pub fn file_type_name(file_type: u64) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        36 => "TQ1_0",
+        37 => "TQ2_0",
+        _ => return None,
+    })
+}
+
+fn io_err(e: std::io::Error) -> String {
+    crate::utils::err_msg(
+        module_path!(),
+        line!(),
+        format!("Failed to read GGUF header: {}", e),
+    )
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> Result<[u8; N], String> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf).map_err(io_err)?;
+    Ok(buf)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, String> {
+    read_array(reader).map(u32::from_le_bytes)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, String> {
+    read_array(reader).map(u64::from_le_bytes)
+}
+
+fn skip_bytes<R: Seek>(reader: &mut R, len: u64) -> Result<(), String> {
+    let len = i64::try_from(len)
+        .map_err(|_| crate::utils::err_msg(module_path!(), line!(), "GGUF length overflow"))?;
+    // Relative seeks keep `BufReader`'s buffer, which matters when skipping token lists.
+    reader.seek_relative(len).map_err(io_err)
+}
+
+/// Reads a length-prefixed string, or skips it and returns `None` if it is oversized.
+fn read_string<R: Read + Seek>(reader: &mut R) -> Result<Option<String>, String> {
+    let len = read_u64(reader)?;
+    if len > MAX_STRING_LEN {
+        skip_bytes(reader, len)?;
+        return Ok(None);
+    }
+    let mut buf = vec![0u8; len as usize];
+    reader.read_exact(&mut buf).map_err(io_err)?;
+    Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
+}
+
+fn skip_string<R: Read + Seek>(reader: &mut R) -> Result<(), String> {
+    let len = read_u64(reader)?;
+    skip_bytes(reader, len)
+}
+
+/// Encoded size of fixed-width value types; `None` for strings and arrays.
+fn fixed_size(value_type: u32) -> Option<u64> {
+    match value_type {
+        0 | 1 | 7 => Some(1),
+        2 | 3 => Some(2),
+        4..=6 => Some(4),
+        10..=12 => Some(8),
+        _ => None,
+    }
+}
+
+fn read_value<R: Read + Seek>(reader: &mut R, value_type: u32) -> Result<MetadataValue, String> {
+    Ok(match value_type {
+        0 => MetadataValue::Uint(u64::from(read_array::<1, _>(reader)?[0])),
+        1 => MetadataValue::Int(i64::from(i8::from_le_bytes(read_array(reader)?))),
+        2 => MetadataValue::Uint(u64::from(u16::from_le_bytes(read_array(reader)?))),
+        3 => MetadataValue::Int(i64::from(i16::from_le_bytes(read_array(reader)?))),
+        4 => MetadataValue::Uint(u64::from(read_u32(reader)?)),
+        5 => MetadataValue::Int(i64::from(i32::from_le_bytes(read_array(reader)?))),
+        6 => MetadataValue::Float(f64::from(f32::from_le_bytes(read_array(reader)?))),
+        7 => MetadataValue::Bool(read_array::<1, _>(reader)?[0] != 0),
+        8 => match read_string(reader)? {
+            Some(value) => MetadataValue::String(value),
+            None => MetadataValue::Skipped,
+        },
+        9 => {
+            let element_type = read_u32(reader)?;
+            let len = read_u64(reader)?;
+            skip_array(reader, element_type, len)?;
+            MetadataValue::Array(len)
+        }
+        10 => MetadataValue::Uint(read_u64(reader)?),
+        11 => MetadataValue::Int(i64::from_le_bytes(read_array(reader)?)),
+        12 => MetadataValue::Float(f64::from_le_bytes(read_array(reader)?)),
+        other => {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Unknown GGUF value type {}", other),
+            ))
+        }
+    })
+}
+
+fn skip_array<R: Read + Seek>(reader: &mut R, element_type: u32, len: u64) -> Result<(), String> {
+    if let Some(size) = fixed_size(element_type) {
+        return skip_bytes(reader, size.saturating_mul(len));
+    }
+    for _ in 0..len {
+        match element_type {
+            8 => skip_string(reader)?,
+            9 => {
+                let inner_type = read_u32(reader)?;
+                let inner_len = read_u64(reader)?;
+                skip_array(reader, inner_type, inner_len)?;
+            }
+            other => {
+                return Err(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Unknown GGUF value type {}", other),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn push_string(buf: &mut Vec<u8>, value: &str) {
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn push_kv(buf: &mut Vec<u8>, key: &str, value_type: u32, value: &[u8]) {
+        push_string(buf, key);
+        buf.extend_from_slice(&value_type.to_le_bytes());
+        buf.extend_from_slice(value);
+    }
+
+    fn push_u32_kv(buf: &mut Vec<u8>, key: &str, value: u32) {
+        push_kv(buf, key, 4, &value.to_le_bytes());
+    }
+
+    #[test]
+    fn reads_metadata_and_counts_parameters() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"GGUF");
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(&2u64.to_le_bytes());
+        buf.extend_from_slice(&9u64.to_le_bytes());
+
+        let mut arch = Vec::new();
+        push_string(&mut arch, "llama");
+        push_kv(&mut buf, "general.architecture", 8, &arch);
+        push_u32_kv(&mut buf, "general.file_type", 15);
+        let mut tokens = Vec::new();
+        tokens.extend_from_slice(&8u32.to_le_bytes());
+        tokens.extend_from_slice(&2u64.to_le_bytes());
+        push_string(&mut tokens, "<s>");
+        push_string(&mut tokens, "</s>");
+        push_kv(&mut buf, "tokenizer.ggml.tokens", 9, &tokens);
+        push_u32_kv(&mut buf, "llama.context_length", 8192);
+        push_u32_kv(&mut buf, "llama.block_count", 32);
+        push_u32_kv(&mut buf, "llama.embedding_length", 4096);
+        push_u32_kv(&mut buf, "llama.attention.head_count", 32);
+        push_u32_kv(&mut buf, "llama.attention.head_count_kv", 8);
+        let mut template = Vec::new();
+        push_string(&mut template, "{{ messages }}");
+        push_kv(&mut buf, "tokenizer.chat_template", 8, &template);
+
+        for (name, dims) in [
+            ("token_embd.weight", vec![4096u64, 32000]),
+            ("norm", vec![4096]),
+        ] {
+            push_string(&mut buf, name);
+            buf.extend_from_slice(&(dims.len() as u32).to_le_bytes());
+            for dim in dims {
+                buf.extend_from_slice(&dim.to_le_bytes());
+            }
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&0u64.to_le_bytes());
+        }
+
+        let header = GgufHeader::from_reader(Cursor::new(buf)).unwrap();
+        assert_eq!(header.architecture(), Some("llama"));
+        assert_eq!(header.quantization(), Some("Q4_K_M"));
+        assert_eq!(header.context_length(), Some(8192));
+        assert_eq!(header.chat_template(), Some("{{ messages }}"));
+        assert_eq!(
+            header.metadata.get("tokenizer.ggml.tokens"),
+            Some(&MetadataValue::Array(2))
+        );
+        assert_eq!(header.parameter_count, 4096 * 32000 + 4096);
+        let shape = header.kv_shape().unwrap();
+        assert_eq!((shape.n_layer, shape.n_head_kv), (32, 8));
+    }
+
+    #[test]
+    fn rejects_non_gguf_files() {
+        assert!(GgufHeader::from_reader(Cursor::new(b"GGML\x01\0\0\0".to_vec())).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+//! JSON schema to GBNF conversion for grammar-constrained llama.cpp sampling. Covers the subset
+//! tool parameters use: objects, arrays, scalar types, `enum`/`const`, `anyOf`/`oneOf` and
+//! local `$ref`s.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+/// Shared rules every generated grammar can reference. Whitespace is bounded so a model can't
+/// loop on it forever.
+const PRIMITIVE_RULES: &[(&str, &str)] = &[
+    ("ws", r#"| " " | "\n" [ \t]{0,20}"#),
+    (
+        "char",
+        r#"[^"\\\x7F\x00-\x1F] | "\\" (["\\bfnrt] | "u" [0-9a-fA-F]{4})"#,
+    ),
+    ("string", r#""\"" char* "\"" ws"#),
+    (
+        "number",
+        r#"("-"? ("0" | [1-9] [0-9]{0,15})) ("." [0-9]{1,16})? ([eE] [-+]? ("0" | [1-9] [0-9]{0,15}))? ws"#,
+    ),
+    ("integer", r#"("-"? ("0" | [1-9] [0-9]{0,15})) ws"#),
+    ("boolean", r#"("true" | "false") ws"#),
+    ("null", r#""null" ws"#),
+    ("value", "object | array | string | number | boolean | null"),
+    (
+        "object",
+        r#""{" ws ( string ":" ws value ("," ws string ":" ws value)* )? "}" ws"#,
+    ),
+    ("array", r#""[" ws ( value ("," ws value)* )? "]" ws"#),
+];
+
+/// GBNF literal matching `text` exactly.
+pub fn gbnf_literal(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// GBNF literal matching the JSON encoding of the string `text`, quotes included.
+pub fn json_string_literal(text: &str) -> String {
+    gbnf_literal(&Value::String(text.to_string()).to_string())
+}
+
+/// Collects rules for one or more schemas into a single grammar.
+#[derive(Default)]
+pub struct GbnfBuilder {
+    rules: Vec<(String, String)>,
+    /// `$ref`s already converted, keyed by schema scope and reference.
+    refs: HashMap<String, String>,
+    scope: String,
+}
+
+impl GbnfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn is_taken(&self, name: &str) -> bool {
+        name == "root"
+            || PRIMITIVE_RULES.iter().any(|(rule, _)| *rule == name)
+            || self.rules.iter().any(|(rule, _)| rule == name)
+            || self.refs.values().any(|rule| rule == name)
+    }
+
+    fn unique_name(&self, hint: &str) -> String {
+        let base: String = hint
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let base = if base.is_empty() {
+            "rule".to_string()
+        } else {
+            base
+        };
+        if !self.is_taken(&base) {
+            return base;
+        }
+        let mut suffix = 1;
+        loop {
+            let candidate = format!("{}-{}", base, suffix);
+            if !self.is_taken(&candidate) {
+                return candidate;
+            }
+            suffix += 1;
+        }
+    }
+
+    /// Adds a rule named after `hint` (made unique) and returns its name.
+    pub fn add_rule(&mut self, hint: &str, body: impl Into<String>) -> String {
+        let name = self.unique_name(hint);
+        self.rules.push((name.clone(), body.into()));
+        name
+    }
+
+    /// Adds the rules matching `schema` and returns the expression to reference it with.
+    pub fn add_schema(&mut self, hint: &str, schema: &Value) -> Result<String, String> {
+        self.scope = hint.to_string();
+        self.visit(hint, schema, schema)
+    }
+
+    /// Renders the grammar with `root` as its start expression.
+    pub fn build(self, root: &str) -> String {
+        let mut out = format!("root ::= {}\n", root);
+        for (name, body) in self
+            .rules
+            .iter()
+            .map(|(name, body)| (name.as_str(), body.as_str()))
+            .chain(PRIMITIVE_RULES.iter().copied())
+        {
+            out.push_str(&format!("{} ::= {}\n", name, body));
+        }
+        out
+    }
+
+    fn visit(&mut self, hint: &str, schema: &Value, root: &Value) -> Result<String, String> {
+        // `true` and empty schemas accept any value.
+        let Some(obj) = schema.as_object() else {
+            return Ok("value".to_string());
+        };
+        if let Some(reference) = obj.get("$ref").and_then(|v| v.as_str()) {
+            return self.visit_ref(reference, root);
+        }
+        if let Some(value) = obj.get("const") {
+            return Ok(format!("{} ws", gbnf_literal(&value.to_string())));
+        }
+        if let Some(values) = obj.get("enum").and_then(|v| v.as_array()) {
+            let alternatives: Vec<String> = values
+                .iter()
+                .map(|value| gbnf_literal(&value.to_string()))
+                .collect();
+            return Ok(format!("({}) ws", alternatives.join(" | ")));
+        }
+        if let Some(variants) = obj
+            .get("anyOf")
+            .or_else(|| obj.get("oneOf"))
+            .and_then(|v| v.as_array())
+        {
+            let alternatives = variants
+                .iter()
+                .enumerate()
+                .map(|(idx, variant)| self.visit(&format!("{}-{}", hint, idx), variant, root))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(self.add_rule(hint, alternatives.join(" | ")));
+        }
+        match obj.get("type") {
+            Some(Value::String(ty)) => self.visit_type(hint, ty, obj, root),
+            Some(Value::Array(types)) => {
+                let alternatives = types
+                    .iter()
+                    .filter_map(|ty| ty.as_str())
+                    .map(|ty| self.visit_type(&format!("{}-{}", hint, ty), ty, obj, root))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(self.add_rule(hint, alternatives.join(" | ")))
+            }
+            _ if obj.contains_key("properties") => self.visit_type(hint, "object", obj, root),
+            _ if obj.contains_key("items") => self.visit_type(hint, "array", obj, root),
+            _ => Ok("value".to_string()),
+        }
+    }
+
+    fn visit_type(
+        &mut self,
+        hint: &str,
+        ty: &str,
+        obj: &Map<String, Value>,
+        root: &Value,
+    ) -> Result<String, String> {
+        match ty {
+            "string" | "number" | "integer" | "boolean" | "null" => Ok(ty.to_string()),
+            "array" => {
+                let item = match obj.get("items") {
+                    Some(items) => self.visit(&format!("{}-item", hint), items, root)?,
+                    None => "value".to_string(),
+                };
+                let list = format!("{} (\",\" ws {})*", item, item);
+                let min_items = obj.get("minItems").and_then(|v| v.as_u64()).unwrap_or(0);
+                let body = if min_items > 0 {
+                    format!("\"[\" ws {} \"]\" ws", list)
+                } else {
+                    format!("\"[\" ws ({})? \"]\" ws", list)
+                };
+                Ok(self.add_rule(hint, body))
+            }
+            "object" => self.visit_object(hint, obj, root),
+            other => Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Unsupported JSON schema type: {}", other),
+            )),
+        }
+    }
+
+    fn visit_object(
+        &mut self,
+        hint: &str,
+        obj: &Map<String, Value>,
+        root: &Value,
+    ) -> Result<String, String> {
+        let Some(properties) = obj
+            .get("properties")
+            .and_then(|v| v.as_object())
+            .filter(|properties| !properties.is_empty())
+        else {
+            return Ok("object".to_string());
+        };
+        let required: Vec<&str> = obj
+            .get("required")
+            .and_then(|v| v.as_array())
+            .map(|keys| keys.iter().filter_map(|key| key.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut required_pairs = Vec::new();
+        let mut optional_pairs = Vec::new();
+        for (key, property) in properties {
+            let value = self.visit(&format!("{}-{}", hint, key), property, root)?;
+            let pair = format!("{} ws \":\" ws {}", json_string_literal(key), value);
+            if required.contains(&key.as_str()) {
+                required_pairs.push(pair);
+            } else {
+                optional_pairs.push(pair);
+            }
+        }
+
+        let mut body = String::from("\"{\" ws ");
+        if required_pairs.is_empty() {
+            // Any subset of the optional keys, in declaration order.
+            let alternatives: Vec<String> = (0..optional_pairs.len())
+                .map(|first| {
+                    let mut chain = optional_pairs[first].clone();
+                    for pair in &optional_pairs[first + 1..] {
+                        chain.push_str(&format!(" (\",\" ws {})?", pair));
+                    }
+                    chain
+                })
+                .collect();
+            body.push_str(&format!("({})? ", alternatives.join(" | ")));
+        } else {
+            body.push_str(&required_pairs.join(" \",\" ws "));
+            for pair in &optional_pairs {
+                body.push_str(&format!(" (\",\" ws {})?", pair));
+            }
+            body.push(' ');
+        }
+        body.push_str("\"}\" ws");
+        Ok(self.add_rule(hint, body))
+    }
+
+    fn visit_ref(&mut self, reference: &str, root: &Value) -> Result<String, String> {
+        let key = format!("{}{}", self.scope, reference);
+        if let Some(name) = self.refs.get(&key) {
+            return Ok(name.clone());
+        }
+        let target = reference
+            .strip_prefix("#/")
+            .and_then(|path| path.split('/').try_fold(root, |node, part| node.get(part)))
+            .ok_or_else(|| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Unresolvable $ref: {}", reference),
+                )
+            })?;
+        let hint = reference.rsplit('/').next().unwrap_or("ref");
+        // Reserve the name first so recursive references resolve to it.
+        let name = self.unique_name(hint);
+        self.refs.insert(key, name.clone());
+        let body = self.visit(&format!("{}-def", name), target, root)?;
+        self.rules.push((name.clone(), body));
+        Ok(name)
+    }
+}
+
+/// Converts a JSON schema into a standalone GBNF grammar.
+pub fn json_schema_to_gbnf(schema: &Value) -> Result<String, String> {
+    let mut builder = GbnfBuilder::new();
+    let root = builder.add_schema("schema", schema)?;
+    Ok(builder.build(&root))
+}
+
+/// Grammar requested through a body's `grammar` (GBNF) or OpenAI-style `response_format`.
+pub fn requested_grammar(body: &Value) -> Result<Option<String>, String> {
+    if let Some(grammar) = body
+        .get("grammar")
+        .and_then(|v| v.as_str())
+        .filter(|g| !g.trim().is_empty())
+    {
+        return Ok(Some(grammar.to_string()));
+    }
+    let Some(format) = body.get("response_format") else {
+        return Ok(None);
+    };
+    match format.get("type").and_then(|v| v.as_str()) {
+        Some("json_schema") => {
+            let schema = format
+                .get("json_schema")
+                .and_then(|v| v.get("schema"))
+                .unwrap_or(&Value::Bool(true));
+            json_schema_to_gbnf(schema).map(Some)
+        }
+        Some("json_object") => json_schema_to_gbnf(&json!({ "type": "object" })).map(Some),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rule names referenced in `grammar` that have no definition.
+    fn undefined_rules(grammar: &str) -> Vec<String> {
+        let mut defined = Vec::new();
+        let mut referenced = Vec::new();
+        for line in grammar.lines() {
+            let (name, body) = line.split_once(" ::= ").expect("rule line");
+            defined.push(name.to_string());
+            let mut chars = body.chars().peekable();
+            let mut ident = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => {
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '\\' => {
+                                    chars.next();
+                                }
+                                '"' => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                    '[' => {
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '\\' => {
+                                    chars.next();
+                                }
+                                ']' => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                    '{' => while chars.next().is_some_and(|c| c != '}') {},
+                    c if c.is_ascii_alphanumeric() || c == '-' => {
+                        ident.push(c);
+                        continue;
+                    }
+                    _ => {}
+                }
+                if !ident.is_empty() {
+                    referenced.push(std::mem::take(&mut ident));
+                }
+            }
+            if !ident.is_empty() {
+                referenced.push(ident);
+            }
+        }
+        referenced.retain(|name| !defined.contains(name));
+        referenced
+    }
+
+    #[test]
+    fn converts_object_schema_with_refs_and_optional_keys() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string" },
+                "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" }, "minItems": 1 },
+                "mood": { "enum": ["calm", "angry"] },
+                "pinned": { "type": ["boolean", "null"] }
+            },
+            "required": ["text"],
+            "$defs": { "tag": { "type": "string" } }
+        });
+        let grammar = json_schema_to_gbnf(&schema).unwrap();
+
+        assert!(grammar.starts_with("root ::= schema\n"), "{grammar}");
+        assert!(
+            grammar.contains(r#""\"text\"" ws ":" ws string"#),
+            "{grammar}"
+        );
+        assert!(
+            grammar.contains(r#" ("," ws "\"mood\"" ws ":" ws"#),
+            "{grammar}"
+        );
+        assert!(
+            grammar.contains(r#"("\"calm\"" | "\"angry\"") ws"#),
+            "{grammar}"
+        );
+        assert!(grammar.contains("tag ::= string"), "{grammar}");
+        assert!(undefined_rules(&grammar).is_empty(), "{grammar}");
+    }
+
+    #[test]
+    fn literal_escapes_quotes_and_newlines() {
+        assert_eq!(gbnf_literal("a\"b\\\n"), r#""a\"b\\\n""#);
+        assert_eq!(json_string_literal("name"), r#""\"name\"""#);
+    }
+}
@@ -0,0 +1,514 @@
+//! Library of local GGUF files for the llama.cpp provider. Metadata is read from the file header
+//! when a model is registered, so listing and fit checks never load weights.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, OptionalExtension, Row};
+use tauri::{AppHandle, Emitter};
+
+use super::gguf::GgufHeader;
+use crate::storage_manager::db::{now_ms, open_db};
+use crate::transport::download::{DownloadError, ResumableDownload};
+use crate::utils::{log_info, log_warn};
+
+/// Contexts shorter than this aren't useful for chat; a model that can't reach it doesn't fit.
+const MIN_USEFUL_CONTEXT: u32 = 2048;
+
+/// Cancel flags of running downloads, keyed by URL.
+static ACTIVE_DOWNLOADS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModelEntry {
+    pub id: String,
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub architecture: Option<String>,
+    pub name: Option<String>,
+    pub parameter_count: Option<u64>,
+    pub quantization: Option<String>,
+    pub context_length: Option<u32>,
+    pub chat_template: Option<String>,
+    pub source_url: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFit {
+    pub available_memory_bytes: Option<u64>,
+    pub model_size_bytes: u64,
+    pub kv_bytes_per_token: Option<u64>,
+    pub max_context_length: Option<u32>,
+    pub recommended_context_length: Option<u32>,
+    /// Whether the weights plus a usable context fit in free memory; `None` if unknown.
+    pub fits: Option<bool>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress {
+    url: String,
+    file_name: String,
+    downloaded: u64,
+    total: u64,
+    status: String,
+}
+
+const SELECT_COLUMNS: &str = "id, path, file_name, size_bytes, architecture, name, \
+     parameter_count, quantization, context_length, chat_template, source_url, created_at, \
+     updated_at";
+
+fn read_entry(row: &Row) -> rusqlite::Result<LocalModelEntry> {
+    Ok(LocalModelEntry {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        file_name: row.get(2)?,
+        size_bytes: row.get::<_, i64>(3)?.max(0) as u64,
+        architecture: row.get(4)?,
+        name: row.get(5)?,
+        parameter_count: row.get::<_, Option<i64>>(6)?.map(|v| v.max(0) as u64),
+        quantization: row.get(7)?,
+        context_length: row.get::<_, Option<i64>>(8)?.map(|v| v.max(0) as u32),
+        chat_template: row.get(9)?,
+        source_url: row.get(10)?,
+        created_at: row.get::<_, i64>(11)?.max(0) as u64,
+        updated_at: row.get::<_, i64>(12)?.max(0) as u64,
+    })
+}
+
+fn get_entry(app: &AppHandle, id: &str) -> Result<LocalModelEntry, String> {
+    let conn = open_db(app)?;
+    conn.query_row(
+        &format!("SELECT {} FROM local_models WHERE id = ?1", SELECT_COLUMNS),
+        params![id],
+        read_entry,
+    )
+    .optional()
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+    .ok_or_else(|| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Local model not found: {}", id),
+        )
+    })
+}
+
+/// Resolves the llama.cpp `model` field: an existing file path is used as is, anything else is
+/// looked up as a library id.
+pub(crate) fn resolve_model_path(app: &AppHandle, model: &str) -> Result<String, String> {
+    if Path::new(model).exists() {
+        return Ok(model.to_string());
+    }
+    lookup_model_path(&open_db(app)?, model)
+}
+
+/// Path of the library entry `id`, or `id` itself when there is none.
+fn lookup_model_path(conn: &rusqlite::Connection, id: &str) -> Result<String, String> {
+    let path: Option<String> = conn
+        .query_row(
+            "SELECT path FROM local_models WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(path.unwrap_or_else(|| id.to_string()))
+}
+
+/// Reads the header of `path` and inserts or refreshes its library entry.
+fn register_file(
+    app: &AppHandle,
+    path: &Path,
+    source_url: Option<&str>,
+) -> Result<LocalModelEntry, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Model file not found: {} ({})", path.display(), e),
+        )
+    })?;
+    if !metadata.is_file() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Not a file: {}", path.display()),
+        ));
+    }
+    let header = GgufHeader::read(path)?;
+    let path_str = path.to_string_lossy().to_string();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path_str.clone());
+
+    let conn = open_db(app)?;
+    let existing: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT id, created_at FROM local_models WHERE path = ?1",
+            params![path_str],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let now = now_ms() as i64;
+    let (id, created_at) = existing.unwrap_or_else(|| (uuid::Uuid::new_v4().to_string(), now));
+    conn.execute(
+        "INSERT INTO local_models (id, path, file_name, size_bytes, architecture, name,
+           parameter_count, quantization, context_length, chat_template, source_url, created_at,
+           updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+         ON CONFLICT(id) DO UPDATE SET
+           file_name = excluded.file_name,
+           size_bytes = excluded.size_bytes,
+           architecture = excluded.architecture,
+           name = excluded.name,
+           parameter_count = excluded.parameter_count,
+           quantization = excluded.quantization,
+           context_length = excluded.context_length,
+           chat_template = excluded.chat_template,
+           source_url = COALESCE(excluded.source_url, local_models.source_url),
+           updated_at = excluded.updated_at",
+        params![
+            id,
+            path_str,
+            file_name,
+            metadata.len() as i64,
+            header.architecture(),
+            header.name(),
+            (header.parameter_count > 0).then_some(header.parameter_count as i64),
+            header.quantization(),
+            header.context_length().map(i64::from),
+            header.chat_template(),
+            source_url,
+            created_at,
+            now,
+        ],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    log_info(
+        app,
+        "llama_library",
+        format!(
+            "registered model path={} arch={:?} quant={:?} params={}",
+            path_str,
+            header.architecture(),
+            header.quantization(),
+            header.parameter_count
+        ),
+    );
+    get_entry(app, &id)
+}
+
+#[tauri::command]
+pub fn llamacpp_library_list(app: AppHandle) -> Result<Vec<LocalModelEntry>, String> {
+    let conn = open_db(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM local_models ORDER BY created_at DESC",
+            SELECT_COLUMNS
+        ))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map([], read_entry)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+#[tauri::command]
+pub async fn llamacpp_library_register(
+    app: AppHandle,
+    path: String,
+) -> Result<LocalModelEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || register_file(&app, Path::new(path.trim()), None))
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+}
+
+/// Removes a model from the library, deleting the file too when `delete_file` is set.
+#[tauri::command]
+pub fn llamacpp_library_remove(
+    app: AppHandle,
+    id: String,
+    delete_file: Option<bool>,
+) -> Result<(), String> {
+    let entry = get_entry(&app, &id)?;
+    if delete_file.unwrap_or(false) {
+        match std::fs::remove_file(&entry.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to delete {}: {}", entry.path, e),
+                ))
+            }
+        }
+    }
+    let conn = open_db(&app)?;
+    conn.execute("DELETE FROM local_models WHERE id = ?1", params![id])
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+/// Estimates whether a model fits in free memory and how much context it can be given, from
+/// the header alone.
+#[tauri::command]
+pub async fn llamacpp_library_check_fit(app: AppHandle, id: String) -> Result<ModelFit, String> {
+    let entry = get_entry(&app, &id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let header = GgufHeader::read(Path::new(&entry.path))?;
+        let available = super::available_memory_bytes();
+        let kv_bytes_per_token = header.kv_shape().map(super::kv_bytes_per_token);
+        let max_context_length = header.context_length();
+        let recommended_context_length = match (available, kv_bytes_per_token) {
+            (Some(available), Some(kv)) => super::recommended_context(
+                entry.size_bytes,
+                kv,
+                available,
+                max_context_length.unwrap_or(u32::MAX),
+            ),
+            _ => None,
+        };
+        let needed = MIN_USEFUL_CONTEXT.min(max_context_length.unwrap_or(MIN_USEFUL_CONTEXT));
+        Ok(ModelFit {
+            available_memory_bytes: available,
+            model_size_bytes: entry.size_bytes,
+            kv_bytes_per_token,
+            max_context_length,
+            recommended_context_length,
+            fits: recommended_context_length.map(|ctx| ctx >= needed),
+        })
+    })
+    .await
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+}
+
+fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::utils::lettuce_dir(app)?.join("models").join("gguf");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(dir)
+}
+
+/// File name to save a download as: the requested name, or the last URL path segment.
+fn download_file_name(url: &str, file_name: Option<&str>) -> Result<String, String> {
+    let name = match file_name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => name.to_string(),
+        None => url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\'])
+        || !name.to_ascii_lowercase().ends_with(".gguf")
+    {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Invalid GGUF file name: {:?}", name),
+        ));
+    }
+    Ok(name)
+}
+
+fn emit_progress(app: &AppHandle, progress: &DownloadProgress) {
+    let _ = app.emit("llamacpp_download_progress", progress);
+}
+
+/// Downloads a GGUF file into the app's model folder and registers it. Interrupted or cancelled
+/// downloads leave a `.part` file that the next attempt for the same URL resumes from.
+#[tauri::command]
+pub async fn llamacpp_library_download(
+    app: AppHandle,
+    url: String,
+    file_name: Option<String>,
+) -> Result<LocalModelEntry, String> {
+    let file_name = download_file_name(&url, file_name.as_deref())?;
+    let dest_path = models_dir(&app)?.join(&file_name);
+    if dest_path.exists() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("{} already exists", dest_path.display()),
+        ));
+    }
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut active = ACTIVE_DOWNLOADS
+            .lock()
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        let active = active.get_or_insert_with(HashMap::new);
+        if active.contains_key(&url) {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "This model is already downloading",
+            ));
+        }
+        active.insert(url.clone(), cancel.clone());
+    }
+
+    let result = download_to(&app, &url, &file_name, &dest_path, &cancel).await;
+    if let Ok(mut active) = ACTIVE_DOWNLOADS.lock() {
+        if let Some(active) = active.as_mut() {
+            active.remove(&url);
+        }
+    }
+    result?;
+
+    let source_url = url.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        register_file(&app, &dest_path, Some(source_url.as_str()))
+    })
+    .await
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+}
+
+async fn download_to(
+    app: &AppHandle,
+    url: &str,
+    file_name: &str,
+    dest_path: &Path,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let mut download = ResumableDownload::start(&client, url, dest_path)
+        .await
+        .map_err(DownloadError::into_message)?;
+    log_info(
+        app,
+        "llama_library",
+        format!(
+            "download started url={} status={} existing={} remaining={}",
+            url, download.status, download.resumed_from, download.remaining
+        ),
+    );
+
+    let mut progress = DownloadProgress {
+        url: url.to_string(),
+        file_name: file_name.to_string(),
+        downloaded: download.resumed_from,
+        total: download.resumed_from + download.remaining,
+        status: "downloading".to_string(),
+    };
+    emit_progress(app, &progress);
+
+    let mut last_emit = std::time::Instant::now();
+    loop {
+        if cancel.load(Ordering::SeqCst) {
+            let _ = download.flush().await;
+            progress.status = "cancelled".to_string();
+            emit_progress(app, &progress);
+            log_warn(
+                app,
+                "llama_library",
+                format!("download cancelled url={}", url),
+            );
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "Download cancelled",
+            ));
+        }
+        let chunk = match download.next_chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(err) => {
+                let _ = download.flush().await;
+                return Err(err.into_message());
+            }
+        };
+        progress.downloaded += chunk.len() as u64;
+        if last_emit.elapsed().as_millis() > 100 {
+            emit_progress(app, &progress);
+            last_emit = std::time::Instant::now();
+        }
+    }
+
+    download
+        .finish()
+        .await
+        .map_err(DownloadError::into_message)?;
+    progress.status = "completed".to_string();
+    emit_progress(app, &progress);
+    log_info(
+        app,
+        "llama_library",
+        format!(
+            "download complete dest={} bytes={}",
+            dest_path.display(),
+            progress.downloaded
+        ),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn llamacpp_library_cancel_download(url: String) -> Result<(), String> {
+    let active = ACTIVE_DOWNLOADS
+        .lock()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if let Some(cancel) = active.as_ref().and_then(|active| active.get(&url)) {
+        cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_name_comes_from_url_and_rejects_paths() {
+        assert_eq!(
+            download_file_name(
+                "https://huggingface.co/org/repo/resolve/main/model-Q4_K_M.gguf?download=true",
+                None
+            )
+            .unwrap(),
+            "model-Q4_K_M.gguf"
+        );
+        assert_eq!(
+            download_file_name("https://example.com/x", Some(" mine.gguf ")).unwrap(),
+            "mine.gguf"
+        );
+        assert!(download_file_name("https://example.com/model.bin", None).is_err());
+        assert!(download_file_name("https://example.com/x", Some("../evil.gguf")).is_err());
+        assert!(download_file_name("https://example.com/x", Some("a/b.gguf")).is_err());
+    }
+
+    #[test]
+    fn library_ids_resolve_to_their_file() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE local_models (id TEXT PRIMARY KEY, path TEXT NOT NULL);
+             INSERT INTO local_models (id, path)
+               VALUES ('5f0c6a2e-0d7b-4c1e-9a53-2f1d8b7e4c10', '/models/qwen.gguf');",
+        )
+        .unwrap();
+        assert_eq!(
+            lookup_model_path(&conn, "5f0c6a2e-0d7b-4c1e-9a53-2f1d8b7e4c10").unwrap(),
+            "/models/qwen.gguf"
+        );
+        assert_eq!(
+            lookup_model_path(&conn, "/models/other.gguf").unwrap(),
+            "/models/other.gguf"
+        );
+    }
+}
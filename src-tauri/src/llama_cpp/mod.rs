@@ -0,0 +1,2462 @@
+pub mod gguf;
+pub mod grammar;
+pub mod library;
+#[cfg(not(mobile))]
+mod session_state;
+#[cfg(not(mobile))]
+mod vision;
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::api::{ApiRequest, ApiResponse};
+use crate::chat_manager::types::{ErrorEnvelope, NormalizedEvent, UsageSummary};
+use crate::transport;
+#[cfg(not(mobile))]
+use crate::utils::{emit_toast, log_error, log_info, log_warn};
+
+const LOCAL_PROVIDER_ID: &str = "llamacpp";
+
+#[cfg(not(mobile))]
+mod desktop {
+    use super::*;
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::context::LlamaContext;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{
+        AddBos, LlamaChatMessage, LlamaChatTemplate, LlamaLoraAdapter, LlamaModel, Special,
+    };
+    use llama_cpp_2::mtmd::{
+        MtmdBitmap, MtmdContext, MtmdContextParams, MtmdInputChunks, MtmdInputText,
+    };
+    use llama_cpp_2::sampling::LlamaSampler;
+    use llama_cpp_2::token::LlamaToken;
+    use std::collections::VecDeque;
+    use std::num::NonZeroU32;
+    use std::path::Path;
+    use std::sync::{Mutex, OnceLock, TryLockError};
+    use tokio::sync::oneshot::error::TryRecvError;
+
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub(crate) struct LlamaCppContextInfo {
+        max_context_length: u32,
+        recommended_context_length: Option<u32>,
+        available_memory_bytes: Option<u64>,
+        model_size_bytes: Option<u64>,
+    }
+
+    /// Context kept alive between requests, so a prompt sharing a prefix with the previous one
+    /// only decodes the new suffix.
+    struct CachedContext {
+        ctx: LlamaContext<'static>,
+        params_key: String,
+        n_ctx: u32,
+        /// Tokens whose KV cells the context holds, in position order.
+        tokens: Vec<LlamaToken>,
+        /// LoRA adapters currently applied to the context.
+        loras: Vec<LoraSpec>,
+    }
+
+    // SAFETY: the context is only touched while holding the engine mutex.
+    unsafe impl Send for CachedContext {}
+
+    struct LoadedLora(LlamaLoraAdapter);
+
+    // SAFETY: adapters are only touched while holding the engine mutex.
+    unsafe impl Send for LoadedLora {}
+
+    /// Multimodal projector (mmproj) for the loaded model, which turns images into embeddings.
+    struct Projector {
+        path: String,
+        ctx: MtmdContext,
+    }
+
+    // SAFETY: the projector is only touched while holding the engine mutex.
+    unsafe impl Send for Projector {}
+
+    /// A prompt with images: runs of text tokens and the projector input for each image.
+    enum PromptPart {
+        Text(Vec<LlamaToken>),
+        Media {
+            chunks: MtmdInputChunks,
+            /// Stand-in token recorded for each of the image's KV cells.
+            token: LlamaToken,
+            n_pos: usize,
+        },
+    }
+
+    /// Smaller model sharing the main model's vocabulary, used to propose tokens that the main
+    /// model then verifies in a single batch.
+    struct DraftEngine {
+        path: String,
+        /// Borrows `model`; declared first so it is dropped first.
+        context: Option<CachedContext>,
+        model: Box<LlamaModel>,
+    }
+
+    struct LlamaState {
+        backend: Option<LlamaBackend>,
+        model_path: Option<String>,
+        model_params_key: Option<String>,
+        /// Borrows `model`; always cleared before the model is replaced or unloaded.
+        context: Option<CachedContext>,
+        /// Adapters initialised against `model`, keyed by path. Kept across requests so switching
+        /// between adapter sets doesn't reload anything; cleared with the model.
+        loras: HashMap<String, LoadedLora>,
+        model: Option<Box<LlamaModel>>,
+        /// Vocabulary-only model used to count tokens for a GGUF that isn't loaded.
+        vocab_model: Option<(String, LlamaModel)>,
+        /// Draft model for speculative decoding, when the request asks for one.
+        draft: Option<DraftEngine>,
+        /// Borrows `model`; always cleared before the model is replaced or unloaded.
+        projector: Option<Projector>,
+    }
+
+    static ENGINE: OnceLock<Mutex<LlamaState>> = OnceLock::new();
+
+    fn engine() -> &'static Mutex<LlamaState> {
+        ENGINE.get_or_init(|| {
+            Mutex::new(LlamaState {
+                backend: None,
+                model_path: None,
+                model_params_key: None,
+                context: None,
+                loras: HashMap::new(),
+                model: None,
+                vocab_model: None,
+                draft: None,
+                projector: None,
+            })
+        })
+    }
+
+    /// Detaches the loaded model's lifetime from the engine guard so a context borrowing it can
+    /// be cached in the same state.
+    fn static_model(model: &LlamaModel) -> &'static LlamaModel {
+        // SAFETY: the model is boxed, so its address is stable, and `LlamaState::context` is
+        // cleared before the box is dropped or replaced.
+        unsafe { &*(model as *const LlamaModel) }
+    }
+
+    fn ensure_backend(state: &mut LlamaState) -> Result<(), String> {
+        if state.backend.is_none() {
+            state.backend = Some(LlamaBackend::init().map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to initialize llama backend: {e}"),
+                )
+            })?);
+        }
+        Ok(())
+    }
+
+    fn load_engine(
+        app: Option<&AppHandle>,
+        model_path: &str,
+        requested_gpu_layers: Option<u32>,
+        lora_adapters: &[LoraSpec],
+    ) -> Result<std::sync::MutexGuard<'static, LlamaState>, String> {
+        let engine = engine();
+
+        let mut guard = engine
+            .lock()
+            .map_err(|_| "llama.cpp engine lock poisoned".to_string())?;
+
+        ensure_backend(&mut guard)?;
+
+        let backend = guard
+            .backend
+            .as_ref()
+            .ok_or_else(|| "llama.cpp backend unavailable".to_string())?;
+        let supports_gpu = backend.supports_gpu_offload();
+        let resolved_gpu_layers = if supports_gpu {
+            requested_gpu_layers.unwrap_or(u32::MAX)
+        } else {
+            0
+        };
+        let model_params_key = format!("gpu_layers={}", resolved_gpu_layers);
+        let should_reload = guard.model.is_none()
+            || guard.model_path.as_deref() != Some(model_path)
+            || guard.model_params_key.as_deref() != Some(&model_params_key);
+        if should_reload {
+            let gpu_params = LlamaModelParams::default().with_n_gpu_layers(resolved_gpu_layers);
+            let cpu_params = LlamaModelParams::default().with_n_gpu_layers(0);
+
+            let model = if supports_gpu {
+                match LlamaModel::load_from_file(backend, model_path, &gpu_params) {
+                    Ok(model) => model,
+                    Err(err) => {
+                        if let Some(app) = app {
+                            log_warn(
+                                app,
+                                "llama_cpp",
+                                format!("GPU model load failed, falling back to CPU: {err}"),
+                            );
+                            emit_toast(
+                                app,
+                                "warning",
+                                "GPU memory is insufficient for this model",
+                                Some(
+                                    "Falling back to CPU + RAM. Performance may be slower."
+                                        .to_string(),
+                                ),
+                            );
+                        }
+                        LlamaModel::load_from_file(backend, model_path, &cpu_params).map_err(
+                            |e| format!("Failed to load llama model with CPU fallback: {e}"),
+                        )?
+                    }
+                }
+            } else {
+                LlamaModel::load_from_file(backend, model_path, &cpu_params).map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to load llama model: {e}"),
+                    )
+                })?
+            };
+
+            guard.context = None;
+            guard.projector = None;
+            guard.loras.clear();
+            guard.model = Some(Box::new(model));
+            guard.model_path = Some(model_path.to_string());
+            guard.model_params_key = Some(model_params_key);
+        }
+
+        for spec in lora_adapters {
+            if guard.loras.contains_key(&spec.path) {
+                continue;
+            }
+            let model = guard
+                .model
+                .as_deref()
+                .ok_or_else(|| "llama.cpp model unavailable".to_string())?;
+            let adapter = model.lora_adapter_init(&spec.path).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to load LoRA adapter {}: {e}", spec.path),
+                )
+            })?;
+            if let Some(app) = app {
+                log_info(
+                    app,
+                    "llama_cpp",
+                    format!("loaded LoRA adapter path={}", spec.path),
+                );
+            }
+            guard.loras.insert(spec.path.clone(), LoadedLora(adapter));
+        }
+
+        Ok(guard)
+    }
+
+    /// Loads the draft model at `draft_path` unless it already is, and checks that it can
+    /// propose tokens for the loaded main model.
+    fn load_draft(
+        app: &AppHandle,
+        state: &mut LlamaState,
+        draft_path: &str,
+        requested_gpu_layers: Option<u32>,
+    ) -> Result<(), String> {
+        if state.draft.as_ref().is_some_and(|d| d.path != draft_path) {
+            state.draft = None;
+        }
+        if state.draft.is_none() {
+            let backend = state
+                .backend
+                .as_ref()
+                .ok_or_else(|| "llama.cpp backend unavailable".to_string())?;
+            let gpu_layers = if backend.supports_gpu_offload() {
+                requested_gpu_layers.unwrap_or(u32::MAX)
+            } else {
+                0
+            };
+            let params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+            let model = LlamaModel::load_from_file(backend, draft_path, &params).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to load draft model: {e}"),
+                )
+            })?;
+            log_info(
+                app,
+                "llama_cpp",
+                format!("loaded draft model path={}", draft_path),
+            );
+            state.draft = Some(DraftEngine {
+                path: draft_path.to_string(),
+                context: None,
+                model: Box::new(model),
+            });
+        }
+
+        let (Some(model), Some(draft)) = (state.model.as_deref(), state.draft.as_ref()) else {
+            return Err("llama.cpp model unavailable".to_string());
+        };
+        if draft.model.n_vocab() != model.n_vocab() {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!(
+                    "Draft model vocabulary ({} tokens) doesn't match the main model ({} tokens). Pick a draft model from the same family.",
+                    draft.model.n_vocab(),
+                    model.n_vocab()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Loads the vision projector at `mmproj_path` for the loaded model unless it already is.
+    fn load_projector(
+        app: &AppHandle,
+        state: &mut LlamaState,
+        mmproj_path: &str,
+        requested_gpu_layers: Option<u32>,
+    ) -> Result<(), String> {
+        if state
+            .projector
+            .as_ref()
+            .is_some_and(|p| p.path == mmproj_path)
+        {
+            return Ok(());
+        }
+        state.projector = None;
+        let use_gpu = requested_gpu_layers != Some(0)
+            && state
+                .backend
+                .as_ref()
+                .is_some_and(|backend| backend.supports_gpu_offload());
+        let model = state
+            .model
+            .as_deref()
+            .ok_or_else(|| "llama.cpp model unavailable".to_string())?;
+        let params = MtmdContextParams {
+            use_gpu,
+            ..MtmdContextParams::default()
+        };
+        let ctx = MtmdContext::init_from_file(mmproj_path, model, &params).map_err(|e| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Failed to load vision projector: {e}"),
+            )
+        })?;
+        if !ctx.support_vision() {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("{} isn't a vision projector", mmproj_path),
+            ));
+        }
+        log_info(
+            app,
+            "llama_cpp",
+            format!("loaded vision projector path={}", mmproj_path),
+        );
+        state.projector = Some(Projector {
+            path: mmproj_path.to_string(),
+            ctx,
+        });
+        Ok(())
+    }
+
+    pub fn unload_engine(app: &AppHandle) -> Result<(), String> {
+        let engine = engine();
+
+        let mut guard = engine
+            .lock()
+            .map_err(|_| "llama.cpp engine lock poisoned".to_string())?;
+
+        guard.vocab_model = None;
+        guard.draft = None;
+        if guard.model.is_some() {
+            guard.context = None;
+            guard.projector = None;
+            guard.loras.clear();
+            guard.model = None;
+            guard.model_path = None;
+            guard.model_params_key = None;
+            log_info(app, "llama_cpp", "unloaded llama.cpp model");
+        }
+
+        Ok(())
+    }
+
+    /// Counts `text` with the vocabulary of the GGUF at `model_path`, reusing the loaded model
+    /// when it matches. Returns `None` while the engine is busy generating.
+    pub fn count_gguf_tokens(model_path: &str, text: &str) -> Result<Option<u32>, String> {
+        let engine = engine();
+        let mut guard = match engine.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Poisoned(_)) => {
+                return Err("llama.cpp engine lock poisoned".to_string())
+            }
+        };
+        ensure_backend(&mut guard)?;
+        let state = &mut *guard;
+
+        let model = if state.model.is_some() && state.model_path.as_deref() == Some(model_path) {
+            state.model.as_deref()
+        } else {
+            if state.vocab_model.as_ref().map(|(path, _)| path.as_str()) != Some(model_path) {
+                let backend = state
+                    .backend
+                    .as_ref()
+                    .ok_or_else(|| "llama.cpp backend unavailable".to_string())?;
+                let params = LlamaModelParams::default().with_vocab_only(true);
+                let vocab =
+                    LlamaModel::load_from_file(backend, model_path, &params).map_err(|e| {
+                        crate::utils::err_msg(
+                            module_path!(),
+                            line!(),
+                            format!("Failed to load llama vocabulary: {e}"),
+                        )
+                    })?;
+                state.vocab_model = Some((model_path.to_string(), vocab));
+            }
+            state.vocab_model.as_ref().map(|(_, model)| model)
+        }
+        .ok_or_else(|| "llama.cpp model unavailable".to_string())?;
+
+        let tokens = model.str_to_token(text, AddBos::Never).map_err(|e| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Failed to tokenize text: {e}"),
+            )
+        })?;
+        Ok(Some(tokens.len() as u32))
+    }
+
+    fn normalize_role(role: &str) -> &'static str {
+        match role {
+            "system" | "developer" => "system",
+            "assistant" => "assistant",
+            "user" => "user",
+            _ => "user",
+        }
+    }
+
+    fn sanitize_text(value: &str) -> String {
+        value.replace('\0', "")
+    }
+
+    pub(crate) fn get_available_memory_bytes() -> Option<u64> {
+        let mut sys = sysinfo::System::new();
+        sys.refresh_memory();
+        Some(sys.available_memory())
+    }
+
+    fn estimate_kv_bytes_per_token(model: &LlamaModel) -> Option<u64> {
+        Some(kv_bytes_per_token(KvShape {
+            n_layer: u64::from(model.n_layer()),
+            n_embd: u64::try_from(model.n_embd()).ok()?,
+            n_head: u64::try_from(model.n_head()).unwrap_or(1),
+            n_head_kv: u64::try_from(model.n_head_kv()).unwrap_or(0),
+        }))
+    }
+
+    fn compute_recommended_context(
+        model: &LlamaModel,
+        available_memory_bytes: Option<u64>,
+        max_context_length: u32,
+    ) -> Option<u32> {
+        recommended_context(
+            model.size(),
+            estimate_kv_bytes_per_token(model)?,
+            available_memory_bytes?,
+            max_context_length,
+        )
+    }
+
+    fn extract_text_content(message: &Value) -> String {
+        let content = message.get("content");
+        match content {
+            Some(Value::String(text)) => sanitize_text(text),
+            Some(Value::Array(parts)) => {
+                let mut out: Vec<String> = Vec::new();
+                for part in parts {
+                    let part_type = part.get("type").and_then(|v| v.as_str());
+                    if part_type == Some("text") {
+                        if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                            let cleaned = sanitize_text(text);
+                            if !cleaned.is_empty() {
+                                out.push(cleaned);
+                            }
+                        }
+                    }
+                }
+                out.join("\n")
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn build_fallback_prompt(messages: &[Value]) -> String {
+        let mut prompt = String::new();
+        for message in messages {
+            let role = message
+                .get("role")
+                .and_then(|v| v.as_str())
+                .map(normalize_role)
+                .unwrap_or("user");
+            let content = extract_text_content(message);
+            if content.is_empty() {
+                continue;
+            }
+            prompt.push_str(role);
+            prompt.push_str(": ");
+            prompt.push_str(&content);
+            prompt.push('\n');
+        }
+        prompt.push_str("assistant: ");
+        prompt
+    }
+
+    fn build_prompt(model: &LlamaModel, messages: &[Value]) -> Result<String, String> {
+        let mut chat_messages = Vec::new();
+        for message in messages {
+            let role = message
+                .get("role")
+                .and_then(|v| v.as_str())
+                .map(normalize_role)
+                .unwrap_or("user");
+            let content = extract_text_content(message);
+            if content.is_empty() {
+                continue;
+            }
+            let chat_message = LlamaChatMessage::new(role.to_string(), content).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Invalid chat message: {e}"),
+                )
+            })?;
+            chat_messages.push(chat_message);
+        }
+
+        if chat_messages.is_empty() {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "No usable chat messages for llama.cpp",
+            ));
+        }
+
+        let template = model
+            .chat_template(None)
+            .or_else(|_| LlamaChatTemplate::new("chatml"))
+            .map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to load chat template: {e}"),
+                )
+            })?;
+
+        let prompt = match model.apply_chat_template(&template, &chat_messages, true) {
+            Ok(text) => text,
+            Err(_) => build_fallback_prompt(messages),
+        };
+
+        Ok(prompt)
+    }
+
+    /// Sequence breakers DRY uses by default in llama.cpp.
+    const DRY_SEQUENCE_BREAKERS: [&str; 4] = ["\n", ":", "\"", "*"];
+
+    /// Sampling settings of a single request.
+    struct SamplingParams {
+        temperature: f64,
+        top_p: f64,
+        top_k: Option<u32>,
+        min_p: Option<f64>,
+        typical_p: Option<f64>,
+        frequency_penalty: Option<f64>,
+        presence_penalty: Option<f64>,
+        repeat_penalty: Option<f64>,
+        repeat_last_n: Option<i32>,
+        dry_multiplier: Option<f64>,
+        dry_base: Option<f64>,
+        dry_allowed_length: Option<u32>,
+        dry_penalty_last_n: Option<i32>,
+        xtc_probability: Option<f64>,
+        xtc_threshold: Option<f64>,
+        /// 0 = off, 1 = Mirostat, 2 = Mirostat 2.0.
+        mirostat: u32,
+        mirostat_tau: f64,
+        mirostat_eta: f64,
+        order: Vec<SamplerStage>,
+        seed: Option<u32>,
+        /// GBNF grammar (start rule `root`) the output must match.
+        grammar: Option<String>,
+    }
+
+    impl SamplingParams {
+        fn from_body(body: &Value, grammar: Option<String>) -> Self {
+            let field = |camel: &str, snake: &str| body.get(camel).or_else(|| body.get(snake));
+            let float = |camel: &str, snake: &str| field(camel, snake).and_then(|v| v.as_f64());
+            let int = |camel: &str, snake: &str| {
+                field(camel, snake)
+                    .and_then(|v| v.as_i64())
+                    .and_then(|v| i32::try_from(v).ok())
+            };
+            let uint = |camel: &str, snake: &str| {
+                field(camel, snake)
+                    .and_then(|v| v.as_u64())
+                    .and_then(|v| u32::try_from(v).ok())
+            };
+            let order: Option<Vec<String>> = field("llamaSamplerOrder", "llama_sampler_order")
+                .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+            Self {
+                temperature: body
+                    .get("temperature")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.7),
+                top_p: body.get("top_p").and_then(|v| v.as_f64()).unwrap_or(1.0),
+                top_k: uint("topK", "top_k").filter(|v| *v > 0),
+                min_p: float("llamaMinP", "llama_min_p"),
+                typical_p: float("llamaTypicalP", "llama_typical_p"),
+                frequency_penalty: body.get("frequency_penalty").and_then(|v| v.as_f64()),
+                presence_penalty: body.get("presence_penalty").and_then(|v| v.as_f64()),
+                repeat_penalty: float("llamaRepeatPenalty", "llama_repeat_penalty"),
+                repeat_last_n: int("llamaRepeatLastN", "llama_repeat_last_n"),
+                dry_multiplier: float("llamaDryMultiplier", "llama_dry_multiplier"),
+                dry_base: float("llamaDryBase", "llama_dry_base"),
+                dry_allowed_length: uint("llamaDryAllowedLength", "llama_dry_allowed_length"),
+                dry_penalty_last_n: int("llamaDryPenaltyLastN", "llama_dry_penalty_last_n"),
+                xtc_probability: float("llamaXtcProbability", "llama_xtc_probability"),
+                xtc_threshold: float("llamaXtcThreshold", "llama_xtc_threshold"),
+                mirostat: uint("llamaMirostat", "llama_mirostat").unwrap_or(0),
+                mirostat_tau: float("llamaMirostatTau", "llama_mirostat_tau").unwrap_or(5.0),
+                mirostat_eta: float("llamaMirostatEta", "llama_mirostat_eta").unwrap_or(0.1),
+                order: SamplerStage::order(order.as_deref()),
+                seed: uint("llamaSeed", "llama_seed"),
+                grammar,
+            }
+        }
+    }
+
+    fn build_sampler(model: &LlamaModel, params: &SamplingParams) -> Result<LlamaSampler, String> {
+        let mut samplers = Vec::new();
+        if let Some(grammar) = params.grammar.as_deref() {
+            // First in the chain so later samplers only ever see grammatical candidates.
+            samplers.push(LlamaSampler::grammar(model, grammar, "root").map_err(|e| {
+                crate::utils::err_msg(module_path!(), line!(), format!("Invalid grammar: {e}"))
+            })?);
+        }
+
+        let seed = params.seed.unwrap_or_else(rand::random::<u32>);
+        // Mirostat picks tokens by its own target surprise; truncation samplers would fight it.
+        let truncate = params.mirostat == 0;
+        for stage in &params.order {
+            match stage {
+                SamplerStage::Penalties => {
+                    let repeat = params.repeat_penalty.unwrap_or(1.0);
+                    let freq = params.frequency_penalty.unwrap_or(0.0);
+                    let present = params.presence_penalty.unwrap_or(0.0);
+                    if repeat != 1.0 || freq != 0.0 || present != 0.0 {
+                        samplers.push(LlamaSampler::penalties(
+                            params.repeat_last_n.unwrap_or(-1),
+                            repeat as f32,
+                            freq as f32,
+                            present as f32,
+                        ));
+                    }
+                }
+                SamplerStage::Dry => {
+                    if let Some(multiplier) = params.dry_multiplier.filter(|m| *m > 0.0) {
+                        samplers.push(LlamaSampler::dry(
+                            model,
+                            multiplier as f32,
+                            params.dry_base.unwrap_or(1.75) as f32,
+                            params.dry_allowed_length.unwrap_or(2) as i32,
+                            params.dry_penalty_last_n.unwrap_or(-1),
+                            DRY_SEQUENCE_BREAKERS,
+                        ));
+                    }
+                }
+                SamplerStage::TopK if truncate => {
+                    samplers.push(LlamaSampler::top_k(params.top_k.unwrap_or(40) as i32));
+                }
+                SamplerStage::TypicalP if truncate => {
+                    if let Some(p) = params.typical_p.filter(|p| *p > 0.0 && *p < 1.0) {
+                        samplers.push(LlamaSampler::typical(p as f32, 1));
+                    }
+                }
+                SamplerStage::TopP if truncate => {
+                    let p = if params.top_p > 0.0 {
+                        params.top_p
+                    } else {
+                        1.0
+                    };
+                    samplers.push(LlamaSampler::top_p(p as f32, 1));
+                }
+                SamplerStage::MinP if truncate => {
+                    if let Some(p) = params.min_p.filter(|p| *p > 0.0) {
+                        samplers.push(LlamaSampler::min_p(p as f32, 1));
+                    }
+                }
+                SamplerStage::Xtc if truncate => {
+                    if let Some(probability) = params.xtc_probability.filter(|p| *p > 0.0) {
+                        samplers.push(LlamaSampler::xtc(
+                            probability as f32,
+                            params.xtc_threshold.unwrap_or(0.1) as f32,
+                            1,
+                            seed,
+                        ));
+                    }
+                }
+                SamplerStage::Temperature => {
+                    if params.temperature > 0.0 {
+                        samplers.push(LlamaSampler::temp(params.temperature as f32));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let tau = params.mirostat_tau as f32;
+        let eta = params.mirostat_eta as f32;
+        samplers.push(if params.temperature <= 0.0 {
+            LlamaSampler::greedy()
+        } else {
+            match params.mirostat {
+                1 => LlamaSampler::mirostat(model.n_vocab(), seed, tau, eta, 100),
+                2 => LlamaSampler::mirostat_v2(seed, tau, eta),
+                _ => LlamaSampler::dist(seed),
+            }
+        });
+
+        Ok(LlamaSampler::chain(samplers, false))
+    }
+
+    /// Merges tool-emulation instructions into the leading system message, adding one if the
+    /// conversation has none.
+    fn with_tool_instructions(messages: &[Value], instructions: &str) -> Vec<Value> {
+        let mut messages = messages.to_vec();
+        let leading_system = messages.first_mut().filter(|m| {
+            m.get("role").and_then(|r| r.as_str()).map(normalize_role) == Some("system")
+        });
+        match leading_system {
+            Some(message) => {
+                let content = extract_text_content(message);
+                message["content"] = Value::String(format!("{}\n\n{}", content, instructions));
+            }
+            None => messages.insert(0, json!({ "role": "system", "content": instructions })),
+        }
+        messages
+    }
+
+    pub async fn llamacpp_context_info(
+        app: AppHandle,
+        model_path: String,
+    ) -> Result<LlamaCppContextInfo, String> {
+        if model_path.trim().is_empty() {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "llama.cpp model path is empty",
+            ));
+        }
+        let model_path = library::resolve_model_path(&app, &model_path)?;
+        if !Path::new(&model_path).exists() {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("llama.cpp model path not found: {}", model_path),
+            ));
+        }
+
+        let engine = load_engine(Some(&app), &model_path, None, &[])?;
+        let model = engine
+            .model
+            .as_ref()
+            .ok_or_else(|| "llama.cpp model unavailable".to_string())?;
+        let max_ctx = model.n_ctx_train().max(1);
+        let available_memory_bytes = get_available_memory_bytes();
+        let recommended_context_length =
+            compute_recommended_context(model, available_memory_bytes, max_ctx);
+
+        Ok(LlamaCppContextInfo {
+            max_context_length: max_ctx,
+            recommended_context_length,
+            available_memory_bytes,
+            model_size_bytes: Some(model.size()),
+        })
+    }
+
+    /// Replaces the context's active LoRA set. Cached KV cells were computed with the old
+    /// weights, so they are dropped.
+    fn set_lora_adapters(
+        cached: &mut CachedContext,
+        loaded: &mut HashMap<String, LoadedLora>,
+        adapters: &[LoraSpec],
+    ) -> Result<(), String> {
+        cached.ctx.clear_kv_cache();
+        cached.tokens.clear();
+        for spec in std::mem::take(&mut cached.loras) {
+            if let Some(lora) = loaded.get_mut(&spec.path) {
+                cached.ctx.lora_adapter_remove(&mut lora.0).map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to remove LoRA adapter {}: {e}", spec.path),
+                    )
+                })?;
+            }
+        }
+        for spec in adapters {
+            let lora = loaded
+                .get_mut(&spec.path)
+                .ok_or_else(|| format!("LoRA adapter not loaded: {}", spec.path))?;
+            cached
+                .ctx
+                .lora_adapter_set(&mut lora.0, spec.scale)
+                .map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to apply LoRA adapter {}: {e}", spec.path),
+                    )
+                })?;
+        }
+        cached.loras = adapters.to_vec();
+        Ok(())
+    }
+
+    /// Number of leading prompt tokens that belong to the system prompt, found by rendering the
+    /// leading system messages alone and comparing. These stay pinned when context is dropped.
+    fn pinned_prefix_len(model: &LlamaModel, messages: &[Value], tokens: &[LlamaToken]) -> usize {
+        let system_count = messages
+            .iter()
+            .take_while(|m| m.get("role").and_then(|v| v.as_str()) == Some("system"))
+            .count();
+        if system_count == 0 {
+            // Still pin the BOS token.
+            return usize::from(tokens.first() == Some(&model.token_bos()));
+        }
+        let system_tokens = build_prompt(model, &messages[..system_count])
+            .ok()
+            .and_then(|prompt| model.str_to_token(&prompt, AddBos::Always).ok())
+            .unwrap_or_default();
+        system_tokens
+            .iter()
+            .zip(tokens)
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// Frees room in a full context: drops the older half of the unpinned KV cells and shifts
+    /// the rest back, like llama.cpp's context shift. Returns how many tokens were dropped.
+    fn shift_context(cached: &mut CachedContext, n_keep: usize) -> Result<usize, String> {
+        let n_past = cached.tokens.len();
+        let discard = context_shift_discard(n_past, n_keep);
+        let removed = discard > 0
+            && cached
+                .ctx
+                .clear_kv_cache_seq(
+                    Some(0),
+                    Some(n_keep as u32),
+                    Some((n_keep + discard) as u32),
+                )
+                .unwrap_or(false);
+        if !removed {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                "Context window is full and this model's cache can't be shifted. Lower max tokens or raise context length.",
+            ));
+        }
+        cached
+            .ctx
+            .kv_cache_seq_add(
+                0,
+                Some((n_keep + discard) as u32),
+                Some(n_past as u32),
+                -(discard as i32),
+            )
+            .map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to shift llama KV cache: {e}"),
+                )
+            })?;
+        cached.tokens.drain(n_keep..n_keep + discard);
+        Ok(discard)
+    }
+
+    /// Tokenizes a rendered prompt whose images were replaced by [`MEDIA_MARKER`]: the text
+    /// between markers with the model's tokenizer and each image with the projector.
+    fn media_prompt(
+        model: &LlamaModel,
+        projector: &MtmdContext,
+        prompt: &str,
+        images: &[vision::PreparedImage],
+    ) -> Result<Vec<PromptPart>, String> {
+        let texts: Vec<&str> = prompt.split(MEDIA_MARKER).collect();
+        if texts.len() != images.len() + 1 {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!(
+                    "Prompt has {} image slots for {} images",
+                    texts.len() - 1,
+                    images.len()
+                ),
+            ));
+        }
+        let mut parts = Vec::with_capacity(texts.len() + images.len());
+        for (index, text) in texts.iter().enumerate() {
+            let add_bos = if index == 0 {
+                AddBos::Always
+            } else {
+                AddBos::Never
+            };
+            let tokens = model.str_to_token(text, add_bos).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to tokenize prompt: {e}"),
+                )
+            })?;
+            if !tokens.is_empty() {
+                parts.push(PromptPart::Text(tokens));
+            }
+
+            let Some(image) = images.get(index) else {
+                continue;
+            };
+            let bitmap = MtmdBitmap::from_image_data(image.width, image.height, &image.rgb)
+                .map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to load image: {e}"),
+                    )
+                })?;
+            let input = MtmdInputText {
+                text: MEDIA_MARKER.to_string(),
+                add_special: false,
+                parse_special: true,
+            };
+            let chunks = projector.tokenize(input, &[&bitmap]).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to encode image: {e}"),
+                )
+            })?;
+            let n_pos = chunks.total_positions();
+            parts.push(PromptPart::Media {
+                chunks,
+                token: LlamaToken(vision::media_token(image)),
+                n_pos,
+            });
+        }
+        Ok(parts)
+    }
+
+    /// The prompt as one token per KV cell, with each image's cells holding its stand-in token.
+    fn media_prompt_tokens(parts: &[PromptPart]) -> Vec<LlamaToken> {
+        let mut tokens = Vec::new();
+        for part in parts {
+            match part {
+                PromptPart::Text(text) => tokens.extend_from_slice(text),
+                PromptPart::Media { token, n_pos, .. } => {
+                    tokens.extend(std::iter::repeat(*token).take(*n_pos))
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Evaluates the prompt from KV position `start`, which must not fall inside an image. Text
+    /// goes through `batch` and images through the projector. Returns the logits index to
+    /// sample the first generated token from.
+    fn eval_media_prompt(
+        cached: &mut CachedContext,
+        projector: &MtmdContext,
+        parts: &[PromptPart],
+        start: usize,
+        batch: &mut LlamaBatch,
+    ) -> Result<i32, String> {
+        let mut pos = 0usize;
+        let mut logits_index = -1;
+        for (index, part) in parts.iter().enumerate() {
+            let is_last = index + 1 == parts.len();
+            let evaluated = match part {
+                PromptPart::Text(text) => {
+                    let end = pos + text.len();
+                    let from = start.max(pos);
+                    if from < end {
+                        batch.clear();
+                        for (offset, token) in text[from - pos..].iter().enumerate() {
+                            batch
+                                .add(
+                                    *token,
+                                    (from + offset) as i32,
+                                    &[0],
+                                    is_last && from + offset + 1 == end,
+                                )
+                                .map_err(|e| {
+                                    crate::utils::err_msg(
+                                        module_path!(),
+                                        line!(),
+                                        format!("Failed to build llama batch: {e}"),
+                                    )
+                                })?;
+                        }
+                        logits_index = batch.n_tokens() - 1;
+                        cached
+                            .ctx
+                            .decode(batch)
+                            .map(|_| cached.tokens.extend_from_slice(&text[from - pos..]))
+                            .map_err(|e| format!("llama_decode failed: {e}"))
+                    } else {
+                        Ok(())
+                    }
+                }
+                PromptPart::Media {
+                    chunks,
+                    token,
+                    n_pos,
+                } if pos >= start => {
+                    logits_index = -1;
+                    chunks
+                        .eval_chunks(
+                            projector,
+                            &cached.ctx,
+                            pos as i32,
+                            0,
+                            cached.n_ctx as i32,
+                            is_last,
+                        )
+                        .map(|_| cached.tokens.extend(std::iter::repeat(*token).take(*n_pos)))
+                        .map_err(|e| format!("Failed to evaluate image: {e}"))
+                }
+                PromptPart::Media { .. } => Ok(()),
+            };
+            if let Err(err) = evaluated {
+                cached.ctx.clear_kv_cache();
+                cached.tokens.clear();
+                return Err(crate::utils::err_msg(module_path!(), line!(), err));
+            }
+            pos += match part {
+                PromptPart::Text(text) => text.len(),
+                PromptPart::Media { n_pos, .. } => *n_pos,
+            };
+        }
+        Ok(logits_index)
+    }
+
+    /// Drops the KV cells from position `keep` on. Returns false when the model's cache can't
+    /// remove a range, in which case nothing was changed.
+    fn truncate_context(cached: &mut CachedContext, keep: usize) -> bool {
+        let trimmed = cached
+            .ctx
+            .clear_kv_cache_seq(Some(0), Some(keep as u32), None)
+            .unwrap_or(false);
+        if trimmed {
+            cached.tokens.truncate(keep);
+        }
+        trimmed
+    }
+
+    /// The draft model's context, recreated when the main context's size or threads change.
+    fn draft_context<'a>(
+        draft: &'a mut DraftEngine,
+        backend: &LlamaBackend,
+        params_key: &str,
+        n_ctx: u32,
+        ctx_params: LlamaContextParams,
+    ) -> Result<&'a mut CachedContext, String> {
+        let reuse = draft
+            .context
+            .as_ref()
+            .is_some_and(|c| c.params_key == params_key && c.n_ctx == n_ctx);
+        if !reuse {
+            draft.context = None;
+            let ctx = static_model(&draft.model)
+                .new_context(backend, ctx_params)
+                .map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to create draft context: {e}"),
+                    )
+                })?;
+            draft.context = Some(CachedContext {
+                ctx,
+                params_key: params_key.to_string(),
+                n_ctx,
+                tokens: Vec::new(),
+                loras: Vec::new(),
+            });
+        }
+        draft
+            .context
+            .as_mut()
+            .ok_or_else(|| "draft context unavailable".to_string())
+    }
+
+    /// Greedily proposes up to `n_draft` tokens continuing `tokens`, reusing whatever prefix
+    /// the draft context already holds.
+    fn draft_tokens(
+        draft: &mut CachedContext,
+        batch: &mut LlamaBatch,
+        sampler: &mut LlamaSampler,
+        tokens: &[LlamaToken],
+        n_draft: usize,
+    ) -> Result<Vec<LlamaToken>, String> {
+        let reused = reusable_prefix_len(&draft.tokens, tokens);
+        if reused < draft.tokens.len() && !truncate_context(draft, reused) {
+            draft.ctx.clear_kv_cache();
+            draft.tokens.clear();
+        }
+        let start = draft.tokens.len();
+        let suffix = &tokens[start..];
+
+        batch.clear();
+        for (offset, token) in suffix.iter().enumerate() {
+            batch
+                .add(
+                    *token,
+                    (start + offset) as i32,
+                    &[0],
+                    offset + 1 == suffix.len(),
+                )
+                .map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to build draft batch: {e}"),
+                    )
+                })?;
+        }
+        let mut drafted = Vec::with_capacity(n_draft);
+        let mut pending = suffix.to_vec();
+        loop {
+            if let Err(e) = draft.ctx.decode(batch) {
+                draft.ctx.clear_kv_cache();
+                draft.tokens.clear();
+                return Err(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Draft llama_decode failed: {e}"),
+                ));
+            }
+            draft.tokens.append(&mut pending);
+
+            let token = sampler.sample(&draft.ctx, batch.n_tokens() - 1);
+            sampler.accept(token);
+            drafted.push(token);
+            if drafted.len() >= n_draft {
+                return Ok(drafted);
+            }
+
+            batch.clear();
+            batch
+                .add(token, draft.tokens.len() as i32, &[0], true)
+                .map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to update draft batch: {e}"),
+                    )
+                })?;
+            pending.push(token);
+        }
+    }
+
+    /// Loads the saved state under `key` when it shares a longer prefix with `tokens` than what
+    /// the context already holds, e.g. after a restart or a switch from another chat.
+    fn restore_saved_state(
+        app: &AppHandle,
+        cached: &mut CachedContext,
+        dir: &Path,
+        key: &str,
+        tokens: &[LlamaToken],
+    ) {
+        let Some(saved) = session_state::read_tokens(dir, key) else {
+            return;
+        };
+        let saved_prefix = saved
+            .iter()
+            .zip(tokens)
+            .take_while(|(a, b)| **a == b.0)
+            .count();
+        if saved_prefix <= reusable_prefix_len(&cached.tokens, tokens) {
+            return;
+        }
+        cached.ctx.clear_kv_cache();
+        cached.tokens.clear();
+        let path = session_state::state_path(dir, key);
+        match cached.ctx.load_session_file(&path, cached.n_ctx as usize) {
+            Ok(loaded) => {
+                log_info(
+                    app,
+                    "llama_cpp",
+                    format!("restored saved state key={} tokens={}", key, loaded.len()),
+                );
+                cached.tokens = loaded;
+            }
+            Err(e) => {
+                log_warn(
+                    app,
+                    "llama_cpp",
+                    format!("discarding unreadable saved state key={}: {e}", key),
+                );
+                cached.ctx.clear_kv_cache();
+                session_state::remove(dir, key);
+            }
+        }
+    }
+
+    /// Saves the context's state under `key` and evicts old states past `budget_bytes`. Runs
+    /// after the reply went out, so the context is only saved if it still holds `tokens` (no
+    /// other request took the engine meanwhile). Failures only cost the next restore, so they
+    /// are logged rather than returned.
+    fn save_state(
+        app: &AppHandle,
+        model_path: &str,
+        dir: &Path,
+        key: &str,
+        tokens: &[LlamaToken],
+        budget_bytes: u64,
+    ) {
+        let Ok(engine) = engine().lock() else {
+            return;
+        };
+        let Some(cached) = engine
+            .context
+            .as_ref()
+            .filter(|c| engine.model_path.as_deref() == Some(model_path) && c.tokens == tokens)
+        else {
+            return;
+        };
+        let tokens: Vec<i32> = tokens.iter().map(|t| t.0).collect();
+        if !session_state::worth_saving(session_state::read_tokens(dir, key).as_deref(), &tokens) {
+            return;
+        }
+        let state_size = cached.ctx.get_state_size() as u64;
+        if state_size > budget_bytes {
+            log_info(
+                app,
+                "llama_cpp",
+                format!(
+                    "not saving state key={}: {} bytes exceed the {} byte budget",
+                    key, state_size, budget_bytes
+                ),
+            );
+            return;
+        }
+        let saved = session_state::save(dir, key, &tokens, |path| {
+            cached
+                .ctx
+                .save_session_file(path, &cached.tokens)
+                .map_err(|e| format!("Failed to save llama state: {e}"))
+        });
+        match saved {
+            Ok(()) => {
+                let evicted = session_state::evict(dir, budget_bytes);
+                log_info(
+                    app,
+                    "llama_cpp",
+                    format!(
+                        "saved state key={} tokens={} evicted={}",
+                        key,
+                        tokens.len(),
+                        evicted
+                    ),
+                );
+            }
+            Err(err) => {
+                session_state::remove(dir, key);
+                log_warn(app, "llama_cpp", format!("failed to save state: {err}"));
+            }
+        }
+    }
+
+    fn emit_context_shift(
+        app: &AppHandle,
+        stream: bool,
+        request_id: Option<&str>,
+        discarded: usize,
+        pinned: usize,
+    ) {
+        if let (true, Some(id)) = (stream, request_id) {
+            transport::emit_normalized(
+                app,
+                id,
+                NormalizedEvent::ContextShift {
+                    discarded: discarded as u32,
+                    pinned: pinned as u32,
+                },
+            );
+        }
+    }
+
+    pub async fn handle_local_request(
+        app: AppHandle,
+        req: ApiRequest,
+    ) -> Result<ApiResponse, String> {
+        let body = req
+            .body
+            .as_ref()
+            .ok_or_else(|| "llama.cpp request missing body".to_string())?;
+        let model_path = body
+            .get("model")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "llama.cpp request missing model path".to_string())?;
+        let model_path = &library::resolve_model_path(&app, model_path)?;
+
+        if !Path::new(model_path).exists() {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("llama.cpp model path not found: {}", model_path),
+            ));
+        }
+
+        let messages = body
+            .get("messages")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| "llama.cpp request missing messages".to_string())?;
+        let emulated_tools = match body.get("tools").and_then(|v| v.as_array()) {
+            Some(tools) => {
+                crate::chat_manager::tooling::emulated_tools(tools, body.get("tool_choice"))?
+            }
+            None => None,
+        };
+        let grammar = match &emulated_tools {
+            Some(emulated) => Some(emulated.grammar.clone()),
+            None => grammar::requested_grammar(body)?,
+        };
+        let sampling = SamplingParams::from_body(body, grammar);
+        let messages = match &emulated_tools {
+            Some(emulated) => with_tool_instructions(messages, &emulated.instructions),
+            None => messages.clone(),
+        };
+        let mmproj_path = match body
+            .get("llamaMmprojPath")
+            .or_else(|| body.get("llama_mmproj_path"))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        {
+            Some(mmproj) => {
+                let path = library::resolve_model_path(&app, mmproj)?;
+                if !Path::new(&path).exists() {
+                    return Err(crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("llama.cpp vision projector not found: {}", path),
+                    ));
+                }
+                Some(path)
+            }
+            None => None,
+        };
+        // Without a projector, image parts are left for `extract_text_content` to skip.
+        let (messages, images) = match mmproj_path {
+            Some(_) => {
+                let (messages, urls, skipped_links) = vision::split_media(&messages, MEDIA_MARKER);
+                if skipped_links > 0 {
+                    log_warn(
+                        &app,
+                        "llama_cpp",
+                        format!(
+                            "left out {} image link(s); local models only read attached images",
+                            skipped_links
+                        ),
+                    );
+                }
+                let images = urls
+                    .iter()
+                    .map(|url| vision::prepare(&vision::image_bytes(url)?, vision::MAX_IMAGE_SIDE))
+                    .collect::<Result<Vec<_>, String>>()?;
+                (messages, images)
+            }
+            None => (messages, Vec::new()),
+        };
+
+        let max_tokens = body
+            .get("max_tokens")
+            .or_else(|| body.get("max_completion_tokens"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(512) as u32;
+        let llama_gpu_layers = body
+            .get("llamaGpuLayers")
+            .or_else(|| body.get("llama_gpu_layers"))
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok());
+        let llama_threads = body
+            .get("llamaThreads")
+            .or_else(|| body.get("llama_threads"))
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v > 0);
+        let llama_threads_batch = body
+            .get("llamaThreadsBatch")
+            .or_else(|| body.get("llama_threads_batch"))
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v > 0);
+        let llama_rope_freq_base = body
+            .get("llamaRopeFreqBase")
+            .or_else(|| body.get("llama_rope_freq_base"))
+            .and_then(|v| v.as_f64());
+        let llama_rope_freq_scale = body
+            .get("llamaRopeFreqScale")
+            .or_else(|| body.get("llama_rope_freq_scale"))
+            .and_then(|v| v.as_f64());
+        let llama_offload_kqv = body
+            .get("llamaOffloadKqv")
+            .or_else(|| body.get("llama_offload_kqv"))
+            .and_then(|v| v.as_bool());
+        let lora_adapters = lora_specs(body);
+        let draft_max = body
+            .get("llamaDraftMax")
+            .or_else(|| body.get("llama_draft_max"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(DEFAULT_DRAFT_MAX);
+        let draft_model_path = match body
+            .get("llamaDraftModel")
+            .or_else(|| body.get("llama_draft_model"))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty() && draft_max > 0)
+        {
+            Some(draft) => {
+                let path = library::resolve_model_path(&app, draft)?;
+                if !Path::new(&path).exists() {
+                    return Err(crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("llama.cpp draft model path not found: {}", path),
+                    ));
+                }
+                Some(path)
+            }
+            None => None,
+        };
+        let session_id = body
+            .get("llamaSessionId")
+            .or_else(|| body.get("llama_session_id"))
+            .and_then(|v| v.as_str());
+        let state_budget_mb = body
+            .get("llamaStateBudgetMb")
+            .or_else(|| body.get("llama_state_budget_mb"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(session_state::DEFAULT_BUDGET_MB);
+        let state_budget_bytes = state_budget_mb.saturating_mul(1024 * 1024);
+        // Only main chat turns ask for their state to be kept; memory and summary requests
+        // would otherwise evict it with one-off prompts.
+        let save_state_requested = body
+            .get("llamaSaveState")
+            .or_else(|| body.get("llama_save_state"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let state_dir = if state_budget_mb > 0 {
+            session_state::state_dir(&app)
+                .map_err(|err| log_warn(&app, "llama_cpp", format!("state dir unavailable: {err}")))
+                .ok()
+        } else {
+            None
+        };
+        let requested_context = body
+            .get("context_length")
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v > 0);
+
+        let request_id = req.request_id.clone();
+        let stream = req.stream.unwrap_or(false);
+
+        log_info(
+            &app,
+            "llama_cpp",
+            format!(
+                "local inference start model_path={} stream={} request_id={:?}",
+                model_path, stream, request_id
+            ),
+        );
+
+        let mut abort_rx = request_id.as_ref().map(|id| {
+            use tauri::Manager;
+            let registry = app.state::<crate::abort_manager::AbortRegistry>();
+            registry.register(id.clone())
+        });
+
+        let mut output = String::new();
+        let mut prompt_tokens = 0u64;
+        let mut cached_prompt_tokens = 0u64;
+        let mut completion_tokens = 0u64;
+        let mut drafted_tokens = 0u64;
+        let mut accepted_draft_tokens = 0u64;
+        let mut pending_save: Option<(String, Vec<LlamaToken>)> = None;
+
+        let result = (|| -> Result<(), String> {
+            let mut engine = load_engine(Some(&app), model_path, llama_gpu_layers, &lora_adapters)?;
+            let state = &mut *engine;
+            match draft_model_path.as_deref() {
+                Some(path) => load_draft(&app, state, path, llama_gpu_layers)?,
+                None => state.draft = None,
+            }
+            match mmproj_path.as_deref() {
+                Some(path) => load_projector(&app, state, path, llama_gpu_layers)?,
+                None => state.projector = None,
+            }
+            let model = static_model(
+                state
+                    .model
+                    .as_deref()
+                    .ok_or_else(|| "llama.cpp model unavailable".to_string())?,
+            );
+            let backend = state
+                .backend
+                .as_ref()
+                .ok_or_else(|| "llama.cpp backend unavailable".to_string())?;
+            let max_ctx = model.n_ctx_train().max(1);
+            let available_memory_bytes = get_available_memory_bytes();
+            let recommended_ctx =
+                compute_recommended_context(model, available_memory_bytes, max_ctx);
+            let params_key = format!(
+                "threads={:?}/{:?} offload_kqv={:?} rope={:?}/{:?}",
+                llama_threads,
+                llama_threads_batch,
+                llama_offload_kqv,
+                llama_rope_freq_base,
+                llama_rope_freq_scale
+            );
+            let cached_ctx_size = state
+                .context
+                .as_ref()
+                .filter(|c| c.params_key == params_key)
+                .map(|c| c.n_ctx);
+            let ctx_size = if let Some(requested) = requested_context {
+                requested.min(max_ctx)
+            } else if let Some(cached) = cached_ctx_size {
+                // The cached context's KV buffers already count against available memory, so
+                // re-deriving the size would shrink it (and miss the cache) every turn.
+                cached
+            } else if let Some(recommended) = recommended_ctx {
+                if recommended == 0 {
+                    return Err(
+                        "llama.cpp model likely won't fit in memory. Try a smaller model or set a shorter context.".to_string(),
+                    );
+                }
+                recommended.min(max_ctx).max(1)
+            } else {
+                max_ctx
+            };
+            let prompt = build_prompt(model, &messages)?;
+            let media_parts = match state.projector.as_ref() {
+                Some(projector) if !images.is_empty() => {
+                    Some(media_prompt(model, &projector.ctx, &prompt, &images)?)
+                }
+                _ => None,
+            };
+            let mut tokens = match &media_parts {
+                Some(parts) => media_prompt_tokens(parts),
+                None => model.str_to_token(&prompt, AddBos::Always).map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to tokenize prompt: {e}"),
+                    )
+                })?,
+            };
+            prompt_tokens = tokens.len() as u64;
+
+            let n_keep = pinned_prefix_len(model, &messages, &tokens).min(ctx_size as usize / 2);
+            if tokens.len() as u32 >= ctx_size && media_parts.is_some() {
+                return Err(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!(
+                        "This conversation and its images need {} tokens but the context holds {}. Raise context length or start a new chat.",
+                        tokens.len(),
+                        ctx_size
+                    ),
+                ));
+            }
+            if tokens.len() as u32 >= ctx_size {
+                let dropped = truncate_prompt(&mut tokens, n_keep, ctx_size as usize);
+                log_warn(
+                    &app,
+                    "llama_cpp",
+                    format!(
+                        "prompt exceeds context: dropped {} tokens after {} pinned (context: {})",
+                        dropped, n_keep, ctx_size
+                    ),
+                );
+                emit_context_shift(&app, stream, request_id.as_deref(), dropped, n_keep);
+            }
+            let state_key = state_dir.as_ref().map(|_| {
+                let pinned: Vec<i32> = tokens[..n_keep].iter().map(|t| t.0).collect();
+                let variant = format!("{} loras={:?}", params_key, lora_adapters);
+                session_state::state_key(model_path, &variant, session_id, &pinned)
+            });
+
+            let n_batch = ctx_size;
+            let mut ctx_params = LlamaContextParams::default()
+                .with_n_ctx(NonZeroU32::new(ctx_size))
+                .with_n_batch(n_batch);
+            if let Some(n_threads) = llama_threads {
+                ctx_params = ctx_params.with_n_threads(n_threads as i32);
+            }
+            if let Some(n_threads_batch) = llama_threads_batch {
+                ctx_params = ctx_params.with_n_threads_batch(n_threads_batch as i32);
+            }
+            let draft_ctx_params = ctx_params.clone();
+            if let Some(offload) = llama_offload_kqv {
+                ctx_params = ctx_params.with_offload_kqv(offload);
+            }
+            if let Some(base) = llama_rope_freq_base {
+                ctx_params = ctx_params.with_rope_freq_base(base as f32);
+            }
+            if let Some(scale) = llama_rope_freq_scale {
+                ctx_params = ctx_params.with_rope_freq_scale(scale as f32);
+            }
+            let reuse_context = state
+                .context
+                .as_ref()
+                .is_some_and(|c| c.params_key == params_key && c.n_ctx == ctx_size);
+            if !reuse_context {
+                state.context = None;
+                let ctx = model.new_context(backend, ctx_params).map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to create llama context: {e}"),
+                    )
+                })?;
+                state.context = Some(CachedContext {
+                    ctx,
+                    params_key,
+                    n_ctx: ctx_size,
+                    tokens: Vec::new(),
+                    loras: Vec::new(),
+                });
+            }
+            let cached = state
+                .context
+                .as_mut()
+                .ok_or_else(|| "llama.cpp context unavailable".to_string())?;
+            if cached.loras != lora_adapters {
+                if let Err(err) = set_lora_adapters(cached, &mut state.loras, &lora_adapters) {
+                    // The context may be left with a partial set; rebuild it next time.
+                    state.context = None;
+                    return Err(err);
+                }
+            }
+
+            if let (Some(dir), Some(key)) = (state_dir.as_deref(), state_key.as_deref()) {
+                restore_saved_state(&app, cached, dir, key, &tokens);
+            }
+
+            let mut reused = reusable_prefix_len(&cached.tokens, &tokens);
+            if media_parts.is_some() {
+                reused = media_boundary(&tokens, reused, |t| t.0 < 0);
+            }
+            if reused < cached.tokens.len() {
+                let trimmed = cached
+                    .ctx
+                    .clear_kv_cache_seq(Some(0), Some(reused as u32), None)
+                    .unwrap_or(false);
+                if !trimmed {
+                    // Some architectures can't drop a range of cells; start over.
+                    cached.ctx.clear_kv_cache();
+                    reused = 0;
+                }
+                cached.tokens.truncate(reused);
+            }
+            cached_prompt_tokens = reused as u64;
+
+            let mut batch = LlamaBatch::new(n_batch as usize, 1);
+            let first_logits = match (&media_parts, state.projector.as_ref()) {
+                (Some(parts), Some(projector)) => {
+                    eval_media_prompt(cached, &projector.ctx, parts, reused, &mut batch)?
+                }
+                _ => {
+                    let suffix = &tokens[reused..];
+                    let last_offset = suffix.len().saturating_sub(1);
+                    for (offset, token) in suffix.iter().enumerate() {
+                        batch
+                            .add(
+                                *token,
+                                (reused + offset) as i32,
+                                &[0],
+                                offset == last_offset,
+                            )
+                            .map_err(|e| {
+                                crate::utils::err_msg(
+                                    module_path!(),
+                                    line!(),
+                                    format!("Failed to build llama batch: {e}"),
+                                )
+                            })?;
+                    }
+
+                    if let Err(e) = cached.ctx.decode(&mut batch) {
+                        cached.ctx.clear_kv_cache();
+                        cached.tokens.clear();
+                        return Err(crate::utils::err_msg(
+                            module_path!(),
+                            line!(),
+                            format!("llama_decode failed: {e}"),
+                        ));
+                    }
+                    cached.tokens.extend_from_slice(suffix);
+                    batch.n_tokens() - 1
+                }
+            };
+            log_info(
+                &app,
+                "llama_cpp",
+                format!(
+                    "prompt cache: reused={} evaluated={} prompt_tokens={}",
+                    reused,
+                    tokens.len() - reused,
+                    tokens.len()
+                ),
+            );
+
+            // The draft model can't see images, so it only helps with text-only prompts.
+            let mut draft = match state.draft.as_mut() {
+                Some(engine) if media_parts.is_none() => Some(draft_context(
+                    engine,
+                    backend,
+                    &format!("threads={:?}/{:?}", llama_threads, llama_threads_batch),
+                    ctx_size,
+                    draft_ctx_params,
+                )?),
+                _ => None,
+            };
+            let mut draft_batch = LlamaBatch::new(n_batch as usize, 1);
+            let mut draft_sampler = LlamaSampler::greedy();
+
+            let mut n_cur = tokens.len() as i32;
+            let mut sampler = build_sampler(model, &sampling)?;
+            // Where the next token's logits are in the last batch, and the drafted tokens decoded
+            // after it that the main model hasn't verified yet.
+            let mut logits_index = first_logits;
+            let mut unverified: VecDeque<LlamaToken> = VecDeque::new();
+
+            while completion_tokens < u64::from(max_tokens) {
+                if let Some(rx) = abort_rx.as_mut() {
+                    match rx.try_recv() {
+                        Ok(()) => {
+                            return Err(crate::utils::err_msg(
+                                module_path!(),
+                                line!(),
+                                "llama.cpp request aborted by user",
+                            ));
+                        }
+                        Err(TryRecvError::Closed) | Err(TryRecvError::Empty) => {}
+                    }
+                }
+
+                let token = sampler.sample(&cached.ctx, logits_index);
+                sampler.accept(token);
+
+                // A drafted token the main model agrees with is already in the cache. A rejected
+                // one is dropped along with everything drafted after it.
+                let decoded = match unverified.pop_front() {
+                    Some(drafted) if drafted == token => {
+                        accepted_draft_tokens += 1;
+                        logits_index += 1;
+                        true
+                    }
+                    Some(_) => {
+                        let keep = n_cur as usize - unverified.len() - 1;
+                        unverified.clear();
+                        if !truncate_context(cached, keep) {
+                            cached.ctx.clear_kv_cache();
+                            cached.tokens.clear();
+                            return Err(crate::utils::err_msg(
+                                module_path!(),
+                                line!(),
+                                "This model's cache can't drop rejected draft tokens. Remove the draft model to use it.",
+                            ));
+                        }
+                        n_cur = keep as i32;
+                        false
+                    }
+                    None => false,
+                };
+
+                if token == model.token_eos() {
+                    break;
+                }
+
+                let piece = model.token_to_str(token, Special::Plaintext).map_err(|e| {
+                    crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("Failed to decode token: {e}"),
+                    )
+                })?;
+
+                output.push_str(&piece);
+                completion_tokens += 1;
+
+                // Emulated tool calls are only known once the reply is complete.
+                if stream && emulated_tools.is_none() {
+                    if let Some(ref id) = request_id {
+                        transport::emit_normalized(
+                            &app,
+                            id,
+                            NormalizedEvent::Delta { text: piece },
+                        );
+                    }
+                }
+
+                if decoded {
+                    continue;
+                }
+
+                if n_cur as u32 >= ctx_size {
+                    if media_parts.is_some() {
+                        return Err(crate::utils::err_msg(
+                            module_path!(),
+                            line!(),
+                            "Context window is full. Conversations with images can't drop older context; raise context length or start a new chat.",
+                        ));
+                    }
+                    let discarded = shift_context(cached, n_keep)?;
+                    n_cur -= discarded as i32;
+                    log_info(
+                        &app,
+                        "llama_cpp",
+                        format!(
+                            "context shift: discarded {} tokens after {} pinned",
+                            discarded, n_keep
+                        ),
+                    );
+                    emit_context_shift(&app, stream, request_id.as_deref(), discarded, n_keep);
+                }
+
+                cached.tokens.push(token);
+                let n_draft = draft_budget(
+                    draft_max,
+                    n_cur as usize,
+                    ctx_size as usize,
+                    (u64::from(max_tokens) - completion_tokens) as usize,
+                );
+                let drafted = match draft.as_mut() {
+                    Some(draft) if n_draft > 0 => draft_tokens(
+                        draft,
+                        &mut draft_batch,
+                        &mut draft_sampler,
+                        &cached.tokens,
+                        n_draft,
+                    )?,
+                    _ => Vec::new(),
+                };
+
+                batch.clear();
+                let next_tokens = std::iter::once(token).chain(drafted.iter().copied());
+                for (offset, next) in next_tokens.enumerate() {
+                    batch
+                        .add(next, n_cur + offset as i32, &[0], true)
+                        .map_err(|e| {
+                            crate::utils::err_msg(
+                                module_path!(),
+                                line!(),
+                                format!("Failed to update llama batch: {e}"),
+                            )
+                        })?;
+                }
+                n_cur += batch.n_tokens();
+
+                if let Err(e) = cached.ctx.decode(&mut batch) {
+                    cached.ctx.clear_kv_cache();
+                    cached.tokens.clear();
+                    return Err(crate::utils::err_msg(
+                        module_path!(),
+                        line!(),
+                        format!("llama_decode failed: {e}"),
+                    ));
+                }
+                cached.tokens.extend_from_slice(&drafted);
+                logits_index = 0;
+                drafted_tokens += drafted.len() as u64;
+                unverified.extend(drafted);
+            }
+
+            // Drafts still unverified when the reply ended aren't part of it.
+            if !unverified.is_empty() {
+                let keep = n_cur as usize - unverified.len();
+                if !truncate_context(cached, keep) {
+                    cached.ctx.clear_kv_cache();
+                    cached.tokens.clear();
+                }
+            }
+            if drafted_tokens > 0 {
+                log_info(
+                    &app,
+                    "llama_cpp",
+                    format!(
+                        "speculative decoding: accepted {} of {} drafted tokens",
+                        accepted_draft_tokens, drafted_tokens
+                    ),
+                );
+            }
+
+            if let Some(key) = state_key.filter(|_| save_state_requested) {
+                pending_save = Some((key, cached.tokens.clone()));
+            }
+
+            Ok(())
+        })();
+
+        if let Some(ref id) = request_id {
+            use tauri::Manager;
+            let registry = app.state::<crate::abort_manager::AbortRegistry>();
+            registry.unregister(id);
+        }
+
+        if let Err(err) = result {
+            log_error(&app, "llama_cpp", format!("local inference error: {}", err));
+            if stream {
+                if let Some(ref id) = request_id {
+                    let envelope = ErrorEnvelope {
+                        code: Some("LOCAL_INFERENCE_FAILED".into()),
+                        message: err.clone(),
+                        provider_id: Some(LOCAL_PROVIDER_ID.to_string()),
+                        request_id: Some(id.clone()),
+                        retryable: Some(false),
+                        status: None,
+                    };
+                    transport::emit_normalized(&app, id, NormalizedEvent::Error { envelope });
+                }
+            }
+            return Err(err);
+        }
+
+        let tool_calls = emulated_tools
+            .as_ref()
+            .and_then(|_| crate::chat_manager::tooling::parse_emulated_tool_calls(&output));
+        let finish_reason = if tool_calls.is_some() {
+            "tool_calls"
+        } else {
+            "stop"
+        };
+
+        if stream {
+            if let Some(ref id) = request_id {
+                if emulated_tools.is_some() && tool_calls.is_none() && !output.is_empty() {
+                    transport::emit_normalized(
+                        &app,
+                        id,
+                        NormalizedEvent::Delta {
+                            text: output.clone(),
+                        },
+                    );
+                }
+                let usage = UsageSummary {
+                    prompt_tokens: Some(prompt_tokens),
+                    completion_tokens: Some(completion_tokens),
+                    total_tokens: Some(prompt_tokens + completion_tokens),
+                    reasoning_tokens: None,
+                    image_tokens: None,
+                    finish_reason: Some(finish_reason.into()),
+                    draft_tokens: (drafted_tokens > 0).then_some(drafted_tokens),
+                    accepted_draft_tokens: (drafted_tokens > 0).then_some(accepted_draft_tokens),
+                };
+                transport::emit_normalized(&app, id, NormalizedEvent::Usage { usage });
+                transport::emit_normalized(&app, id, NormalizedEvent::Done);
+            }
+        }
+
+        if let (Some(dir), Some((key, tokens))) = (state_dir, pending_save) {
+            let app = app.clone();
+            let model_path = model_path.to_string();
+            tauri::async_runtime::spawn_blocking(move || {
+                save_state(&app, &model_path, &dir, &key, &tokens, state_budget_bytes);
+            });
+        }
+
+        let mut usage_value = json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+            "prompt_tokens_details": { "cached_tokens": cached_prompt_tokens },
+        });
+        if drafted_tokens > 0 {
+            usage_value["completion_tokens_details"] = json!({
+                "draft_tokens": drafted_tokens,
+                "accepted_draft_tokens": accepted_draft_tokens,
+            });
+        }
+
+        let message = match tool_calls {
+            Some(calls) => {
+                json!({ "role": "assistant", "content": Value::Null, "tool_calls": calls })
+            }
+            None => json!({ "role": "assistant", "content": output }),
+        };
+        let data = json!({
+            "id": "local-llama",
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "finish_reason": finish_reason
+            }],
+            "usage": usage_value,
+        });
+
+        Ok(ApiResponse {
+            status: 200,
+            ok: true,
+            headers: HashMap::new(),
+            data,
+        })
+    }
+}
+
+#[cfg(not(mobile))]
+pub use desktop::count_gguf_tokens;
+#[cfg(mobile)]
+pub fn count_gguf_tokens(_model_path: &str, _text: &str) -> Result<Option<u32>, String> {
+    Err(crate::utils::err_msg(
+        module_path!(),
+        line!(),
+        "llama.cpp is only supported on desktop builds",
+    ))
+}
+
+#[cfg(not(mobile))]
+pub use desktop::handle_local_request;
+#[cfg(mobile)]
+pub async fn handle_local_request(
+    _app: AppHandle,
+    _req: ApiRequest,
+) -> Result<ApiResponse, String> {
+    Err(crate::utils::err_msg(
+        module_path!(),
+        line!(),
+        "llama.cpp is only supported on desktop builds",
+    ))
+}
+
+#[tauri::command]
+pub async fn llamacpp_context_info(
+    app: AppHandle,
+    model_path: String,
+) -> Result<serde_json::Value, String> {
+    #[cfg(not(mobile))]
+    {
+        let info = desktop::llamacpp_context_info(app, model_path).await?;
+        return serde_json::to_value(info).map_err(|e| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Failed to serialize context info: {e}"),
+            )
+        });
+    }
+    #[cfg(mobile)]
+    {
+        let _ = app;
+        let _ = model_path;
+        Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "llama.cpp is only supported on desktop builds",
+        ))
+    }
+}
+
+#[tauri::command]
+pub async fn llamacpp_unload(app: AppHandle) -> Result<(), String> {
+    #[cfg(not(mobile))]
+    {
+        return desktop::unload_engine(&app);
+    }
+    #[cfg(mobile)]
+    {
+        let _ = app;
+        Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "llama.cpp is only supported on desktop builds",
+        ))
+    }
+}
+
+/// Available system memory, or `None` where it can't be measured.
+pub(crate) fn available_memory_bytes() -> Option<u64> {
+    #[cfg(not(mobile))]
+    {
+        desktop::get_available_memory_bytes()
+    }
+    #[cfg(mobile)]
+    {
+        None
+    }
+}
+
+/// Model dimensions that determine how large the KV cache grows per token.
+#[derive(Debug, Clone, Copy)]
+pub struct KvShape {
+    pub n_layer: u64,
+    pub n_embd: u64,
+    pub n_head: u64,
+    /// Zero when the model doesn't report it (older models), meaning no GQA.
+    pub n_head_kv: u64,
+}
+
+/// Bytes of KV cache one token of context takes, assuming llama.cpp's default F16 cache.
+pub(crate) fn kv_bytes_per_token(shape: KvShape) -> u64 {
+    let n_head = shape.n_head.max(1);
+    let n_head_kv = if shape.n_head_kv == 0 {
+        n_head
+    } else {
+        shape.n_head_kv
+    };
+
+    // GQA Ratio: In Llama 3, this is 8/32 = 0.25
+    // We calculate the effective embedding size for the KV cache
+    let gqa_correction = n_head_kv as f64 / n_head as f64;
+    let effective_n_embd = (shape.n_embd as f64 * gqa_correction) as u64;
+
+    // F16 (2 bytes) is the default KV cache type in llama.cpp unless changed.
+    // K cache + V cache = 2 matrices
+    let bytes_per_value = 2_u64;
+
+    shape
+        .n_layer
+        .saturating_mul(effective_n_embd)
+        .saturating_mul(2 * bytes_per_value)
+}
+
+/// Largest context that fits in `available` bytes next to the weights, keeping a fifth of the
+/// memory (at least 512 MiB) in reserve. Capped at the model's trained context.
+pub(crate) fn recommended_context(
+    model_size: u64,
+    kv_bytes_per_token: u64,
+    available: u64,
+    max_context_length: u32,
+) -> Option<u32> {
+    if kv_bytes_per_token == 0 {
+        return None;
+    }
+    let reserve = (available / 5).max(512 * 1024 * 1024);
+    let available_for_ctx = available.saturating_sub(model_size.saturating_add(reserve));
+    let recommended = (available_for_ctx / kv_bytes_per_token).min(u64::from(max_context_length));
+    Some(recommended as u32)
+}
+
+/// A LoRA adapter requested for a generation.
+#[cfg(not(mobile))]
+#[derive(Debug, Clone, PartialEq)]
+struct LoraSpec {
+    path: String,
+    scale: f32,
+}
+
+/// Reads `llamaLoraAdapters` from a request body. Entries are `{ "path", "scale" }` objects or
+/// bare paths (scale 1); blank paths, repeats and zero scales are dropped.
+#[cfg(not(mobile))]
+fn lora_specs(body: &Value) -> Vec<LoraSpec> {
+    let entries = body
+        .get("llamaLoraAdapters")
+        .or_else(|| body.get("llama_lora_adapters"))
+        .and_then(|v| v.as_array());
+    let mut specs: Vec<LoraSpec> = Vec::new();
+    for entry in entries.into_iter().flatten() {
+        let (path, scale) = match entry {
+            Value::String(path) => (path.as_str(), 1.0),
+            Value::Object(obj) => (
+                obj.get("path").and_then(|v| v.as_str()).unwrap_or_default(),
+                obj.get("scale").and_then(|v| v.as_f64()).unwrap_or(1.0),
+            ),
+            _ => continue,
+        };
+        let path = path.trim();
+        if path.is_empty() || scale == 0.0 || specs.iter().any(|s| s.path == path) {
+            continue;
+        }
+        specs.push(LoraSpec {
+            path: path.to_string(),
+            scale: scale as f32,
+        });
+    }
+    specs
+}
+
+/// Tokens a context shift drops from a full context of `n_past` tokens: half of those after the
+/// `n_keep` pinned ones.
+#[cfg(not(mobile))]
+fn context_shift_discard(n_past: usize, n_keep: usize) -> usize {
+    n_past.saturating_sub(n_keep) / 2
+}
+
+/// Shortens a prompt that doesn't fit in `n_ctx` by dropping its oldest tokens after the pinned
+/// first `n_keep`, leaving half the unpinned space for generation. Returns how many were dropped.
+#[cfg(not(mobile))]
+fn truncate_prompt<T>(tokens: &mut Vec<T>, n_keep: usize, n_ctx: usize) -> usize {
+    let n_keep = n_keep.min(n_ctx / 2);
+    let keep_tail = (n_ctx - n_keep) / 2;
+    let dropped = tokens.len().saturating_sub(n_keep + keep_tail);
+    tokens.drain(n_keep..n_keep + dropped);
+    dropped
+}
+
+/// Tokens proposed per step when `llamaDraftModel` is set without `llamaDraftMax`.
+#[cfg(not(mobile))]
+const DEFAULT_DRAFT_MAX: usize = 8;
+
+/// How many tokens the draft model may propose after the token about to be decoded at
+/// position `n_past`: no more than requested, than fit in the context, or than the reply
+/// still has room for.
+#[cfg(not(mobile))]
+fn draft_budget(draft_max: usize, n_past: usize, n_ctx: usize, remaining: usize) -> usize {
+    draft_max
+        .min(n_ctx.saturating_sub(n_past + 1))
+        .min(remaining)
+}
+
+/// Placeholder for an image in a rendered prompt; llama.cpp's default media marker.
+#[cfg(not(mobile))]
+const MEDIA_MARKER: &str = "<__media__>";
+
+/// Moves a reusable prefix length back to the start of an image it would cut through. An
+/// image's KV cells are only ever evaluated together, and each of them holds the image's
+/// stand-in token.
+#[cfg(not(mobile))]
+fn media_boundary<T: PartialEq>(
+    tokens: &[T],
+    reused: usize,
+    is_media: impl Fn(&T) -> bool,
+) -> usize {
+    let mut start = reused.min(tokens.len());
+    while start > 0
+        && start < tokens.len()
+        && is_media(&tokens[start])
+        && tokens[start - 1] == tokens[start]
+    {
+        start -= 1;
+    }
+    start
+}
+
+/// How many leading prompt tokens can keep their cached KV cells. At least one token is always
+/// re-evaluated so the last position has fresh logits to sample from.
+#[cfg(not(mobile))]
+fn reusable_prefix_len<T: PartialEq>(cached: &[T], prompt: &[T]) -> usize {
+    let common = cached
+        .iter()
+        .zip(prompt)
+        .take_while(|(a, b)| a == b)
+        .count();
+    common.min(prompt.len().saturating_sub(1))
+}
+
+/// One orderable stage of the llama.cpp sampler chain. A grammar always runs first and the final
+/// token pick (distribution, Mirostat or greedy) always runs last.
+#[cfg(not(mobile))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SamplerStage {
+    Penalties,
+    Dry,
+    TopK,
+    TypicalP,
+    TopP,
+    MinP,
+    Xtc,
+    Temperature,
+}
+
+#[cfg(not(mobile))]
+impl SamplerStage {
+    /// llama.cpp's default order.
+    const DEFAULT_ORDER: [SamplerStage; 8] = [
+        SamplerStage::Penalties,
+        SamplerStage::Dry,
+        SamplerStage::TopK,
+        SamplerStage::TypicalP,
+        SamplerStage::TopP,
+        SamplerStage::MinP,
+        SamplerStage::Xtc,
+        SamplerStage::Temperature,
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "penalties" | "repetition_penalty" | "repeat_penalty" => Some(Self::Penalties),
+            "dry" => Some(Self::Dry),
+            "top_k" => Some(Self::TopK),
+            "typical_p" | "typ_p" | "typical" => Some(Self::TypicalP),
+            "top_p" => Some(Self::TopP),
+            "min_p" => Some(Self::MinP),
+            "xtc" => Some(Self::Xtc),
+            "temperature" | "temp" => Some(Self::Temperature),
+            _ => None,
+        }
+    }
+
+    /// Stages in the requested order, skipping unknown names and repeats. Like llama.cpp, stages
+    /// left out of a custom order don't run; a missing or unusable order means the default.
+    fn order(requested: Option<&[String]>) -> Vec<Self> {
+        let mut order: Vec<Self> = Vec::new();
+        for stage in requested
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|name| Self::from_name(name))
+        {
+            if !order.contains(&stage) {
+                order.push(stage);
+            }
+        }
+        if order.is_empty() {
+            Self::DEFAULT_ORDER.to_vec()
+        } else {
+            order
+        }
+    }
+}
+
+pub fn is_llama_cpp(provider_id: Option<&str>) -> bool {
+    provider_id == Some(LOCAL_PROVIDER_ID)
+}
+
+#[cfg(all(test, not(mobile)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reusable_prefix_stops_at_divergence_and_leaves_last_token() {
+        assert_eq!(reusable_prefix_len(&[1, 2, 3, 4], &[1, 2, 9, 4, 5]), 2);
+        assert_eq!(reusable_prefix_len(&[1, 2, 3], &[1, 2, 3, 4, 5]), 3);
+        assert_eq!(reusable_prefix_len(&[1, 2, 3], &[1, 2, 3]), 2);
+        assert_eq!(reusable_prefix_len::<i32>(&[], &[1, 2]), 0);
+        assert_eq!(reusable_prefix_len(&[1, 2], &[]), 0);
+    }
+
+    #[test]
+    fn sampler_order_parses_names_and_falls_back_to_default() {
+        let requested: Vec<String> = ["Min-P", "temperature", "bogus", "min_p", "top_k"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            SamplerStage::order(Some(&requested)),
+            vec![
+                SamplerStage::MinP,
+                SamplerStage::Temperature,
+                SamplerStage::TopK
+            ]
+        );
+        assert_eq!(
+            SamplerStage::order(None),
+            SamplerStage::DEFAULT_ORDER.to_vec()
+        );
+        assert_eq!(
+            SamplerStage::order(Some(&["bogus".to_string()])),
+            SamplerStage::DEFAULT_ORDER.to_vec()
+        );
+    }
+
+    #[test]
+    fn recommended_context_accounts_for_gqa_and_reserve() {
+        // Llama 3 8B: 32 layers, 4096 embd, 32 heads, 8 KV heads -> 128 KiB per token.
+        let kv = kv_bytes_per_token(KvShape {
+            n_layer: 32,
+            n_embd: 4096,
+            n_head: 32,
+            n_head_kv: 8,
+        });
+        assert_eq!(kv, 128 * 1024);
+
+        let gib = 1024 * 1024 * 1024;
+        // 16 GiB free, 5 GiB weights, 3.2 GiB reserve -> ~7.8 GiB of KV cache.
+        assert_eq!(recommended_context(5 * gib, kv, 16 * gib, 8192), Some(8192));
+        assert_eq!(
+            recommended_context(5 * gib, kv, 8 * gib, 131072),
+            Some(11468)
+        );
+        assert_eq!(recommended_context(5 * gib, kv, 4 * gib, 8192), Some(0));
+        assert_eq!(recommended_context(5 * gib, 0, 16 * gib, 8192), None);
+    }
+
+    #[test]
+    fn lora_specs_accept_objects_and_paths() {
+        let body = json!({
+            "llamaLoraAdapters": [
+                { "path": " /loras/a.gguf ", "scale": 0.5 },
+                "/loras/b.gguf",
+                { "path": "/loras/a.gguf", "scale": 1.0 },
+                { "path": "/loras/off.gguf", "scale": 0 },
+                { "scale": 1.0 },
+                42
+            ]
+        });
+        assert_eq!(
+            lora_specs(&body),
+            vec![
+                LoraSpec {
+                    path: "/loras/a.gguf".to_string(),
+                    scale: 0.5
+                },
+                LoraSpec {
+                    path: "/loras/b.gguf".to_string(),
+                    scale: 1.0
+                },
+            ]
+        );
+        assert!(lora_specs(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn context_shift_keeps_pinned_prefix() {
+        assert_eq!(context_shift_discard(4096, 96), 2000);
+        assert_eq!(context_shift_discard(10, 10), 0);
+
+        let mut tokens: Vec<u32> = (0..100).collect();
+        assert_eq!(truncate_prompt(&mut tokens, 10, 64), 63);
+        assert_eq!(tokens.len(), 37);
+        assert_eq!(&tokens[..11], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 73]);
+        assert_eq!(tokens.last(), Some(&99));
+    }
+
+    #[test]
+    fn draft_budget_respects_context_and_reply_limits() {
+        assert_eq!(draft_budget(8, 100, 4096, 500), 8);
+        assert_eq!(draft_budget(8, 4090, 4096, 500), 5);
+        assert_eq!(draft_budget(8, 4095, 4096, 500), 0);
+        assert_eq!(draft_budget(8, 100, 4096, 3), 3);
+        assert_eq!(draft_budget(0, 100, 4096, 500), 0);
+    }
+
+    #[test]
+    fn media_boundary_never_splits_an_image() {
+        let tokens = [1, 2, -7, -7, -7, 3, -9, -9, 4];
+        let is_media = |t: &i32| *t < 0;
+        assert_eq!(media_boundary(&tokens, 4, is_media), 2);
+        assert_eq!(media_boundary(&tokens, 2, is_media), 2);
+        assert_eq!(media_boundary(&tokens, 5, is_media), 5);
+        assert_eq!(media_boundary(&tokens, 7, is_media), 6);
+        assert_eq!(media_boundary(&tokens, 8, is_media), 8);
+    }
+}
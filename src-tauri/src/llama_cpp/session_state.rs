@@ -0,0 +1,190 @@
+//! On-disk copies of llama.cpp context state, so a long processed prompt survives app restarts
+//! and session switches. Each entry is a llama.cpp session file plus a sidecar listing its
+//! tokens, which is checked against the new prompt before the much larger state is loaded.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+/// Disk budget for saved states when the request doesn't set one.
+pub const DEFAULT_BUDGET_MB: u64 = 2048;
+/// Prompts shorter than this are cheap to re-evaluate and aren't saved.
+pub const MIN_SAVED_TOKENS: usize = 256;
+/// A state that only extends the saved one by fewer tokens than this isn't rewritten; the
+/// difference is cheap to re-evaluate.
+pub const MIN_STATE_GROWTH: usize = 512;
+
+const STATE_EXT: &str = "llstate";
+const TOKENS_EXT: &str = "tokens";
+
+pub fn state_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::utils::lettuce_dir(app)?.join("llama_state");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(dir)
+}
+
+/// Names the saved state of a conversation: the model and context settings it was computed
+/// with, the chat session (when known) and the pinned system prompt tokens.
+pub fn state_key(
+    model_path: &str,
+    variant: &str,
+    session_id: Option<&str>,
+    pinned: &[i32],
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [model_path, variant, session_id.unwrap_or_default()] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    for token in pinned {
+        hasher.update(token.to_le_bytes());
+    }
+    hasher.finalize()[..12]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn state_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(key).with_extension(STATE_EXT)
+}
+
+fn tokens_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(key).with_extension(TOKENS_EXT)
+}
+
+/// Tokens of the saved state under `key`, if there is a complete one.
+pub fn read_tokens(dir: &Path, key: &str) -> Option<Vec<i32>> {
+    if !state_path(dir, key).exists() {
+        return None;
+    }
+    let bytes = std::fs::read(tokens_path(dir, key)).ok()?;
+    Some(
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect(),
+    )
+}
+
+/// Whether a context holding `tokens` is worth saving over the `saved` state of its key.
+pub fn worth_saving(saved: Option<&[i32]>, tokens: &[i32]) -> bool {
+    if tokens.len() < MIN_SAVED_TOKENS {
+        return false;
+    }
+    match saved {
+        Some(saved) if tokens.starts_with(saved) => tokens.len() >= saved.len() + MIN_STATE_GROWTH,
+        _ => true,
+    }
+}
+
+/// Saves a state through `write_state`. The sidecar is removed first and written last, so an
+/// interrupted save never leaves a state that looks valid.
+pub fn save(
+    dir: &Path,
+    key: &str,
+    tokens: &[i32],
+    write_state: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let sidecar = tokens_path(dir, key);
+    let _ = std::fs::remove_file(&sidecar);
+    write_state(&state_path(dir, key))?;
+    let bytes: Vec<u8> = tokens.iter().flat_map(|t| t.to_le_bytes()).collect();
+    std::fs::write(&sidecar, bytes)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+pub fn remove(dir: &Path, key: &str) {
+    let _ = std::fs::remove_file(tokens_path(dir, key));
+    let _ = std::fs::remove_file(state_path(dir, key));
+}
+
+/// Deletes the least recently written states until the directory fits in `budget_bytes`.
+/// Returns how many were removed.
+pub fn evict(dir: &Path, budget_bytes: u64) -> usize {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut entries = Vec::new();
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(STATE_EXT) {
+            continue;
+        }
+        let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let sidecar_size = std::fs::metadata(tokens_path(dir, key))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push((key.to_string(), meta.len() + sidecar_size, modified));
+    }
+    let evicted = plan_eviction(entries, budget_bytes);
+    for key in &evicted {
+        remove(dir, key);
+    }
+    evicted.len()
+}
+
+/// Keys to drop, oldest first, so the newest states total at most `budget_bytes`.
+fn plan_eviction(mut entries: Vec<(String, u64, SystemTime)>, budget_bytes: u64) -> Vec<String> {
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.2));
+    let mut total = 0u64;
+    let mut evicted = Vec::new();
+    for (key, size, _) in entries {
+        total = total.saturating_add(size);
+        if total > budget_bytes {
+            evicted.push(key);
+        }
+    }
+    evicted.reverse();
+    evicted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn eviction_keeps_newest_states_within_budget() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let entries = vec![
+            ("old".to_string(), 400, at(1)),
+            ("new".to_string(), 300, at(3)),
+            ("mid".to_string(), 400, at(2)),
+        ];
+        assert_eq!(plan_eviction(entries.clone(), 800), vec!["old".to_string()]);
+        assert_eq!(
+            plan_eviction(entries.clone(), 200),
+            vec!["old".to_string(), "mid".to_string(), "new".to_string()]
+        );
+        assert!(plan_eviction(entries, 2000).is_empty());
+    }
+
+    #[test]
+    fn saves_only_states_that_grew_or_diverged() {
+        let tokens: Vec<i32> = (0..2000).collect();
+        assert!(!worth_saving(None, &tokens[..100]));
+        assert!(worth_saving(None, &tokens));
+        assert!(!worth_saving(Some(&tokens[..1800]), &tokens));
+        assert!(worth_saving(Some(&tokens[..1000]), &tokens));
+        assert!(worth_saving(Some(&[7, 8, 9]), &tokens[..300]));
+    }
+
+    #[test]
+    fn key_depends_on_session_and_pinned_tokens() {
+        let base = state_key("/m.gguf", "rope", Some("s1"), &[1, 2, 3]);
+        assert_eq!(base.len(), 24);
+        assert_eq!(base, state_key("/m.gguf", "rope", Some("s1"), &[1, 2, 3]));
+        assert_ne!(base, state_key("/m.gguf", "rope", Some("s2"), &[1, 2, 3]));
+        assert_ne!(base, state_key("/m.gguf", "rope", Some("s1"), &[1, 2]));
+    }
+}
@@ -0,0 +1,182 @@
+//! Image input for local multimodal models: pulls image parts out of chat messages and turns
+//! attachments into RGB bitmaps for the model's projector (mmproj).
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// Longest side images are scaled down to. Projectors resize to their own input size anyway;
+/// this only bounds decode memory and the tokens spent on dynamic-resolution models.
+pub const MAX_IMAGE_SIDE: u32 = 1024;
+
+/// A decoded image ready for the projector.
+pub struct PreparedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Packed RGB, row by row.
+    pub rgb: Vec<u8>,
+}
+
+/// Stands in for an image link in the prompt; local inference doesn't fetch remote images.
+const IMAGE_LINK_PLACEHOLDER: &str = "[image link not loaded]";
+
+/// Replaces every attached image part in `messages` with a text part holding `marker`, which
+/// the projector later expands into the image's embeddings. Image links become a placeholder
+/// instead. Returns the rewritten messages, the image URLs in prompt order and how many links
+/// were left out.
+pub fn split_media(messages: &[Value], marker: &str) -> (Vec<Value>, Vec<String>, usize) {
+    let mut urls = Vec::new();
+    let mut skipped_links = 0;
+    let rewritten = messages
+        .iter()
+        .map(|message| {
+            let Some(parts) = message.get("content").and_then(|v| v.as_array()) else {
+                return message.clone();
+            };
+            let parts: Vec<Value> = parts
+                .iter()
+                .map(|part| {
+                    let url = (part.get("type").and_then(|v| v.as_str()) == Some("image_url"))
+                        .then(|| {
+                            part.get("image_url")
+                                .and_then(|v| v.get("url").or(Some(v)))
+                                .and_then(|v| v.as_str())
+                        })
+                        .flatten();
+                    match url {
+                        Some(url) if url.starts_with("data:") => {
+                            urls.push(url.to_string());
+                            json!({ "type": "text", "text": marker })
+                        }
+                        Some(_) => {
+                            skipped_links += 1;
+                            json!({ "type": "text", "text": IMAGE_LINK_PLACEHOLDER })
+                        }
+                        None => part.clone(),
+                    }
+                })
+                .collect();
+            let mut message = message.clone();
+            message["content"] = Value::Array(parts);
+            message
+        })
+        .collect();
+    (rewritten, urls, skipped_links)
+}
+
+/// Bytes of an image part's URL. Only inline `data:` URLs are accepted; local inference
+/// doesn't fetch remote images.
+pub fn image_bytes(url: &str) -> Result<Vec<u8>, String> {
+    let Some(rest) = url.strip_prefix("data:") else {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Local models only accept attached images, not image links",
+        ));
+    };
+    let (_, data) = rest.split_once(";base64,").ok_or_else(|| {
+        crate::utils::err_msg(module_path!(), line!(), "Image data URL isn't base64")
+    })?;
+    STANDARD
+        .decode(data.trim())
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Decodes an image, flattens transparency onto white and scales it to fit `max_side`.
+pub fn prepare(bytes: &[u8], max_side: u32) -> Result<PreparedImage, String> {
+    let decoded = image::load_from_memory(bytes)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let (width, height) = fit_within(decoded.width(), decoded.height(), max_side);
+    let resized = if (width, height) == decoded.dimensions() {
+        decoded
+    } else {
+        decoded.resize_exact(width, height, FilterType::CatmullRom)
+    };
+    let rgb = flatten_alpha(&resized);
+    Ok(PreparedImage {
+        width,
+        height,
+        rgb: rgb.into_raw(),
+    })
+}
+
+/// Stand-in token for the KV cells of an image, derived from its pixels. Real tokens are never
+/// negative, so prompts only share a cached prefix through an image when it's the same image.
+pub fn media_token(image: &PreparedImage) -> i32 {
+    let mut hasher = Sha256::new();
+    hasher.update(image.width.to_le_bytes());
+    hasher.update(image.height.to_le_bytes());
+    hasher.update(&image.rgb);
+    let digest = hasher.finalize();
+    i32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]]) | i32::MIN
+}
+
+fn fit_within(width: u32, height: u32, max_side: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_side || longest == 0 {
+        return (width, height);
+    }
+    let scale = |side: u32| ((side as u64 * max_side as u64) / longest as u64).max(1) as u32;
+    (scale(width), scale(height))
+}
+
+fn flatten_alpha(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_become_markers_in_prompt_order() {
+        let messages = vec![
+            json!({ "role": "system", "content": "Describe images." }),
+            json!({ "role": "user", "content": [
+                { "type": "text", "text": "Compare" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,AA==" } },
+                { "type": "image_url", "image_url": "data:image/png;base64,AQ==" },
+                { "type": "image_url", "image_url": { "url": "https://example.com/cat.png" } },
+            ] }),
+        ];
+        let (rewritten, urls, skipped_links) = split_media(&messages, "<__media__>");
+        assert_eq!(
+            urls,
+            vec!["data:image/png;base64,AA==", "data:image/png;base64,AQ=="]
+        );
+        assert_eq!(rewritten[0], messages[0]);
+        assert_eq!(rewritten[1]["content"][1]["text"], "<__media__>");
+        assert_eq!(rewritten[1]["content"][2]["type"], "text");
+        assert_eq!(skipped_links, 1);
+        assert_eq!(rewritten[1]["content"][3]["text"], IMAGE_LINK_PLACEHOLDER);
+        assert_eq!(image_bytes(&urls[1]).unwrap(), vec![1]);
+        assert!(image_bytes("https://example.com/cat.png").is_err());
+    }
+
+    #[test]
+    fn preparation_scales_down_and_flattens_alpha() {
+        assert_eq!(fit_within(4000, 3000, 1024), (1024, 768));
+        assert_eq!(fit_within(10, 5000, 1024), (2, 1024));
+        assert_eq!(fit_within(640, 480, 1024), (640, 480));
+
+        let transparent = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            2,
+            2,
+            image::Rgba([0, 0, 0, 0]),
+        ));
+        assert_eq!(
+            flatten_alpha(&transparent).get_pixel(0, 0).0,
+            [255, 255, 255]
+        );
+    }
+}
@@ -0,0 +1,551 @@
+//! Persistent queue for dynamic memory cycles.
+//!
+//! Chat turns enqueue a job instead of running the memory cycle inline. A single worker runs
+//! due jobs one at a time, retries failures with exponential backoff and reports progress on
+//! the `memory-job:update` event. Jobs live in the `memory_jobs` table, so queued work survives
+//! an app restart; a job that was running when the app closed is queued again on startup.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::storage_manager::db::{now_ms, open_db};
+use crate::storage_manager::memories::MemoryOwner;
+use crate::utils::{log_error, log_info, log_warn};
+
+pub const MEMORY_JOB_EVENT: &str = "memory-job:update";
+
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_BASE_DELAY_MS: i64 = 15_000;
+const RETRY_MAX_DELAY_MS: i64 = 15 * 60_000;
+const IDLE_POLL: Duration = Duration::from_secs(60);
+
+const JOB_COLUMNS: &str = "id, session_id, group_session_id, status, attempts, max_attempts, \
+     last_error, run_at, created_at, updated_at";
+
+/// Wakes the worker when a job is enqueued.
+#[derive(Default)]
+pub struct MemoryJobQueue {
+    notify: Notify,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryJob {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub group_session_id: Option<String>,
+    /// "queued", "running" or "failed" (retries exhausted). Finished jobs are deleted.
+    pub status: String,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub last_error: Option<String>,
+    pub run_at: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl MemoryJob {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            group_session_id: row.get(2)?,
+            status: row.get(3)?,
+            attempts: row.get(4)?,
+            max_attempts: row.get(5)?,
+            last_error: row.get(6)?,
+            run_at: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+
+    fn owner(&self) -> Option<MemoryOwner<'_>> {
+        match (&self.session_id, &self.group_session_id) {
+            (Some(id), _) => Some(MemoryOwner::Session(id)),
+            (None, Some(id)) => Some(MemoryOwner::GroupSession(id)),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum JobOutcome {
+    Completed,
+    Retrying {
+        run_at: i64,
+    },
+    /// Failed, but a follow-up job for the same session is already queued and will redo it.
+    Superseded,
+    Failed,
+}
+
+fn job_owner_column(owner: MemoryOwner<'_>) -> Result<&'static str, String> {
+    match owner {
+        MemoryOwner::Session(_) => Ok("session_id"),
+        MemoryOwner::GroupSession(_) => Ok("group_session_id"),
+        MemoryOwner::Character(_) => Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Character memories have no memory cycle",
+        )),
+    }
+}
+
+/// 15s, 30s, 1m, 2m, ... capped at 15 minutes.
+fn retry_delay_ms(attempts: u32) -> i64 {
+    let exponent = attempts.saturating_sub(1).min(16);
+    (RETRY_BASE_DELAY_MS << exponent).min(RETRY_MAX_DELAY_MS)
+}
+
+/// Queues a memory cycle for `owner`. A job still waiting for that session absorbs the
+/// request (and is made due now) instead of a second job being added; a job that is already
+/// running gets a follow-up so messages sent meanwhile are still processed.
+fn insert_or_coalesce(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    now: i64,
+) -> Result<String, String> {
+    let column = job_owner_column(owner)?;
+    let queued: Option<String> = conn
+        .query_row(
+            &format!(
+                "SELECT id FROM memory_jobs WHERE {} = ?1 AND status = 'queued' LIMIT 1",
+                column
+            ),
+            params![owner.id()],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    if let Some(id) = queued {
+        conn.execute(
+            "UPDATE memory_jobs SET run_at = MIN(run_at, ?1), updated_at = ?1 WHERE id = ?2",
+            params![now, &id],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        return Ok(id);
+    }
+
+    // New work supersedes a job that already gave up.
+    conn.execute(
+        &format!(
+            "DELETE FROM memory_jobs WHERE {} = ?1 AND status = 'failed'",
+            column
+        ),
+        params![owner.id()],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        &format!(
+            "INSERT INTO memory_jobs (id, {}, status, attempts, max_attempts, run_at, created_at, updated_at)
+             VALUES (?1, ?2, 'queued', 0, ?3, ?4, ?4, ?4)",
+            column
+        ),
+        params![&id, owner.id(), MAX_ATTEMPTS, now],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(id)
+}
+
+/// Marks the oldest due job as running. Jobs whose session already has a running job wait.
+fn claim_next(conn: &Connection, now: i64) -> Result<Option<MemoryJob>, String> {
+    let job = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM memory_jobs j
+                 WHERE status = 'queued' AND run_at <= ?1
+                   AND NOT EXISTS (
+                     SELECT 1 FROM memory_jobs r WHERE r.status = 'running'
+                       AND (r.session_id = j.session_id OR r.group_session_id = j.group_session_id)
+                   )
+                 ORDER BY run_at, created_at LIMIT 1",
+                JOB_COLUMNS
+            ),
+            params![now],
+            MemoryJob::from_row,
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    let Some(mut job) = job else {
+        return Ok(None);
+    };
+    job.attempts += 1;
+    job.status = "running".to_string();
+    job.updated_at = now;
+    conn.execute(
+        "UPDATE memory_jobs SET status = 'running', attempts = ?1, updated_at = ?2 WHERE id = ?3",
+        params![job.attempts, now, &job.id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(Some(job))
+}
+
+fn finish_job(
+    conn: &Connection,
+    job: &MemoryJob,
+    result: Result<(), &str>,
+    now: i64,
+) -> Result<JobOutcome, String> {
+    let has_follow_up = match job.owner() {
+        Some(owner) if result.is_err() => conn
+            .query_row(
+                &format!(
+                    "SELECT EXISTS(SELECT 1 FROM memory_jobs
+                     WHERE {} = ?1 AND status = 'queued' AND id != ?2)",
+                    job_owner_column(owner)?
+                ),
+                params![owner.id(), &job.id],
+                |r| r.get(0),
+            )
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?,
+        _ => false,
+    };
+    let outcome = match result {
+        Ok(()) => JobOutcome::Completed,
+        Err(_) if has_follow_up => JobOutcome::Superseded,
+        Err(_) if job.attempts >= job.max_attempts => JobOutcome::Failed,
+        Err(_) => JobOutcome::Retrying {
+            run_at: now + retry_delay_ms(job.attempts),
+        },
+    };
+
+    let (status, run_at) = match outcome {
+        JobOutcome::Completed | JobOutcome::Superseded => {
+            conn.execute("DELETE FROM memory_jobs WHERE id = ?1", params![&job.id])
+                .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+            return Ok(outcome);
+        }
+        JobOutcome::Retrying { run_at } => ("queued", run_at),
+        JobOutcome::Failed => ("failed", job.run_at),
+    };
+    conn.execute(
+        "UPDATE memory_jobs SET status = ?1, run_at = ?2, last_error = ?3, updated_at = ?4
+         WHERE id = ?5",
+        params![status, run_at, result.err(), now, &job.id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(outcome)
+}
+
+fn requeue_interrupted(conn: &Connection, now: i64) -> Result<usize, String> {
+    conn.execute(
+        "UPDATE memory_jobs SET status = 'queued', run_at = ?1, updated_at = ?1
+         WHERE status = 'running'",
+        params![now],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+fn next_run_at(conn: &Connection) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT MIN(run_at) FROM memory_jobs WHERE status = 'queued'",
+        [],
+        |r| r.get(0),
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Queues a dynamic memory cycle for a session or group session and wakes the worker.
+pub fn enqueue_memory_job(app: &AppHandle, owner: MemoryOwner<'_>) -> Result<String, String> {
+    let conn = open_db(app)?;
+    let id = insert_or_coalesce(&conn, owner, now_ms() as i64)?;
+    emit_job_update(app, &id, owner, "queued", json!({}));
+    if let Some(queue) = app.try_state::<MemoryJobQueue>() {
+        queue.notify.notify_one();
+    }
+    Ok(id)
+}
+
+/// Drops a waiting job for `owner`; used when the user runs the cycle directly.
+pub fn cancel_queued_memory_job(app: &AppHandle, owner: MemoryOwner<'_>) -> Result<(), String> {
+    let column = job_owner_column(owner)?;
+    let conn = open_db(app)?;
+    conn.execute(
+        &format!(
+            "DELETE FROM memory_jobs WHERE {} = ?1 AND status IN ('queued', 'failed')",
+            column
+        ),
+        params![owner.id()],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn emit_job_update(
+    app: &AppHandle,
+    job_id: &str,
+    owner: MemoryOwner<'_>,
+    status: &str,
+    extra: serde_json::Value,
+) {
+    let mut payload = json!({
+        "jobId": job_id,
+        "sessionId": owner.id(),
+        "isGroup": matches!(owner, MemoryOwner::GroupSession(_)),
+        "status": status,
+    });
+    if let (Some(target), serde_json::Value::Object(extra)) = (payload.as_object_mut(), extra) {
+        target.extend(extra);
+    }
+    let _ = app.emit(MEMORY_JOB_EVENT, payload);
+}
+
+async fn run_job(app: &AppHandle, job: &MemoryJob) -> Result<(), String> {
+    match job.owner() {
+        Some(MemoryOwner::Session(id)) => {
+            crate::chat_manager::run_queued_dynamic_memory_cycle(app, id).await
+        }
+        Some(MemoryOwner::GroupSession(id)) => {
+            crate::group_chat_manager::run_queued_group_memory_cycle(app, id).await
+        }
+        _ => Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Memory job {} has no session", job.id),
+        )),
+    }
+}
+
+async fn process_next_job(app: &AppHandle) -> Result<bool, String> {
+    let Some(job) = claim_next(&*open_db(app)?, now_ms() as i64)? else {
+        return Ok(false);
+    };
+    let Some(owner) = job.owner() else {
+        finish_job(&*open_db(app)?, &job, Ok(()), now_ms() as i64)?;
+        return Ok(true);
+    };
+    emit_job_update(
+        app,
+        &job.id,
+        owner,
+        "running",
+        json!({ "attempt": job.attempts, "maxAttempts": job.max_attempts }),
+    );
+
+    let result = run_job(app, &job).await;
+    let outcome = finish_job(
+        &*open_db(app)?,
+        &job,
+        result.as_ref().map(|_| ()).map_err(|e| e.as_str()),
+        now_ms() as i64,
+    )?;
+    match outcome {
+        JobOutcome::Completed => emit_job_update(app, &job.id, owner, "completed", json!({})),
+        JobOutcome::Superseded => emit_job_update(
+            app,
+            &job.id,
+            owner,
+            "superseded",
+            json!({ "error": result.err().unwrap_or_default() }),
+        ),
+        JobOutcome::Retrying { run_at } => {
+            let error = result.err().unwrap_or_default();
+            log_warn(
+                app,
+                "memory_jobs",
+                format!(
+                    "job {} attempt {}/{} failed, retrying at {}: {}",
+                    job.id, job.attempts, job.max_attempts, run_at, error
+                ),
+            );
+            emit_job_update(
+                app,
+                &job.id,
+                owner,
+                "retrying",
+                json!({ "attempt": job.attempts, "nextRunAt": run_at, "error": error }),
+            );
+        }
+        JobOutcome::Failed => {
+            let error = result.err().unwrap_or_default();
+            log_error(
+                app,
+                "memory_jobs",
+                format!(
+                    "job {} failed after {} attempts: {}",
+                    job.id, job.attempts, error
+                ),
+            );
+            emit_job_update(app, &job.id, owner, "failed", json!({ "error": error }));
+        }
+    }
+    Ok(true)
+}
+
+/// Starts the queue worker. Call once, after migrations have run.
+pub fn start_memory_job_worker(app: AppHandle) {
+    match open_db(&app).and_then(|conn| requeue_interrupted(&conn, now_ms() as i64)) {
+        Ok(0) => {}
+        Ok(count) => log_info(
+            &app,
+            "memory_jobs",
+            format!("requeued {} interrupted memory jobs", count),
+        ),
+        Err(err) => log_error(
+            &app,
+            "memory_jobs",
+            format!("startup recovery failed: {}", err),
+        ),
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match process_next_job(&app).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(err) => log_error(&app, "memory_jobs", err),
+            }
+
+            let wait = match open_db(&app).and_then(|conn| next_run_at(&conn)) {
+                Ok(Some(run_at)) => {
+                    Duration::from_millis((run_at - now_ms() as i64).max(0) as u64).min(IDLE_POLL)
+                }
+                _ => IDLE_POLL,
+            };
+            let queue = app.state::<MemoryJobQueue>();
+            tokio::select! {
+                _ = queue.notify.notified() => {}
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    });
+}
+
+/// Pending and failed memory jobs, optionally for a single session or group session.
+#[tauri::command]
+pub fn memory_jobs_list(
+    app: AppHandle,
+    session_id: Option<String>,
+) -> Result<Vec<MemoryJob>, String> {
+    let conn = open_db(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM memory_jobs
+             WHERE ?1 IS NULL OR session_id = ?1 OR group_session_id = ?1
+             ORDER BY created_at",
+            JOB_COLUMNS
+        ))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let jobs = stmt
+        .query_map(params![session_id], MemoryJob::from_row)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(jobs)
+}
+
+/// Makes a failed or backing-off job due now with a fresh set of attempts.
+#[tauri::command]
+pub fn memory_job_retry(app: AppHandle, job_id: String) -> Result<(), String> {
+    let conn = open_db(&app)?;
+    let now = now_ms() as i64;
+    conn.execute(
+        "UPDATE memory_jobs SET status = 'queued', attempts = 0, run_at = ?1, updated_at = ?1
+         WHERE id = ?2 AND status != 'running'",
+        params![now, &job_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if let Some(queue) = app.try_state::<MemoryJobQueue>() {
+        queue.notify.notify_one();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn memory_job_cancel(app: AppHandle, job_id: String) -> Result<(), String> {
+    let conn = open_db(&app)?;
+    conn.execute(
+        "DELETE FROM memory_jobs WHERE id = ?1 AND status != 'running'",
+        params![&job_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE memory_jobs (
+               id TEXT PRIMARY KEY,
+               session_id TEXT,
+               group_session_id TEXT,
+               status TEXT NOT NULL,
+               attempts INTEGER NOT NULL DEFAULT 0,
+               max_attempts INTEGER NOT NULL,
+               last_error TEXT,
+               run_at INTEGER NOT NULL,
+               created_at INTEGER NOT NULL,
+               updated_at INTEGER NOT NULL
+             );",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM memory_jobs", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn coalesces_waiting_jobs_and_backs_off() {
+        let conn = setup();
+        let first = insert_or_coalesce(&conn, MemoryOwner::Session("s1"), 100).unwrap();
+        let second = insert_or_coalesce(&conn, MemoryOwner::Session("s1"), 200).unwrap();
+        assert_eq!(first, second);
+        insert_or_coalesce(&conn, MemoryOwner::GroupSession("g1"), 300).unwrap();
+        assert_eq!(count(&conn), 2);
+
+        let job = claim_next(&conn, 1_000).unwrap().unwrap();
+        assert_eq!(job.id, first);
+        assert_eq!(job.attempts, 1);
+
+        // A turn finishing while the cycle runs queues a follow-up; it waits for the running job.
+        let follow_up = insert_or_coalesce(&conn, MemoryOwner::Session("s1"), 1_100).unwrap();
+        assert_ne!(follow_up, first);
+        let group_job = claim_next(&conn, 1_200).unwrap().unwrap();
+        assert_eq!(group_job.group_session_id.as_deref(), Some("g1"));
+        assert!(claim_next(&conn, 1_200).unwrap().is_none());
+
+        assert_eq!(
+            finish_job(&conn, &group_job, Err("timeout"), 2_000).unwrap(),
+            JobOutcome::Retrying {
+                run_at: 2_000 + RETRY_BASE_DELAY_MS
+            }
+        );
+        assert_eq!(
+            finish_job(&conn, &job, Err("timeout"), 2_000).unwrap(),
+            JobOutcome::Superseded
+        );
+        assert_eq!(retry_delay_ms(3), 4 * RETRY_BASE_DELAY_MS);
+        assert_eq!(retry_delay_ms(30), RETRY_MAX_DELAY_MS);
+
+        let exhausted = MemoryJob {
+            attempts: MAX_ATTEMPTS,
+            ..group_job
+        };
+        assert_eq!(
+            finish_job(&conn, &exhausted, Err("timeout"), 3_000).unwrap(),
+            JobOutcome::Failed
+        );
+        assert_eq!(count(&conn), 2);
+
+        // New work replaces a job that gave up.
+        let fresh = insert_or_coalesce(&conn, MemoryOwner::GroupSession("g1"), 4_000).unwrap();
+        assert_ne!(fresh, exhausted.id);
+        assert_eq!(count(&conn), 2);
+    }
+}
@@ -7,7 +7,7 @@ use crate::storage_manager::{settings::storage_read_settings, settings::storage_
 use crate::utils::log_info;
 
 /// Current migration version
-pub const CURRENT_MIGRATION_VERSION: u32 = 34;
+pub const CURRENT_MIGRATION_VERSION: u32 = 43;
 
 pub fn run_migrations(app: &AppHandle) -> Result<(), String> {
     log_info(app, "migrations", "Starting migration check");
@@ -20,6 +20,15 @@ pub fn run_migrations(app: &AppHandle) -> Result<(), String> {
         migrate_v31_to_v32(app)?;
         migrate_v32_to_v33(app)?;
         migrate_v33_to_v34(app)?;
+        migrate_v34_to_v35(app)?;
+        migrate_v35_to_v36(app)?;
+        migrate_v36_to_v37(app)?;
+        migrate_v37_to_v38(app)?;
+        migrate_v38_to_v39(app)?;
+        migrate_v39_to_v40(app)?;
+        migrate_v40_to_v41(app)?;
+        migrate_v41_to_v42(app)?;
+        migrate_v42_to_v43(app)?;
         log_info(
             app,
             "migrations",
@@ -384,6 +393,96 @@ pub fn run_migrations(app: &AppHandle) -> Result<(), String> {
         version = 34;
     }
 
+    if version < 35 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v34 -> v35: Move dynamic memories into the memories table",
+        );
+        migrate_v34_to_v35(app)?;
+        version = 35;
+    }
+
+    if version < 36 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v35 -> v36: Add full-text index over memories",
+        );
+        migrate_v35_to_v36(app)?;
+        version = 36;
+    }
+
+    if version < 37 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v36 -> v37: Add character-level memories",
+        );
+        migrate_v36_to_v37(app)?;
+        version = 37;
+    }
+
+    if version < 38 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v37 -> v38: Add knowledge graph tables",
+        );
+        migrate_v37_to_v38(app)?;
+        version = 38;
+    }
+
+    if version < 39 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v38 -> v39: Add memory provenance and edit history",
+        );
+        migrate_v38_to_v39(app)?;
+        version = 39;
+    }
+
+    if version < 40 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v39 -> v40: Add memory job queue",
+        );
+        migrate_v39_to_v40(app)?;
+        version = 40;
+    }
+
+    if version < 41 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v40 -> v41: Add embedding cache",
+        );
+        migrate_v40_to_v41(app)?;
+        version = 41;
+    }
+
+    if version < 42 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v41 -> v42: Tag memory vectors with their embedding model",
+        );
+        migrate_v41_to_v42(app)?;
+        version = 42;
+    }
+
+    if version < 43 {
+        log_info(
+            app,
+            "migrations",
+            "Running migration v42 -> v43: Add local GGUF model library",
+        );
+        migrate_v42_to_v43(app)?;
+        version = 43;
+    }
+
     // Update the stored version
     set_migration_version(app, version)?;
 
@@ -1443,6 +1542,358 @@ fn migrate_v33_to_v34(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+fn migrate_v34_to_v35(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+    use crate::storage_manager::memories::migrate_memory_columns;
+
+    let conn = open_db(app)?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS memories (
+          row_id INTEGER PRIMARY KEY AUTOINCREMENT,
+          id TEXT NOT NULL,
+          session_id TEXT,
+          group_session_id TEXT,
+          position INTEGER NOT NULL DEFAULT 0,
+          text TEXT NOT NULL,
+          category TEXT,
+          embedding BLOB,
+          embedding_dim INTEGER NOT NULL DEFAULT 0,
+          token_count INTEGER NOT NULL DEFAULT 0,
+          is_cold INTEGER NOT NULL DEFAULT 0,
+          is_pinned INTEGER NOT NULL DEFAULT 0,
+          importance_score REAL NOT NULL DEFAULT 1.0,
+          access_count INTEGER NOT NULL DEFAULT 0,
+          last_accessed_at INTEGER NOT NULL DEFAULT 0,
+          created_at INTEGER NOT NULL DEFAULT 0,
+          updated_at INTEGER NOT NULL DEFAULT 0,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(group_session_id) REFERENCES group_sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_session_id
+          ON memories(session_id, id) WHERE session_id IS NOT NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_group_session_id
+          ON memories(group_session_id, id) WHERE group_session_id IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_memories_session_hot
+          ON memories(session_id, is_cold, is_pinned);
+        CREATE INDEX IF NOT EXISTS idx_memories_group_session_hot
+          ON memories(group_session_id, is_cold, is_pinned);
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    let moved = migrate_memory_columns(&conn)?;
+    if moved > 0 {
+        log_info(
+            app,
+            "migrations",
+            format!(
+                "Moved memories of {} sessions into the memories table",
+                moved
+            ),
+        );
+    }
+    Ok(())
+}
+
+fn migrate_v35_to_v36(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS memories_fts USING fts5(
+          text,
+          content='memories',
+          content_rowid='row_id'
+        );
+        CREATE TRIGGER IF NOT EXISTS memories_fts_ai AFTER INSERT ON memories BEGIN
+          INSERT INTO memories_fts(rowid, text) VALUES (new.row_id, new.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS memories_fts_ad AFTER DELETE ON memories BEGIN
+          INSERT INTO memories_fts(memories_fts, rowid, text) VALUES ('delete', old.row_id, old.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS memories_fts_au AFTER UPDATE OF text ON memories BEGIN
+          INSERT INTO memories_fts(memories_fts, rowid, text) VALUES ('delete', old.row_id, old.text);
+          INSERT INTO memories_fts(rowid, text) VALUES (new.row_id, new.text);
+        END;
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    // init_db creates the index empty on existing databases, so backfill whenever the
+    // indexed document count has drifted from the table.
+    let indexed: i64 = conn
+        .query_row("SELECT COUNT(*) FROM memories_fts_docsize", [], |r| {
+            r.get(0)
+        })
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM memories", [], |r| r.get(0))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if indexed != total {
+        conn.execute(
+            "INSERT INTO memories_fts(memories_fts) VALUES('rebuild')",
+            [],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        log_info(
+            app,
+            "migrations",
+            format!("Rebuilt memory keyword index ({} rows)", total),
+        );
+    }
+    Ok(())
+}
+
+fn migrate_v36_to_v37(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+
+    let mut has_character_id = false;
+    let mut stmt = conn
+        .prepare("PRAGMA table_info(memories)")
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    for col in rows {
+        let name = col.map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        if name == "character_id" {
+            has_character_id = true;
+        }
+    }
+
+    if !has_character_id {
+        conn.execute(
+            "ALTER TABLE memories ADD COLUMN character_id TEXT REFERENCES characters(id) ON DELETE CASCADE",
+            [],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+
+    conn.execute_batch(
+        r#"
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_character_id
+          ON memories(character_id, id) WHERE character_id IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_memories_character_hot
+          ON memories(character_id, is_cold, is_pinned);
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn migrate_v37_to_v38(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS kg_entities (
+          id TEXT PRIMARY KEY,
+          session_id TEXT NOT NULL,
+          name TEXT NOT NULL,
+          kind TEXT NOT NULL DEFAULT 'other',
+          description TEXT,
+          created_at INTEGER NOT NULL DEFAULT 0,
+          updated_at INTEGER NOT NULL DEFAULT 0,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS kg_relations (
+          id TEXT PRIMARY KEY,
+          session_id TEXT NOT NULL,
+          source_id TEXT NOT NULL,
+          target_id TEXT NOT NULL,
+          relation TEXT NOT NULL,
+          message_ids TEXT NOT NULL DEFAULT '[]',
+          created_at INTEGER NOT NULL DEFAULT 0,
+          updated_at INTEGER NOT NULL DEFAULT 0,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(source_id) REFERENCES kg_entities(id) ON DELETE CASCADE,
+          FOREIGN KEY(target_id) REFERENCES kg_entities(id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_kg_entities_name
+          ON kg_entities(session_id, name COLLATE NOCASE);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_kg_relations_triple
+          ON kg_relations(session_id, source_id, relation, target_id);
+        CREATE INDEX IF NOT EXISTS idx_kg_relations_target ON kg_relations(target_id);
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn migrate_v38_to_v39(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+
+    let mut has_provenance = false;
+    let mut stmt = conn
+        .prepare("PRAGMA table_info(memories)")
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    for col in rows {
+        let name = col.map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        if name == "provenance" {
+            has_provenance = true;
+        }
+    }
+
+    if !has_provenance {
+        conn.execute("ALTER TABLE memories ADD COLUMN provenance TEXT", [])
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS memory_events (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          memory_id TEXT NOT NULL,
+          session_id TEXT,
+          group_session_id TEXT,
+          character_id TEXT,
+          kind TEXT NOT NULL,
+          actor TEXT NOT NULL,
+          text TEXT,
+          previous_text TEXT,
+          details TEXT,
+          created_at INTEGER NOT NULL,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(group_session_id) REFERENCES group_sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(character_id) REFERENCES characters(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_memory_events_session
+          ON memory_events(session_id, memory_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_events_group_session
+          ON memory_events(group_session_id, memory_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_events_character
+          ON memory_events(character_id, memory_id);
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn migrate_v39_to_v40(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS memory_jobs (
+          id TEXT PRIMARY KEY,
+          session_id TEXT,
+          group_session_id TEXT,
+          status TEXT NOT NULL,
+          attempts INTEGER NOT NULL DEFAULT 0,
+          max_attempts INTEGER NOT NULL,
+          last_error TEXT,
+          run_at INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          updated_at INTEGER NOT NULL,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(group_session_id) REFERENCES group_sessions(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_memory_jobs_status ON memory_jobs(status, run_at);
+        CREATE INDEX IF NOT EXISTS idx_memory_jobs_session ON memory_jobs(session_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_jobs_group_session
+          ON memory_jobs(group_session_id);
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn migrate_v40_to_v41(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS embedding_cache (
+          text_hash TEXT NOT NULL,
+          model TEXT NOT NULL,
+          embedding BLOB NOT NULL,
+          dim INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          last_used_at INTEGER NOT NULL,
+          PRIMARY KEY(text_hash, model)
+        );
+        CREATE INDEX IF NOT EXISTS idx_embedding_cache_last_used
+          ON embedding_cache(last_used_at);
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+/// Adds `memories.embedding_model`. Existing vectors are tagged with the backend recorded as
+/// having produced them, or else the one configured now.
+fn migrate_v41_to_v42(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+
+    let mut has_embedding_model = false;
+    let mut stmt = conn
+        .prepare("PRAGMA table_info(memories)")
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    for col in rows {
+        let name = col.map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        if name == "embedding_model" {
+            has_embedding_model = true;
+        }
+    }
+
+    if !has_embedding_model {
+        conn.execute_batch("ALTER TABLE memories ADD COLUMN embedding_model TEXT;")
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+
+    // Existing vectors came from the embedding source active before this version; tag them
+    // with it so a later switch (e.g. v2 -> v3) re-embeds them.
+    let active_backend = crate::embedding_model::active_embedding_backend_id(app);
+    crate::storage_manager::memories::tag_untagged_vectors(&conn, active_backend.as_deref())?;
+    Ok(())
+}
+
+fn migrate_v42_to_v43(app: &AppHandle) -> Result<(), String> {
+    use crate::storage_manager::db::open_db;
+
+    let conn = open_db(app)?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS local_models (
+          id TEXT PRIMARY KEY,
+          path TEXT NOT NULL UNIQUE,
+          file_name TEXT NOT NULL,
+          size_bytes INTEGER NOT NULL,
+          architecture TEXT,
+          name TEXT,
+          parameter_count INTEGER,
+          quantization TEXT,
+          context_length INTEGER,
+          chat_template TEXT,
+          source_url TEXT,
+          created_at INTEGER NOT NULL,
+          updated_at INTEGER NOT NULL
+        );
+        "#,
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
 fn migrate_v27_to_v28(app: &AppHandle) -> Result<(), String> {
     use crate::storage_manager::db::open_db;
 
@@ -1619,7 +2070,7 @@ fn migrate_v15_to_v16(app: &AppHandle) -> Result<(), String> {
         return Ok(());
     }
 
-    use crate::tokenizer::count_tokens;
+    use crate::tokenizer::count_embedding_tokens as count_tokens;
 
     // Backfill token counts for memory_embeddings
     let mut stmt = conn
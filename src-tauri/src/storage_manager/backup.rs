@@ -15,7 +15,11 @@ use zip::write::SimpleFileOptions;
 use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
 use super::db::open_db;
+use super::knowledge_graph::{read_graph, replace_graph, KnowledgeGraph};
 use super::legacy::storage_root;
+use super::memories::{
+    absorb_memory_column, import_memories_json, stored_memories_json, MemoryOwner,
+};
 use crate::utils::log_info;
 #[cfg(target_os = "android")]
 use tauri_plugin_android_fs::{AndroidFs, AndroidFsExt};
@@ -408,6 +412,9 @@ fn export_characters(app: &tauri::AppHandle) -> Result<Vec<JsonValue>, String> {
 
         char_json["rules"] = serde_json::json!(rules);
         char_json["scenes"] = serde_json::json!(scenes_with_variants);
+        if let Some(stored) = stored_memories_json(&conn, MemoryOwner::Character(&char_id))? {
+            char_json["memory_embeddings"] = JsonValue::String(stored);
+        }
         result.push(char_json);
     }
 
@@ -463,6 +470,12 @@ fn export_sessions(app: &tauri::AppHandle) -> Result<Vec<JsonValue>, String> {
     // For each session, get messages
     let mut result = Vec::new();
     for (session_id, mut session_json) in sessions {
+        if let Some(stored) = stored_memories_json(&conn, MemoryOwner::Session(&session_id))? {
+            session_json["memory_embeddings"] = JsonValue::String(stored);
+        }
+        session_json["knowledge_graph"] = serde_json::to_value(read_graph(&conn, &session_id)?)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
         let mut messages_stmt = conn
             .prepare("SELECT id, role, content, created_at, prompt_tokens, completion_tokens, total_tokens,
                              selected_variant_id, is_pinned, memory_refs, used_lorebook_entries, attachments, reasoning FROM messages
@@ -568,6 +581,10 @@ fn export_group_sessions(app: &tauri::AppHandle) -> Result<Vec<JsonValue>, Strin
 
     let mut result = Vec::new();
     for (session_id, mut session_json) in sessions {
+        if let Some(stored) = stored_memories_json(&conn, MemoryOwner::GroupSession(&session_id))? {
+            session_json["memory_embeddings"] = JsonValue::String(stored);
+        }
+
         let mut participation_stmt = conn
             .prepare(
                 "SELECT id, character_id, speak_count, last_spoke_turn, last_spoke_at
@@ -1436,6 +1453,15 @@ fn import_characters(app: &tauri::AppHandle, data: &JsonValue) -> Result<(), Str
                     item.get("updated_at").and_then(|v| v.as_i64()),
                 ],
             ).map_err(|e| crate::utils::err_msg(module_path!(), line!(), format!("Failed to insert character '{}': {}", char_name, e)))?;
+            if let Some(stored) = item.get("memory_embeddings").and_then(|v| v.as_str()) {
+                import_memories_json(&conn, MemoryOwner::Character(char_id), stored)?;
+                // Writing the store bumps the character; keep the backed-up timestamp.
+                conn.execute(
+                    "UPDATE characters SET updated_at = ?1 WHERE id = ?2",
+                    params![item.get("updated_at").and_then(|v| v.as_i64()), char_id],
+                )
+                .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+            }
             char_count += 1;
 
             // Insert rules
@@ -1581,6 +1607,12 @@ fn import_sessions(app: &tauri::AppHandle, data: &JsonValue) -> Result<(), Strin
                     item.get("updated_at").and_then(|v| v.as_i64()),
                 ],
             ).map_err(|e| crate::utils::err_msg(module_path!(), line!(), format!("Failed to insert session (character_id={}): {}", character_id, e)))?;
+            absorb_memory_column(&conn, MemoryOwner::Session(session_id))?;
+            if let Some(graph) = item.get("knowledge_graph") {
+                let graph: KnowledgeGraph = serde_json::from_value(graph.clone())
+                    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+                replace_graph(&conn, session_id, &graph)?;
+            }
             session_count += 1;
 
             // Insert messages
@@ -1709,6 +1741,7 @@ fn import_group_sessions(app: &tauri::AppHandle, data: &JsonValue) -> Result<(),
                 ],
             )
             .map_err(|e| crate::utils::err_msg(module_path!(), line!(), format!("Failed to insert group session {}: {}", session_id, e)))?;
+            absorb_memory_column(&conn, MemoryOwner::GroupSession(session_id))?;
             session_count += 1;
 
             if let Some(participants) = item.get("participation").and_then(|v| v.as_array()) {
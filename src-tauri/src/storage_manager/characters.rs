@@ -2,6 +2,12 @@ use rusqlite::{params, OptionalExtension};
 use serde_json::{Map as JsonMap, Value as JsonValue};
 
 use super::db::{now_ms, open_db};
+use super::memories::{
+    read_memories, read_memories_json, read_memory_timeline, write_memories, MemoryActor,
+    MemoryOwner,
+};
+use super::sessions::normalize_memory_category;
+use crate::embedding_model;
 use crate::utils::{log_error, log_info};
 
 fn read_character(conn: &rusqlite::Connection, id: &str) -> Result<JsonValue, String> {
@@ -646,3 +652,95 @@ pub fn character_delete(app: tauri::AppHandle, id: String) -> Result<(), String>
     );
     Ok(())
 }
+
+/// List a character's long-term memories (shared across all of its chats) as JSON.
+#[tauri::command]
+pub fn character_memories_list(
+    app: tauri::AppHandle,
+    character_id: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    read_memories_json(&conn, MemoryOwner::Character(&character_id))
+}
+
+#[tauri::command]
+pub async fn character_memory_update(
+    app: tauri::AppHandle,
+    character_id: String,
+    memory_id: String,
+    new_memory: String,
+    new_category: Option<String>,
+) -> Result<String, String> {
+    let category = normalize_memory_category(new_category)?;
+    let (embedding, embedding_model_id) =
+        match embedding_model::compute_embedding(app.clone(), new_memory.clone()).await {
+            Ok(vec) => (vec, embedding_model::active_embedding_backend_id(&app)),
+            Err(err) => {
+                log_error(
+                    &app,
+                    "character_memory_update",
+                    format!("embedding failed: {}", err),
+                );
+                (Vec::new(), None)
+            }
+        };
+
+    let conn = open_db(&app)?;
+    let owner = MemoryOwner::Character(&character_id);
+    let mut memories = read_memories(&conn, owner)?;
+    let memory = memories
+        .iter_mut()
+        .find(|m| m.id == memory_id)
+        .ok_or_else(|| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Character memory {} not found", memory_id),
+            )
+        })?;
+    memory.text = new_memory;
+    memory.embedding = embedding;
+    memory.embedding_model = embedding_model_id;
+    memory.category = category;
+    write_memories(&conn, owner, &memories, MemoryActor::User)?;
+
+    read_memories_json(&conn, owner)
+}
+
+#[tauri::command]
+pub fn character_memory_delete(
+    app: tauri::AppHandle,
+    character_id: String,
+    memory_id: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    let owner = MemoryOwner::Character(&character_id);
+    let mut memories = read_memories(&conn, owner)?;
+    memories.retain(|m| m.id != memory_id);
+    write_memories(&conn, owner, &memories, MemoryActor::User)?;
+    log_info(
+        &app,
+        "character_memory_delete",
+        format!("Deleted memory {} of character {}", memory_id, character_id),
+    );
+
+    read_memories_json(&conn, owner)
+}
+
+/// Edit history of a character's long-term memories, oldest first. With `memory_id`, only
+/// that memory and the memories it was merged from.
+#[tauri::command]
+pub fn character_memory_timeline(
+    app: tauri::AppHandle,
+    character_id: String,
+    memory_id: Option<String>,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    let events = read_memory_timeline(
+        &conn,
+        MemoryOwner::Character(&character_id),
+        memory_id.as_deref(),
+    )?;
+    serde_json::to_string(&events)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
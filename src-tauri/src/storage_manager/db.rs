@@ -614,6 +614,137 @@ pub fn init_db(_app: &tauri::AppHandle, conn: &Connection) -> Result<(), String>
           FOREIGN KEY(message_id) REFERENCES group_messages(id) ON DELETE CASCADE
         );
 
+        -- Dynamic memories (owned by either a chat session or a group session)
+        CREATE TABLE IF NOT EXISTS memories (
+          row_id INTEGER PRIMARY KEY AUTOINCREMENT,
+          id TEXT NOT NULL,
+          session_id TEXT,
+          group_session_id TEXT,
+          character_id TEXT,
+          position INTEGER NOT NULL DEFAULT 0,
+          text TEXT NOT NULL,
+          category TEXT,
+          embedding BLOB,
+          embedding_dim INTEGER NOT NULL DEFAULT 0,
+          embedding_model TEXT,
+          token_count INTEGER NOT NULL DEFAULT 0,
+          is_cold INTEGER NOT NULL DEFAULT 0,
+          is_pinned INTEGER NOT NULL DEFAULT 0,
+          importance_score REAL NOT NULL DEFAULT 1.0,
+          access_count INTEGER NOT NULL DEFAULT 0,
+          last_accessed_at INTEGER NOT NULL DEFAULT 0,
+          created_at INTEGER NOT NULL DEFAULT 0,
+          updated_at INTEGER NOT NULL DEFAULT 0,
+          provenance TEXT,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(group_session_id) REFERENCES group_sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(character_id) REFERENCES characters(id) ON DELETE CASCADE
+        );
+
+        -- Edit history of memories; rows outlive the memory they describe
+        CREATE TABLE IF NOT EXISTS memory_events (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          memory_id TEXT NOT NULL,
+          session_id TEXT,
+          group_session_id TEXT,
+          character_id TEXT,
+          kind TEXT NOT NULL,
+          actor TEXT NOT NULL,
+          text TEXT,
+          previous_text TEXT,
+          details TEXT,
+          created_at INTEGER NOT NULL,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(group_session_id) REFERENCES group_sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(character_id) REFERENCES characters(id) ON DELETE CASCADE
+        );
+
+        -- Queued dynamic memory cycles; finished jobs are deleted
+        CREATE TABLE IF NOT EXISTS memory_jobs (
+          id TEXT PRIMARY KEY,
+          session_id TEXT,
+          group_session_id TEXT,
+          status TEXT NOT NULL,
+          attempts INTEGER NOT NULL DEFAULT 0,
+          max_attempts INTEGER NOT NULL,
+          last_error TEXT,
+          run_at INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          updated_at INTEGER NOT NULL,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(group_session_id) REFERENCES group_sessions(id) ON DELETE CASCADE
+        );
+
+        -- Embedding vectors keyed by blake3(text) and the backend id that produced them
+        CREATE TABLE IF NOT EXISTS embedding_cache (
+          text_hash TEXT NOT NULL,
+          model TEXT NOT NULL,
+          embedding BLOB NOT NULL,
+          dim INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          last_used_at INTEGER NOT NULL,
+          PRIMARY KEY(text_hash, model)
+        );
+
+        -- GGUF files registered for the llama.cpp provider, with metadata read from their headers
+        CREATE TABLE IF NOT EXISTS local_models (
+          id TEXT PRIMARY KEY,
+          path TEXT NOT NULL UNIQUE,
+          file_name TEXT NOT NULL,
+          size_bytes INTEGER NOT NULL,
+          architecture TEXT,
+          name TEXT,
+          parameter_count INTEGER,
+          quantization TEXT,
+          context_length INTEGER,
+          chat_template TEXT,
+          source_url TEXT,
+          created_at INTEGER NOT NULL,
+          updated_at INTEGER NOT NULL
+        );
+
+        -- Keyword index over memory text (external content, kept in sync by triggers)
+        CREATE VIRTUAL TABLE IF NOT EXISTS memories_fts USING fts5(
+          text,
+          content='memories',
+          content_rowid='row_id'
+        );
+        CREATE TRIGGER IF NOT EXISTS memories_fts_ai AFTER INSERT ON memories BEGIN
+          INSERT INTO memories_fts(rowid, text) VALUES (new.row_id, new.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS memories_fts_ad AFTER DELETE ON memories BEGIN
+          INSERT INTO memories_fts(memories_fts, rowid, text) VALUES ('delete', old.row_id, old.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS memories_fts_au AFTER UPDATE OF text ON memories BEGIN
+          INSERT INTO memories_fts(memories_fts, rowid, text) VALUES ('delete', old.row_id, old.text);
+          INSERT INTO memories_fts(rowid, text) VALUES (new.row_id, new.text);
+        END;
+
+        -- Knowledge graph extracted by the dynamic memory cycle
+        CREATE TABLE IF NOT EXISTS kg_entities (
+          id TEXT PRIMARY KEY,
+          session_id TEXT NOT NULL,
+          name TEXT NOT NULL,
+          kind TEXT NOT NULL DEFAULT 'other',
+          description TEXT,
+          created_at INTEGER NOT NULL DEFAULT 0,
+          updated_at INTEGER NOT NULL DEFAULT 0,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS kg_relations (
+          id TEXT PRIMARY KEY,
+          session_id TEXT NOT NULL,
+          source_id TEXT NOT NULL,
+          target_id TEXT NOT NULL,
+          relation TEXT NOT NULL,
+          message_ids TEXT NOT NULL DEFAULT '[]',
+          created_at INTEGER NOT NULL DEFAULT 0,
+          updated_at INTEGER NOT NULL DEFAULT 0,
+          FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+          FOREIGN KEY(source_id) REFERENCES kg_entities(id) ON DELETE CASCADE,
+          FOREIGN KEY(target_id) REFERENCES kg_entities(id) ON DELETE CASCADE
+        );
+
         -- Indexes
         CREATE INDEX IF NOT EXISTS idx_sessions_character ON sessions(character_id);
         CREATE INDEX IF NOT EXISTS idx_messages_session ON messages(session_id);
@@ -636,6 +767,20 @@ pub fn init_db(_app: &tauri::AppHandle, conn: &Connection) -> Result<(), String>
         CREATE INDEX IF NOT EXISTS idx_group_messages_turn ON group_messages(session_id, turn_number);
         CREATE INDEX IF NOT EXISTS idx_group_messages_speaker ON group_messages(speaker_character_id);
         CREATE INDEX IF NOT EXISTS idx_group_message_variants_message ON group_message_variants(message_id);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_session_id ON memories(session_id, id) WHERE session_id IS NOT NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_group_session_id ON memories(group_session_id, id) WHERE group_session_id IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_memories_session_hot ON memories(session_id, is_cold, is_pinned);
+        CREATE INDEX IF NOT EXISTS idx_memories_group_session_hot ON memories(group_session_id, is_cold, is_pinned);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_kg_entities_name ON kg_entities(session_id, name COLLATE NOCASE);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_kg_relations_triple ON kg_relations(session_id, source_id, relation, target_id);
+        CREATE INDEX IF NOT EXISTS idx_kg_relations_target ON kg_relations(target_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_events_session ON memory_events(session_id, memory_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_events_group_session ON memory_events(group_session_id, memory_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_events_character ON memory_events(character_id, memory_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_jobs_status ON memory_jobs(status, run_at);
+        CREATE INDEX IF NOT EXISTS idx_memory_jobs_session ON memory_jobs(session_id);
+        CREATE INDEX IF NOT EXISTS idx_memory_jobs_group_session ON memory_jobs(group_session_id);
+        CREATE INDEX IF NOT EXISTS idx_embedding_cache_last_used ON embedding_cache(last_used_at);
       "#,
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
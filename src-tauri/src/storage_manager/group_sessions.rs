@@ -4,6 +4,15 @@ use tauri::State;
 use uuid::Uuid;
 
 use super::db::{now_ms, SwappablePool};
+use super::memories::{
+    absorb_memory_column, read_memories, read_memories_json, read_memory_timeline,
+    undo_memory_merge, write_memories, MemoryActor, MemoryOwner,
+};
+use super::memory_transfer::{
+    apply_memory_import, current_embedding_model, embed_imported_memories, export_memories,
+    parse_memory_export, plan_memory_import,
+};
+use crate::chat_manager::types::{MemoryProvenance, MemoryScoreBreakdown};
 use crate::utils::{log_info, log_info_global};
 
 // ============================================================================
@@ -99,6 +108,9 @@ pub struct MemoryEmbedding {
     pub id: String,
     pub text: String,
     pub embedding: Vec<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    #[serde(default)]
     pub created_at: i64,
     #[serde(default)]
     pub token_count: i32,
@@ -112,9 +124,17 @@ pub struct MemoryEmbedding {
     pub access_count: i32,
     #[serde(default)]
     pub is_pinned: bool,
+    /// Ephemeral match score (similarity) from retrieval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_score: Option<f32>,
     /// Category tag for clustering (e.g. character_trait, relationship, plot_event)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
+    /// Ephemeral per-signal scores behind `match_score` (hybrid retrieval only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_breakdown: Option<MemoryScoreBreakdown>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<MemoryProvenance>,
 }
 
 fn default_importance() -> f32 {
@@ -224,8 +244,11 @@ fn read_group_session(conn: &Connection, id: &str) -> Result<Option<GroupSession
             .get::<_, Option<String>>(7)
             .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
             .unwrap_or_else(|| "[]".to_string());
-        let memory_embeddings: Vec<MemoryEmbedding> =
-            serde_json::from_str(&memory_embeddings_json).unwrap_or_default();
+        // Rows not yet moved to the `memories` table still carry the legacy column.
+        let mut memory_embeddings = read_memories(conn, MemoryOwner::GroupSession(id))?;
+        if memory_embeddings.is_empty() {
+            memory_embeddings = serde_json::from_str(&memory_embeddings_json).unwrap_or_default();
+        }
 
         let memory_summary: String = row
             .get::<_, Option<String>>(8)
@@ -921,13 +944,8 @@ pub fn group_session_duplicate_with_messages(
             )
             .ok();
 
-        let memory_embeddings: Option<String> = conn
-            .query_row(
-                "SELECT memory_embeddings FROM group_sessions WHERE id = ?1",
-                params![source_id],
-                |row| row.get(0),
-            )
-            .ok();
+        let memory_embeddings: Option<String> =
+            read_memories_json(&conn, MemoryOwner::GroupSession(&source_id)).ok();
 
         let memory_summary: String = conn
             .query_row(
@@ -1014,6 +1032,9 @@ pub fn group_session_duplicate_with_messages(
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
 
+    // The copied memories were staged in the legacy column; move them into the table.
+    absorb_memory_column(&conn, MemoryOwner::GroupSession(&new_id))?;
+
     // Create participation records for each character
     ensure_participation_records(&conn, &new_id, &source.character_ids)?;
 
@@ -1877,10 +1898,18 @@ pub fn group_session_update_memories(
     let conn = pool.get_connection()?;
     let now = now_ms();
 
+    let memory_embeddings: Vec<MemoryEmbedding> = serde_json::from_str(&memory_embeddings_json)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
+
     conn.execute(
-        "UPDATE group_sessions SET memory_embeddings = ?1, memory_summary = ?2, memory_summary_token_count = ?3, updated_at = ?4 WHERE id = ?5",
+        "UPDATE group_sessions SET memory_summary = ?1, memory_summary_token_count = ?2, updated_at = ?3 WHERE id = ?4",
         params![
-            memory_embeddings_json,
             memory_summary.unwrap_or_default(),
             memory_summary_token_count.unwrap_or(0),
             now,
@@ -1924,16 +1953,20 @@ pub fn group_session_update_memories_internal(
     let now = now_ms();
     let memories_json = serde_json::to_string(memories)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let memory_embeddings_json = serde_json::to_string(memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     let memory_tool_events_json = serde_json::to_string(memory_tool_events)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
+    write_memories(
+        conn,
+        MemoryOwner::GroupSession(session_id),
+        memory_embeddings,
+        MemoryActor::MemoryManager,
+    )?;
+
     conn.execute(
-        "UPDATE group_sessions SET memories = ?1, memory_embeddings = ?2, memory_summary = ?3, memory_summary_token_count = ?4, memory_tool_events = ?5, updated_at = ?6 WHERE id = ?7",
+        "UPDATE group_sessions SET memories = ?1, memory_summary = ?2, memory_summary_token_count = ?3, memory_tool_events = ?4, updated_at = ?5 WHERE id = ?6",
         params![
             memories_json,
-            memory_embeddings_json,
             memory_summary.unwrap_or(""),
             memory_summary_token_count,
             memory_tool_events_json,
@@ -1970,37 +2003,38 @@ pub async fn group_session_add_memory(
     let conn = pool.get_connection()?;
 
     // Read current memories and embeddings
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM group_sessions WHERE id = ?",
+            "SELECT memories FROM group_sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let mut memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
-    let mut memory_embeddings: Vec<MemoryEmbedding> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+    let mut memory_embeddings = read_memories(&conn, MemoryOwner::GroupSession(&session_id))?;
 
     // Add new memory
     memories.push(memory.clone());
 
     // Compute embedding (best-effort)
-    let embedding = match embedding_model::compute_embedding(app.clone(), memory.clone()).await {
-        Ok(vec) => vec,
-        Err(_) => Vec::new(),
-    };
+    let (embedding, embedding_model_id) =
+        match embedding_model::compute_embedding(app.clone(), memory.clone()).await {
+            Ok(vec) => (vec, embedding_model::active_embedding_backend_id(&app)),
+            Err(_) => (Vec::new(), None),
+        };
 
     // Count tokens (best-effort)
-    let token_count = crate::tokenizer::count_tokens(&app, &memory).unwrap_or(0);
+    let token_count = crate::tokenizer::count_tokens_for_model_id(&app, None, &memory);
 
     memory_embeddings.push(MemoryEmbedding {
         id: uuid::Uuid::new_v4().to_string(),
         text: memory,
         embedding,
+        embedding_model: embedding_model_id,
         created_at: now_ms() as i64,
         token_count: token_count as i32,
         is_cold: false,
@@ -2008,19 +2042,29 @@ pub async fn group_session_add_memory(
         last_accessed_at: now_ms() as i64,
         access_count: 0,
         is_pinned: false,
+        match_score: None,
         category: None,
+        score_breakdown: None,
+        provenance: Some(MemoryProvenance {
+            origin: "user".to_string(),
+            ..Default::default()
+        }),
     });
 
     // Save back
     let new_memories_json = serde_json::to_string(&memories)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
     let now = now_ms();
 
     conn.execute(
-        "UPDATE group_sessions SET memories = ?, memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_memories_json, new_embeddings_json, now, &session_id],
+        "UPDATE group_sessions SET memories = ?, updated_at = ? WHERE id = ?",
+        params![new_memories_json, now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -2043,20 +2087,19 @@ pub fn group_session_remove_memory(
     let conn = pool.get_connection()?;
 
     // Read current memories and embeddings
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM group_sessions WHERE id = ?",
+            "SELECT memories FROM group_sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let mut memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
-    let mut memory_embeddings: Vec<MemoryEmbedding> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+    let mut memory_embeddings = read_memories(&conn, MemoryOwner::GroupSession(&session_id))?;
 
     // Remove at index if valid
     if memory_index < memories.len() {
@@ -2069,13 +2112,17 @@ pub fn group_session_remove_memory(
     // Save back
     let new_memories_json = serde_json::to_string(&memories)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
     let now = now_ms();
 
     conn.execute(
-        "UPDATE group_sessions SET memories = ?, memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_memories_json, new_embeddings_json, now, &session_id],
+        "UPDATE group_sessions SET memories = ?, updated_at = ? WHERE id = ?",
+        params![new_memories_json, now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -2102,20 +2149,19 @@ pub async fn group_session_update_memory(
     let conn = pool.get_connection()?;
 
     // Read current memories and embeddings
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM group_sessions WHERE id = ?",
+            "SELECT memories FROM group_sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let mut memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
-    let mut memory_embeddings: Vec<MemoryEmbedding> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+    let mut memory_embeddings = read_memories(&conn, MemoryOwner::GroupSession(&session_id))?;
 
     // Update at index if valid
     if memory_index < memories.len() {
@@ -2124,29 +2170,37 @@ pub async fn group_session_update_memory(
 
     if memory_index < memory_embeddings.len() {
         // Recompute embedding
-        let embedding =
+        let (embedding, embedding_model_id) =
             match embedding_model::compute_embedding(app.clone(), new_memory.clone()).await {
-                Ok(vec) => vec,
-                Err(_) => memory_embeddings[memory_index].embedding.clone(),
+                Ok(vec) => (vec, embedding_model::active_embedding_backend_id(&app)),
+                Err(_) => (
+                    memory_embeddings[memory_index].embedding.clone(),
+                    memory_embeddings[memory_index].embedding_model.clone(),
+                ),
             };
 
-        let token_count = crate::tokenizer::count_tokens(&app, &new_memory).unwrap_or(0);
+        let token_count = crate::tokenizer::count_tokens_for_model_id(&app, None, &new_memory);
 
         memory_embeddings[memory_index].text = new_memory;
         memory_embeddings[memory_index].embedding = embedding;
+        memory_embeddings[memory_index].embedding_model = embedding_model_id;
         memory_embeddings[memory_index].token_count = token_count as i32;
     }
 
     // Save back
     let new_memories_json = serde_json::to_string(&memories)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
     let now = now_ms();
 
     conn.execute(
-        "UPDATE group_sessions SET memories = ?, memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_memories_json, new_embeddings_json, now, &session_id],
+        "UPDATE group_sessions SET memories = ?, updated_at = ? WHERE id = ?",
+        params![new_memories_json, now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -2169,18 +2223,7 @@ pub fn group_session_toggle_memory_pin(
     let conn = pool.get_connection()?;
 
     // Read current embeddings
-    let current_embeddings_json: String = conn
-        .query_row(
-            "SELECT memory_embeddings FROM group_sessions WHERE id = ?",
-            params![&session_id],
-            |r| r.get(0),
-        )
-        .optional()
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| "[]".to_string());
-
-    let mut memory_embeddings: Vec<MemoryEmbedding> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+    let mut memory_embeddings = read_memories(&conn, MemoryOwner::GroupSession(&session_id))?;
 
     let now = now_ms() as i64;
 
@@ -2196,12 +2239,16 @@ pub fn group_session_toggle_memory_pin(
     }
 
     // Save back
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
 
     conn.execute(
-        "UPDATE group_sessions SET memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_embeddings_json, now, &session_id],
+        "UPDATE group_sessions SET updated_at = ? WHERE id = ?",
+        params![now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -2225,18 +2272,7 @@ pub fn group_session_set_memory_cold_state(
     let conn = pool.get_connection()?;
 
     // Read current embeddings
-    let current_embeddings_json: String = conn
-        .query_row(
-            "SELECT memory_embeddings FROM group_sessions WHERE id = ?",
-            params![&session_id],
-            |r| r.get(0),
-        )
-        .optional()
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| "[]".to_string());
-
-    let mut memory_embeddings: Vec<MemoryEmbedding> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+    let mut memory_embeddings = read_memories(&conn, MemoryOwner::GroupSession(&session_id))?;
 
     let now = now_ms() as i64;
 
@@ -2259,11 +2295,15 @@ pub fn group_session_set_memory_cold_state(
     }
 
     // Save back
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
     conn.execute(
-        "UPDATE group_sessions SET memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_embeddings_json, now, &session_id],
+        "UPDATE group_sessions SET updated_at = ? WHERE id = ?",
+        params![now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -2275,3 +2315,113 @@ pub fn group_session_set_memory_cold_state(
     }
     Ok(None)
 }
+
+/// Undo an automatic memory consolidation in a group session
+#[tauri::command]
+pub fn group_session_undo_memory_merge(
+    app: tauri::AppHandle,
+    session_id: String,
+    memory_id: String,
+    pool: State<'_, SwappablePool>,
+) -> Result<Option<String>, String> {
+    let conn = pool.get_connection()?;
+
+    if !undo_memory_merge(&conn, MemoryOwner::GroupSession(&session_id), &memory_id)? {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("No merge to undo for memory {}", memory_id),
+        ));
+    }
+    crate::embedding_model::embed_pending_memories(&app);
+
+    // Return updated session
+    if let Some(session) = read_group_session(&conn, &session_id)? {
+        return Ok(Some(serde_json::to_string(&session).map_err(|e| {
+            crate::utils::err_to_string(module_path!(), line!(), e)
+        })?));
+    }
+    Ok(None)
+}
+
+/// Edit history of a group session's memories, oldest first. With `memory_id`, only that
+/// memory and the memories it was merged from.
+#[tauri::command]
+pub fn group_session_memory_timeline(
+    session_id: String,
+    memory_id: Option<String>,
+    pool: State<'_, SwappablePool>,
+) -> Result<String, String> {
+    let conn = pool.get_connection()?;
+    let events = read_memory_timeline(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        memory_id.as_deref(),
+    )?;
+    serde_json::to_string(&events)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Exports a group session's memories, summary and (optionally) embeddings as a standalone
+/// JSON file.
+#[tauri::command]
+pub fn group_session_memories_export(
+    app: tauri::AppHandle,
+    session_id: String,
+    include_embeddings: Option<bool>,
+    pool: State<'_, SwappablePool>,
+) -> Result<String, String> {
+    let conn = pool.get_connection()?;
+    let export = export_memories(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        include_embeddings.unwrap_or(true),
+        current_embedding_model(&app),
+    )?;
+    serde_json::to_string_pretty(&export)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Imports a memory export into a group session, skipping texts it already has and
+/// re-embedding vectors from another embedding model.
+#[tauri::command]
+pub async fn group_session_memories_import(
+    app: tauri::AppHandle,
+    session_id: String,
+    import_json: String,
+    replace: Option<bool>,
+    pool: State<'_, SwappablePool>,
+) -> Result<Option<String>, String> {
+    let replace = replace.unwrap_or(false);
+    let export = parse_memory_export(&import_json)?;
+    let conn = pool.get_connection()?;
+    let existing = read_memories(&conn, MemoryOwner::GroupSession(&session_id))?;
+    let mut plan = plan_memory_import(&existing, &export, replace);
+    // Group memories are counted with the default model, as when they are added.
+    let reembedded = embed_imported_memories(&app, &mut plan, &export, None).await;
+    apply_memory_import(
+        &conn,
+        MemoryOwner::GroupSession(&session_id),
+        &plan,
+        &export,
+        replace,
+    )?;
+    log_info(
+        &app,
+        "group_session_memories_import",
+        format!(
+            "Imported {} memories into group session {} ({} skipped, {} re-embedded)",
+            plan.imported.len(),
+            session_id,
+            plan.skipped,
+            reembedded
+        ),
+    );
+
+    if let Some(session) = read_group_session(&conn, &session_id)? {
+        return Ok(Some(serde_json::to_string(&session).map_err(|e| {
+            crate::utils::err_to_string(module_path!(), line!(), e)
+        })?));
+    }
+    Ok(None)
+}
@@ -245,9 +245,11 @@ fn import_characters(conn: &mut rusqlite::Connection, json: &str) -> Result<(),
         let created_at = c.get("createdAt").and_then(|v| v.as_i64()).unwrap_or(now);
         let updated_at = c.get("updatedAt").and_then(|v| v.as_i64()).unwrap_or(now);
 
+        // Upsert so re-importing keeps the character's sessions and memories.
         tx.execute(
-            r#"INSERT OR REPLACE INTO characters (id, name, avatar_path, avatar_crop_x, avatar_crop_y, avatar_crop_scale, background_image_path, description, definition, default_scene_id, default_model_id, prompt_template_id, system_prompt, disable_avatar_gradient, created_at, updated_at)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+            r#"INSERT INTO characters (id, name, avatar_path, avatar_crop_x, avatar_crop_y, avatar_crop_scale, background_image_path, description, definition, default_scene_id, default_model_id, prompt_template_id, system_prompt, disable_avatar_gradient, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(id) DO UPDATE SET name = excluded.name, avatar_path = excluded.avatar_path, avatar_crop_x = excluded.avatar_crop_x, avatar_crop_y = excluded.avatar_crop_y, avatar_crop_scale = excluded.avatar_crop_scale, background_image_path = excluded.background_image_path, description = excluded.description, definition = excluded.definition, default_scene_id = excluded.default_scene_id, default_model_id = excluded.default_model_id, prompt_template_id = excluded.prompt_template_id, system_prompt = excluded.system_prompt, disable_avatar_gradient = excluded.disable_avatar_gradient, created_at = excluded.created_at, updated_at = excluded.updated_at"#,
             params![
                 id,
                 name,
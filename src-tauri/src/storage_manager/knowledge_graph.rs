@@ -0,0 +1,673 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::db::{now_ms, open_db};
+use crate::utils::log_info;
+
+/// Entity kinds the memory cycle may record; anything else is stored as "other".
+pub const ENTITY_KINDS: &[&str] = &["person", "place", "item", "organization", "other"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KgEntity {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// A typed edge `source --relation--> target`, with the ids of the messages that support it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KgRelation {
+    pub id: String,
+    pub source_id: String,
+    pub target_id: String,
+    pub relation: String,
+    #[serde(default)]
+    pub message_ids: Vec<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeGraph {
+    pub entities: Vec<KgEntity>,
+    pub relations: Vec<KgRelation>,
+}
+
+fn normalize_kind(kind: Option<&str>) -> String {
+    let kind = kind.map(|k| k.trim().to_lowercase()).unwrap_or_default();
+    if ENTITY_KINDS.contains(&kind.as_str()) {
+        kind
+    } else {
+        "other".to_string()
+    }
+}
+
+/// Relations are stored as lowercase snake_case ("sister of" -> "sister_of").
+pub fn normalize_relation(relation: &str) -> Option<String> {
+    let words: Vec<String> = relation
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join("_"))
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+}
+
+fn row_to_entity(row: &rusqlite::Row<'_>) -> rusqlite::Result<KgEntity> {
+    Ok(KgEntity {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        description: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+fn row_to_relation(row: &rusqlite::Row<'_>) -> rusqlite::Result<KgRelation> {
+    let message_ids: String = row.get(4)?;
+    Ok(KgRelation {
+        id: row.get(0)?,
+        source_id: row.get(1)?,
+        target_id: row.get(2)?,
+        relation: row.get(3)?,
+        message_ids: serde_json::from_str(&message_ids).unwrap_or_default(),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+const ENTITY_COLUMNS: &str = "id, name, kind, description, created_at, updated_at";
+const RELATION_COLUMNS: &str =
+    "id, source_id, target_id, relation, message_ids, created_at, updated_at";
+
+fn find_entity_by_name(
+    conn: &Connection,
+    session_id: &str,
+    name: &str,
+) -> Result<Option<KgEntity>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM kg_entities WHERE session_id = ?1 AND name = ?2 COLLATE NOCASE",
+            ENTITY_COLUMNS
+        ),
+        params![session_id, name],
+        row_to_entity,
+    )
+    .optional()
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Creates the entity or refreshes an existing one with the same name (case-insensitive).
+/// A known kind never gets downgraded to "other", and a missing description keeps the old one.
+pub fn upsert_entity(
+    conn: &Connection,
+    session_id: &str,
+    name: &str,
+    kind: Option<&str>,
+    description: Option<&str>,
+) -> Result<KgEntity, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Entity name is empty",
+        ));
+    }
+    let now = now_ms() as i64;
+    let kind = kind.map(|k| normalize_kind(Some(k)));
+    let description = non_empty(description);
+
+    if let Some(mut entity) = find_entity_by_name(conn, session_id, name)? {
+        if let Some(kind) = kind.filter(|k| k != "other") {
+            entity.kind = kind;
+        }
+        if description.is_some() {
+            entity.description = description;
+        }
+        entity.updated_at = now;
+        conn.execute(
+            "UPDATE kg_entities SET kind = ?1, description = ?2, updated_at = ?3 WHERE id = ?4",
+            params![&entity.kind, &entity.description, now, &entity.id],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        return Ok(entity);
+    }
+
+    let entity = KgEntity {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        kind: kind.unwrap_or_else(|| "other".to_string()),
+        description,
+        created_at: now,
+        updated_at: now,
+    };
+    conn.execute(
+        "INSERT INTO kg_entities (id, session_id, name, kind, description, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            &entity.id,
+            session_id,
+            &entity.name,
+            &entity.kind,
+            &entity.description,
+            now,
+            now
+        ],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(entity)
+}
+
+/// Records `source --relation--> target`, creating missing entities. Recording a relation
+/// that already exists only adds the new supporting message ids.
+pub fn record_relation(
+    conn: &Connection,
+    session_id: &str,
+    source: &str,
+    relation: &str,
+    target: &str,
+    message_ids: &[String],
+) -> Result<KgRelation, String> {
+    let relation = normalize_relation(relation)
+        .ok_or_else(|| crate::utils::err_msg(module_path!(), line!(), "Relation type is empty"))?;
+    let source = upsert_entity(conn, session_id, source, None, None)?;
+    let target = upsert_entity(conn, session_id, target, None, None)?;
+    let now = now_ms() as i64;
+
+    let existing = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM kg_relations
+                 WHERE session_id = ?1 AND source_id = ?2 AND relation = ?3 AND target_id = ?4",
+                RELATION_COLUMNS
+            ),
+            params![session_id, &source.id, &relation, &target.id],
+            row_to_relation,
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    if let Some(mut found) = existing {
+        for id in message_ids {
+            if !found.message_ids.contains(id) {
+                found.message_ids.push(id.clone());
+            }
+        }
+        found.updated_at = now;
+        let ids_json = serde_json::to_string(&found.message_ids)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        conn.execute(
+            "UPDATE kg_relations SET message_ids = ?1, updated_at = ?2 WHERE id = ?3",
+            params![ids_json, now, &found.id],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        return Ok(found);
+    }
+
+    let mut unique_ids: Vec<String> = Vec::new();
+    for id in message_ids {
+        if !unique_ids.contains(id) {
+            unique_ids.push(id.clone());
+        }
+    }
+    let created = KgRelation {
+        id: uuid::Uuid::new_v4().to_string(),
+        source_id: source.id,
+        target_id: target.id,
+        relation,
+        message_ids: unique_ids,
+        created_at: now,
+        updated_at: now,
+    };
+    let ids_json = serde_json::to_string(&created.message_ids)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    conn.execute(
+        "INSERT INTO kg_relations (id, session_id, source_id, target_id, relation, message_ids, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            &created.id,
+            session_id,
+            &created.source_id,
+            &created.target_id,
+            &created.relation,
+            ids_json,
+            now,
+            now
+        ],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(created)
+}
+
+pub fn read_graph(conn: &Connection, session_id: &str) -> Result<KnowledgeGraph, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM kg_entities WHERE session_id = ?1 ORDER BY name COLLATE NOCASE",
+            ENTITY_COLUMNS
+        ))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let entities = stmt
+        .query_map(params![session_id], row_to_entity)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM kg_relations WHERE session_id = ?1 ORDER BY created_at ASC",
+            RELATION_COLUMNS
+        ))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let relations = stmt
+        .query_map(params![session_id], row_to_relation)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    Ok(KnowledgeGraph {
+        entities,
+        relations,
+    })
+}
+
+/// Replaces the session's whole graph, keeping ids and timestamps. Used by backup restore
+/// and sync, where the incoming graph is authoritative.
+pub fn replace_graph(
+    conn: &Connection,
+    session_id: &str,
+    graph: &KnowledgeGraph,
+) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM kg_relations WHERE session_id = ?1",
+        params![session_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    conn.execute(
+        "DELETE FROM kg_entities WHERE session_id = ?1",
+        params![session_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    for entity in &graph.entities {
+        conn.execute(
+            "INSERT INTO kg_entities (id, session_id, name, kind, description, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                &entity.id,
+                session_id,
+                &entity.name,
+                &entity.kind,
+                &entity.description,
+                entity.created_at,
+                entity.updated_at
+            ],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    for relation in &graph.relations {
+        let ids_json = serde_json::to_string(&relation.message_ids)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        conn.execute(
+            "INSERT INTO kg_relations (id, session_id, source_id, target_id, relation, message_ids, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                &relation.id,
+                session_id,
+                &relation.source_id,
+                &relation.target_id,
+                &relation.relation,
+                ids_json,
+                relation.created_at,
+                relation.updated_at
+            ],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    Ok(())
+}
+
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    let mut start = 0;
+    while let Some(found) = haystack[start..].find(needle) {
+        let begin = start + found;
+        let end = begin + needle.len();
+        if !is_word(haystack[..begin].chars().next_back())
+            && !is_word(haystack[end..].chars().next())
+        {
+            return true;
+        }
+        start = begin + needle.chars().next().map_or(1, char::len_utf8);
+    }
+    false
+}
+
+/// Ids of the entities whose name appears in `text` as a whole word (case-insensitive).
+pub fn mentioned_entity_ids(graph: &KnowledgeGraph, text: &str) -> Vec<String> {
+    let text = text.to_lowercase();
+    graph
+        .entities
+        .iter()
+        .filter(|e| {
+            let name = e.name.trim().to_lowercase();
+            !name.is_empty() && contains_word(&text, &name)
+        })
+        .map(|e| e.id.clone())
+        .collect()
+}
+
+/// The part of `graph` within `hops` relations of the seed entities.
+pub fn subgraph(graph: &KnowledgeGraph, seeds: &[String], hops: usize) -> KnowledgeGraph {
+    let mut included: HashSet<&str> = seeds.iter().map(String::as_str).collect();
+    let mut frontier = included.clone();
+    for _ in 0..hops {
+        let mut next = HashSet::new();
+        for relation in &graph.relations {
+            let (source, target) = (relation.source_id.as_str(), relation.target_id.as_str());
+            if frontier.contains(source) && !included.contains(target) {
+                next.insert(target);
+            }
+            if frontier.contains(target) && !included.contains(source) {
+                next.insert(source);
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        included.extend(next.iter().copied());
+        frontier = next;
+    }
+
+    KnowledgeGraph {
+        entities: graph
+            .entities
+            .iter()
+            .filter(|e| included.contains(e.id.as_str()))
+            .cloned()
+            .collect(),
+        relations: graph
+            .relations
+            .iter()
+            .filter(|r| {
+                included.contains(r.source_id.as_str()) && included.contains(r.target_id.as_str())
+            })
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Renders a (sub)graph as prompt lines: entities first, then "A --relation--> B" edges.
+pub fn format_graph(graph: &KnowledgeGraph) -> String {
+    let name_of = |id: &str| {
+        graph
+            .entities
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.name.as_str())
+            .unwrap_or("?")
+    };
+    let mut lines: Vec<String> = graph
+        .entities
+        .iter()
+        .map(|e| match e.description.as_deref() {
+            Some(description) => format!("- {} ({}): {}", e.name, e.kind, description),
+            None => format!("- {} ({})", e.name, e.kind),
+        })
+        .collect();
+    lines.extend(graph.relations.iter().map(|r| {
+        format!(
+            "- {} --{}--> {}",
+            name_of(&r.source_id),
+            r.relation,
+            name_of(&r.target_id)
+        )
+    }));
+    lines.join("\n")
+}
+
+fn graph_json(conn: &Connection, session_id: &str) -> Result<String, String> {
+    let graph = read_graph(conn, session_id)?;
+    serde_json::to_string(&graph)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Graph edits don't touch the sessions row, so bump it for sync to notice the change.
+fn touch_session(conn: &Connection, session_id: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE sessions SET updated_at = ?1 WHERE id = ?2",
+        params![now_ms() as i64, session_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn not_found(what: &str, id: &str) -> String {
+    crate::utils::err_msg(
+        module_path!(),
+        line!(),
+        format!("{} {} not found", what, id),
+    )
+}
+
+#[tauri::command]
+pub fn session_knowledge_graph(
+    app: tauri::AppHandle,
+    session_id: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    graph_json(&conn, &session_id)
+}
+
+/// Rename or correct an entity. Renaming onto another entity's name is rejected.
+#[tauri::command]
+pub fn knowledge_graph_entity_update(
+    app: tauri::AppHandle,
+    session_id: String,
+    entity_id: String,
+    name: String,
+    kind: String,
+    description: Option<String>,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Entity name is empty",
+        ));
+    }
+    if let Some(other) = find_entity_by_name(&conn, &session_id, name)? {
+        if other.id != entity_id {
+            return Err(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("An entity named {} already exists", other.name),
+            ));
+        }
+    }
+    let changed = conn
+        .execute(
+            "UPDATE kg_entities SET name = ?1, kind = ?2, description = ?3, updated_at = ?4
+             WHERE session_id = ?5 AND id = ?6",
+            params![
+                name,
+                normalize_kind(Some(&kind)),
+                non_empty(description.as_deref()),
+                now_ms() as i64,
+                &session_id,
+                &entity_id
+            ],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if changed == 0 {
+        return Err(not_found("Entity", &entity_id));
+    }
+    touch_session(&conn, &session_id)?;
+    graph_json(&conn, &session_id)
+}
+
+/// Delete an entity together with every relation touching it.
+#[tauri::command]
+pub fn knowledge_graph_entity_delete(
+    app: tauri::AppHandle,
+    session_id: String,
+    entity_id: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    conn.execute(
+        "DELETE FROM kg_relations WHERE session_id = ?1 AND (source_id = ?2 OR target_id = ?2)",
+        params![&session_id, &entity_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    conn.execute(
+        "DELETE FROM kg_entities WHERE session_id = ?1 AND id = ?2",
+        params![&session_id, &entity_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    touch_session(&conn, &session_id)?;
+    log_info(
+        &app,
+        "knowledge_graph",
+        format!("Deleted entity {} from session {}", entity_id, session_id),
+    );
+    graph_json(&conn, &session_id)
+}
+
+#[tauri::command]
+pub fn knowledge_graph_relation_update(
+    app: tauri::AppHandle,
+    session_id: String,
+    relation_id: String,
+    relation: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    let relation = normalize_relation(&relation)
+        .ok_or_else(|| crate::utils::err_msg(module_path!(), line!(), "Relation type is empty"))?;
+    let changed = conn
+        .execute(
+            "UPDATE kg_relations SET relation = ?1, updated_at = ?2 WHERE session_id = ?3 AND id = ?4",
+            params![relation, now_ms() as i64, &session_id, &relation_id],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    if changed == 0 {
+        return Err(not_found("Relation", &relation_id));
+    }
+    touch_session(&conn, &session_id)?;
+    graph_json(&conn, &session_id)
+}
+
+#[tauri::command]
+pub fn knowledge_graph_relation_delete(
+    app: tauri::AppHandle,
+    session_id: String,
+    relation_id: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    conn.execute(
+        "DELETE FROM kg_relations WHERE session_id = ?1 AND id = ?2",
+        params![&session_id, &relation_id],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    touch_session(&conn, &session_id)?;
+    graph_json(&conn, &session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE kg_entities (
+              id TEXT PRIMARY KEY, session_id TEXT NOT NULL, name TEXT NOT NULL,
+              kind TEXT NOT NULL DEFAULT 'other', description TEXT,
+              created_at INTEGER NOT NULL DEFAULT 0, updated_at INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE kg_relations (
+              id TEXT PRIMARY KEY, session_id TEXT NOT NULL, source_id TEXT NOT NULL,
+              target_id TEXT NOT NULL, relation TEXT NOT NULL, message_ids TEXT NOT NULL DEFAULT '[]',
+              created_at INTEGER NOT NULL DEFAULT 0, updated_at INTEGER NOT NULL DEFAULT 0
+            );
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn relations_merge_evidence_and_subgraph_follows_edges() {
+        let conn = test_conn();
+        upsert_entity(&conn, "s1", "Mara", Some("person"), Some("A smith")).unwrap();
+        record_relation(&conn, "s1", "mara", "Sister of", "Lena", &["m1".into()]).unwrap();
+        let again = record_relation(
+            &conn,
+            "s1",
+            "Mara",
+            "sister_of",
+            "Lena",
+            &["m1".into(), "m2".into()],
+        )
+        .unwrap();
+        assert_eq!(again.message_ids, vec!["m1", "m2"]);
+        record_relation(&conn, "s1", "Lena", "lives_in", "Oakvale", &[]).unwrap();
+        record_relation(&conn, "s1", "Oakvale", "part_of", "The North", &[]).unwrap();
+
+        let graph = read_graph(&conn, "s1").unwrap();
+        assert_eq!(graph.entities.len(), 4);
+        assert_eq!(graph.relations.len(), 3);
+        let mara = graph.entities.iter().find(|e| e.name == "Mara").unwrap();
+        assert_eq!(mara.kind, "person");
+
+        let seeds = mentioned_entity_ids(&graph, "Who is MARA's sister? Not Maran.");
+        assert_eq!(seeds, vec![mara.id.clone()]);
+        let around = subgraph(&graph, &seeds, 1);
+        let names: Vec<&str> = around.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Lena", "Mara"]);
+        assert!(format_graph(&around).contains("- Mara --sister_of--> Lena"));
+        assert_eq!(subgraph(&graph, &seeds, 2).entities.len(), 3);
+    }
+
+    #[test]
+    fn replace_graph_copies_ids_and_drops_stale_rows() {
+        let source = test_conn();
+        record_relation(&source, "s1", "Mara", "sister_of", "Lena", &["m1".into()]).unwrap();
+        let graph = read_graph(&source, "s1").unwrap();
+
+        let target = test_conn();
+        upsert_entity(&target, "s1", "Stale", None, None).unwrap();
+        upsert_entity(&target, "s2", "Other", None, None).unwrap();
+        replace_graph(&target, "s1", &graph).unwrap();
+
+        let copied = read_graph(&target, "s1").unwrap();
+        assert_eq!(copied.entities, graph.entities);
+        assert_eq!(copied.relations, graph.relations);
+        assert_eq!(read_graph(&target, "s2").unwrap().entities.len(), 1);
+    }
+}
@@ -5,6 +5,9 @@ use std::collections::BTreeMap;
 use uuid::Uuid;
 
 use super::db::DbConnection;
+use super::lorebook_formats::{
+    detect_lorebook_format, lorebook_format_info, parse_lorebook_import,
+};
 use crate::utils::now_millis;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,14 +102,6 @@ struct WorldInfoExportEntry {
     character_filter: Option<JsonValue>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-struct WorldInfoImport {
-    name: String,
-    #[serde(default)]
-    entries: JsonValue,
-}
-
 impl LorebookEntry {
     fn from_row(row: &Row) -> rusqlite::Result<Self> {
         let keywords_json: String = row.get(5)?;
@@ -611,7 +606,7 @@ fn value_to_string_list(value: Option<&JsonValue>) -> Vec<String> {
         .unwrap_or_default()
 }
 
-fn parse_world_info_entries(entries_value: &JsonValue) -> Vec<LorebookEntry> {
+pub(super) fn parse_world_info_entries(entries_value: &JsonValue) -> Vec<LorebookEntry> {
     let entries: Vec<(Option<i64>, &JsonValue)> = if let Some(map) = entries_value.as_object() {
         map.iter()
             .map(|(key, value)| (key.parse::<i64>().ok(), value))
@@ -956,17 +951,55 @@ pub fn lorebook_export(app: tauri::AppHandle, lorebook_id: String) -> Result<Str
     })
 }
 
+fn parse_lorebook_import_json(
+    import_json: &str,
+    fallback_name: Option<&str>,
+) -> Result<super::lorebook_formats::ParsedLorebookImport, String> {
+    let raw_value: JsonValue = serde_json::from_str(import_json).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Invalid lorebook import JSON: {}", e),
+        )
+    })?;
+    parse_lorebook_import(&raw_value, fallback_name)
+}
+
 #[tauri::command]
-pub fn lorebook_import(app: tauri::AppHandle, import_json: String) -> Result<String, String> {
-    let parsed: WorldInfoImport = serde_json::from_str(&import_json).map_err(|e| {
+pub fn lorebook_detect_format(import_json: String) -> Result<String, String> {
+    let raw_value: JsonValue = serde_json::from_str(&import_json).map_err(|e| {
         crate::utils::err_msg(
             module_path!(),
             line!(),
             format!("Invalid lorebook import JSON: {}", e),
         )
     })?;
+    let format = detect_lorebook_format(&raw_value)
+        .ok_or_else(|| "Unsupported lorebook file format".to_string())?;
+    serde_json::to_string(&lorebook_format_info(format))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Parse an import file without writing anything, listing fields we cannot represent.
+#[tauri::command]
+pub fn lorebook_import_preview(
+    import_json: String,
+    fallback_name: Option<String>,
+) -> Result<String, String> {
+    let parsed = parse_lorebook_import_json(&import_json, fallback_name.as_deref())?;
+    serde_json::to_string(&parsed)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+#[tauri::command]
+pub fn lorebook_import(
+    app: tauri::AppHandle,
+    import_json: String,
+    fallback_name: Option<String>,
+) -> Result<String, String> {
+    let parsed = parse_lorebook_import_json(&import_json, fallback_name.as_deref())?;
 
-    let mut parsed_entries = parse_world_info_entries(&parsed.entries);
+    let mut parsed_entries = parsed.entries;
     let now = now_millis()? as i64;
     let lorebook = Lorebook {
         id: Uuid::new_v4().to_string(),
@@ -0,0 +1,532 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use super::lorebook::{parse_world_info_entries, LorebookEntry};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LorebookFileFormat {
+    WorldInfo,
+    NovelAi,
+    Agnai,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LorebookFormatInfo {
+    pub id: LorebookFileFormat,
+    pub label: String,
+    pub extension: String,
+}
+
+pub fn lorebook_format_info(format: LorebookFileFormat) -> LorebookFormatInfo {
+    match format {
+        LorebookFileFormat::WorldInfo => LorebookFormatInfo {
+            id: format,
+            label: "SillyTavern World Info".to_string(),
+            extension: ".json".to_string(),
+        },
+        LorebookFileFormat::NovelAi => LorebookFormatInfo {
+            id: format,
+            label: "NovelAI Lorebook".to_string(),
+            extension: ".lorebook".to_string(),
+        },
+        LorebookFileFormat::Agnai => LorebookFormatInfo {
+            id: format,
+            label: "Agnai Memory Book".to_string(),
+            extension: ".json".to_string(),
+        },
+    }
+}
+
+/// A field present in the source file that has no equivalent on `LorebookEntry`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmappedLorebookField {
+    pub path: String,
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedLorebookImport {
+    pub format: LorebookFileFormat,
+    pub name: String,
+    pub entries: Vec<LorebookEntry>,
+    pub unmapped_fields: Vec<UnmappedLorebookField>,
+    pub warnings: Vec<String>,
+}
+
+const DEFAULT_NOVELAI_NAME: &str = "NovelAI Lorebook";
+const DEFAULT_AGNAI_NAME: &str = "Agnai Memory Book";
+const DEFAULT_WORLD_INFO_NAME: &str = "Imported lorebook";
+
+// Paths are relative to the lorebook root ("name") or to a single entry ("contextConfig.prefix").
+const WORLD_INFO_ROOT_MAPPED: &[&str] = &["name", "entries"];
+const WORLD_INFO_ROOT_IGNORED: &[&str] = &["extensions", "is_creation", "originalData"];
+const WORLD_INFO_ENTRY_MAPPED: &[&str] = &[
+    "keys",
+    "key",
+    "name",
+    "content",
+    "enabled",
+    "disable",
+    "insertion_order",
+    "displayIndex",
+    "constant",
+    "case_sensitive",
+    "priority",
+    "order",
+];
+const WORLD_INFO_ENTRY_IGNORED: &[&str] = &["uid", "id", "comment", "extensions"];
+
+const NOVELAI_ROOT_MAPPED: &[&str] = &["entries"];
+const NOVELAI_ROOT_IGNORED: &[&str] = &["lorebookVersion"];
+const NOVELAI_ENTRY_MAPPED: &[&str] = &[
+    "text",
+    "displayName",
+    "keys",
+    "enabled",
+    "forceActivation",
+    "contextConfig.prefix",
+    "contextConfig.suffix",
+    "contextConfig.budgetPriority",
+];
+const NOVELAI_ENTRY_IGNORED: &[&str] = &["id", "lastUpdatedAt"];
+
+const AGNAI_ROOT_MAPPED: &[&str] = &["name", "entries"];
+const AGNAI_ROOT_IGNORED: &[&str] = &["kind", "_id", "userId"];
+const AGNAI_ENTRY_MAPPED: &[&str] = &["name", "entry", "keywords", "priority", "weight", "enabled"];
+const AGNAI_ENTRY_IGNORED: &[&str] = &["id", "_id"];
+
+fn entry_values(entries_value: Option<&JsonValue>) -> Vec<&JsonValue> {
+    match entries_value {
+        Some(JsonValue::Array(list)) => list.iter().collect(),
+        Some(JsonValue::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn first_entry_has(value: &JsonValue, fields: &[&str]) -> bool {
+    entry_values(value.get("entries"))
+        .first()
+        .and_then(|entry| entry.as_object())
+        .map(|obj| fields.iter().all(|field| obj.contains_key(*field)))
+        .unwrap_or(false)
+}
+
+pub fn detect_lorebook_format(value: &JsonValue) -> Option<LorebookFileFormat> {
+    if !value.is_object() {
+        return None;
+    }
+
+    if value.get("lorebookVersion").is_some() || first_entry_has(value, &["text", "keys"]) {
+        return Some(LorebookFileFormat::NovelAi);
+    }
+
+    if value.get("kind").and_then(|v| v.as_str()) == Some("memory")
+        || first_entry_has(value, &["entry", "keywords"])
+    {
+        return Some(LorebookFileFormat::Agnai);
+    }
+
+    match value.get("entries") {
+        Some(JsonValue::Object(_)) | Some(JsonValue::Array(_)) => {
+            Some(LorebookFileFormat::WorldInfo)
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_lorebook_import(
+    value: &JsonValue,
+    fallback_name: Option<&str>,
+) -> Result<ParsedLorebookImport, String> {
+    let format = detect_lorebook_format(value)
+        .ok_or_else(|| "Unsupported lorebook file format".to_string())?;
+
+    let fallback_name = fallback_name
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty());
+
+    let mut warnings = Vec::new();
+    let (name, entries, unmapped_fields) = match format {
+        LorebookFileFormat::WorldInfo => {
+            let name = string_field(value, "name")
+                .or_else(|| fallback_name.map(str::to_string))
+                .unwrap_or_else(|| DEFAULT_WORLD_INFO_NAME.to_string());
+            let entries =
+                parse_world_info_entries(value.get("entries").unwrap_or(&JsonValue::Null));
+            let unmapped = collect_unmapped_fields(
+                value,
+                (WORLD_INFO_ROOT_MAPPED, WORLD_INFO_ROOT_IGNORED),
+                (WORLD_INFO_ENTRY_MAPPED, WORLD_INFO_ENTRY_IGNORED),
+            );
+            (name, entries, unmapped)
+        }
+        LorebookFileFormat::NovelAi => {
+            let name = fallback_name.unwrap_or(DEFAULT_NOVELAI_NAME).to_string();
+            let entries = parse_novelai_entries(value, &mut warnings);
+            let unmapped = collect_unmapped_fields(
+                value,
+                (NOVELAI_ROOT_MAPPED, NOVELAI_ROOT_IGNORED),
+                (NOVELAI_ENTRY_MAPPED, NOVELAI_ENTRY_IGNORED),
+            );
+            (name, entries, unmapped)
+        }
+        LorebookFileFormat::Agnai => {
+            let name = string_field(value, "name")
+                .or_else(|| fallback_name.map(str::to_string))
+                .unwrap_or_else(|| DEFAULT_AGNAI_NAME.to_string());
+            let entries = parse_agnai_entries(value);
+            let unmapped = collect_unmapped_fields(
+                value,
+                (AGNAI_ROOT_MAPPED, AGNAI_ROOT_IGNORED),
+                (AGNAI_ENTRY_MAPPED, AGNAI_ENTRY_IGNORED),
+            );
+            (name, entries, unmapped)
+        }
+    };
+
+    Ok(ParsedLorebookImport {
+        format,
+        name,
+        entries,
+        unmapped_fields,
+        warnings,
+    })
+}
+
+fn string_field(value: &JsonValue, field: &str) -> Option<String> {
+    value
+        .get(field)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn string_list(value: Option<&JsonValue>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn number_field(value: Option<&JsonValue>) -> Option<i32> {
+    value
+        .and_then(|v| v.as_i64().or_else(|| v.as_f64().map(|f| f.round() as i64)))
+        .map(|n| n.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+}
+
+fn new_entry(title: String, content: String, keywords: Vec<String>) -> LorebookEntry {
+    LorebookEntry {
+        id: Uuid::new_v4().to_string(),
+        lorebook_id: String::new(),
+        title,
+        enabled: true,
+        always_active: false,
+        keywords,
+        case_sensitive: false,
+        content,
+        priority: 0,
+        display_order: 0,
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+/// NovelAI keys wrapped in slashes are regular expressions; we only match plain keywords.
+fn is_regex_key(key: &str) -> bool {
+    key.len() > 2 && key.starts_with('/') && key[1..].contains('/')
+}
+
+fn parse_novelai_entries(value: &JsonValue, warnings: &mut Vec<String>) -> Vec<LorebookEntry> {
+    let mut parsed: Vec<(i32, usize, LorebookEntry)> = Vec::new();
+
+    for (index, item) in entry_values(value.get("entries")).into_iter().enumerate() {
+        let Some(obj) = item.as_object() else {
+            continue;
+        };
+        let text = obj.get("text").and_then(|v| v.as_str()).unwrap_or("");
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let keys = string_list(obj.get("keys"));
+        let title = string_field(item, "displayName")
+            .or_else(|| keys.first().cloned())
+            .unwrap_or_else(|| format!("Entry {}", index + 1));
+
+        for key in keys.iter().filter(|key| is_regex_key(key)) {
+            warnings.push(format!(
+                "Entry \"{}\" uses regex key {} which will be matched as plain text",
+                title, key
+            ));
+        }
+
+        let context_config = obj.get("contextConfig");
+        let prefix = context_config
+            .and_then(|c| c.get("prefix"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let suffix = context_config
+            .and_then(|c| c.get("suffix"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let mut content = String::new();
+        if !prefix.trim().is_empty() {
+            content.push_str(prefix);
+        }
+        content.push_str(text);
+        if !suffix.trim().is_empty() {
+            content.push_str(suffix);
+        }
+
+        let budget_priority =
+            number_field(context_config.and_then(|c| c.get("budgetPriority"))).unwrap_or(400);
+
+        let mut entry = new_entry(title, content.trim().to_string(), keys);
+        entry.enabled = obj.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true);
+        entry.always_active = obj
+            .get("forceActivation")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        entry.priority = budget_priority;
+        parsed.push((budget_priority, index, entry));
+    }
+
+    // NovelAI inserts higher budget priorities first.
+    parsed.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    parsed
+        .into_iter()
+        .enumerate()
+        .map(|(order, (_, _, mut entry))| {
+            entry.display_order = order as i32;
+            entry
+        })
+        .collect()
+}
+
+fn parse_agnai_entries(value: &JsonValue) -> Vec<LorebookEntry> {
+    let mut parsed: Vec<(i32, usize, LorebookEntry)> = Vec::new();
+
+    for (index, item) in entry_values(value.get("entries")).into_iter().enumerate() {
+        let Some(obj) = item.as_object() else {
+            continue;
+        };
+        let text = obj.get("entry").and_then(|v| v.as_str()).unwrap_or("");
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let keys = string_list(obj.get("keywords"));
+        let title = string_field(item, "name")
+            .or_else(|| keys.first().cloned())
+            .unwrap_or_else(|| format!("Entry {}", index + 1));
+
+        let mut entry = new_entry(title, text.trim().to_string(), keys);
+        entry.enabled = obj.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true);
+        entry.priority = number_field(obj.get("priority")).unwrap_or(0);
+        let weight = number_field(obj.get("weight")).unwrap_or(0);
+        parsed.push((weight, index, entry));
+    }
+
+    // Agnai places higher weights closer to the end of the prompt.
+    parsed.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    parsed
+        .into_iter()
+        .enumerate()
+        .map(|(order, (_, _, mut entry))| {
+            entry.display_order = order as i32;
+            entry
+        })
+        .collect()
+}
+
+/// Values that carry no information (null, false, 0, empty strings and containers) are
+/// not reported, since most exporters write every field with its default.
+fn is_meaningful(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => false,
+        JsonValue::Bool(b) => *b,
+        JsonValue::Number(n) => n.as_f64().map(|f| f != 0.0).unwrap_or(true),
+        JsonValue::String(s) => !s.trim().is_empty(),
+        JsonValue::Array(items) => !items.is_empty(),
+        JsonValue::Object(map) => !map.is_empty(),
+    }
+}
+
+fn walk_unmapped(
+    value: &JsonValue,
+    relative: &str,
+    display_prefix: &str,
+    known: (&[&str], &[&str]),
+    counts: &mut BTreeMap<String, usize>,
+) {
+    let Some(obj) = value.as_object() else {
+        return;
+    };
+    let (mapped, ignored) = known;
+
+    for (key, child) in obj {
+        let path = if relative.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", relative, key)
+        };
+        if mapped.contains(&path.as_str()) || ignored.contains(&path.as_str()) {
+            continue;
+        }
+
+        let nested_prefix = format!("{}.", path);
+        let has_known_children = mapped
+            .iter()
+            .chain(ignored.iter())
+            .any(|known_path| known_path.starts_with(&nested_prefix));
+        if has_known_children && child.is_object() {
+            walk_unmapped(child, &path, display_prefix, known, counts);
+            continue;
+        }
+
+        if is_meaningful(child) {
+            *counts
+                .entry(format!("{}{}", display_prefix, path))
+                .or_insert(0) += 1;
+        }
+    }
+}
+
+fn collect_unmapped_fields(
+    value: &JsonValue,
+    root_known: (&[&str], &[&str]),
+    entry_known: (&[&str], &[&str]),
+) -> Vec<UnmappedLorebookField> {
+    let mut counts = BTreeMap::new();
+    walk_unmapped(value, "", "", root_known, &mut counts);
+    for entry in entry_values(value.get("entries")) {
+        walk_unmapped(entry, "", "entries[].", entry_known, &mut counts);
+    }
+
+    counts
+        .into_iter()
+        .map(|(path, occurrences)| UnmappedLorebookField { path, occurrences })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_formats() {
+        let novelai = json!({ "lorebookVersion": 5, "entries": [] });
+        let agnai = json!({ "kind": "memory", "name": "Book", "entries": [] });
+        let world_info = json!({ "name": "Book", "entries": { "0": { "content": "x" } } });
+
+        assert_eq!(
+            detect_lorebook_format(&novelai),
+            Some(LorebookFileFormat::NovelAi)
+        );
+        assert_eq!(
+            detect_lorebook_format(&agnai),
+            Some(LorebookFileFormat::Agnai)
+        );
+        assert_eq!(
+            detect_lorebook_format(&world_info),
+            Some(LorebookFileFormat::WorldInfo)
+        );
+        assert_eq!(detect_lorebook_format(&json!({ "name": "x" })), None);
+    }
+
+    #[test]
+    fn parses_novelai_entries_and_reports_unmapped() {
+        let value = json!({
+            "lorebookVersion": 5,
+            "entries": [
+                {
+                    "text": "Low priority lore",
+                    "displayName": "Low",
+                    "keys": ["low"],
+                    "enabled": false,
+                    "contextConfig": { "budgetPriority": 100, "tokenBudget": 2048, "prefix": "" }
+                },
+                {
+                    "text": "Mara is a smith.",
+                    "displayName": "Mara",
+                    "keys": ["mara", "/smith(s)?/i"],
+                    "forceActivation": true,
+                    "searchRange": 1000,
+                    "contextConfig": { "budgetPriority": 500, "prefix": "[Lore] " }
+                }
+            ]
+        });
+
+        let parsed = parse_lorebook_import(&value, Some("My Book")).unwrap();
+        assert_eq!(parsed.format, LorebookFileFormat::NovelAi);
+        assert_eq!(parsed.name, "My Book");
+        assert_eq!(parsed.entries.len(), 2);
+
+        let first = &parsed.entries[0];
+        assert_eq!(first.title, "Mara");
+        assert_eq!(first.content, "[Lore] Mara is a smith.");
+        assert!(first.always_active);
+        assert_eq!(first.priority, 500);
+        assert_eq!(first.display_order, 0);
+        assert!(!parsed.entries[1].enabled);
+        assert_eq!(parsed.warnings.len(), 1);
+
+        let paths: Vec<&str> = parsed
+            .unmapped_fields
+            .iter()
+            .map(|field| field.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "entries[].contextConfig.tokenBudget",
+                "entries[].searchRange"
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_agnai_entries_by_weight() {
+        let value = json!({
+            "kind": "memory",
+            "name": "Agnai Book",
+            "description": "Shared lore",
+            "entries": [
+                { "name": "Late", "entry": "Placed last", "keywords": ["b"], "priority": 1, "weight": 10, "enabled": true },
+                { "name": "Early", "entry": "Placed first", "keywords": ["a"], "priority": 5, "weight": 1, "enabled": true }
+            ]
+        });
+
+        let parsed = parse_lorebook_import(&value, None).unwrap();
+        assert_eq!(parsed.format, LorebookFileFormat::Agnai);
+        assert_eq!(parsed.name, "Agnai Book");
+        assert_eq!(parsed.entries[0].title, "Early");
+        assert_eq!(parsed.entries[0].priority, 5);
+        assert_eq!(parsed.entries[1].title, "Late");
+        assert_eq!(parsed.unmapped_fields.len(), 1);
+        assert_eq!(parsed.unmapped_fields[0].path, "description");
+    }
+
+    #[test]
+    fn unnamed_world_info_falls_back_to_file_name() {
+        let value = json!({ "entries": {} });
+        let named = parse_lorebook_import(&value, Some("  dragons  ")).unwrap();
+        assert_eq!(named.name, "dragons");
+        let unnamed = parse_lorebook_import(&value, None).unwrap();
+        assert_eq!(unnamed.name, "Imported lorebook");
+    }
+}
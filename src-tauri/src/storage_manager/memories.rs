@@ -0,0 +1,1271 @@
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+
+use super::db::now_ms;
+use super::group_sessions::MemoryEmbedding;
+use crate::chat_manager::types::MemoryProvenance;
+
+/// Which session a row in the `memories` table belongs to. `Character` rows form the
+/// character's long-term store, shared by all of that character's chats.
+#[derive(Debug, Clone, Copy)]
+pub enum MemoryOwner<'a> {
+    Session(&'a str),
+    GroupSession(&'a str),
+    Character(&'a str),
+}
+
+impl<'a> MemoryOwner<'a> {
+    pub fn id(&self) -> &'a str {
+        match self {
+            MemoryOwner::Session(id)
+            | MemoryOwner::GroupSession(id)
+            | MemoryOwner::Character(id) => id,
+        }
+    }
+
+    fn owner_column(&self) -> &'static str {
+        match self {
+            MemoryOwner::Session(_) => "session_id",
+            MemoryOwner::GroupSession(_) => "group_session_id",
+            MemoryOwner::Character(_) => "character_id",
+        }
+    }
+
+    fn owner_table(&self) -> &'static str {
+        match self {
+            MemoryOwner::Session(_) => "sessions",
+            MemoryOwner::GroupSession(_) => "group_sessions",
+            MemoryOwner::Character(_) => "characters",
+        }
+    }
+}
+
+/// Who caused a memory write; recorded with each `memory_events` row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryActor {
+    User,
+    MemoryManager,
+}
+
+impl MemoryActor {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MemoryActor::User => "user",
+            MemoryActor::MemoryManager => "memory_manager",
+        }
+    }
+}
+
+const MEMORY_COLUMNS: &str = "id, text, category, embedding, token_count, is_cold, is_pinned, importance_score, access_count, last_accessed_at, created_at, provenance, embedding_model";
+
+/// `meta` key naming the embedding backend query vectors are computed with. Kept current by
+/// the embedding model; semantic retrieval only scores rows embedded by that backend.
+pub const QUERY_EMBEDDING_MODEL_META_KEY: &str = "embedding_query_backend";
+
+/// `meta` key naming the backend every stored vector has been re-embedded with.
+pub const ACTIVE_EMBEDDING_BACKEND_META_KEY: &str = "embedding_backend";
+
+/// Packs an embedding as little-endian f32s.
+pub fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(embedding.len() * 4);
+    for value in embedding {
+        blob.extend_from_slice(&value.to_le_bytes());
+    }
+    blob
+}
+
+pub fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn row_to_memory(row: &rusqlite::Row<'_>) -> rusqlite::Result<MemoryEmbedding> {
+    let blob: Option<Vec<u8>> = row.get(3)?;
+    Ok(MemoryEmbedding {
+        id: row.get(0)?,
+        text: row.get(1)?,
+        category: row.get(2)?,
+        embedding: blob.map(|b| blob_to_embedding(&b)).unwrap_or_default(),
+        token_count: row.get(4)?,
+        is_cold: row.get::<_, i64>(5)? != 0,
+        is_pinned: row.get::<_, i64>(6)? != 0,
+        importance_score: row.get::<_, f64>(7)? as f32,
+        access_count: row.get(8)?,
+        last_accessed_at: row.get(9)?,
+        created_at: row.get(10)?,
+        provenance: row
+            .get::<_, Option<String>>(11)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        embedding_model: row.get(12)?,
+        match_score: None,
+        score_breakdown: None,
+    })
+}
+
+fn query_memories(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    filter: &str,
+) -> Result<Vec<MemoryEmbedding>, String> {
+    let sql = format!(
+        "SELECT {} FROM memories WHERE {} = ?1{} ORDER BY position ASC, row_id ASC",
+        MEMORY_COLUMNS,
+        owner.owner_column(),
+        filter
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map(params![owner.id()], row_to_memory)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row.map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?);
+    }
+    Ok(out)
+}
+
+/// All memories of an owner, in their stored order.
+pub fn read_memories(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+) -> Result<Vec<MemoryEmbedding>, String> {
+    query_memories(conn, owner, "")
+}
+
+/// Only the rows semantic retrieval can score: hot (or pinned) and embedded by the backend
+/// queries use. Rows still waiting to be re-embedded after a backend switch, and untagged
+/// rows whose backend is unknown, are left out.
+pub fn read_retrievable_memories(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+) -> Result<Vec<MemoryEmbedding>, String> {
+    let filter = format!(
+        " AND (is_cold = 0 OR is_pinned = 1) AND embedding_dim > 0
+          AND embedding_model = COALESCE(
+            (SELECT value FROM meta WHERE key = '{}'), embedding_model)",
+        QUERY_EMBEDDING_MODEL_META_KEY
+    );
+    query_memories(conn, owner, &filter)
+}
+
+/// Turns free text into an FTS5 query: every word of 3+ alphanumeric chars, quoted and
+/// OR-ed together so punctuation or FTS syntax in the input can't break the MATCH.
+fn fts_match_query(query: &str) -> Option<String> {
+    let mut seen = HashSet::new();
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(|word| word.to_lowercase())
+        .filter(|word| seen.insert(word.clone()))
+        .map(|word| format!("\"{}\"", word))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+/// BM25 keyword ranking over all of an owner's memories, cold ones included. Returns
+/// `(memory id, score)` best first; scores are positive, higher meaning more relevant.
+pub fn bm25_rank_memories(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<(String, f32)>, String> {
+    let Some(match_query) = fts_match_query(query) else {
+        return Ok(Vec::new());
+    };
+    let sql = format!(
+        "SELECT m.id, bm25(memories_fts) AS rank FROM memories_fts \
+         JOIN memories m ON m.row_id = memories_fts.rowid \
+         WHERE memories_fts MATCH ?1 AND m.{} = ?2 \
+         ORDER BY rank ASC LIMIT ?3",
+        owner.owner_column()
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map(params![match_query, owner.id(), limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (id, rank) =
+            row.map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        // SQLite's bm25() is negated so that ascending order is best-first.
+        out.push((id, (-rank) as f32));
+    }
+    Ok(out)
+}
+
+/// Memories as camelCase JSON objects, matching the old `memory_embeddings` column layout.
+pub fn read_memory_values(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+) -> Result<Vec<JsonValue>, String> {
+    read_memories(conn, owner)?
+        .into_iter()
+        .map(|m| {
+            serde_json::to_value(m)
+                .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+        })
+        .collect()
+}
+
+pub fn read_memories_json(conn: &Connection, owner: MemoryOwner<'_>) -> Result<String, String> {
+    let memories = read_memories(conn, owner)?;
+    serde_json::to_string(&memories)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Like [`read_memories_json`], but `None` when the owner has no rows so callers can fall
+/// back to the legacy column.
+pub fn stored_memories_json(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+) -> Result<Option<String>, String> {
+    let memories = read_memories(conn, owner)?;
+    if memories.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(&memories)
+        .map(Some)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+fn same_memory(a: &MemoryEmbedding, b: &MemoryEmbedding) -> bool {
+    a.text == b.text
+        && a.category == b.category
+        && a.embedding == b.embedding
+        && a.embedding_model == b.embedding_model
+        && a.token_count == b.token_count
+        && a.is_cold == b.is_cold
+        && a.is_pinned == b.is_pinned
+        && a.importance_score == b.importance_score
+        && a.access_count == b.access_count
+        && a.last_accessed_at == b.last_accessed_at
+        && a.created_at == b.created_at
+        && a.provenance == b.provenance
+}
+
+/// Memory ids are short and may collide; keep them unique per owner.
+fn unique_memory_id(id: &str, position: usize, seen: &mut HashSet<String>) -> String {
+    if !id.is_empty() && seen.insert(id.to_string()) {
+        return id.to_string();
+    }
+    let mut candidate = if id.is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        format!("{}-{}", id, position)
+    };
+    while !seen.insert(candidate.clone()) {
+        candidate = uuid::Uuid::new_v4().to_string();
+    }
+    candidate
+}
+
+/// One `memory_events` row, the audit log behind the memory timeline. `kind` is one of
+/// created, merged, restored, edited, pinned, unpinned, decayed, demoted, promoted (cold
+/// to hot), shared (copied to the character) or deleted.
+struct MemoryEvent<'a> {
+    memory_id: &'a str,
+    kind: &'a str,
+    text: Option<&'a str>,
+    previous_text: Option<&'a str>,
+    details: Option<JsonValue>,
+}
+
+fn log_memory_event(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    actor: MemoryActor,
+    event: MemoryEvent<'_>,
+) -> Result<(), String> {
+    let sql = format!(
+        "INSERT INTO memory_events (memory_id, {}, kind, actor, text, previous_text, details, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        owner.owner_column()
+    );
+    conn.execute(
+        &sql,
+        params![
+            event.memory_id,
+            owner.id(),
+            event.kind,
+            actor.as_str(),
+            event.text,
+            event.previous_text,
+            event.details.map(|d| d.to_string()),
+            now_ms() as i64,
+        ],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+fn has_memory_events(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memory_id: &str,
+) -> Result<bool, String> {
+    let sql = format!(
+        "SELECT EXISTS(SELECT 1 FROM memory_events WHERE {} = ?1 AND memory_id = ?2)",
+        owner.owner_column()
+    );
+    conn.query_row(&sql, params![owner.id(), memory_id], |r| r.get(0))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// The events a write of `new` over `old` (None for a new row) amounts to.
+fn memory_change_events<'a>(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    old: Option<&'a MemoryEmbedding>,
+    new: &'a MemoryEmbedding,
+    id: &'a str,
+) -> Result<Vec<MemoryEvent<'a>>, String> {
+    let mut events = Vec::new();
+    let Some(old) = old else {
+        let kind = if has_memory_events(conn, owner, id)? {
+            "restored"
+        } else if new.provenance.as_ref().map(|p| p.origin.as_str()) == Some("merge") {
+            "merged"
+        } else {
+            "created"
+        };
+        events.push(MemoryEvent {
+            memory_id: id,
+            kind,
+            text: Some(&new.text),
+            previous_text: None,
+            details: new
+                .provenance
+                .as_ref()
+                .and_then(|p| serde_json::to_value(p).ok()),
+        });
+        return Ok(events);
+    };
+
+    if old.text != new.text || old.category != new.category {
+        events.push(MemoryEvent {
+            memory_id: id,
+            kind: "edited",
+            text: Some(&new.text),
+            previous_text: Some(&old.text),
+            details: (old.category != new.category).then(|| {
+                serde_json::json!({ "category": new.category, "previousCategory": old.category })
+            }),
+        });
+    }
+    if old.is_pinned != new.is_pinned {
+        events.push(MemoryEvent {
+            memory_id: id,
+            kind: if new.is_pinned { "pinned" } else { "unpinned" },
+            text: None,
+            previous_text: None,
+            details: None,
+        });
+    }
+    if old.is_cold != new.is_cold {
+        // Going cold because importance ran down is decay; anything else is a demotion.
+        let kind = if !new.is_cold {
+            "promoted"
+        } else if new.importance_score < old.importance_score {
+            "decayed"
+        } else {
+            "demoted"
+        };
+        events.push(MemoryEvent {
+            memory_id: id,
+            kind,
+            text: None,
+            previous_text: None,
+            details: Some(serde_json::json!({ "importanceScore": new.importance_score })),
+        });
+    }
+    Ok(events)
+}
+
+/// `actor` is None for imports, which copy memories rather than change them and so log nothing.
+fn write_memories_inner(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memories: &[MemoryEmbedding],
+    actor: Option<MemoryActor>,
+) -> Result<(), String> {
+    let existing: HashMap<String, (usize, MemoryEmbedding)> = read_memories(conn, owner)?
+        .into_iter()
+        .enumerate()
+        .map(|(position, m)| (m.id.clone(), (position, m)))
+        .collect();
+
+    let column = owner.owner_column();
+    let update_sql = format!(
+        "UPDATE memories SET position = ?1, text = ?2, category = ?3, embedding = ?4, embedding_dim = ?5,
+                token_count = ?6, is_cold = ?7, is_pinned = ?8, importance_score = ?9, access_count = ?10,
+                last_accessed_at = ?11, created_at = ?12, updated_at = ?13, provenance = ?16,
+                embedding_model = ?17
+         WHERE {} = ?14 AND id = ?15",
+        column
+    );
+    let insert_sql = format!(
+        "INSERT INTO memories (position, text, category, embedding, embedding_dim, token_count, is_cold,
+                is_pinned, importance_score, access_count, last_accessed_at, created_at, updated_at, {}, id,
+                provenance, embedding_model)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        column
+    );
+
+    let now = now_ms() as i64;
+    let mut changed = false;
+    let mut seen: HashSet<String> = HashSet::new();
+    for (position, memory) in memories.iter().enumerate() {
+        let id = unique_memory_id(&memory.id, position, &mut seen);
+        let previous = existing.get(&id);
+        if let Some((old_position, old)) = previous {
+            if *old_position == position && same_memory(old, memory) {
+                continue;
+            }
+        }
+        changed = true;
+
+        let blob = if memory.embedding.is_empty() {
+            None
+        } else {
+            Some(embedding_to_blob(&memory.embedding))
+        };
+        let sql = if previous.is_some() {
+            &update_sql
+        } else {
+            &insert_sql
+        };
+        conn.execute(
+            sql,
+            params![
+                position as i64,
+                &memory.text,
+                &memory.category,
+                blob,
+                memory.embedding.len() as i64,
+                memory.token_count,
+                memory.is_cold as i64,
+                memory.is_pinned as i64,
+                memory.importance_score as f64,
+                memory.access_count,
+                memory.last_accessed_at,
+                memory.created_at,
+                now,
+                owner.id(),
+                &id,
+                memory
+                    .provenance
+                    .as_ref()
+                    .and_then(|p| serde_json::to_string(p).ok()),
+                memory
+                    .embedding_model
+                    .as_deref()
+                    .filter(|_| !memory.embedding.is_empty()),
+            ],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+        let Some(actor) = actor else {
+            continue;
+        };
+        for event in memory_change_events(conn, owner, previous.map(|(_, old)| old), memory, &id)? {
+            log_memory_event(conn, owner, actor, event)?;
+        }
+    }
+
+    // Originals of a merge are logged as merged into the new memory rather than deleted.
+    let merged_into: HashMap<&str, &str> = memories
+        .iter()
+        .filter_map(|m| m.provenance.as_ref().map(|p| (m.id.as_str(), p)))
+        .flat_map(|(into, p)| p.merged_from.iter().map(move |from| (from.as_str(), into)))
+        .collect();
+    let delete_sql = format!("DELETE FROM memories WHERE {} = ?1 AND id = ?2", column);
+    // In stored order, so the timeline lists removals the way the memories were listed.
+    let mut removed: Vec<(&String, &(usize, MemoryEmbedding))> = existing
+        .iter()
+        .filter(|(id, _)| !seen.contains(*id))
+        .collect();
+    removed.sort_by_key(|(_, (position, _))| *position);
+    for (id, (_, old)) in removed {
+        changed = true;
+        conn.execute(&delete_sql, params![owner.id(), id])
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        let Some(actor) = actor else {
+            continue;
+        };
+        let (kind, details) = match merged_into.get(id.as_str()) {
+            Some(into) => ("merged", Some(serde_json::json!({ "into": into }))),
+            None => ("deleted", None),
+        };
+        log_memory_event(
+            conn,
+            owner,
+            actor,
+            MemoryEvent {
+                memory_id: id,
+                kind,
+                text: None,
+                previous_text: Some(&old.text),
+                details,
+            },
+        )?;
+    }
+
+    // Sessions are touched when saved; a character's store is the only thing that changed,
+    // so bump the character for sync to pick it up.
+    if let (true, MemoryOwner::Character(character_id)) = (changed, owner) {
+        conn.execute(
+            "UPDATE characters SET updated_at = ?1 WHERE id = ?2",
+            params![now, character_id],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    Ok(())
+}
+
+/// Replaces an owner's memories with `memories`, touching only the rows that changed and
+/// logging each change to `memory_events` under `actor`.
+pub fn write_memories(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memories: &[MemoryEmbedding],
+    actor: MemoryActor,
+) -> Result<(), String> {
+    write_memories_in_savepoint(conn, owner, memories, Some(actor))
+}
+
+fn write_memories_in_savepoint(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memories: &[MemoryEmbedding],
+    actor: Option<MemoryActor>,
+) -> Result<(), String> {
+    // A savepoint rather than a transaction so this also works inside a caller's transaction.
+    conn.execute_batch("SAVEPOINT write_memories")
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    match write_memories_inner(conn, owner, memories, actor) {
+        Ok(()) => conn
+            .execute_batch("RELEASE write_memories")
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e)),
+        Err(err) => {
+            let _ = conn.execute_batch("ROLLBACK TO write_memories; RELEASE write_memories");
+            Err(err)
+        }
+    }
+}
+
+/// Parses memory entries, failing on the first one that isn't a valid memory: the callers
+/// replace the owner's whole set, so a dropped entry would be deleted.
+fn parse_memory_values(values: &[JsonValue]) -> Result<Vec<MemoryEmbedding>, String> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            MemoryEmbedding::deserialize(value).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Memory entry {} is invalid: {}", index, e),
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_memories_json(json: &str) -> Result<Vec<MemoryEmbedding>, String> {
+    let values: Vec<JsonValue> = serde_json::from_str(json).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Invalid memories JSON: {}", e),
+        )
+    })?;
+    parse_memory_values(&values)
+}
+
+/// [`write_memories`] for JSON coming from the frontend.
+pub fn write_memory_values(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    values: &[JsonValue],
+    actor: MemoryActor,
+) -> Result<(), String> {
+    write_memories(conn, owner, &parse_memory_values(values)?, actor)
+}
+
+/// Replaces an owner's memories with ones that already existed elsewhere (a legacy column, a
+/// backup, a sync peer). Nothing is logged: the timeline records changes, not copies.
+pub fn import_memories_json(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    json: &str,
+) -> Result<(), String> {
+    write_memories_in_savepoint(conn, owner, &parse_memories_json(json)?, None)
+}
+
+fn undo_memory_merge_inner(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memory_id: &str,
+) -> Result<bool, String> {
+    let table = owner.owner_table();
+    let events_json: Option<String> = conn
+        .query_row(
+            &format!("SELECT memory_tool_events FROM {} WHERE id = ?1", table),
+            params![owner.id()],
+            |r| r.get(0),
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let mut events: Vec<JsonValue> =
+        serde_json::from_str(events_json.as_deref().unwrap_or("[]")).unwrap_or_default();
+
+    let Some(action) = events
+        .iter_mut()
+        .rev()
+        .filter_map(|event| event.get_mut("actions").and_then(|a| a.as_array_mut()))
+        .flat_map(|actions| actions.iter_mut())
+        .find(|action| {
+            action.get("name").and_then(|v| v.as_str()) == Some("merge_memories")
+                && action.get("memoryId").and_then(|v| v.as_str()) == Some(memory_id)
+                && !action
+                    .get("undone")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+        })
+    else {
+        return Ok(false);
+    };
+
+    // Merges record only each original's id and text (older ones kept whole rows). Restored
+    // rows come back without a vector and are re-embedded by the caller.
+    let now = now_ms() as i64;
+    let originals: Vec<MemoryEmbedding> = action
+        .get("originals")
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| {
+                    let mut value = v.clone();
+                    let fields = value.as_object_mut()?;
+                    fields
+                        .entry("embedding")
+                        .or_insert_with(|| serde_json::json!([]));
+                    fields.entry("createdAt").or_insert_with(|| now.into());
+                    fields.entry("lastAccessedAt").or_insert_with(|| now.into());
+                    serde_json::from_value(value).ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(obj) = action.as_object_mut() {
+        obj.insert("undone".into(), JsonValue::Bool(true));
+        obj.insert("undoneAt".into(), JsonValue::from(now));
+    }
+
+    let mut memories = read_memories(conn, owner)?;
+    // The merged memory may have been deleted since; the originals still come back.
+    let position = memories
+        .iter()
+        .position(|m| m.id == memory_id)
+        .unwrap_or(memories.len());
+    if position < memories.len() {
+        memories.remove(position);
+    }
+    let present: HashSet<String> = memories.iter().map(|m| m.id.clone()).collect();
+    let restored: Vec<MemoryEmbedding> = originals
+        .into_iter()
+        .filter(|m| !present.contains(&m.id))
+        .collect();
+    memories.splice(position..position, restored);
+    write_memories(conn, owner, &memories, MemoryActor::User)?;
+
+    let texts: Vec<&str> = memories.iter().map(|m| m.text.as_str()).collect();
+    let memories_json = serde_json::to_string(&texts)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let events_json = serde_json::to_string(&events)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    conn.execute(
+        &format!(
+            "UPDATE {} SET memories = ?1, memory_tool_events = ?2, updated_at = ?3 WHERE id = ?4",
+            table
+        ),
+        params![memories_json, events_json, now_ms() as i64, owner.id()],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(true)
+}
+
+/// Reverses the latest `merge_memories` action that produced `memory_id`: the merged memory
+/// is replaced by the originals recorded with the action, and the action is marked undone.
+/// Returns false when there is no such (not yet undone) merge.
+pub fn undo_memory_merge(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memory_id: &str,
+) -> Result<bool, String> {
+    conn.execute_batch("SAVEPOINT undo_memory_merge")
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    match undo_memory_merge_inner(conn, owner, memory_id) {
+        Ok(undone) => conn
+            .execute_batch("RELEASE undo_memory_merge")
+            .map(|_| undone)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e)),
+        Err(err) => {
+            let _ = conn.execute_batch("ROLLBACK TO undo_memory_merge; RELEASE undo_memory_merge");
+            Err(err)
+        }
+    }
+}
+
+/// Adds the `source` session's `memory` to a character's long-term store. A memory whose
+/// text is already stored is not duplicated; the stored copy is returned instead.
+pub fn add_character_memory(
+    conn: &Connection,
+    character_id: &str,
+    source: MemoryOwner<'_>,
+    memory: MemoryEmbedding,
+    actor: MemoryActor,
+) -> Result<MemoryEmbedding, String> {
+    let owner = MemoryOwner::Character(character_id);
+    let mut memories = read_memories(conn, owner)?;
+    let text = memory.text.trim().to_lowercase();
+    if let Some(existing) = memories
+        .iter()
+        .find(|m| m.text.trim().to_lowercase() == text)
+    {
+        return Ok(existing.clone());
+    }
+
+    let now = now_ms() as i64;
+    let source_memory_id = memory.id.clone();
+    let origin = memory.provenance.clone().unwrap_or_default();
+    let mut shared = MemoryEmbedding {
+        is_cold: false,
+        importance_score: 1.0,
+        access_count: 0,
+        last_accessed_at: now,
+        provenance: Some(MemoryProvenance {
+            origin: "shared".to_string(),
+            merged_from: Vec::new(),
+            source_memory_id: Some(source_memory_id.clone()),
+            source_session_id: Some(source.id().to_string()),
+            ..origin
+        }),
+        ..memory
+    };
+    if shared.id.is_empty() || memories.iter().any(|m| m.id == shared.id) {
+        shared.id = uuid::Uuid::new_v4().to_string();
+    }
+    memories.push(shared.clone());
+    write_memories(conn, owner, &memories, actor)?;
+    log_memory_event(
+        conn,
+        source,
+        actor,
+        MemoryEvent {
+            memory_id: &source_memory_id,
+            kind: "shared",
+            text: None,
+            previous_text: None,
+            details: Some(serde_json::json!({
+                "characterId": character_id,
+                "characterMemoryId": shared.id,
+            })),
+        },
+    )?;
+    Ok(shared)
+}
+
+/// Copies the session memory `memory_id` into the character's long-term store.
+/// Returns `None` when the session has no such memory.
+pub fn share_memory_with_character(
+    conn: &Connection,
+    from: MemoryOwner<'_>,
+    memory_id: &str,
+    character_id: &str,
+    actor: MemoryActor,
+) -> Result<Option<MemoryEmbedding>, String> {
+    let Some(memory) = read_memories(conn, from)?
+        .into_iter()
+        .find(|m| m.id == memory_id)
+    else {
+        return Ok(None);
+    };
+    add_character_memory(conn, character_id, from, memory, actor).map(Some)
+}
+
+/// The `memory_events` of an owner, oldest first, as camelCase JSON. With `memory_id`, only
+/// that memory's events plus those of the memories it was merged from, recursively, so the
+/// timeline reaches back to the messages the merged facts came from.
+pub fn read_memory_timeline(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    memory_id: Option<&str>,
+) -> Result<Vec<JsonValue>, String> {
+    let sql = format!(
+        "SELECT id, memory_id, kind, actor, text, previous_text, details, created_at
+         FROM memory_events WHERE {} = ?1 ORDER BY created_at ASC, id ASC",
+        owner.owner_column()
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let rows = stmt
+        .query_map(params![owner.id()], |row| {
+            let details: Option<String> = row.get(6)?;
+            Ok(serde_json::json!({
+                "id": row.get::<_, i64>(0)?,
+                "memoryId": row.get::<_, String>(1)?,
+                "kind": row.get::<_, String>(2)?,
+                "actor": row.get::<_, String>(3)?,
+                "text": row.get::<_, Option<String>>(4)?,
+                "previousText": row.get::<_, Option<String>>(5)?,
+                "details": details
+                    .and_then(|d| serde_json::from_str::<JsonValue>(&d).ok())
+                    .unwrap_or(JsonValue::Null),
+                "createdAt": row.get::<_, i64>(7)?,
+            }))
+        })
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let mut events = Vec::new();
+    for row in rows {
+        events.push(row.map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?);
+    }
+    let Some(memory_id) = memory_id else {
+        return Ok(events);
+    };
+
+    let mut lineage: HashSet<String> = HashSet::from([memory_id.to_string()]);
+    let mut pending = vec![memory_id.to_string()];
+    while let Some(id) = pending.pop() {
+        let sources = events
+            .iter()
+            .filter(|e| e["memoryId"] == id.as_str() && e["kind"] == "merged")
+            .filter_map(|e| e["details"]["mergedFrom"].as_array())
+            .flatten()
+            .filter_map(|v| v.as_str());
+        for source in sources {
+            if lineage.insert(source.to_string()) {
+                pending.push(source.to_string());
+            }
+        }
+    }
+    events.retain(|e| {
+        e["memoryId"]
+            .as_str()
+            .is_some_and(|id| lineage.contains(id))
+    });
+    Ok(events)
+}
+
+/// Moves whatever sits in the owner's legacy `memory_embeddings` column into the table
+/// (replacing existing rows) and resets the column to `[]`.
+pub fn absorb_memory_column(conn: &Connection, owner: MemoryOwner<'_>) -> Result<(), String> {
+    let select_sql = format!(
+        "SELECT memory_embeddings FROM {} WHERE id = ?1",
+        owner.owner_table()
+    );
+    let json: Option<String> = conn
+        .query_row(&select_sql, params![owner.id()], |r| r.get(0))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let Ok(memories) = parse_memories_json(json.as_deref().unwrap_or("[]")) else {
+        // Left in the column rather than dropped; the parse error is logged.
+        return Ok(());
+    };
+    write_memories_in_savepoint(conn, owner, &memories, None)?;
+
+    let clear_sql = format!(
+        "UPDATE {} SET memory_embeddings = '[]' WHERE id = ?1",
+        owner.owner_table()
+    );
+    conn.execute(&clear_sql, params![owner.id()])
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Ok(())
+}
+
+/// Moves every non-empty legacy `memory_embeddings` column into the table.
+/// Returns the number of sessions moved.
+pub fn migrate_memory_columns(conn: &Connection) -> Result<usize, String> {
+    let mut moved = 0;
+    for table in ["sessions", "group_sessions"] {
+        let sql = format!(
+            "SELECT id FROM {} WHERE memory_embeddings IS NOT NULL AND memory_embeddings NOT IN ('', '[]')",
+            table
+        );
+        let ids: Vec<String> = {
+            let mut stmt = conn
+                .prepare(&sql)
+                .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+            let rows = stmt
+                .query_map([], |r| r.get(0))
+                .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        };
+        for id in &ids {
+            let owner = if table == "sessions" {
+                MemoryOwner::Session(id)
+            } else {
+                MemoryOwner::GroupSession(id)
+            };
+            absorb_memory_column(conn, owner)?;
+            moved += 1;
+        }
+    }
+    Ok(moved)
+}
+
+/// Tags vectors stored before rows recorded their embedding backend with the backend they
+/// were last re-embedded with, or else `active_backend`, the one configured now. Rows left
+/// untagged (no backend known) are treated as stale. Returns the number of rows tagged.
+pub fn tag_untagged_vectors(
+    conn: &Connection,
+    active_backend: Option<&str>,
+) -> Result<usize, String> {
+    let backend = format!(
+        "COALESCE((SELECT value FROM meta WHERE key = '{}'), ?1)",
+        ACTIVE_EMBEDDING_BACKEND_META_KEY
+    );
+    conn.execute(
+        &format!(
+            "UPDATE memories SET embedding_model = {0}
+              WHERE embedding_model IS NULL AND embedding_dim > 0 AND {0} IS NOT NULL",
+            backend
+        ),
+        params![active_backend],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE sessions (
+              id TEXT PRIMARY KEY, memories TEXT NOT NULL DEFAULT '[]',
+              memory_embeddings TEXT NOT NULL DEFAULT '[]', memory_tool_events TEXT NOT NULL DEFAULT '[]',
+              updated_at INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE group_sessions (id TEXT PRIMARY KEY, memory_embeddings TEXT NOT NULL DEFAULT '[]');
+            CREATE TABLE characters (id TEXT PRIMARY KEY, updated_at INTEGER NOT NULL DEFAULT 0);
+            CREATE TABLE memories (
+              row_id INTEGER PRIMARY KEY AUTOINCREMENT, id TEXT NOT NULL, session_id TEXT,
+              group_session_id TEXT, character_id TEXT, position INTEGER NOT NULL DEFAULT 0, text TEXT NOT NULL,
+              category TEXT, embedding BLOB, embedding_dim INTEGER NOT NULL DEFAULT 0,
+              token_count INTEGER NOT NULL DEFAULT 0, is_cold INTEGER NOT NULL DEFAULT 0,
+              is_pinned INTEGER NOT NULL DEFAULT 0, importance_score REAL NOT NULL DEFAULT 1.0,
+              access_count INTEGER NOT NULL DEFAULT 0, last_accessed_at INTEGER NOT NULL DEFAULT 0,
+              created_at INTEGER NOT NULL DEFAULT 0, updated_at INTEGER NOT NULL DEFAULT 0, provenance TEXT,
+              embedding_model TEXT
+            );
+            CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT);
+            CREATE TABLE memory_events (
+              id INTEGER PRIMARY KEY AUTOINCREMENT, memory_id TEXT NOT NULL, session_id TEXT,
+              group_session_id TEXT, character_id TEXT, kind TEXT NOT NULL, actor TEXT NOT NULL,
+              text TEXT, previous_text TEXT, details TEXT, created_at INTEGER NOT NULL
+            );
+            CREATE VIRTUAL TABLE memories_fts USING fts5(text, content='memories', content_rowid='row_id');
+            CREATE TRIGGER memories_fts_ai AFTER INSERT ON memories BEGIN
+              INSERT INTO memories_fts(rowid, text) VALUES (new.row_id, new.text);
+            END;
+            CREATE TRIGGER memories_fts_ad AFTER DELETE ON memories BEGIN
+              INSERT INTO memories_fts(memories_fts, rowid, text) VALUES ('delete', old.row_id, old.text);
+            END;
+            CREATE TRIGGER memories_fts_au AFTER UPDATE OF text ON memories BEGIN
+              INSERT INTO memories_fts(memories_fts, rowid, text) VALUES ('delete', old.row_id, old.text);
+              INSERT INTO memories_fts(rowid, text) VALUES (new.row_id, new.text);
+            END;
+            INSERT INTO sessions (id, memory_embeddings) VALUES
+              ('s1', '[{"id":"a","text":"likes tea","embedding":[0.5,-1.0],"isPinned":true},{"id":"a","text":"dup id","embedding":[]}]');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let values = vec![0.0, 1.5, -2.25, f32::MIN_POSITIVE];
+        let blob = embedding_to_blob(&values);
+        assert_eq!(blob.len(), 16);
+        assert_eq!(blob_to_embedding(&blob), values);
+    }
+
+    #[test]
+    fn migrates_column_and_dedupes_ids() {
+        let conn = test_conn();
+        assert_eq!(migrate_memory_columns(&conn).unwrap(), 1);
+
+        let memories = read_memories(&conn, MemoryOwner::Session("s1")).unwrap();
+        assert_eq!(memories.len(), 2);
+        assert_eq!(memories[0].embedding, vec![0.5, -1.0]);
+        assert!(memories[0].is_pinned);
+        assert_ne!(memories[0].id, memories[1].id);
+
+        // Legacy vectors stay out of semantic retrieval until tagged with their backend.
+        let owner = MemoryOwner::Session("s1");
+        assert!(read_retrievable_memories(&conn, owner).unwrap().is_empty());
+        tag_untagged_vectors(&conn, Some("v3")).unwrap();
+        assert_eq!(read_retrievable_memories(&conn, owner).unwrap().len(), 1);
+
+        let column: String = conn
+            .query_row(
+                "SELECT memory_embeddings FROM sessions WHERE id = 's1'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(column, "[]");
+    }
+
+    #[test]
+    fn retrieval_skips_vectors_of_other_models() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+        let mut memories = read_memories(&conn, owner).unwrap();
+        memories[0].embedding_model = Some("v2".to_string());
+        memories[1].embedding = vec![1.0, 0.0];
+        memories[1].embedding_model = Some("v3".to_string());
+        write_memories(&conn, owner, &memories, MemoryActor::User).unwrap();
+        assert_eq!(read_retrievable_memories(&conn, owner).unwrap().len(), 2);
+
+        memories[0].embedding_model = None;
+        write_memories(&conn, owner, &memories, MemoryActor::User).unwrap();
+        assert_eq!(read_retrievable_memories(&conn, owner).unwrap().len(), 1);
+        memories[0].embedding_model = Some("v2".to_string());
+        write_memories(&conn, owner, &memories, MemoryActor::User).unwrap();
+
+        conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, 'v3')",
+            params![QUERY_EMBEDDING_MODEL_META_KEY],
+        )
+        .unwrap();
+        let retrievable = read_retrievable_memories(&conn, owner).unwrap();
+        assert_eq!(retrievable.len(), 1);
+        assert_eq!(retrievable[0].embedding_model.as_deref(), Some("v3"));
+        assert_eq!(
+            read_memories(&conn, owner).unwrap()[0]
+                .embedding_model
+                .as_deref(),
+            Some("v2")
+        );
+    }
+
+    #[test]
+    fn tags_vectors_of_a_pre_tagging_database() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+        // Rows written before tagging: one vector, one row never embedded.
+        conn.execute("UPDATE memories SET embedding_model = NULL", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, 'v3')",
+            params![QUERY_EMBEDDING_MODEL_META_KEY],
+        )
+        .unwrap();
+        assert!(read_retrievable_memories(&conn, owner).unwrap().is_empty());
+
+        assert_eq!(tag_untagged_vectors(&conn, None).unwrap(), 0);
+        assert_eq!(tag_untagged_vectors(&conn, Some("v2")).unwrap(), 1);
+        let memories = read_memories(&conn, owner).unwrap();
+        assert_eq!(memories[0].embedding_model.as_deref(), Some("v2"));
+        assert_eq!(memories[1].embedding_model, None);
+        // A v1/v2 -> v3 switch: the v2 vectors wait for re-embedding.
+        assert!(read_retrievable_memories(&conn, owner).unwrap().is_empty());
+
+        conn.execute("UPDATE memories SET embedding_model = NULL", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, 'v3')",
+            params![ACTIVE_EMBEDDING_BACKEND_META_KEY],
+        )
+        .unwrap();
+        assert_eq!(tag_untagged_vectors(&conn, Some("v2")).unwrap(), 1);
+        assert_eq!(read_retrievable_memories(&conn, owner).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn write_removes_missing_rows() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+        let mut memories = read_memories(&conn, owner).unwrap();
+        memories.remove(0);
+        memories[0].is_cold = true;
+        write_memories(&conn, owner, &memories, MemoryActor::User).unwrap();
+
+        let stored = read_memories(&conn, owner).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].is_cold);
+        assert_eq!(stored[0].text, "dup id");
+    }
+
+    #[test]
+    fn invalid_entry_fails_the_write_and_keeps_rows() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+        let values = vec![
+            serde_json::json!({ "id": "a", "text": "likes tea", "embedding": [] }),
+            serde_json::json!({ "id": "b", "text": 42, "embedding": [] }),
+        ];
+        let err = write_memory_values(&conn, owner, &values, MemoryActor::User).unwrap_err();
+        assert!(err.contains("Memory entry 1"), "{err}");
+        assert_eq!(read_memories(&conn, owner).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn bm25_follows_text_edits() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+
+        let hits = bm25_rank_memories(&conn, owner, "Does she like TEA?", 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "a");
+        assert!(hits[0].1 > 0.0);
+
+        let mut memories = read_memories(&conn, owner).unwrap();
+        memories[0].text = "prefers coffee".to_string();
+        write_memories(&conn, owner, &memories, MemoryActor::User).unwrap();
+        assert!(bm25_rank_memories(&conn, owner, "tea", 5)
+            .unwrap()
+            .is_empty());
+        assert!(bm25_rank_memories(&conn, owner, "\"* OR", 5)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn undo_merge_restores_originals() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+        let originals = read_memories(&conn, owner).unwrap();
+
+        let mut merged = originals[0].clone();
+        merged.id = "m".to_string();
+        merged.text = "likes tea, dup id".to_string();
+        write_memories(
+            &conn,
+            owner,
+            std::slice::from_ref(&merged),
+            MemoryActor::MemoryManager,
+        )
+        .unwrap();
+        let recorded: Vec<JsonValue> = originals
+            .iter()
+            .map(|m| serde_json::json!({ "id": m.id, "text": m.text }))
+            .collect();
+        let events = serde_json::json!([{
+            "actions": [{ "name": "merge_memories", "memoryId": "m", "originals": recorded }]
+        }]);
+        conn.execute(
+            "UPDATE sessions SET memory_tool_events = ?1 WHERE id = 's1'",
+            params![events.to_string()],
+        )
+        .unwrap();
+
+        assert!(undo_memory_merge(&conn, owner, "m").unwrap());
+        let restored = read_memories(&conn, owner).unwrap();
+        let ids: Vec<&str> = restored.iter().map(|m| m.id.as_str()).collect();
+        let expected: Vec<&str> = originals.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(restored[0].text, originals[0].text);
+        assert!(restored.iter().all(|m| m.embedding.is_empty()));
+        assert!(!undo_memory_merge(&conn, owner, "m").unwrap());
+    }
+
+    #[test]
+    fn sharing_copies_once_to_the_character() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let session = MemoryOwner::Session("s1");
+        let character = MemoryOwner::Character("c1");
+
+        let shared = share_memory_with_character(&conn, session, "a", "c1", MemoryActor::User)
+            .unwrap()
+            .unwrap();
+        assert_eq!(shared.text, "likes tea");
+        let again = share_memory_with_character(&conn, session, "a", "c1", MemoryActor::User)
+            .unwrap()
+            .unwrap();
+        assert_eq!(again.id, shared.id);
+        assert_eq!(read_memories(&conn, character).unwrap().len(), 1);
+        assert_eq!(read_memories(&conn, session).unwrap().len(), 2);
+        assert!(
+            share_memory_with_character(&conn, session, "missing", "c1", MemoryActor::User)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn timeline_follows_edits_and_merges() {
+        let conn = test_conn();
+        migrate_memory_columns(&conn).unwrap();
+        let owner = MemoryOwner::Session("s1");
+        let mut memories = read_memories(&conn, owner).unwrap();
+        let original_ids: Vec<String> = memories.iter().map(|m| m.id.clone()).collect();
+        // Moving the legacy column in is a copy, not a change.
+        assert!(read_memory_timeline(&conn, owner, None).unwrap().is_empty());
+
+        memories[0].text = "loves green tea".to_string();
+        write_memories(&conn, owner, &memories, MemoryActor::User).unwrap();
+
+        let mut merged = memories[0].clone();
+        merged.id = "m".to_string();
+        merged.provenance = Some(MemoryProvenance {
+            origin: "merge".to_string(),
+            merged_from: original_ids.clone(),
+            ..Default::default()
+        });
+        write_memories(&conn, owner, &[merged], MemoryActor::MemoryManager).unwrap();
+
+        let timeline = read_memory_timeline(&conn, owner, Some("m")).unwrap();
+        let kinds: Vec<(&str, &str)> = timeline
+            .iter()
+            .map(|e| (e["memoryId"].as_str().unwrap(), e["kind"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (original_ids[0].as_str(), "edited"),
+                ("m", "merged"),
+                (original_ids[0].as_str(), "merged"),
+                (original_ids[1].as_str(), "merged"),
+            ]
+        );
+        assert_eq!(timeline[0]["actor"], "user");
+        assert_eq!(timeline[0]["previousText"], "likes tea");
+        assert_eq!(timeline[2]["details"]["into"], "m");
+        assert_eq!(
+            read_memories(&conn, owner).unwrap()[0]
+                .provenance
+                .as_ref()
+                .unwrap()
+                .merged_from,
+            original_ids
+        );
+    }
+}
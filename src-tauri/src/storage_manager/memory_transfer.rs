@@ -0,0 +1,354 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::AppHandle;
+
+use super::db::now_ms;
+use super::group_sessions::MemoryEmbedding;
+use super::memories::{read_memories, write_memories, MemoryActor, MemoryOwner};
+use crate::utils::log_warn;
+
+pub const MEMORY_EXPORT_FORMAT: &str = "lettuceai-memories";
+pub const MEMORY_EXPORT_VERSION: u32 = 1;
+
+/// A session's memories as written to a standalone export file. `embedding_model` is the
+/// embedding backend id (ONNX source version or remote model) the vectors came from;
+/// importers re-embed when it doesn't match theirs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: i64,
+    pub source_session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub summary_token_count: i64,
+    pub memories: Vec<MemoryEmbedding>,
+}
+
+/// The owner's memories after an import, with the positions of the rows that came from the
+/// export file.
+#[derive(Debug, Clone)]
+pub struct MemoryImportPlan {
+    pub memories: Vec<MemoryEmbedding>,
+    pub imported: Vec<usize>,
+    pub skipped: usize,
+    /// Token count of the exported summary, recounted for the owner's model on import.
+    pub summary_token_count: i64,
+}
+
+/// Table and display-name column of the session kinds that can export memories.
+fn owner_table(owner: MemoryOwner<'_>) -> Result<(&'static str, &'static str), String> {
+    match owner {
+        MemoryOwner::Session(_) => Ok(("sessions", "title")),
+        MemoryOwner::GroupSession(_) => Ok(("group_sessions", "name")),
+        MemoryOwner::Character(_) => Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            "Character memories can't be exported as a session",
+        )),
+    }
+}
+
+/// The embedding backend `compute_embedding` currently uses, if one is available.
+pub fn current_embedding_model(app: &AppHandle) -> Option<String> {
+    crate::embedding_model::active_embedding_backend_id(app)
+}
+
+pub fn export_memories(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    include_embeddings: bool,
+    embedding_model: Option<String>,
+) -> Result<MemoryExport, String> {
+    let (table, title_column) = owner_table(owner)?;
+    let (title, summary, summary_token_count): (Option<String>, String, i64) = conn
+        .query_row(
+            &format!(
+                "SELECT {}, memory_summary, memory_summary_token_count FROM {} WHERE id = ?1",
+                title_column, table
+            ),
+            params![owner.id()],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .ok_or_else(|| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Session {} not found", owner.id()),
+            )
+        })?;
+
+    let mut memories = read_memories(conn, owner)?;
+    if !include_embeddings {
+        for memory in memories.iter_mut() {
+            memory.embedding.clear();
+            memory.embedding_model = None;
+        }
+    }
+
+    Ok(MemoryExport {
+        format: MEMORY_EXPORT_FORMAT.to_string(),
+        version: MEMORY_EXPORT_VERSION,
+        exported_at: now_ms() as i64,
+        source_session_id: owner.id().to_string(),
+        source_title: title,
+        embedding_model: if include_embeddings {
+            embedding_model
+        } else {
+            None
+        },
+        summary,
+        summary_token_count,
+        memories,
+    })
+}
+
+pub fn parse_memory_export(json: &str) -> Result<MemoryExport, String> {
+    let export: MemoryExport = serde_json::from_str(json).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Invalid memory export: {}", e),
+        )
+    })?;
+    if export.format != MEMORY_EXPORT_FORMAT || export.version > MEMORY_EXPORT_VERSION {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!(
+                "Unsupported memory export (format {}, version {})",
+                export.format, export.version
+            ),
+        ));
+    }
+    Ok(export)
+}
+
+/// Appends the export's memories to `existing` (or replaces them), skipping texts the owner
+/// already has. Imported memories get fresh ids and keep a provenance link to the session
+/// they were exported from.
+pub fn plan_memory_import(
+    existing: &[MemoryEmbedding],
+    export: &MemoryExport,
+    replace: bool,
+) -> MemoryImportPlan {
+    let mut memories: Vec<MemoryEmbedding> = if replace {
+        Vec::new()
+    } else {
+        existing.to_vec()
+    };
+    let mut texts: HashSet<String> = memories
+        .iter()
+        .map(|m| m.text.trim().to_lowercase())
+        .collect();
+    let mut imported = Vec::new();
+    let mut skipped = 0;
+
+    for memory in &export.memories {
+        if memory.text.trim().is_empty() || !texts.insert(memory.text.trim().to_lowercase()) {
+            skipped += 1;
+            continue;
+        }
+        let mut provenance = memory.provenance.clone().unwrap_or_default();
+        if provenance.origin.is_empty() {
+            provenance.origin = "user".to_string();
+        }
+        provenance
+            .source_session_id
+            .get_or_insert_with(|| export.source_session_id.clone());
+        provenance
+            .source_memory_id
+            .get_or_insert_with(|| memory.id.clone());
+
+        imported.push(memories.len());
+        memories.push(MemoryEmbedding {
+            id: uuid::Uuid::new_v4().to_string(),
+            provenance: Some(provenance),
+            ..memory.clone()
+        });
+    }
+
+    MemoryImportPlan {
+        memories,
+        imported,
+        skipped,
+        summary_token_count: export.summary_token_count,
+    }
+}
+
+/// Whether an imported vector must be recomputed: it is missing, or it came from another
+/// (or an unrecorded) embedding model. Without a local model there is nothing to compare
+/// against, so the vector is kept.
+pub fn needs_reembedding(
+    export_model: Option<&str>,
+    current_model: Option<&str>,
+    embedding: &[f32],
+) -> bool {
+    match current_model {
+        None => false,
+        Some(current) => embedding.is_empty() || export_model != Some(current),
+    }
+}
+
+/// Recomputes embeddings of the imported memories of `plan`, and their token counts and the
+/// summary's with `model_id`, the chat model of the owner (the default model when `None`).
+/// Returns how many were re-embedded.
+pub async fn embed_imported_memories(
+    app: &AppHandle,
+    plan: &mut MemoryImportPlan,
+    export: &MemoryExport,
+    model_id: Option<&str>,
+) -> usize {
+    if !export.summary.trim().is_empty() {
+        plan.summary_token_count =
+            crate::tokenizer::count_tokens_for_model_id(app, model_id, &export.summary) as i64;
+    }
+    let export_model = export.embedding_model.as_deref();
+    let current_model = current_embedding_model(app);
+    let mut stale = Vec::new();
+    for &idx in &plan.imported {
+        let memory = &mut plan.memories[idx];
+        memory.token_count =
+            crate::tokenizer::count_tokens_for_model_id(app, model_id, &memory.text) as i32;
+        let memory_model = memory.embedding_model.as_deref().or(export_model);
+        if needs_reembedding(memory_model, current_model.as_deref(), &memory.embedding) {
+            stale.push(idx);
+        } else if memory.embedding_model.is_none() && !memory.embedding.is_empty() {
+            memory.embedding_model = memory_model.map(str::to_string);
+        }
+    }
+    if stale.is_empty() {
+        return 0;
+    }
+
+    let texts: Vec<String> = stale
+        .iter()
+        .map(|&idx| plan.memories[idx].text.clone())
+        .collect();
+    match crate::embedding_model::compute_embeddings(app.clone(), texts).await {
+        Ok(embeddings) => {
+            for (&idx, embedding) in stale.iter().zip(embeddings) {
+                plan.memories[idx].embedding = embedding;
+                plan.memories[idx].embedding_model = current_model.clone();
+            }
+            stale.len()
+        }
+        Err(err) => {
+            log_warn(
+                app,
+                "memory_import",
+                format!("embedding failed for imported memories: {}", err),
+            );
+            for &idx in &stale {
+                plan.memories[idx].embedding.clear();
+                plan.memories[idx].embedding_model = None;
+            }
+            0
+        }
+    }
+}
+
+/// Writes an import plan: the memories, the owner's memory texts and, when replacing or
+/// when the owner has none yet, the exported summary.
+pub fn apply_memory_import(
+    conn: &Connection,
+    owner: MemoryOwner<'_>,
+    plan: &MemoryImportPlan,
+    export: &MemoryExport,
+    replace: bool,
+) -> Result<(), String> {
+    let (table, _) = owner_table(owner)?;
+    write_memories(conn, owner, &plan.memories, MemoryActor::User)?;
+
+    let texts: Vec<&str> = plan.memories.iter().map(|m| m.text.as_str()).collect();
+    let memories_json = serde_json::to_string(&texts)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    let now = now_ms() as i64;
+    conn.execute(
+        &format!(
+            "UPDATE {} SET memories = ?1, updated_at = ?2 WHERE id = ?3",
+            table
+        ),
+        params![memories_json, now, owner.id()],
+    )
+    .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+
+    if !export.summary.trim().is_empty() {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET memory_summary = ?1, memory_summary_token_count = ?2
+                 WHERE id = ?3 AND (?4 OR TRIM(memory_summary) = '')",
+                table
+            ),
+            params![
+                &export.summary,
+                plan.summary_token_count,
+                owner.id(),
+                replace
+            ],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(id: &str, text: &str) -> MemoryEmbedding {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": text,
+            "embedding": [0.1, 0.2],
+            "isPinned": true,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn import_skips_known_texts_and_links_the_source() {
+        let export = MemoryExport {
+            format: MEMORY_EXPORT_FORMAT.to_string(),
+            version: MEMORY_EXPORT_VERSION,
+            exported_at: 0,
+            source_session_id: "src".to_string(),
+            source_title: None,
+            embedding_model: Some("v2".to_string()),
+            summary: String::new(),
+            summary_token_count: 0,
+            memories: vec![memory("a", "Likes tea"), memory("b", "Has a sister")],
+        };
+        let existing = vec![memory("x", "likes tea ")];
+
+        let plan = plan_memory_import(&existing, &export, false);
+        assert_eq!(plan.memories.len(), 2);
+        assert_eq!(plan.summary_token_count, export.summary_token_count);
+        assert_eq!(plan.imported, vec![1]);
+        assert_eq!(plan.skipped, 1);
+        let added = &plan.memories[1];
+        assert_ne!(added.id, "b");
+        assert!(added.is_pinned);
+        let provenance = added.provenance.as_ref().unwrap();
+        assert_eq!(provenance.source_session_id.as_deref(), Some("src"));
+        assert_eq!(provenance.source_memory_id.as_deref(), Some("b"));
+
+        let replaced = plan_memory_import(&existing, &export, true);
+        assert_eq!(replaced.imported, vec![0, 1]);
+
+        assert!(!needs_reembedding(Some("v2"), Some("v2"), &[0.1]));
+        assert!(needs_reembedding(Some("v2"), Some("v3"), &[0.1]));
+        assert!(needs_reembedding(None, Some("v3"), &[0.1]));
+        assert!(needs_reembedding(Some("v3"), Some("v3"), &[]));
+        assert!(!needs_reembedding(Some("v2"), None, &[]));
+    }
+}
@@ -4,9 +4,13 @@ pub mod db;
 pub mod entity_transfer;
 pub mod group_sessions;
 pub mod importer;
+pub mod knowledge_graph;
 pub mod legacy;
 pub mod lorebook;
+pub mod lorebook_formats;
 pub mod media;
+pub mod memories;
+pub mod memory_transfer;
 pub mod models;
 pub mod personas;
 pub mod providers;
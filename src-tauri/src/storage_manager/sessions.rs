@@ -5,6 +5,14 @@ use std::collections::HashMap;
 use uuid;
 
 use super::db::{now_ms, open_db};
+use super::memories::{
+    read_memories, read_memories_json, read_memory_timeline, read_memory_values,
+    share_memory_with_character, undo_memory_merge, write_memory_values, MemoryActor, MemoryOwner,
+};
+use super::memory_transfer::{
+    apply_memory_import, current_embedding_model, embed_imported_memories, export_memories,
+    parse_memory_export, plan_memory_import,
+};
 use crate::embedding_model;
 use crate::utils::{log_error, log_info, log_warn};
 
@@ -17,7 +25,9 @@ const ALLOWED_MEMORY_CATEGORIES: &[&str] = &[
     "other",
 ];
 
-fn normalize_memory_category(category: Option<String>) -> Result<Option<String>, String> {
+pub(crate) fn normalize_memory_category(
+    category: Option<String>,
+) -> Result<Option<String>, String> {
     let normalized = category
         .map(|c| c.trim().to_string())
         .filter(|c| !c.is_empty());
@@ -85,6 +95,20 @@ fn json_usage_summary(
     }
 }
 
+/// Memories live in the `memories` table; the legacy column is only consulted for rows
+/// that haven't been migrated yet.
+fn memory_embeddings_value(
+    conn: &rusqlite::Connection,
+    id: &str,
+    legacy_json: &str,
+) -> Result<JsonValue, String> {
+    let stored = read_memory_values(conn, MemoryOwner::Session(id))?;
+    if stored.is_empty() {
+        return Ok(serde_json::from_str(legacy_json).unwrap_or_else(|_| JsonValue::Array(vec![])));
+    }
+    Ok(JsonValue::Array(stored))
+}
+
 fn read_session_meta(conn: &rusqlite::Connection, id: &str) -> Result<Option<JsonValue>, String> {
     let row = conn
         .query_row(
@@ -146,8 +170,7 @@ fn read_session_meta(conn: &rusqlite::Connection, id: &str) -> Result<Option<Jso
 
     let memories: JsonValue =
         serde_json::from_str(&memories_json).unwrap_or_else(|_| JsonValue::Array(vec![]));
-    let memory_embeddings: JsonValue =
-        serde_json::from_str(&memory_embeddings_json).unwrap_or_else(|_| JsonValue::Array(vec![]));
+    let memory_embeddings = memory_embeddings_value(conn, id, &memory_embeddings_json)?;
     let memory_tool_events: JsonValue =
         serde_json::from_str(&memory_tool_events_json).unwrap_or_else(|_| JsonValue::Array(vec![]));
 
@@ -344,8 +367,7 @@ fn read_session(conn: &rusqlite::Connection, id: &str) -> Result<Option<JsonValu
     // Parse memories JSON array
     let memories: JsonValue =
         serde_json::from_str(&memories_json).unwrap_or_else(|_| JsonValue::Array(vec![]));
-    let memory_embeddings: JsonValue =
-        serde_json::from_str(&memory_embeddings_json).unwrap_or_else(|_| JsonValue::Array(vec![]));
+    let memory_embeddings = memory_embeddings_value(conn, id, &memory_embeddings_json)?;
     let memory_tool_events: JsonValue =
         serde_json::from_str(&memory_tool_events_json).unwrap_or_else(|_| JsonValue::Array(vec![]));
 
@@ -950,10 +972,8 @@ pub fn session_upsert_meta(app: tauri::AppHandle, session_json: String) -> Resul
         Some(v) => serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string()),
         None => "[]".to_string(),
     };
-    let memory_embeddings_json = match s.get("memoryEmbeddings") {
-        Some(v) => serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string()),
-        None => "[]".to_string(),
-    };
+    // Memories go to the `memories` table; the legacy column is left empty.
+    let memory_embeddings = s.get("memoryEmbeddings").and_then(|v| v.as_array());
     let memory_status = s
         .get("memoryStatus")
         .and_then(|v| v.as_str())
@@ -1021,7 +1041,7 @@ pub fn session_upsert_meta(app: tauri::AppHandle, session_json: String) -> Resul
             presence_penalty,
             top_k,
             &memories_json,
-            &memory_embeddings_json,
+            "[]",
             memory_summary,
             memory_summary_token_count,
             &memory_tool_events_json,
@@ -1034,6 +1054,15 @@ pub fn session_upsert_meta(app: tauri::AppHandle, session_json: String) -> Resul
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
+    if let Some(values) = memory_embeddings {
+        write_memory_values(
+            &conn,
+            MemoryOwner::Session(&id),
+            values,
+            MemoryActor::MemoryManager,
+        )?;
+    }
+
     Ok(())
 }
 
@@ -1374,10 +1403,8 @@ pub fn session_upsert(app: tauri::AppHandle, session_json: String) -> Result<(),
         Some(v) => serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string()),
         None => "[]".to_string(),
     };
-    let memory_embeddings_json = match s.get("memoryEmbeddings") {
-        Some(v) => serde_json::to_string(v).unwrap_or_else(|_| "[]".to_string()),
-        None => "[]".to_string(),
-    };
+    // Memories go to the `memories` table; the legacy column is left empty.
+    let memory_embeddings = s.get("memoryEmbeddings").and_then(|v| v.as_array());
 
     let adv = s.get("advancedModelSettings");
     let temperature = adv
@@ -1422,9 +1449,18 @@ pub fn session_upsert(app: tauri::AppHandle, session_json: String) -> Result<(),
               memory_tool_events=excluded.memory_tool_events,
               archived=excluded.archived,
               updated_at=excluded.updated_at"#,
-        params![&id, character_id, title, system_prompt, selected_scene_id, persona_id, persona_disabled, voice_autoplay, temperature, top_p, max_output_tokens, frequency_penalty, presence_penalty, top_k, &memories_json, &memory_embeddings_json, memory_summary, memory_summary_token_count, &memory_tool_events_json, archived, created_at, updated_at],
+        params![&id, character_id, title, system_prompt, selected_scene_id, persona_id, persona_disabled, voice_autoplay, temperature, top_p, max_output_tokens, frequency_penalty, presence_penalty, top_k, &memories_json, "[]", memory_summary, memory_summary_token_count, &memory_tool_events_json, archived, created_at, updated_at],
     ).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
+    if let Some(values) = memory_embeddings {
+        write_memory_values(
+            &tx,
+            MemoryOwner::Session(&id),
+            values,
+            MemoryActor::MemoryManager,
+        )?;
+    }
+
     if let Some(msgs) = s.get("messages").and_then(|v| v.as_array()) {
         for m in msgs {
             let mid = m
@@ -1660,6 +1696,21 @@ pub fn message_toggle_pin_state(
     }
 }
 
+/// The default model of the session's character, which token counts of its memories follow.
+fn session_character_model_id(conn: &rusqlite::Connection, session_id: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT c.default_model_id FROM sessions s
+         JOIN characters c ON c.id = s.character_id
+         WHERE s.id = ?1",
+        params![session_id],
+        |r| r.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .flatten()
+}
+
 #[tauri::command]
 pub async fn session_add_memory(
     app: tauri::AppHandle,
@@ -1675,20 +1726,20 @@ pub async fn session_add_memory(
     let conn = open_db(&app)?;
 
     // Read current memories
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM sessions WHERE id = ?",
+            "SELECT memories FROM sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let mut memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
     let mut memory_embeddings: Vec<JsonValue> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+        read_memory_values(&conn, MemoryOwner::Session(&session_id))?;
 
     // Add new memory (clone so we can still use `memory` for the embedding)
     memories.push(memory.clone());
@@ -1705,30 +1756,41 @@ pub async fn session_add_memory(
             Vec::new()
         }
     };
-
-    // Count tokens (best-effort)
-    let token_count = crate::tokenizer::count_tokens(&app, &memory).unwrap_or(0);
+    let embedding_model_id = Some(&embedding)
+        .filter(|embedding| !embedding.is_empty())
+        .and_then(|_| embedding_model::active_embedding_backend_id(&app));
+
+    // Count tokens with the model the character replies with (best-effort)
+    let character_model_id = session_character_model_id(&conn, &session_id);
+    let token_count =
+        crate::tokenizer::count_tokens_for_model_id(&app, character_model_id.as_deref(), &memory);
     let normalized_category = normalize_memory_category(memory_category)?;
 
     memory_embeddings.push(serde_json::json!({
         "id": uuid::Uuid::new_v4().to_string(),
         "text": memory.clone(),
         "embedding": embedding,
+        "embeddingModel": embedding_model_id,
         "createdAt": now_ms() as i64,
         "tokenCount": token_count,
         "category": normalized_category,
+        "provenance": { "origin": "user" },
     }));
 
     // Save back
     let new_memories_json = serde_json::to_string(&memories)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memory_values(
+        &conn,
+        MemoryOwner::Session(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
     let now = now_ms() as i64;
 
     conn.execute(
-        "UPDATE sessions SET memories = ?, memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_memories_json, new_embeddings_json, now, &session_id],
+        "UPDATE sessions SET memories = ?, updated_at = ? WHERE id = ?",
+        params![new_memories_json, now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -1749,20 +1811,20 @@ pub fn session_remove_memory(
     let conn = open_db(&app)?;
 
     // Read current memories
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM sessions WHERE id = ?",
+            "SELECT memories FROM sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let mut memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
     let mut memory_embeddings: Vec<JsonValue> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+        read_memory_values(&conn, MemoryOwner::Session(&session_id))?;
 
     // Remove memory at index
     if memory_index < memories.len() {
@@ -1775,13 +1837,17 @@ pub fn session_remove_memory(
         // Save back
         let new_memories_json = serde_json::to_string(&memories)
             .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-        let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        write_memory_values(
+            &conn,
+            MemoryOwner::Session(&session_id),
+            &memory_embeddings,
+            MemoryActor::User,
+        )?;
         let now = now_ms() as i64;
 
         conn.execute(
-            "UPDATE sessions SET memories = ?, memory_embeddings = ?, updated_at = ? WHERE id = ?",
-            params![new_memories_json, new_embeddings_json, now, &session_id],
+            "UPDATE sessions SET memories = ?, updated_at = ? WHERE id = ?",
+            params![new_memories_json, now, &session_id],
         )
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
@@ -1805,20 +1871,20 @@ pub async fn session_update_memory(
     let conn = open_db(&app)?;
 
     // Read current memories
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM sessions WHERE id = ?",
+            "SELECT memories FROM sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let mut memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
     let mut memory_embeddings: Vec<JsonValue> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+        read_memory_values(&conn, MemoryOwner::Session(&session_id))?;
 
     // Update memory at index
     if memory_index < memories.len() {
@@ -1838,6 +1904,9 @@ pub async fn session_update_memory(
                     Vec::new()
                 }
             };
+        let embedding_model_id = Some(&embedding)
+            .filter(|embedding| !embedding.is_empty())
+            .and_then(|_| embedding_model::active_embedding_backend_id(&app));
 
         if memory_index < memory_embeddings.len() {
             if let Some(obj) = memory_embeddings
@@ -1852,6 +1921,7 @@ pub async fn session_update_memory(
                     "embedding".into(),
                     JsonValue::Array(embedding.iter().map(|f| JsonValue::from(*f)).collect()),
                 );
+                obj.insert("embeddingModel".into(), JsonValue::from(embedding_model_id));
                 match normalized_category.as_ref() {
                     Some(category) => {
                         obj.insert("category".into(), JsonValue::String(category.clone()));
@@ -1866,6 +1936,7 @@ pub async fn session_update_memory(
                 "id": uuid::Uuid::new_v4().to_string(),
                 "text": memories[memory_index].clone(),
                 "embedding": embedding,
+                "embeddingModel": embedding_model_id,
                 "createdAt": now_ms() as i64,
                 "category": normalized_category,
             }));
@@ -1874,13 +1945,17 @@ pub async fn session_update_memory(
         // Save back
         let new_memories_json = serde_json::to_string(&memories)
             .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-        let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        write_memory_values(
+            &conn,
+            MemoryOwner::Session(&session_id),
+            &memory_embeddings,
+            MemoryActor::User,
+        )?;
         let now = now_ms() as i64;
 
         conn.execute(
-            "UPDATE sessions SET memories = ?, memory_embeddings = ?, updated_at = ? WHERE id = ?",
-            params![new_memories_json, new_embeddings_json, now, &session_id],
+            "UPDATE sessions SET memories = ?, updated_at = ? WHERE id = ?",
+            params![new_memories_json, now, &session_id],
         )
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
@@ -1902,18 +1977,8 @@ pub fn session_toggle_memory_pin(
     let conn = open_db(&app)?;
 
     // Read current memory embeddings
-    let current_embeddings_json: String = conn
-        .query_row(
-            "SELECT memory_embeddings FROM sessions WHERE id = ?",
-            params![&session_id],
-            |r| r.get(0),
-        )
-        .optional()
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| "[]".to_string());
-
     let mut memory_embeddings: Vec<JsonValue> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+        read_memory_values(&conn, MemoryOwner::Session(&session_id))?;
 
     let now = now_ms() as i64;
 
@@ -1937,11 +2002,15 @@ pub fn session_toggle_memory_pin(
         }
 
         // Save back
-        let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        write_memory_values(
+            &conn,
+            MemoryOwner::Session(&session_id),
+            &memory_embeddings,
+            MemoryActor::User,
+        )?;
         conn.execute(
-            "UPDATE sessions SET memory_embeddings = ?, updated_at = ? WHERE id = ?",
-            params![new_embeddings_json, now, &session_id],
+            "UPDATE sessions SET updated_at = ? WHERE id = ?",
+            params![now, &session_id],
         )
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
@@ -1964,20 +2033,20 @@ pub fn session_set_memory_cold_state(
     let conn = open_db(&app)?;
 
     // Read current memories + embeddings so we can keep alignment.
-    let (current_memories_json, current_embeddings_json): (String, String) = conn
+    let current_memories_json: String = conn
         .query_row(
-            "SELECT memories, memory_embeddings FROM sessions WHERE id = ?",
+            "SELECT memories FROM sessions WHERE id = ?",
             params![&session_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
-        .unwrap_or_else(|| ("[]".to_string(), "[]".to_string()));
+        .unwrap_or_else(|| "[]".to_string());
 
     let memories: Vec<String> =
         serde_json::from_str(&current_memories_json).unwrap_or_else(|_| vec![]);
     let mut memory_embeddings: Vec<JsonValue> =
-        serde_json::from_str(&current_embeddings_json).unwrap_or_else(|_| vec![]);
+        read_memory_values(&conn, MemoryOwner::Session(&session_id))?;
 
     if memory_index >= memories.len() {
         if let Some(json) = read_session_meta(&conn, &session_id)? {
@@ -2028,11 +2097,15 @@ pub fn session_set_memory_cold_state(
         }
     }
 
-    let new_embeddings_json = serde_json::to_string(&memory_embeddings)
-        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    write_memory_values(
+        &conn,
+        MemoryOwner::Session(&session_id),
+        &memory_embeddings,
+        MemoryActor::User,
+    )?;
     conn.execute(
-        "UPDATE sessions SET memory_embeddings = ?, updated_at = ? WHERE id = ?",
-        params![new_embeddings_json, now, &session_id],
+        "UPDATE sessions SET updated_at = ? WHERE id = ?",
+        params![now, &session_id],
     )
     .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -2043,3 +2116,161 @@ pub fn session_set_memory_cold_state(
     }
     Ok(None)
 }
+
+/// Undo an automatic memory consolidation, restoring the memories it merged into `memory_id`.
+#[tauri::command]
+pub fn session_undo_memory_merge(
+    app: tauri::AppHandle,
+    session_id: String,
+    memory_id: String,
+) -> Result<Option<String>, String> {
+    let conn = open_db(&app)?;
+
+    if !undo_memory_merge(&conn, MemoryOwner::Session(&session_id), &memory_id)? {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("No merge to undo for memory {}", memory_id),
+        ));
+    }
+    crate::embedding_model::embed_pending_memories(&app);
+
+    if let Some(json) = read_session_meta(&conn, &session_id)? {
+        return Ok(Some(serde_json::to_string(&json).map_err(|e| {
+            crate::utils::err_to_string(module_path!(), line!(), e)
+        })?));
+    }
+    Ok(None)
+}
+
+/// Copy a session memory into its character's long-term memory. Returns the character's
+/// memories as JSON.
+#[tauri::command]
+pub fn session_share_memory(
+    app: tauri::AppHandle,
+    session_id: String,
+    memory_id: String,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+
+    let character_id: String = conn
+        .query_row(
+            "SELECT character_id FROM sessions WHERE id = ?",
+            params![&session_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .ok_or_else(|| {
+            crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Session {} not found", session_id),
+            )
+        })?;
+
+    let shared = share_memory_with_character(
+        &conn,
+        MemoryOwner::Session(&session_id),
+        &memory_id,
+        &character_id,
+        MemoryActor::User,
+    )?;
+    if shared.is_none() {
+        return Err(crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Memory {} not found", memory_id),
+        ));
+    }
+    log_info(
+        &app,
+        "session_share_memory",
+        format!(
+            "Shared memory {} with character {}",
+            memory_id, character_id
+        ),
+    );
+
+    read_memories_json(&conn, MemoryOwner::Character(&character_id))
+}
+
+/// Edit history of a session's memories, oldest first. With `memory_id`, only that memory
+/// and the memories it was merged from.
+#[tauri::command]
+pub fn session_memory_timeline(
+    app: tauri::AppHandle,
+    session_id: String,
+    memory_id: Option<String>,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    let events = read_memory_timeline(
+        &conn,
+        MemoryOwner::Session(&session_id),
+        memory_id.as_deref(),
+    )?;
+    serde_json::to_string(&events)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Exports a session's memories, summary and (optionally) embeddings as a standalone JSON file.
+#[tauri::command]
+pub fn session_memories_export(
+    app: tauri::AppHandle,
+    session_id: String,
+    include_embeddings: Option<bool>,
+) -> Result<String, String> {
+    let conn = open_db(&app)?;
+    let export = export_memories(
+        &conn,
+        MemoryOwner::Session(&session_id),
+        include_embeddings.unwrap_or(true),
+        current_embedding_model(&app),
+    )?;
+    serde_json::to_string_pretty(&export)
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Imports a memory export into a session. Memories whose text the session already has are
+/// skipped; vectors from another embedding model are recomputed.
+#[tauri::command]
+pub async fn session_memories_import(
+    app: tauri::AppHandle,
+    session_id: String,
+    import_json: String,
+    replace: Option<bool>,
+) -> Result<Option<String>, String> {
+    let replace = replace.unwrap_or(false);
+    let export = parse_memory_export(&import_json)?;
+    let conn = open_db(&app)?;
+    let existing = read_memories(&conn, MemoryOwner::Session(&session_id))?;
+    let mut plan = plan_memory_import(&existing, &export, replace);
+    let character_model_id = session_character_model_id(&conn, &session_id);
+    let reembedded =
+        embed_imported_memories(&app, &mut plan, &export, character_model_id.as_deref()).await;
+    apply_memory_import(
+        &conn,
+        MemoryOwner::Session(&session_id),
+        &plan,
+        &export,
+        replace,
+    )?;
+    log_info(
+        &app,
+        "session_memories_import",
+        format!(
+            "Imported {} memories into session {} ({} skipped, {} re-embedded)",
+            plan.imported.len(),
+            session_id,
+            plan.skipped,
+            reembedded
+        ),
+    );
+
+    if let Some(json) = read_session_meta(&conn, &session_id)? {
+        return Ok(Some(serde_json::to_string(&json).map_err(|e| {
+            crate::utils::err_to_string(module_path!(), line!(), e)
+        })?));
+    }
+    Ok(None)
+}
@@ -36,7 +36,7 @@ pub async fn storage_reset_database(app: tauri::AppHandle) -> Result<(), String>
         let tables: Vec<String> = {
             let mut stmt = conn
                 .prepare(
-                    "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
+                    "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE 'memories_fts%'",
                 )
                 .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
             let table_iter = stmt
@@ -61,6 +61,12 @@ pub async fn storage_reset_database(app: tauri::AppHandle) -> Result<(), String>
             tx.execute(&sql, [])
                 .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
         }
+        // FTS shadow tables can't be cleared row by row; reset the index as a whole.
+        tx.execute(
+            "INSERT INTO memories_fts(memories_fts) VALUES('delete-all')",
+            [],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
         tx.commit()
             .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
@@ -1,11 +1,16 @@
 use rusqlite::params;
 
 use crate::storage_manager::db::DbConnection;
+use crate::storage_manager::knowledge_graph::{read_graph, replace_graph, KnowledgeGraph};
+use crate::storage_manager::memories::{
+    absorb_memory_column, import_memories_json, stored_memories_json, MemoryOwner,
+};
 use crate::sync::models::{
-    AudioProvider, AudioVoiceCache, Character, CharacterLorebookLink, CharacterRule, GroupMessage,
-    GroupMessageVariant, GroupParticipation, GroupSession, Message, MessageVariant, Model,
-    ModelPricingCache, Persona, PromptTemplate, ProviderCredential, Scene, SceneVariant, Secret,
-    Session, Settings, SyncLorebook, SyncLorebookEntry, UsageMetadata, UsageRecord, UserVoice,
+    AudioProvider, AudioVoiceCache, Character, CharacterLorebookLink, CharacterMemories,
+    CharacterRule, GroupMessage, GroupMessageVariant, GroupParticipation, GroupSession, Message,
+    MessageVariant, Model, ModelPricingCache, Persona, PromptTemplate, ProviderCredential, Scene,
+    SceneVariant, Secret, Session, SessionKnowledgeGraph, Settings, SyncLorebook,
+    SyncLorebookEntry, UsageMetadata, UsageRecord, UserVoice,
 };
 use crate::sync::protocol::{Manifest, ManifestV2, SyncLayer};
 
@@ -615,7 +620,18 @@ fn fetch_characters(conn: &DbConnection, ids: &[String]) -> Result<Vec<u8>, Stri
         .map(|r| r.unwrap())
         .collect();
 
-    bincode::serialize(&(chars, rules, scenes, variants, links))
+    // Long-term memories
+    let mut memories = Vec::new();
+    for id in ids {
+        if let Some(stored) = stored_memories_json(conn, MemoryOwner::Character(id))? {
+            memories.push(CharacterMemories {
+                character_id: id.clone(),
+                memories: stored,
+            });
+        }
+    }
+
+    bincode::serialize(&(chars, rules, scenes, variants, links, memories))
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
 }
 
@@ -630,7 +646,7 @@ fn fetch_sessions(conn: &DbConnection, ids: &[String]) -> Result<Vec<u8>, String
     let mut stmt = conn
         .prepare(&sql)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let sessions: Vec<Session> = stmt
+    let mut sessions: Vec<Session> = stmt
         .query_map(rusqlite::params_from_iter(ids.iter()), |r| {
             Ok(Session {
                 id: r.get(0)?,
@@ -663,6 +679,13 @@ fn fetch_sessions(conn: &DbConnection, ids: &[String]) -> Result<Vec<u8>, String
         .map(|r| r.unwrap())
         .collect();
 
+    // Memories live in their own table; ship them in the legacy column so the wire format is unchanged.
+    for session in &mut sessions {
+        if let Some(stored) = stored_memories_json(conn, MemoryOwner::Session(&session.id))? {
+            session.memory_embeddings = stored;
+        }
+    }
+
     // Messages
     let sql_msg = format!("SELECT id, session_id, role, content, created_at, prompt_tokens, completion_tokens, total_tokens, selected_variant_id, is_pinned, memory_refs, used_lorebook_entries, attachments, reasoning FROM messages WHERE session_id IN ({})", placeholders);
     let mut stmt = conn
@@ -766,7 +789,18 @@ fn fetch_sessions(conn: &DbConnection, ids: &[String]) -> Result<Vec<u8>, String
         .map(|r| r.unwrap())
         .collect();
 
-    bincode::serialize(&(sessions, messages, variants, usages, metadata))
+    // Knowledge graphs, one per session so an emptied graph also clears on the peer
+    let mut graphs = Vec::new();
+    for id in ids {
+        let graph = serde_json::to_string(&read_graph(conn, id)?)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        graphs.push(SessionKnowledgeGraph {
+            session_id: id.clone(),
+            graph,
+        });
+    }
+
+    bincode::serialize(&(sessions, messages, variants, usages, metadata, graphs))
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
 }
 
@@ -780,7 +814,7 @@ fn fetch_group_sessions(conn: &DbConnection, ids: &[String]) -> Result<Vec<u8>,
     let mut stmt = conn
         .prepare(&sql)
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
-    let sessions: Vec<GroupSession> = stmt
+    let mut sessions: Vec<GroupSession> = stmt
         .query_map(rusqlite::params_from_iter(ids.iter()), |r| {
             Ok(GroupSession {
                 id: r.get(0)?,
@@ -804,6 +838,12 @@ fn fetch_group_sessions(conn: &DbConnection, ids: &[String]) -> Result<Vec<u8>,
         .map(|r| r.unwrap())
         .collect();
 
+    for session in &mut sessions {
+        if let Some(stored) = stored_memories_json(conn, MemoryOwner::GroupSession(&session.id))? {
+            session.memory_embeddings = stored;
+        }
+    }
+
     let sql_part = format!("SELECT id, session_id, character_id, speak_count, last_spoke_turn, last_spoke_at FROM group_participation WHERE session_id IN ({})", placeholders);
     let mut stmt = conn
         .prepare(&sql_part)
@@ -1190,6 +1230,15 @@ type CharactersData = (
     Vec<Scene>,
     Vec<SceneVariant>,
     Vec<CharacterLorebookLink>,
+    Vec<CharacterMemories>,
+);
+
+type LegacyCharactersDataV2 = (
+    Vec<Character>,
+    Vec<CharacterRule>,
+    Vec<Scene>,
+    Vec<SceneVariant>,
+    Vec<CharacterLorebookLink>,
 );
 
 type LegacyCharactersDataV1 = (
@@ -1296,14 +1345,17 @@ struct LegacySceneV0 {
 }
 
 fn apply_characters(conn: &mut DbConnection, data: &[u8]) -> Result<(), String> {
-    let (chars, rules, scenes, variants, links) = deserialize_characters(data)?;
+    let (chars, rules, scenes, variants, links, memories) = deserialize_characters(data)?;
     let tx = conn
         .transaction()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
     for c in chars {
-        tx.execute(r#"INSERT OR REPLACE INTO characters (id, name, avatar_path, avatar_crop_x, avatar_crop_y, avatar_crop_scale, background_image_path, description, definition, default_scene_id, default_model_id, memory_type, prompt_template_id, system_prompt, voice_config, voice_autoplay, disable_avatar_gradient, custom_gradient_enabled, custom_gradient_colors, custom_text_color, custom_text_secondary, created_at, updated_at)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)"#,
+        // An upsert, not INSERT OR REPLACE: replacing deletes the row first, which cascades to
+        // the character's sessions and long-term memories.
+        tx.execute(r#"INSERT INTO characters (id, name, avatar_path, avatar_crop_x, avatar_crop_y, avatar_crop_scale, background_image_path, description, definition, default_scene_id, default_model_id, memory_type, prompt_template_id, system_prompt, voice_config, voice_autoplay, disable_avatar_gradient, custom_gradient_enabled, custom_gradient_colors, custom_text_color, custom_text_secondary, created_at, updated_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)
+                    ON CONFLICT(id) DO UPDATE SET name = excluded.name, avatar_path = excluded.avatar_path, avatar_crop_x = excluded.avatar_crop_x, avatar_crop_y = excluded.avatar_crop_y, avatar_crop_scale = excluded.avatar_crop_scale, background_image_path = excluded.background_image_path, description = excluded.description, definition = excluded.definition, default_scene_id = excluded.default_scene_id, default_model_id = excluded.default_model_id, memory_type = excluded.memory_type, prompt_template_id = excluded.prompt_template_id, system_prompt = excluded.system_prompt, voice_config = excluded.voice_config, voice_autoplay = excluded.voice_autoplay, disable_avatar_gradient = excluded.disable_avatar_gradient, custom_gradient_enabled = excluded.custom_gradient_enabled, custom_gradient_colors = excluded.custom_gradient_colors, custom_text_color = excluded.custom_text_color, custom_text_secondary = excluded.custom_text_secondary, created_at = excluded.created_at, updated_at = excluded.updated_at"#,
                     params![c.id, c.name, c.avatar_path, c.avatar_crop_x, c.avatar_crop_y, c.avatar_crop_scale, c.background_image_path, c.description, c.definition, c.default_scene_id, c.default_model_id, c.memory_type, c.prompt_template_id, c.system_prompt, c.voice_config, c.voice_autoplay, c.disable_avatar_gradient, c.custom_gradient_enabled, c.custom_gradient_colors, c.custom_text_color, c.custom_text_secondary, c.created_at, c.updated_at]).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
 
@@ -1338,27 +1390,40 @@ fn apply_characters(conn: &mut DbConnection, data: &[u8]) -> Result<(), String>
                     params![l.character_id, l.lorebook_id, l.enabled, l.display_order, l.created_at, l.updated_at]).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
 
+    for m in memories {
+        let owner = MemoryOwner::Character(&m.character_id);
+        let updated_at: i64 = tx
+            .query_row(
+                "SELECT updated_at FROM characters WHERE id = ?1",
+                params![m.character_id],
+                |r| r.get(0),
+            )
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        import_memories_json(&tx, owner, &m.memories)?;
+        // Keep the sender's timestamp so the write doesn't read as a newer local edit.
+        tx.execute(
+            "UPDATE characters SET updated_at = ?1 WHERE id = ?2",
+            params![updated_at, m.character_id],
+        )
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+
     tx.commit()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     Ok(())
 }
 
-fn deserialize_characters(
-    data: &[u8],
-) -> Result<
-    (
-        Vec<Character>,
-        Vec<CharacterRule>,
-        Vec<Scene>,
-        Vec<SceneVariant>,
-        Vec<CharacterLorebookLink>,
-    ),
-    String,
-> {
+fn deserialize_characters(data: &[u8]) -> Result<CharactersData, String> {
     if let Ok(payload) = bincode::deserialize::<CharactersData>(data) {
         return Ok(payload);
     }
 
+    if let Ok((chars, rules, scenes, variants, links)) =
+        bincode::deserialize::<LegacyCharactersDataV2>(data)
+    {
+        return Ok((chars, rules, scenes, variants, links, Vec::new()));
+    }
+
     if let Ok((chars, rules, scenes, variants, links)) =
         bincode::deserialize::<LegacyCharactersDataV1>(data)
     {
@@ -1400,7 +1465,14 @@ fn deserialize_characters(
                 selected_variant_id: s.selected_variant_id,
             })
             .collect();
-        return Ok((mapped_chars, rules, mapped_scenes, variants, links));
+        return Ok((
+            mapped_chars,
+            rules,
+            mapped_scenes,
+            variants,
+            links,
+            Vec::new(),
+        ));
     }
 
     if let Ok((chars, rules, scenes, variants, links)) =
@@ -1444,7 +1516,14 @@ fn deserialize_characters(
                 selected_variant_id: s.selected_variant_id,
             })
             .collect();
-        return Ok((mapped_chars, rules, mapped_scenes, variants, links));
+        return Ok((
+            mapped_chars,
+            rules,
+            mapped_scenes,
+            variants,
+            links,
+            Vec::new(),
+        ));
     }
 
     if let Ok((chars, rules, scenes, variants, links)) =
@@ -1488,7 +1567,14 @@ fn deserialize_characters(
                 selected_variant_id: None,
             })
             .collect();
-        return Ok((mapped_chars, rules, mapped_scenes, variants, links));
+        return Ok((
+            mapped_chars,
+            rules,
+            mapped_scenes,
+            variants,
+            links,
+            Vec::new(),
+        ));
     }
 
     bincode::deserialize::<CharactersData>(data)
@@ -1501,6 +1587,15 @@ type SessionsData = (
     Vec<MessageVariant>,
     Vec<UsageRecord>,
     Vec<UsageMetadata>,
+    Vec<SessionKnowledgeGraph>,
+);
+
+type LegacySessionsDataV2 = (
+    Vec<Session>,
+    Vec<Message>,
+    Vec<MessageVariant>,
+    Vec<UsageRecord>,
+    Vec<UsageMetadata>,
 );
 
 type LegacySessionsDataV1 = (
@@ -1607,15 +1702,19 @@ struct LegacyMessageVariantV1 {
 }
 
 fn apply_sessions(conn: &mut DbConnection, data: &[u8]) -> Result<(), String> {
-    let (sessions, messages, variants, usages, metadata) = deserialize_sessions(data)?;
+    let (sessions, messages, variants, usages, metadata, graphs) = deserialize_sessions(data)?;
     let tx = conn
         .transaction()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
     for s in sessions {
-        tx.execute(r#"INSERT OR REPLACE INTO sessions (id, character_id, title, system_prompt, selected_scene_id, persona_id, persona_disabled, voice_autoplay, temperature, top_p, max_output_tokens, frequency_penalty, presence_penalty, top_k, memories, memory_embeddings, memory_summary, memory_summary_token_count, memory_tool_events, archived, created_at, updated_at, memory_status, memory_error)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)"#,
+        // An upsert, not INSERT OR REPLACE: replacing deletes the row first, which cascades to
+        // the session's memories, timeline, memory jobs and knowledge graph.
+        tx.execute(r#"INSERT INTO sessions (id, character_id, title, system_prompt, selected_scene_id, persona_id, persona_disabled, voice_autoplay, temperature, top_p, max_output_tokens, frequency_penalty, presence_penalty, top_k, memories, memory_embeddings, memory_summary, memory_summary_token_count, memory_tool_events, archived, created_at, updated_at, memory_status, memory_error)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)
+                    ON CONFLICT(id) DO UPDATE SET character_id = excluded.character_id, title = excluded.title, system_prompt = excluded.system_prompt, selected_scene_id = excluded.selected_scene_id, persona_id = excluded.persona_id, persona_disabled = excluded.persona_disabled, voice_autoplay = excluded.voice_autoplay, temperature = excluded.temperature, top_p = excluded.top_p, max_output_tokens = excluded.max_output_tokens, frequency_penalty = excluded.frequency_penalty, presence_penalty = excluded.presence_penalty, top_k = excluded.top_k, memories = excluded.memories, memory_embeddings = excluded.memory_embeddings, memory_summary = excluded.memory_summary, memory_summary_token_count = excluded.memory_summary_token_count, memory_tool_events = excluded.memory_tool_events, archived = excluded.archived, created_at = excluded.created_at, updated_at = excluded.updated_at, memory_status = excluded.memory_status, memory_error = excluded.memory_error"#,
                     params![s.id, s.character_id, s.title, s.system_prompt, s.selected_scene_id, s.persona_id, s.persona_disabled, s.voice_autoplay, s.temperature, s.top_p, s.max_output_tokens, s.frequency_penalty, s.presence_penalty, s.top_k, s.memories, s.memory_embeddings, s.memory_summary, s.memory_summary_token_count, s.memory_tool_events, s.archived, s.created_at, s.updated_at, s.memory_status, s.memory_error]).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        absorb_memory_column(&tx, MemoryOwner::Session(&s.id))?;
     }
 
     for m in messages {
@@ -1649,6 +1748,12 @@ fn apply_sessions(conn: &mut DbConnection, data: &[u8]) -> Result<(), String> {
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     }
 
+    for g in graphs {
+        let graph: KnowledgeGraph = serde_json::from_str(&g.graph)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        replace_graph(&tx, &g.session_id, &graph)?;
+    }
+
     tx.commit()
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
     Ok(())
@@ -1667,9 +1772,12 @@ fn apply_group_sessions(conn: &mut DbConnection, data: &[u8]) -> Result<(), Stri
         .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
 
     for s in sessions {
-        tx.execute(r#"INSERT OR REPLACE INTO group_sessions (id, name, character_ids, persona_id, created_at, updated_at, archived, chat_type, starting_scene, background_image_path, memories, memory_embeddings, memory_summary, memory_summary_token_count, memory_tool_events)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"#,
+        // Upsert for the same reason as sessions: REPLACE would cascade to memories and jobs.
+        tx.execute(r#"INSERT INTO group_sessions (id, name, character_ids, persona_id, created_at, updated_at, archived, chat_type, starting_scene, background_image_path, memories, memory_embeddings, memory_summary, memory_summary_token_count, memory_tool_events)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                    ON CONFLICT(id) DO UPDATE SET name = excluded.name, character_ids = excluded.character_ids, persona_id = excluded.persona_id, created_at = excluded.created_at, updated_at = excluded.updated_at, archived = excluded.archived, chat_type = excluded.chat_type, starting_scene = excluded.starting_scene, background_image_path = excluded.background_image_path, memories = excluded.memories, memory_embeddings = excluded.memory_embeddings, memory_summary = excluded.memory_summary, memory_summary_token_count = excluded.memory_summary_token_count, memory_tool_events = excluded.memory_tool_events"#,
                     params![s.id, s.name, s.character_ids, s.persona_id, s.created_at, s.updated_at, s.archived, s.chat_type, s.starting_scene, s.background_image_path, s.memories, s.memory_embeddings, s.memory_summary, s.memory_summary_token_count, s.memory_tool_events]).map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+        absorb_memory_column(&tx, MemoryOwner::GroupSession(&s.id))?;
     }
 
     for p in participation {
@@ -1709,22 +1817,17 @@ fn apply_group_sessions(conn: &mut DbConnection, data: &[u8]) -> Result<(), Stri
     Ok(())
 }
 
-fn deserialize_sessions(
-    data: &[u8],
-) -> Result<
-    (
-        Vec<Session>,
-        Vec<Message>,
-        Vec<MessageVariant>,
-        Vec<UsageRecord>,
-        Vec<UsageMetadata>,
-    ),
-    String,
-> {
+fn deserialize_sessions(data: &[u8]) -> Result<SessionsData, String> {
     if let Ok(payload) = bincode::deserialize::<SessionsData>(data) {
         return Ok(payload);
     }
 
+    if let Ok((sessions, messages, variants, usages, metadata)) =
+        bincode::deserialize::<LegacySessionsDataV2>(data)
+    {
+        return Ok((sessions, messages, variants, usages, metadata, Vec::new()));
+    }
+
     if let Ok((sessions, messages, variants, usages, metadata)) =
         bincode::deserialize::<LegacySessionsDataV1>(data)
     {
@@ -1795,6 +1898,7 @@ fn deserialize_sessions(
             mapped_variants,
             usages,
             metadata,
+            Vec::new(),
         ));
     }
 
@@ -1868,6 +1972,7 @@ fn deserialize_sessions(
             mapped_variants,
             usages,
             metadata,
+            Vec::new(),
         ));
     }
 
@@ -213,6 +213,13 @@ pub struct CharacterLorebookLink {
     pub updated_at: i64,
 }
 
+/// A character's long-term memories, as the JSON array of the `memories` table rows.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterMemories {
+    pub character_id: String,
+    pub memories: String,
+}
+
 // Layer 4: Sessions
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -308,6 +315,13 @@ pub struct UsageMetadata {
     pub value: String,
 }
 
+/// A session's knowledge graph, as the JSON of `kg_entities` and `kg_relations` rows.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionKnowledgeGraph {
+    pub session_id: String,
+    pub graph: String,
+}
+
 // Layer 5: Group Sessions
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,29 +1,373 @@
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
 use tauri::AppHandle;
+use tiktoken_rs::CoreBPE;
 use tokenizers::Tokenizer;
 
-static TOKENIZER: Mutex<Option<Tokenizer>> = Mutex::new(None);
+use crate::chat_manager::types::Model;
+use crate::utils::{emit_toast, log_info, log_warn};
+
+/// OpenAI BPE rank tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BpeRanks {
+    /// GPT-4o, GPT-4.1, GPT-5 and the o-series.
+    O200k,
+    /// GPT-4 and GPT-3.5.
+    Cl100k,
+}
+
+/// Which tokenizer a model is counted with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum TokenizerSpec {
+    Tiktoken(BpeRanks),
+    /// Vocabulary of the local GGUF model, configured as a file path or a library id.
+    Gguf(String),
+    /// `tokenizer.json` of a public Hugging Face repo sharing the family's vocabulary.
+    HuggingFace(&'static str),
+    Heuristic,
+}
+
+impl TokenizerSpec {
+    fn label(&self) -> String {
+        match self {
+            TokenizerSpec::Tiktoken(BpeRanks::O200k) => "o200k_base".to_string(),
+            TokenizerSpec::Tiktoken(BpeRanks::Cl100k) => "cl100k_base".to_string(),
+            TokenizerSpec::Gguf(_) => "gguf".to_string(),
+            TokenizerSpec::HuggingFace(repo) => format!("hf:{}", repo),
+            TokenizerSpec::Heuristic => "heuristic".to_string(),
+        }
+    }
+}
+
+/// Open-weight families and a public repo carrying their tokenizer, checked in order against
+/// the model name.
+const HF_FAMILIES: &[(&[&str], &str)] = &[
+    (
+        &["llama-3", "llama3"],
+        "NousResearch/Meta-Llama-3-8B-Instruct",
+    ),
+    (&["qwen"], "Qwen/Qwen2.5-7B-Instruct"),
+    (&["deepseek"], "deepseek-ai/DeepSeek-V3"),
+    // Nemo, Ministral and Pixtral moved to the 131k Tekken vocabulary; the older models
+    // share the 32k SentencePiece one.
+    (
+        &["mistral-nemo", "ministral", "pixtral"],
+        "unsloth/Mistral-Nemo-Instruct-2407",
+    ),
+    (
+        &["mistral", "mixtral", "codestral"],
+        "unsloth/mistral-7b-instruct-v0.3",
+    ),
+    (&["gemma"], "unsloth/gemma-2-9b-it"),
+    (&["phi-3", "phi3", "phi-4", "phi4"], "microsoft/phi-4"),
+];
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCount {
+    pub tokens: u32,
+    pub tokenizer: String,
+    /// False when the count is a heuristic estimate.
+    pub exact: bool,
+}
+
+enum LoadedTokenizer {
+    Bpe(Arc<CoreBPE>),
+    HuggingFace(Arc<Tokenizer>),
+}
+
+static LOADED: Mutex<Option<HashMap<TokenizerSpec, LoadedTokenizer>>> = Mutex::new(None);
+/// Repos whose tokenizer download was started this run; failures are not retried until restart.
+static REQUESTED_DOWNLOADS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+fn openai_ranks(name: &str) -> Option<BpeRanks> {
+    const O200K_PREFIXES: &[&str] = &[
+        "gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "gpt-oss", "chatgpt", "o1", "o3", "o4",
+    ];
+    if O200K_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+        return Some(BpeRanks::O200k);
+    }
+    if name.starts_with("gpt-4")
+        || name.starts_with("gpt-3.5")
+        || name.starts_with("text-embedding")
+    {
+        return Some(BpeRanks::Cl100k);
+    }
+    None
+}
+
+fn resolve_spec(provider_id: &str, model_name: &str) -> TokenizerSpec {
+    if crate::llama_cpp::is_llama_cpp(Some(provider_id)) {
+        return TokenizerSpec::Gguf(model_name.to_string());
+    }
+    // Routers prefix the vendor ("openai/gpt-4o"), fine-tunes use "ft:".
+    let name = model_name
+        .rsplit('/')
+        .next()
+        .unwrap_or(model_name)
+        .trim_start_matches("ft:")
+        .to_ascii_lowercase();
+    if let Some(ranks) = openai_ranks(&name) {
+        return TokenizerSpec::Tiktoken(ranks);
+    }
+    if let Some((_, repo)) = HF_FAMILIES
+        .iter()
+        .find(|(markers, _)| markers.iter().any(|marker| name.contains(marker)))
+    {
+        return TokenizerSpec::HuggingFace(repo);
+    }
+    if provider_id == "openai" {
+        return TokenizerSpec::Tiktoken(BpeRanks::O200k);
+    }
+    TokenizerSpec::Heuristic
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// Rough count for models without a known tokenizer: about four characters per token, one
+/// per CJK character.
+pub fn estimate_tokens(text: &str) -> u32 {
+    let (cjk, other) = text.chars().fold((0u32, 0u32), |(cjk, other), c| {
+        if is_cjk(c) {
+            (cjk + 1, other)
+        } else {
+            (cjk, other + 1)
+        }
+    });
+    cjk + other.div_ceil(4)
+}
+
+fn loaded(spec: &TokenizerSpec) -> Option<LoadedTokenizer> {
+    let guard = LOADED.lock().ok()?;
+    match guard.as_ref()?.get(spec)? {
+        LoadedTokenizer::Bpe(bpe) => Some(LoadedTokenizer::Bpe(bpe.clone())),
+        LoadedTokenizer::HuggingFace(tokenizer) => {
+            Some(LoadedTokenizer::HuggingFace(tokenizer.clone()))
+        }
+    }
+}
+
+fn remember(spec: TokenizerSpec, tokenizer: LoadedTokenizer) {
+    if let Ok(mut guard) = LOADED.lock() {
+        guard
+            .get_or_insert_with(HashMap::new)
+            .insert(spec, tokenizer);
+    }
+}
 
-/// Get or initialize the global tokenizer instance
-fn get_tokenizer(app: &AppHandle) -> Result<Tokenizer, String> {
-    let mut tokenizer_lock = TOKENIZER.lock().map_err(|e| {
+fn load_bpe(ranks: BpeRanks) -> Result<Arc<CoreBPE>, String> {
+    let spec = TokenizerSpec::Tiktoken(ranks);
+    if let Some(LoadedTokenizer::Bpe(bpe)) = loaded(&spec) {
+        return Ok(bpe);
+    }
+    let bpe = match ranks {
+        BpeRanks::O200k => tiktoken_rs::o200k_base(),
+        BpeRanks::Cl100k => tiktoken_rs::cl100k_base(),
+    }
+    .map(Arc::new)
+    .map_err(|e| {
         crate::utils::err_msg(
             module_path!(),
             line!(),
-            format!("Failed to lock tokenizer: {}", e),
+            format!("Failed to load {} ranks: {}", spec.label(), e),
         )
     })?;
+    remember(spec, LoadedTokenizer::Bpe(bpe.clone()));
+    Ok(bpe)
+}
 
-    if tokenizer_lock.is_none() {
-        let model_dir = crate::embedding_model::embedding_model_dir(app)?;
-        let tokenizer_path = model_dir.join("tokenizer.json");
+fn hf_tokenizer_path(app: &AppHandle, repo: &str) -> Result<PathBuf, String> {
+    Ok(crate::utils::lettuce_dir(app)?
+        .join("models")
+        .join("tokenizers")
+        .join(repo.replace('/', "--"))
+        .join("tokenizer.json"))
+}
 
-        if !tokenizer_path.exists() {
-            return Err(
-                "Tokenizer not found. Please download the embedding model first.".to_string(),
+async fn download_hf_tokenizer(repo: &str, path: &Path) -> Result<(), String> {
+    let url = format!(
+        "https://huggingface.co/{}/resolve/main/tokenizer.json",
+        repo
+    );
+    let bytes = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?
+        .bytes()
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    Tokenizer::from_bytes(&bytes).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Invalid tokenizer.json from {}: {}", repo, e),
+        )
+    })?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))?;
+    }
+    let partial = path.with_extension("json.part");
+    std::fs::write(&partial, &bytes)
+        .and_then(|_| std::fs::rename(&partial, path))
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+/// Loads the cached tokenizer of `repo`. When it isn't on disk yet a background download is
+/// started and `None` returned, so callers fall back to the estimate until it lands.
+fn load_hf_tokenizer(
+    app: &AppHandle,
+    repo: &'static str,
+) -> Result<Option<Arc<Tokenizer>>, String> {
+    let spec = TokenizerSpec::HuggingFace(repo);
+    if let Some(LoadedTokenizer::HuggingFace(tokenizer)) = loaded(&spec) {
+        return Ok(Some(tokenizer));
+    }
+    let path = hf_tokenizer_path(app, repo)?;
+    if !path.exists() {
+        let mut requested = REQUESTED_DOWNLOADS
+            .lock()
+            .map_err(|e| crate::utils::err_msg(module_path!(), line!(), e.to_string()))?;
+        if !requested.contains(&repo) {
+            requested.push(repo);
+            let app = app.clone();
+            log_info(
+                &app,
+                "tokenizer",
+                format!("downloading tokenizer.json from huggingface.co/{}", repo),
             );
+            emit_toast(
+                &app,
+                "info",
+                "Downloading tokenizer",
+                Some(format!(
+                    "Fetching the {} tokenizer for exact token counts.",
+                    repo
+                )),
+            );
+            tauri::async_runtime::spawn(async move {
+                match download_hf_tokenizer(repo, &path).await {
+                    Ok(()) => log_info(
+                        &app,
+                        "tokenizer",
+                        format!("downloaded tokenizer of {}", repo),
+                    ),
+                    Err(err) => log_warn(
+                        &app,
+                        "tokenizer",
+                        format!("tokenizer download for {} failed: {}", repo, err),
+                    ),
+                }
+            });
+        }
+        return Ok(None);
+    }
+    let tokenizer = Tokenizer::from_file(&path).map(Arc::new).map_err(|e| {
+        crate::utils::err_msg(
+            module_path!(),
+            line!(),
+            format!("Failed to load tokenizer from {:?}: {}", path, e),
+        )
+    })?;
+    remember(spec, LoadedTokenizer::HuggingFace(tokenizer.clone()));
+    Ok(Some(tokenizer))
+}
+
+/// Exact count with `spec`, or `None` when its tokenizer isn't available right now.
+fn count_with_spec(
+    app: &AppHandle,
+    spec: &TokenizerSpec,
+    text: &str,
+) -> Result<Option<u32>, String> {
+    match spec {
+        TokenizerSpec::Tiktoken(ranks) => {
+            Ok(Some(load_bpe(*ranks)?.encode_ordinary(text).len() as u32))
+        }
+        TokenizerSpec::Gguf(model) => {
+            // Models picked from the library are configured by id rather than path.
+            let model_path = crate::llama_cpp::library::resolve_model_path(app, model)?;
+            crate::llama_cpp::count_gguf_tokens(&model_path, text)
         }
+        TokenizerSpec::HuggingFace(repo) => {
+            let Some(tokenizer) = load_hf_tokenizer(app, repo)? else {
+                return Ok(None);
+            };
+            let encoding = tokenizer.encode(text, false).map_err(|e| {
+                crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Tokenization failed: {}", e),
+                )
+            })?;
+            Ok(Some(encoding.get_ids().len() as u32))
+        }
+        TokenizerSpec::Heuristic => Ok(None),
+    }
+}
 
+fn count_spec_tokens(app: &AppHandle, spec: &TokenizerSpec, text: &str) -> TokenCount {
+    if text.is_empty() {
+        return TokenCount {
+            tokens: 0,
+            tokenizer: spec.label(),
+            exact: true,
+        };
+    }
+    match count_with_spec(app, spec, text) {
+        Ok(Some(tokens)) => TokenCount {
+            tokens,
+            tokenizer: spec.label(),
+            exact: true,
+        },
+        result => {
+            if let Err(err) = result {
+                log_warn(app, "tokenizer", err);
+            }
+            TokenCount {
+                tokens: estimate_tokens(text),
+                tokenizer: TokenizerSpec::Heuristic.label(),
+                exact: false,
+            }
+        }
+    }
+}
+
+/// Counts `text` with the tokenizer of `model`'s family, falling back to the estimate when the
+/// model is unknown or its tokenizer can't be loaded.
+pub fn count_model_tokens(app: &AppHandle, model: Option<&Model>, text: &str) -> u32 {
+    let spec = model
+        .map(|model| resolve_spec(&model.provider_id, &model.name))
+        .unwrap_or(TokenizerSpec::Heuristic);
+    count_spec_tokens(app, &spec, text).tokens
+}
+
+/// Like [`count_model_tokens`] for a stored model id; `None` uses the default chat model.
+pub fn count_tokens_for_model_id(app: &AppHandle, model_id: Option<&str>, text: &str) -> u32 {
+    let settings = match crate::chat_manager::storage::load_settings(app) {
+        Ok(settings) => settings,
+        Err(_) => return estimate_tokens(text),
+    };
+    let model_id = model_id.or(settings.default_model_id.as_deref());
+    let model = model_id.and_then(|id| settings.models.iter().find(|m| m.id == id));
+    count_model_tokens(app, model, text)
+}
+
+static EMBEDDING_TOKENIZER: Mutex<Option<Arc<Tokenizer>>> = Mutex::new(None);
+
+/// Counts `text` with the embedding model's tokenizer, as token counts were computed before
+/// they followed the chat model. Kept for the v15 -> v16 migration.
+pub(crate) fn count_embedding_tokens(app: &AppHandle, text: &str) -> Result<u32, String> {
+    let mut guard = EMBEDDING_TOKENIZER
+        .lock()
+        .map_err(|e| crate::utils::err_msg(module_path!(), line!(), e.to_string()))?;
+    if guard.is_none() {
+        let tokenizer_path =
+            crate::embedding_model::embedding_model_dir(app)?.join("tokenizer.json");
         let tokenizer = Tokenizer::from_file(&tokenizer_path).map_err(|e| {
             crate::utils::err_msg(
                 module_path!(),
@@ -31,30 +375,98 @@ fn get_tokenizer(app: &AppHandle) -> Result<Tokenizer, String> {
                 format!("Failed to load tokenizer from {:?}: {}", tokenizer_path, e),
             )
         })?;
-
-        *tokenizer_lock = Some(tokenizer);
+        *guard = Some(Arc::new(tokenizer));
     }
-
-    Ok(tokenizer_lock.as_ref().unwrap().clone())
-}
-
-/// Count tokens in a text string using the embedding model's tokenizer
-pub fn count_tokens(app: &AppHandle, text: &str) -> Result<u32, String> {
-    let tokenizer = get_tokenizer(app)?;
-
-    let encoding = tokenizer.encode(text, false).map_err(|e| {
+    let encoding = guard.as_ref().unwrap().encode(text, false).map_err(|e| {
         crate::utils::err_msg(
             module_path!(),
             line!(),
             format!("Tokenization failed: {}", e),
         )
     })?;
-
     Ok(encoding.get_ids().len() as u32)
 }
 
-/// Count tokens for multiple texts
-#[allow(dead_code)]
-pub fn count_tokens_batch(app: &AppHandle, texts: &[String]) -> Result<Vec<u32>, String> {
-    texts.iter().map(|text| count_tokens(app, text)).collect()
+/// Counts `text` for `model_id`, which is a configured model id or else a raw model name.
+#[tauri::command]
+pub async fn count_tokens(
+    app: AppHandle,
+    model_id: String,
+    text: String,
+) -> Result<TokenCount, String> {
+    let settings = crate::chat_manager::storage::load_settings(&app)?;
+    let spec = match settings.models.iter().find(|m| m.id == model_id) {
+        Some(model) => resolve_spec(&model.provider_id, &model.name),
+        None => resolve_spec("", &model_id),
+    };
+    tauri::async_runtime::spawn_blocking(move || count_spec_tokens(&app, &spec, &text))
+        .await
+        .map_err(|e| crate::utils::err_to_string(module_path!(), line!(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_tokenizer_by_model_family() {
+        let cases = [
+            (
+                "openai",
+                "gpt-4o-mini",
+                TokenizerSpec::Tiktoken(BpeRanks::O200k),
+            ),
+            (
+                "openrouter",
+                "openai/gpt-4-turbo",
+                TokenizerSpec::Tiktoken(BpeRanks::Cl100k),
+            ),
+            (
+                "openai",
+                "o3-mini",
+                TokenizerSpec::Tiktoken(BpeRanks::O200k),
+            ),
+            (
+                "openai",
+                "my-proxy-model",
+                TokenizerSpec::Tiktoken(BpeRanks::O200k),
+            ),
+            (
+                "llamacpp",
+                "/models/qwen.gguf",
+                TokenizerSpec::Gguf("/models/qwen.gguf".to_string()),
+            ),
+            (
+                "llamacpp",
+                "5f0c6a2e-0d7b-4c1e-9a53-2f1d8b7e4c10",
+                TokenizerSpec::Gguf("5f0c6a2e-0d7b-4c1e-9a53-2f1d8b7e4c10".to_string()),
+            ),
+            (
+                "ollama",
+                "llama3.1:8b",
+                TokenizerSpec::HuggingFace("NousResearch/Meta-Llama-3-8B-Instruct"),
+            ),
+            (
+                "openrouter",
+                "mistralai/Mixtral-8x7B-Instruct",
+                TokenizerSpec::HuggingFace("unsloth/mistral-7b-instruct-v0.3"),
+            ),
+            (
+                "mistral",
+                "mistral-nemo-instruct-2407",
+                TokenizerSpec::HuggingFace("unsloth/Mistral-Nemo-Instruct-2407"),
+            ),
+            ("anthropic", "claude-sonnet-4", TokenizerSpec::Heuristic),
+        ];
+        for (provider, name, expected) in cases {
+            assert_eq!(resolve_spec(provider, name), expected, "{provider}/{name}");
+        }
+    }
+
+    #[test]
+    fn estimate_counts_cjk_per_character() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world!"), 3);
+        assert_eq!(estimate_tokens("こんにちは"), 5);
+    }
 }
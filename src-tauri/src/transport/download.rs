@@ -0,0 +1,245 @@
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+pub(crate) enum DownloadError {
+    /// Network errors and 5xx / 408 / 429 responses.
+    Retryable(String),
+    Fatal(String),
+}
+
+impl DownloadError {
+    pub(crate) fn into_message(self) -> String {
+        match self {
+            DownloadError::Retryable(msg) | DownloadError::Fatal(msg) => msg,
+        }
+    }
+}
+
+/// Partial file a download of `url` into `dest_path` writes to. It is keyed by the URL so a
+/// different source for the same destination never continues these bytes.
+pub(crate) fn part_path(dest_path: &Path, url: &str) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let file_name = dest_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dest_path.with_file_name(format!("{}.{}.part", file_name, &digest[..12]))
+}
+
+/// Removes every partial file left for `dest_path`, whatever URL it came from.
+pub(crate) fn remove_part_files(dest_path: &Path) {
+    let (Some(dir), Some(file_name)) = (dest_path.parent(), dest_path.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}.", file_name.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(&prefix) && name.ends_with(".part") {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Total size from a `Content-Range` header such as `bytes */1234` or `bytes 0-9/1234`.
+fn content_range_total(value: &str) -> Option<u64> {
+    value.rsplit('/').next()?.trim().parse().ok()
+}
+
+/// A download into a partial file that continues the bytes an earlier attempt left, with an
+/// HTTP range request, and is moved into place by [`ResumableDownload::finish`].
+pub(crate) struct ResumableDownload {
+    dest_path: PathBuf,
+    part_path: PathBuf,
+    /// `None` once the partial file already holds the whole body.
+    response: Option<reqwest::Response>,
+    file: tokio::fs::File,
+    /// Bytes kept from the earlier attempt; 0 when the download starts over.
+    pub(crate) resumed_from: u64,
+    /// Bytes the response still carries, when the server says.
+    pub(crate) remaining: u64,
+    pub(crate) status: reqwest::StatusCode,
+}
+
+impl ResumableDownload {
+    pub(crate) async fn start(
+        client: &reqwest::Client,
+        url: &str,
+        dest_path: &Path,
+    ) -> Result<Self, DownloadError> {
+        let part_path = part_path(dest_path, url);
+        let mut existing = tokio::fs::metadata(&part_path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        let (response, status) = loop {
+            let mut request = client.get(url);
+            if existing > 0 {
+                request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+            }
+            let response = request.send().await.map_err(|e| {
+                DownloadError::Retryable(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to start download: {}", e),
+                ))
+            })?;
+            let status = response.status();
+            if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+                let total = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(content_range_total);
+                if total == Some(existing) {
+                    break (None, status);
+                }
+                // The partial file doesn't match what the server holds; start over.
+                let _ = tokio::fs::remove_file(&part_path).await;
+                existing = 0;
+                continue;
+            }
+            if !status.is_success() {
+                let message = crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Download failed with status: {}", status),
+                );
+                return Err(
+                    if status.is_server_error()
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    {
+                        DownloadError::Retryable(message)
+                    } else {
+                        DownloadError::Fatal(message)
+                    },
+                );
+            }
+            if status != reqwest::StatusCode::PARTIAL_CONTENT {
+                // The server ignored the range.
+                existing = 0;
+            }
+            break (Some(response), status);
+        };
+
+        let remaining = response
+            .as_ref()
+            .and_then(|response| response.content_length())
+            .unwrap_or(0);
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(existing > 0)
+            .truncate(existing == 0)
+            .open(&part_path)
+            .await
+            .map_err(|e| {
+                DownloadError::Fatal(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to create file: {}", e),
+                ))
+            })?;
+        Ok(Self {
+            dest_path: dest_path.to_path_buf(),
+            part_path,
+            response,
+            file,
+            resumed_from: existing,
+            remaining,
+            status,
+        })
+    }
+
+    /// Writes the next chunk of the body to the partial file and returns it, or `None` at the
+    /// end of the body.
+    pub(crate) async fn next_chunk(&mut self) -> Result<Option<Bytes>, DownloadError> {
+        let Some(response) = self.response.as_mut() else {
+            return Ok(None);
+        };
+        let Some(chunk) = response.chunk().await.map_err(|e| {
+            DownloadError::Retryable(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Error reading chunk: {}", e),
+            ))
+        })?
+        else {
+            self.response = None;
+            return Ok(None);
+        };
+        self.file.write_all(&chunk).await.map_err(|e| {
+            DownloadError::Fatal(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Error writing to file: {}", e),
+            ))
+        })?;
+        Ok(Some(chunk))
+    }
+
+    /// Flushes what was written so far; the partial file stays for the next attempt.
+    pub(crate) async fn flush(&mut self) -> Result<(), DownloadError> {
+        self.file.flush().await.map_err(|e| {
+            DownloadError::Fatal(crate::utils::err_msg(
+                module_path!(),
+                line!(),
+                format!("Error flushing file: {}", e),
+            ))
+        })
+    }
+
+    /// Drops the partial file so the next attempt starts over.
+    pub(crate) async fn discard(self) {
+        drop(self.file);
+        let _ = tokio::fs::remove_file(&self.part_path).await;
+    }
+
+    /// Moves the finished file to the destination.
+    pub(crate) async fn finish(mut self) -> Result<(), DownloadError> {
+        self.flush().await?;
+        drop(self.file);
+        tokio::fs::rename(&self.part_path, &self.dest_path)
+            .await
+            .map_err(|e| {
+                DownloadError::Fatal(crate::utils::err_msg(
+                    module_path!(),
+                    line!(),
+                    format!("Failed to rename file: {}", e),
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_file_is_keyed_by_url() {
+        let dest = Path::new("/models/model.gguf");
+        let first = part_path(dest, "https://a.example/model.gguf");
+        assert_eq!(first.parent(), dest.parent());
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("model.gguf."));
+        assert_eq!(first, part_path(dest, "https://a.example/model.gguf"));
+        assert_ne!(first, part_path(dest, "https://b.example/model.gguf"));
+    }
+
+    #[test]
+    fn reads_total_from_content_range() {
+        assert_eq!(content_range_total("bytes */1234"), Some(1234));
+        assert_eq!(content_range_total("bytes 0-9/50"), Some(50));
+        assert_eq!(content_range_total("bytes 0-9/*"), None);
+    }
+}
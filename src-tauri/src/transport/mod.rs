@@ -1,3 +1,5 @@
+pub(crate) mod download;
+
 use serde_json::{json, Value};
 use std::time::Duration;
 use tauri::Emitter;
@@ -43,6 +45,11 @@ pub fn emit_normalized(app: &tauri::AppHandle, request_id: &str, event: Normaliz
             "type": "toolCall",
             "data": calls,
         }),
+        NormalizedEvent::ContextShift { discarded, pinned } => json!({
+            "requestId": request_id,
+            "type": "contextShift",
+            "data": { "discarded": discarded, "pinned": pinned },
+        }),
         NormalizedEvent::Error { envelope } => json!({
             "requestId": request_id,
             "type": "error",
@@ -8,6 +8,9 @@ export interface AdvancedSettings {
   helpMeReplyEnabled?: boolean;
   manualModeContextWindow?: number;
   embeddingMaxTokens?: number; // 1024, 2048, or 4096
+  embeddingBackend?: "onnx" | "remote" | "custom";
+  embeddingRemote?: { baseUrl: string; model: string; apiKey?: string; dimensions?: number };
+  embeddingCustomModelId?: string;
   accessibility?: {
     send: { enabled: boolean; volume: number };
     success: { enabled: boolean; volume: number };
@@ -19,11 +22,18 @@ export interface AdvancedSettings {
     maxEntries: number;
     minSimilarityThreshold: number;
     retrievalLimit: number;
-    retrievalStrategy: "smart" | "cosine";
+    retrievalStrategy: "smart" | "cosine" | "hybrid";
     hotMemoryTokenBudget: number;
     decayRate: number;
+    decayModel?: "perCycle" | "halfLife";
+    decayHalfLifeHours?: number;
     coldThreshold: number;
     contextEnrichmentEnabled?: boolean;
+    rerankerEnabled?: boolean;
+    consolidationEnabled?: boolean;
+    consolidationThreshold?: number;
+    characterMemoryEnabled?: boolean;
+    knowledgeGraphEnabled?: boolean;
   };
   groupDynamicMemory?: {
     enabled: boolean;
@@ -31,11 +41,18 @@ export interface AdvancedSettings {
     maxEntries: number;
     minSimilarityThreshold: number;
     retrievalLimit: number;
-    retrievalStrategy: "smart" | "cosine";
+    retrievalStrategy: "smart" | "cosine" | "hybrid";
     hotMemoryTokenBudget: number;
     decayRate: number;
+    decayModel?: "perCycle" | "halfLife";
+    decayHalfLifeHours?: number;
     coldThreshold: number;
     contextEnrichmentEnabled?: boolean;
+    rerankerEnabled?: boolean;
+    consolidationEnabled?: boolean;
+    consolidationThreshold?: number;
+    characterMemoryEnabled?: boolean;
+    knowledgeGraphEnabled?: boolean;
   };
 }
 
@@ -1,6 +1,47 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
+import type {
+  CustomEmbeddingModel,
+  CustomEmbeddingModelConfig,
+  DynamicMemorySettings,
+  MemoryDecayTrajectory,
+  MemoryJob,
+  RemoteEmbeddingConfig,
+} from "./schemas";
+
+export type LocalGgufModel = {
+  id: string;
+  path: string;
+  fileName: string;
+  sizeBytes: number;
+  architecture: string | null;
+  name: string | null;
+  parameterCount: number | null;
+  quantization: string | null;
+  contextLength: number | null;
+  chatTemplate: string | null;
+  sourceUrl: string | null;
+  createdAt: number;
+  updatedAt: number;
+};
+
+export type LocalGgufModelFit = {
+  availableMemoryBytes: number | null;
+  modelSizeBytes: number;
+  kvBytesPerToken: number | null;
+  maxContextLength: number | null;
+  recommendedContextLength: number | null;
+  fits: boolean | null;
+};
+
+export type LocalGgufDownloadProgress = {
+  url: string;
+  fileName: string;
+  downloaded: number;
+  total: number;
+  status: "downloading" | "cancelled" | "completed";
+};
 
 async function readJsonCommand<T>(
   command: string,
@@ -63,9 +104,51 @@ export const storageBridge = {
       advancedJson: advanced == null ? "null" : JSON.stringify(advanced),
     }) as Promise<void>,
   abortRequest: (requestId: string) => invoke("abort_request", { requestId }) as Promise<void>,
+  countTokens: (modelId: string, text: string) =>
+    invoke<{ tokens: number; tokenizer: string; exact: boolean }>("count_tokens", {
+      modelId,
+      text,
+    }),
+
+  // Local GGUF model library (llama.cpp)
+  llamacppLibraryList: () => invoke<LocalGgufModel[]>("llamacpp_library_list"),
+  llamacppLibraryRegister: (path: string) =>
+    invoke<LocalGgufModel>("llamacpp_library_register", { path }),
+  llamacppLibraryRemove: (id: string, deleteFile = false) =>
+    invoke("llamacpp_library_remove", { id, deleteFile }) as Promise<void>,
+  llamacppLibraryCheckFit: (id: string) =>
+    invoke<LocalGgufModelFit>("llamacpp_library_check_fit", { id }),
+  llamacppLibraryDownload: (url: string, fileName?: string) =>
+    invoke<LocalGgufModel>("llamacpp_library_download", { url, fileName: fileName ?? null }),
+  llamacppLibraryCancelDownload: (url: string) =>
+    invoke("llamacpp_library_cancel_download", { url }) as Promise<void>,
+  listenToLlamacppDownloadProgress: (callback: (progress: LocalGgufDownloadProgress) => void) =>
+    listen<LocalGgufDownloadProgress>("llamacpp_download_progress", (event) =>
+      callback(event.payload),
+    ),
 
   // Embedding model download
   checkEmbeddingModel: () => invoke<boolean>("check_embedding_model"),
+  checkRerankerModel: () => invoke<boolean>("check_reranker_model"),
+  downloadRerankerModel: () => invoke("download_reranker_model") as Promise<void>,
+  listenToRerankerDownloadProgress: (
+    callback: (progress: {
+      downloaded: number;
+      total: number;
+      status: string;
+      currentFileIndex: number;
+      totalFiles: number;
+      currentFileName: string;
+    }) => void,
+  ) =>
+    listen<{
+      downloaded: number;
+      total: number;
+      status: string;
+      currentFileIndex: number;
+      totalFiles: number;
+      currentFileName: string;
+    }>("reranker_download_progress", (event) => callback(event.payload)),
   getEmbeddingModelInfo: () =>
     invoke<{
       installed: boolean;
@@ -74,7 +157,30 @@ export const storageBridge = {
       selectedSourceVersion?: string | null;
       availableVersions?: string[];
       maxTokens: number;
+      backend?: "onnx" | "remote";
+      backendId?: string | null;
+      dimension?: number | null;
     }>("get_embedding_model_info"),
+  testEmbeddingBackend: (config?: RemoteEmbeddingConfig) =>
+    invoke<number>("test_embedding_backend", { config: config ?? null }),
+  getEmbeddingReembedStatus: () =>
+    invoke<{ running: boolean; backendId: string | null; pending: number; total: number }>(
+      "get_embedding_reembed_status",
+    ),
+  listenToEmbeddingReembedProgress: (
+    callback: (progress: {
+      done: number;
+      total: number;
+      status: "running" | "completed" | "failed";
+      error?: string;
+    }) => void,
+  ) =>
+    listen<{
+      done: number;
+      total: number;
+      status: "running" | "completed" | "failed";
+      error?: string;
+    }>("embedding-reembed:progress", (event) => callback(event.payload)),
   startEmbeddingDownload: (version?: string) =>
     invoke("start_embedding_download", { version: version ?? null }) as Promise<void>,
   getEmbeddingDownloadProgress: () =>
@@ -105,7 +211,38 @@ export const storageBridge = {
       currentFileName: string;
     }>("embedding_download_progress", (event) => callback(event.payload)),
   cancelEmbeddingDownload: () => invoke("cancel_embedding_download") as Promise<void>,
+  verifyEmbeddingModel: (version?: string) =>
+    invoke<{
+      source: string;
+      checkedAgainst: "installed" | "remote" | "none";
+      ok: boolean;
+      files: {
+        name: string;
+        status: "ok" | "missing" | "sizeMismatch" | "checksumMismatch" | "unverified";
+        expectedSize: number | null;
+        actualSize: number | null;
+      }[];
+    }>("verify_embedding_model", { version: version ?? null }),
   computeEmbedding: (text: string) => invoke<number[]>("compute_embedding", { text }),
+  computeEmbeddings: (texts: string[]) => invoke<number[][]>("compute_embeddings", { texts }),
+  embeddingCacheStats: () =>
+    invoke<{
+      entries: number;
+      sizeBytes: number;
+      models: string[];
+      hits: number;
+      misses: number;
+    }>("embedding_cache_stats"),
+  embeddingCacheClear: () => invoke<number>("embedding_cache_clear"),
+  listCustomEmbeddingModels: () => invoke<CustomEmbeddingModel[]>("list_custom_embedding_models"),
+  registerCustomEmbeddingModel: (config: CustomEmbeddingModelConfig) =>
+    invoke<CustomEmbeddingModel>("register_custom_embedding_model", { config }),
+  updateCustomEmbeddingModel: (id: string, config: CustomEmbeddingModelConfig) =>
+    invoke<CustomEmbeddingModel>("update_custom_embedding_model", { id, config }),
+  deleteCustomEmbeddingModel: (id: string) =>
+    invoke("delete_custom_embedding_model", { id }) as Promise<void>,
+  validateCustomEmbeddingModel: (id: string) =>
+    invoke<CustomEmbeddingModel>("validate_custom_embedding_model", { id }),
   initializeEmbeddingModel: () => invoke("initialize_embedding_model") as Promise<void>,
   clearEmbeddingRuntimeCache: () => invoke("clear_embedding_runtime_cache") as Promise<void>,
   runEmbeddingTest: () =>
@@ -279,6 +416,77 @@ export const storageBridge = {
     invoke<string | null>("session_set_memory_cold_state", { sessionId, memoryIndex, isCold }).then(
       (s) => (typeof s === "string" ? JSON.parse(s) : null),
     ),
+  sessionUndoMemoryMerge: (sessionId: string, memoryId: string) =>
+    invoke<string | null>("session_undo_memory_merge", { sessionId, memoryId }).then((s) =>
+      typeof s === "string" ? JSON.parse(s) : null,
+    ),
+  sessionShareMemory: (sessionId: string, memoryId: string) =>
+    invoke<string>("session_share_memory", { sessionId, memoryId }).then(
+      (s) => JSON.parse(s) as any[],
+    ),
+  sessionMemoryTimeline: (sessionId: string, memoryId?: string) =>
+    invoke<string>("session_memory_timeline", { sessionId, memoryId: memoryId ?? null }).then(
+      (s) => JSON.parse(s) as any[],
+    ),
+  sessionMemoriesExport: (sessionId: string, includeEmbeddings = true) =>
+    invoke<string>("session_memories_export", { sessionId, includeEmbeddings }),
+  sessionMemoriesImport: (sessionId: string, importJson: string, replace = false) =>
+    invoke<string | null>("session_memories_import", { sessionId, importJson, replace }).then(
+      (s) => (typeof s === "string" ? JSON.parse(s) : null),
+    ),
+  characterMemoriesList: (characterId: string) =>
+    invoke<string>("character_memories_list", { characterId }).then(
+      (s) => JSON.parse(s) as any[],
+    ),
+  characterMemoryUpdate: (
+    characterId: string,
+    memoryId: string,
+    newMemory: string,
+    newCategory?: string,
+  ) =>
+    invoke<string>("character_memory_update", {
+      characterId,
+      memoryId,
+      newMemory,
+      newCategory: newCategory ?? null,
+    }).then((s) => JSON.parse(s) as any[]),
+  characterMemoryDelete: (characterId: string, memoryId: string) =>
+    invoke<string>("character_memory_delete", { characterId, memoryId }).then(
+      (s) => JSON.parse(s) as any[],
+    ),
+  characterMemoryTimeline: (characterId: string, memoryId?: string) =>
+    invoke<string>("character_memory_timeline", {
+      characterId,
+      memoryId: memoryId ?? null,
+    }).then((s) => JSON.parse(s) as any[]),
+  sessionKnowledgeGraph: (sessionId: string) =>
+    invoke<string>("session_knowledge_graph", { sessionId }).then((s) => JSON.parse(s)),
+  knowledgeGraphEntityUpdate: (
+    sessionId: string,
+    entityId: string,
+    name: string,
+    kind: string,
+    description?: string,
+  ) =>
+    invoke<string>("knowledge_graph_entity_update", {
+      sessionId,
+      entityId,
+      name,
+      kind,
+      description: description ?? null,
+    }).then((s) => JSON.parse(s)),
+  knowledgeGraphEntityDelete: (sessionId: string, entityId: string) =>
+    invoke<string>("knowledge_graph_entity_delete", { sessionId, entityId }).then((s) =>
+      JSON.parse(s),
+    ),
+  knowledgeGraphRelationUpdate: (sessionId: string, relationId: string, relation: string) =>
+    invoke<string>("knowledge_graph_relation_update", { sessionId, relationId, relation }).then(
+      (s) => JSON.parse(s),
+    ),
+  knowledgeGraphRelationDelete: (sessionId: string, relationId: string) =>
+    invoke<string>("knowledge_graph_relation_delete", { sessionId, relationId }).then((s) =>
+      JSON.parse(s),
+    ),
 
   // Messages (paged)
   messagesList: (sessionId: string, limit: number, beforeCreatedAt?: number, beforeId?: string) =>
@@ -305,6 +513,24 @@ export const storageBridge = {
     }) as Promise<void>,
   triggerDynamicMemory: (sessionId: string) =>
     invoke("trigger_dynamic_memory", { sessionId }) as Promise<void>,
+  simulateDynamicMemoryDecay: (
+    sessionId: string,
+    overrides?: DynamicMemorySettings,
+    horizonHours?: number,
+    stepHours?: number,
+    cyclesPerDay?: number,
+  ) =>
+    invoke<MemoryDecayTrajectory[]>("simulate_dynamic_memory_decay", {
+      sessionId,
+      overrides: overrides ?? null,
+      horizonHours: horizonHours ?? null,
+      stepHours: stepHours ?? null,
+      cyclesPerDay: cyclesPerDay ?? null,
+    }),
+  memoryJobsList: (sessionId?: string) =>
+    invoke<MemoryJob[]>("memory_jobs_list", { sessionId: sessionId ?? null }),
+  memoryJobRetry: (jobId: string) => invoke("memory_job_retry", { jobId }) as Promise<void>,
+  memoryJobCancel: (jobId: string) => invoke("memory_job_cancel", { jobId }) as Promise<void>,
   usageSummary: () =>
     invoke("storage_usage_summary") as Promise<{
       fileCount: number;
@@ -578,6 +804,20 @@ export const storageBridge = {
     invoke<string>("group_chat_get_selection_prompt", { sessionId, userMessage }),
   groupChatRetryDynamicMemory: (sessionId: string) =>
     invoke("group_chat_retry_dynamic_memory", { sessionId }) as Promise<void>,
+  groupChatSimulateMemoryDecay: (
+    sessionId: string,
+    overrides?: DynamicMemorySettings,
+    horizonHours?: number,
+    stepHours?: number,
+    cyclesPerDay?: number,
+  ) =>
+    invoke<MemoryDecayTrajectory[]>("group_chat_simulate_memory_decay", {
+      sessionId,
+      overrides: overrides ?? null,
+      horizonHours: horizonHours ?? null,
+      stepHours: stepHours ?? null,
+      cyclesPerDay: cyclesPerDay ?? null,
+    }),
 
   // Group Session Memory Operations
   groupSessionUpdateMemories: (
@@ -624,6 +864,24 @@ export const storageBridge = {
       memoryIndex,
       isCold,
     }).then((s) => (typeof s === "string" ? JSON.parse(s) : null)),
+  groupSessionUndoMemoryMerge: (sessionId: string, memoryId: string) =>
+    invoke<string | null>("group_session_undo_memory_merge", {
+      sessionId,
+      memoryId,
+    }).then((s) => (typeof s === "string" ? JSON.parse(s) : null)),
+  groupSessionMemoryTimeline: (sessionId: string, memoryId?: string) =>
+    invoke<string>("group_session_memory_timeline", {
+      sessionId,
+      memoryId: memoryId ?? null,
+    }).then((s) => JSON.parse(s) as any[]),
+  groupSessionMemoriesExport: (sessionId: string, includeEmbeddings = true) =>
+    invoke<string>("group_session_memories_export", { sessionId, includeEmbeddings }),
+  groupSessionMemoriesImport: (sessionId: string, importJson: string, replace = false) =>
+    invoke<string | null>("group_session_memories_import", {
+      sessionId,
+      importJson,
+      replace,
+    }).then((s) => (typeof s === "string" ? JSON.parse(s) : null)),
 
   backupPickFile: async (): Promise<{ path: string; filename: string } | null> => {
     try {
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Lorebook } from "./schemas";
+import type { Lorebook, LorebookEntry } from "./schemas";
 
 export async function exportLorebook(lorebookId: string): Promise<string> {
   try {
@@ -10,9 +10,14 @@ export async function exportLorebook(lorebookId: string): Promise<string> {
   }
 }
 
-export async function importLorebook(importJson: string): Promise<Lorebook> {
+function fallbackLorebookName(fileName?: string): string | null {
+  return fileName?.replace(/\.[^.]+$/, "").trim() || null;
+}
+
+export async function importLorebook(importJson: string, fileName?: string): Promise<Lorebook> {
   try {
-    const lorebookJson = await invoke<string>("lorebook_import", { importJson });
+    const fallbackName = fallbackLorebookName(fileName);
+    const lorebookJson = await invoke<string>("lorebook_import", { importJson, fallbackName });
     return JSON.parse(lorebookJson) as Lorebook;
   } catch (error) {
     console.error("[importLorebook] Failed to import lorebook:", error);
@@ -20,6 +25,47 @@ export async function importLorebook(importJson: string): Promise<Lorebook> {
   }
 }
 
+export type LorebookFormatInfo = {
+  id: string;
+  label: string;
+  extension: string;
+};
+
+export type LorebookImportPreview = {
+  format: string;
+  name: string;
+  entries: LorebookEntry[];
+  unmappedFields: { path: string; occurrences: number }[];
+  warnings: string[];
+};
+
+export async function detectLorebookFormat(importJson: string): Promise<LorebookFormatInfo> {
+  try {
+    const formatJson = await invoke<string>("lorebook_detect_format", { importJson });
+    return JSON.parse(formatJson) as LorebookFormatInfo;
+  } catch (error) {
+    console.error("[detectLorebookFormat] Failed to detect lorebook format:", error);
+    throw new Error(typeof error === "string" ? error : "Unsupported lorebook file format");
+  }
+}
+
+/** Parses an import file without writing it, listing fields that won't be imported. */
+export async function previewLorebookImport(
+  importJson: string,
+  fileName?: string,
+): Promise<LorebookImportPreview> {
+  try {
+    const previewJson = await invoke<string>("lorebook_import_preview", {
+      importJson,
+      fallbackName: fallbackLorebookName(fileName),
+    });
+    return JSON.parse(previewJson) as LorebookImportPreview;
+  } catch (error) {
+    console.error("[previewLorebookImport] Failed to preview lorebook:", error);
+    throw new Error(typeof error === "string" ? error : "Failed to read lorebook");
+  }
+}
+
 export async function downloadJson(json: string, filename: string): Promise<void> {
   try {
     const savedPath = await invoke<string>("save_json_to_downloads", {
@@ -0,0 +1,25 @@
+import { storageBridge } from "./files";
+import { downloadJson } from "./lorebookTransfer";
+
+export function generateMemoryExportFilename(sessionTitle: string): string {
+  const safeName = sessionTitle.replace(/[^a-z0-9_-]/gi, "_").toLowerCase();
+  const timestamp = new Date().toISOString().split("T")[0];
+  return `memories_${safeName || "session"}_${timestamp}.json`;
+}
+
+export async function downloadSessionMemories(
+  sessionId: string,
+  sessionTitle: string,
+  options: { group?: boolean; includeEmbeddings?: boolean } = {},
+): Promise<void> {
+  const includeEmbeddings = options.includeEmbeddings ?? true;
+  try {
+    const json = options.group
+      ? await storageBridge.groupSessionMemoriesExport(sessionId, includeEmbeddings)
+      : await storageBridge.sessionMemoriesExport(sessionId, includeEmbeddings);
+    await downloadJson(json, generateMemoryExportFilename(sessionTitle));
+  } catch (error) {
+    console.error("[downloadSessionMemories] Failed to export memories:", error);
+    throw new Error(typeof error === "string" ? error : "Failed to export memories");
+  }
+}
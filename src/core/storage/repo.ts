@@ -780,6 +780,9 @@ export async function getEmbeddingModelInfo(): Promise<{
   selectedSourceVersion?: string | null;
   availableVersions?: string[];
   maxTokens: number;
+  backend?: "onnx" | "remote";
+  backendId?: string | null;
+  dimension?: number | null;
 }> {
   return storageBridge.getEmbeddingModelInfo();
 }
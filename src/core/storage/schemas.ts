@@ -64,6 +64,28 @@ export const AdvancedModelSettingsSchema = z.object({
   llamaRopeFreqBase: z.number().min(0).max(1_000_000).nullable().optional(),
   llamaRopeFreqScale: z.number().min(0).max(10).nullable().optional(),
   llamaOffloadKqv: z.boolean().nullable().optional(),
+  llamaMinP: z.number().min(0).max(1).nullable().optional(),
+  llamaTypicalP: z.number().min(0).max(1).nullable().optional(),
+  llamaRepeatPenalty: z.number().min(0).max(2).nullable().optional(),
+  llamaRepeatLastN: z.number().int().min(-1).max(8192).nullable().optional(),
+  llamaMirostat: z.number().int().min(0).max(2).nullable().optional(),
+  llamaMirostatTau: z.number().min(0).max(10).nullable().optional(),
+  llamaMirostatEta: z.number().min(0).max(1).nullable().optional(),
+  llamaDryMultiplier: z.number().min(0).max(5).nullable().optional(),
+  llamaDryBase: z.number().min(1).max(4).nullable().optional(),
+  llamaDryAllowedLength: z.number().int().min(0).max(100).nullable().optional(),
+  llamaDryPenaltyLastN: z.number().int().min(-1).max(8192).nullable().optional(),
+  llamaXtcProbability: z.number().min(0).max(1).nullable().optional(),
+  llamaXtcThreshold: z.number().min(0).max(0.5).nullable().optional(),
+  llamaSamplerOrder: z.array(z.string().min(1)).nullable().optional(),
+  llamaLoraAdapters: z
+    .array(z.object({ path: z.string().min(1), scale: z.number().min(-4).max(4) }))
+    .nullable()
+    .optional(),
+  llamaStateBudgetMb: z.number().int().min(0).max(65_536).nullable().optional(),
+  llamaDraftModel: z.string().min(1).nullable().optional(),
+  llamaDraftMax: z.number().int().min(0).max(64).nullable().optional(),
+  llamaMmprojPath: z.string().min(1).nullable().optional(),
   // Ollama specific settings
   ollamaNumCtx: z.number().int().min(0).max(262_144).nullable().optional(),
   ollamaNumPredict: z.number().int().min(0).max(131_072).nullable().optional(),
@@ -326,6 +348,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -365,6 +406,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -404,6 +464,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: true,
       ollamaNumPredict: true,
       ollamaNumKeep: true,
@@ -443,6 +522,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -482,6 +580,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -521,6 +638,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -560,6 +696,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -599,6 +754,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -638,6 +812,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -677,6 +870,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -716,6 +928,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -755,6 +986,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -794,6 +1044,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -833,6 +1102,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -872,6 +1160,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -911,6 +1218,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -950,6 +1276,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -989,6 +1334,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -1025,6 +1389,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: true,
       llamaRopeFreqScale: true,
       llamaOffloadKqv: true,
+      llamaMinP: true,
+      llamaTypicalP: true,
+      llamaRepeatPenalty: true,
+      llamaRepeatLastN: true,
+      llamaMirostat: true,
+      llamaMirostatTau: true,
+      llamaMirostatEta: true,
+      llamaDryMultiplier: true,
+      llamaDryBase: true,
+      llamaDryAllowedLength: true,
+      llamaDryPenaltyLastN: true,
+      llamaXtcProbability: true,
+      llamaXtcThreshold: true,
+      llamaSamplerOrder: true,
+      llamaLoraAdapters: true,
+      llamaStateBudgetMb: true,
+      llamaDraftModel: true,
+      llamaDraftMax: true,
+      llamaMmprojPath: true,
       reasoningEnabled: false,
       reasoningEffort: false,
       reasoningBudgetTokens: false,
@@ -1067,6 +1450,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -1106,6 +1508,25 @@ export const PROVIDER_PARAMETER_SUPPORT = {
       llamaRopeFreqBase: false,
       llamaRopeFreqScale: false,
       llamaOffloadKqv: false,
+      llamaMinP: false,
+      llamaTypicalP: false,
+      llamaRepeatPenalty: false,
+      llamaRepeatLastN: false,
+      llamaMirostat: false,
+      llamaMirostatTau: false,
+      llamaMirostatEta: false,
+      llamaDryMultiplier: false,
+      llamaDryBase: false,
+      llamaDryAllowedLength: false,
+      llamaDryPenaltyLastN: false,
+      llamaXtcProbability: false,
+      llamaXtcThreshold: false,
+      llamaSamplerOrder: false,
+      llamaLoraAdapters: false,
+      llamaStateBudgetMb: false,
+      llamaDraftModel: false,
+      llamaDraftMax: false,
+      llamaMmprojPath: false,
       ollamaNumCtx: false,
       ollamaNumPredict: false,
       ollamaNumKeep: false,
@@ -1230,6 +1651,19 @@ export type StoredMessage = z.infer<typeof MessageSchema>;
 // Group Chat Schemas
 // ============================================================================
 
+/** What produced a memory; origin is memory_cycle, user, merge or shared */
+export const MemoryProvenanceSchema = z.object({
+  origin: z.string(),
+  startMessageId: z.string().optional(),
+  endMessageId: z.string().optional(),
+  modelId: z.string().optional(),
+  promptTemplateId: z.string().optional(),
+  mergedFrom: z.array(z.string()).optional(),
+  sourceMemoryId: z.string().optional(),
+  sourceSessionId: z.string().optional(),
+});
+export type MemoryProvenance = z.infer<typeof MemoryProvenanceSchema>;
+
 export const GroupMemoryEmbeddingSchema = z.object({
   id: z.string(),
   text: z.string(),
@@ -1242,6 +1676,7 @@ export const GroupMemoryEmbeddingSchema = z.object({
   accessCount: z.number().int().default(0),
   isPinned: z.boolean().default(false),
   category: z.string().nullable().optional(),
+  provenance: MemoryProvenanceSchema.optional(),
 });
 export type GroupMemoryEmbedding = z.infer<typeof GroupMemoryEmbeddingSchema>;
 
@@ -1269,14 +1704,42 @@ export const DynamicMemorySettingsSchema = z.object({
   maxEntries: z.number().min(10).max(200).default(50),
   minSimilarityThreshold: z.number().min(0).max(1).default(0.35),
   retrievalLimit: z.number().min(1).max(20).default(5),
-  retrievalStrategy: z.enum(["smart", "cosine"]).default("smart"),
+  retrievalStrategy: z.enum(["smart", "cosine", "hybrid"]).default("smart"),
   hotMemoryTokenBudget: z.number().min(500).max(10000).default(2000),
   decayRate: z.number().min(0.01).max(0.3).default(0.08),
+  decayModel: z.enum(["perCycle", "halfLife"]).optional(),
+  decayHalfLifeHours: z.number().min(1).max(2160).optional(),
   coldThreshold: z.number().min(0.1).max(0.5).default(0.3),
   contextEnrichmentEnabled: z.boolean().default(true),
+  rerankerEnabled: z.boolean().optional(),
+  consolidationEnabled: z.boolean().optional(),
+  consolidationThreshold: z.number().min(0.5).max(0.99).optional(),
+  characterMemoryEnabled: z.boolean().optional(),
+  knowledgeGraphEnabled: z.boolean().optional(),
 });
 export type DynamicMemorySettings = z.infer<typeof DynamicMemorySettingsSchema>;
 
+/** Projected importance of one memory if never retrieved again (decay simulation) */
+export type MemoryDecayTrajectory = {
+  memoryId: string;
+  text: string;
+  points: { hours: number; importanceScore: number; isCold: boolean }[];
+  coldAfterHours: number | null;
+};
+
+export type MemoryJob = {
+  id: string;
+  sessionId: string | null;
+  groupSessionId: string | null;
+  status: "queued" | "running" | "failed";
+  attempts: number;
+  maxAttempts: number;
+  lastError: string | null;
+  runAt: number;
+  createdAt: number;
+  updatedAt: number;
+};
+
 export const GroupSessionSchema = z.object({
   id: z.string().uuid(),
   name: z.string(),
@@ -1520,6 +1983,43 @@ export function createDefaultAppState(): AppState {
   };
 }
 
+/** OpenAI-compatible `/v1/embeddings` endpoint (OpenAI, Ollama, llama.cpp server). */
+export const RemoteEmbeddingConfigSchema = z.object({
+  baseUrl: z.string(),
+  model: z.string(),
+  apiKey: z.string().optional(),
+  dimensions: z.number().int().positive().optional(),
+});
+export type RemoteEmbeddingConfig = z.infer<typeof RemoteEmbeddingConfigSchema>;
+
+/** User-supplied ONNX embedding model: a directory with the model and its tokenizer.json. */
+export type CustomEmbeddingModelConfig = {
+  name: string;
+  dir: string;
+  modelFile?: string;
+  tokenizerFile?: string;
+  /** Output tensor to read; the model's first output when unset */
+  outputName?: string | null;
+  pooling: "mean" | "cls" | "lastToken";
+  normalize: boolean;
+  maxSeqLength: number;
+  dimension: number;
+};
+
+export type CustomEmbeddingModel = CustomEmbeddingModelConfig & {
+  id: string;
+  modelFile: string;
+  tokenizerFile: string;
+  createdAt: number;
+  /** Set when the last validation passed; only validated models can be activated */
+  validatedAt: number | null;
+  validation: {
+    passed: boolean;
+    checks: { name: string; passed: boolean; detail: string }[];
+    checkedAt: number;
+  } | null;
+};
+
 export const SettingsSchema = z.object({
   $version: z.literal(2),
   defaultProviderCredentialId: z.string().uuid().nullable(),
@@ -1545,6 +2045,9 @@ export const SettingsSchema = z.object({
       embeddingMaxTokens: z.number().optional(), // 1024, 2048, or 4096
       embeddingModelVersion: z.enum(["v2", "v3"]).optional(),
       embeddingKeepModelLoaded: z.boolean().optional(),
+      embeddingBackend: z.enum(["onnx", "remote", "custom"]).optional(),
+      embeddingRemote: RemoteEmbeddingConfigSchema.optional(),
+      embeddingCustomModelId: z.string().optional(),
       dynamicMemory: DynamicMemorySettingsSchema.optional(),
       groupDynamicMemory: DynamicMemorySettingsSchema.optional(),
       accessibility: AccessibilitySettingsSchema.optional(),
@@ -1675,6 +2178,7 @@ export const SessionSchema = z.object({
         lastAccessedAt: z.number().int().default(0),
         isPinned: z.boolean().default(false),
         category: z.string().nullable().optional(),
+        provenance: MemoryProvenanceSchema.optional(),
       }),
     )
     .default([])
@@ -17,6 +17,22 @@ export const ADVANCED_LLAMA_THREADS_BATCH_RANGE = { min: 1, max: 256 };
 export const ADVANCED_LLAMA_SEED_RANGE = { min: 0, max: 2_147_483_647 };
 export const ADVANCED_LLAMA_ROPE_FREQ_BASE_RANGE = { min: 0, max: 1_000_000 };
 export const ADVANCED_LLAMA_ROPE_FREQ_SCALE_RANGE = { min: 0, max: 10 };
+export const ADVANCED_LLAMA_MIROSTAT_RANGE = { min: 0, max: 2 };
+export const ADVANCED_LLAMA_MIN_P_RANGE = { min: 0, max: 1 };
+export const ADVANCED_LLAMA_TYPICAL_P_RANGE = { min: 0, max: 1 };
+export const ADVANCED_LLAMA_REPEAT_PENALTY_RANGE = { min: 0, max: 2 };
+export const ADVANCED_LLAMA_REPEAT_LAST_N_RANGE = { min: -1, max: 8192 };
+export const ADVANCED_LLAMA_MIROSTAT_TAU_RANGE = { min: 0, max: 10 };
+export const ADVANCED_LLAMA_MIROSTAT_ETA_RANGE = { min: 0, max: 1 };
+export const ADVANCED_LLAMA_DRY_MULTIPLIER_RANGE = { min: 0, max: 5 };
+export const ADVANCED_LLAMA_DRY_BASE_RANGE = { min: 1, max: 4 };
+export const ADVANCED_LLAMA_DRY_ALLOWED_LENGTH_RANGE = { min: 0, max: 100 };
+export const ADVANCED_LLAMA_DRY_PENALTY_LAST_N_RANGE = { min: -1, max: 8192 };
+export const ADVANCED_LLAMA_XTC_PROBABILITY_RANGE = { min: 0, max: 1 };
+export const ADVANCED_LLAMA_XTC_THRESHOLD_RANGE = { min: 0, max: 0.5 };
+export const ADVANCED_LLAMA_LORA_SCALE_RANGE = { min: -4, max: 4 };
+export const ADVANCED_LLAMA_STATE_BUDGET_MB_RANGE = { min: 0, max: 65_536 };
+export const ADVANCED_LLAMA_DRAFT_MAX_RANGE = { min: 0, max: 64 };
 export const ADVANCED_OLLAMA_NUM_CTX_RANGE = { min: 0, max: 262_144 };
 export const ADVANCED_OLLAMA_NUM_PREDICT_RANGE = { min: 0, max: 131_072 };
 export const ADVANCED_OLLAMA_NUM_KEEP_RANGE = { min: 0, max: 32_768 };
@@ -61,6 +77,17 @@ export function sanitizeAdvancedModelSettings(input: AdvancedModelSettings): Adv
     return cleaned.length > 0 ? cleaned : null;
   };
 
+  const normalizeLoraAdapters = (value: unknown): { path: string; scale: number }[] | null => {
+    if (!Array.isArray(value)) return null;
+    const cleaned = value
+      .map((v) => ({
+        path: typeof v?.path === "string" ? v.path.trim() : "",
+        scale: sanitize(v?.scale, ADVANCED_LLAMA_LORA_SCALE_RANGE, false) ?? 1,
+      }))
+      .filter((v) => v.path.length > 0);
+    return cleaned.length > 0 ? cleaned : null;
+  };
+
   return {
     temperature: sanitize(input.temperature, ADVANCED_TEMPERATURE_RANGE, false),
     topP: sanitize(input.topP, ADVANCED_TOP_P_RANGE, false),
@@ -84,6 +111,49 @@ export function sanitizeAdvancedModelSettings(input: AdvancedModelSettings): Adv
       false,
     ),
     llamaOffloadKqv: input.llamaOffloadKqv ?? null,
+    llamaMinP: sanitize(input.llamaMinP, ADVANCED_LLAMA_MIN_P_RANGE, false),
+    llamaTypicalP: sanitize(input.llamaTypicalP, ADVANCED_LLAMA_TYPICAL_P_RANGE, false),
+    llamaRepeatPenalty: sanitize(
+      input.llamaRepeatPenalty,
+      ADVANCED_LLAMA_REPEAT_PENALTY_RANGE,
+      false,
+    ),
+    llamaRepeatLastN: sanitize(input.llamaRepeatLastN, ADVANCED_LLAMA_REPEAT_LAST_N_RANGE, true),
+    llamaMirostat: sanitize(input.llamaMirostat, ADVANCED_LLAMA_MIROSTAT_RANGE, true),
+    llamaMirostatTau: sanitize(input.llamaMirostatTau, ADVANCED_LLAMA_MIROSTAT_TAU_RANGE, false),
+    llamaMirostatEta: sanitize(input.llamaMirostatEta, ADVANCED_LLAMA_MIROSTAT_ETA_RANGE, false),
+    llamaDryMultiplier: sanitize(
+      input.llamaDryMultiplier,
+      ADVANCED_LLAMA_DRY_MULTIPLIER_RANGE,
+      false,
+    ),
+    llamaDryBase: sanitize(input.llamaDryBase, ADVANCED_LLAMA_DRY_BASE_RANGE, false),
+    llamaDryAllowedLength: sanitize(
+      input.llamaDryAllowedLength,
+      ADVANCED_LLAMA_DRY_ALLOWED_LENGTH_RANGE,
+      true,
+    ),
+    llamaDryPenaltyLastN: sanitize(
+      input.llamaDryPenaltyLastN,
+      ADVANCED_LLAMA_DRY_PENALTY_LAST_N_RANGE,
+      true,
+    ),
+    llamaXtcProbability: sanitize(
+      input.llamaXtcProbability,
+      ADVANCED_LLAMA_XTC_PROBABILITY_RANGE,
+      false,
+    ),
+    llamaXtcThreshold: sanitize(input.llamaXtcThreshold, ADVANCED_LLAMA_XTC_THRESHOLD_RANGE, false),
+    llamaSamplerOrder: normalizeStop(input.llamaSamplerOrder),
+    llamaLoraAdapters: normalizeLoraAdapters(input.llamaLoraAdapters),
+    llamaStateBudgetMb: sanitize(
+      input.llamaStateBudgetMb,
+      ADVANCED_LLAMA_STATE_BUDGET_MB_RANGE,
+      true,
+    ),
+    llamaDraftModel: input.llamaDraftModel?.trim() || null,
+    llamaDraftMax: sanitize(input.llamaDraftMax, ADVANCED_LLAMA_DRAFT_MAX_RANGE, true),
+    llamaMmprojPath: input.llamaMmprojPath?.trim() || null,
     ollamaNumCtx: sanitize(input.ollamaNumCtx, ADVANCED_OLLAMA_NUM_CTX_RANGE, true),
     ollamaNumPredict: sanitize(input.ollamaNumPredict, ADVANCED_OLLAMA_NUM_PREDICT_RANGE, true),
     ollamaNumKeep: sanitize(input.ollamaNumKeep, ADVANCED_OLLAMA_NUM_KEEP_RANGE, true),
@@ -23,6 +23,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { AvatarImage } from "../AvatarImage";
 import { useAvatar } from "../../hooks/useAvatar";
 import { importLorebook, readFileAsText } from "../../../core/storage/lorebookTransfer";
+import { confirmLorebookImport } from "../confirmLorebookImport";
 
 type CreationGoal = "character" | "persona" | "lorebook";
 type CreationStatus = "active" | "previewShown" | "completed" | "cancelled";
@@ -146,7 +147,11 @@ export function CreateMenu({ isOpen, onClose }: { isOpen: boolean; onClose: () =
     try {
       setIsImportingLorebook(true);
       const raw = await readFileAsText(file);
-      const imported = await importLorebook(raw);
+      if (!(await confirmLorebookImport(raw, file.name))) {
+        setIsImportingLorebook(false);
+        return;
+      }
+      const imported = await importLorebook(raw, file.name);
       navigate(`/library/lorebooks/${imported.id}`);
       handleClose();
     } catch (error) {
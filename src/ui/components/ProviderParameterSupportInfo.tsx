@@ -24,6 +24,25 @@ const PARAMETER_LABELS: Record<keyof AdvancedModelSettings, string> = {
   llamaRopeFreqBase: "llama.cpp RoPE Base",
   llamaRopeFreqScale: "llama.cpp RoPE Scale",
   llamaOffloadKqv: "llama.cpp Offload KQV",
+  llamaMinP: "llama.cpp Min P",
+  llamaTypicalP: "llama.cpp Typical P",
+  llamaRepeatPenalty: "llama.cpp Repeat Penalty",
+  llamaRepeatLastN: "llama.cpp Repeat Window",
+  llamaMirostat: "llama.cpp Mirostat",
+  llamaMirostatTau: "llama.cpp Mirostat Tau",
+  llamaMirostatEta: "llama.cpp Mirostat Eta",
+  llamaDryMultiplier: "llama.cpp DRY Multiplier",
+  llamaDryBase: "llama.cpp DRY Base",
+  llamaDryAllowedLength: "llama.cpp DRY Allowed Length",
+  llamaDryPenaltyLastN: "llama.cpp DRY Window",
+  llamaXtcProbability: "llama.cpp XTC Probability",
+  llamaXtcThreshold: "llama.cpp XTC Threshold",
+  llamaSamplerOrder: "llama.cpp Sampler Order",
+  llamaLoraAdapters: "llama.cpp LoRA Adapters",
+  llamaStateBudgetMb: "llama.cpp Saved State Budget",
+  llamaDraftModel: "llama.cpp Draft Model",
+  llamaDraftMax: "llama.cpp Draft Length",
+  llamaMmprojPath: "llama.cpp Vision Projector",
   ollamaNumCtx: "Ollama Num Ctx",
   ollamaNumPredict: "Ollama Num Predict",
   ollamaNumKeep: "Ollama Num Keep",
@@ -59,6 +78,25 @@ const PARAMETER_DESCRIPTIONS: Record<keyof AdvancedModelSettings, string> = {
   llamaRopeFreqBase: "RoPE base frequency override",
   llamaRopeFreqScale: "RoPE frequency scale override",
   llamaOffloadKqv: "Offload KQV ops/KV cache to GPU",
+  llamaMinP: "Min-p sampling (0-1)",
+  llamaTypicalP: "Typical sampling (0-1)",
+  llamaRepeatPenalty: "Penalize repetition (0-2)",
+  llamaRepeatLastN: "Tokens checked (-1 = whole context)",
+  llamaMirostat: "0 = off, 1 or 2 = enabled",
+  llamaMirostatTau: "Target entropy",
+  llamaMirostatEta: "Learning rate",
+  llamaDryMultiplier: "Penalize repeated phrases (0 = off)",
+  llamaDryBase: "Penalty growth per extra token",
+  llamaDryAllowedLength: "Shorter repeats go unpenalized",
+  llamaDryPenaltyLastN: "Tokens scanned (-1 = whole context)",
+  llamaXtcProbability: "Chance to drop top choices (0 = off)",
+  llamaXtcThreshold: "Min probability of dropped tokens",
+  llamaSamplerOrder: "Order samplers are applied in",
+  llamaLoraAdapters: "Adapter files applied over the base model",
+  llamaStateBudgetMb: "Disk space for saved prompt caches (MB, 0 = off)",
+  llamaDraftModel: "Smaller GGUF that proposes tokens to speed up generation",
+  llamaDraftMax: "Tokens drafted per step (0 = off)",
+  llamaMmprojPath: "mmproj GGUF that lets the model see images",
   ollamaNumCtx: "Ollama context window size",
   ollamaNumPredict: "Max tokens to generate",
   ollamaNumKeep: "Tokens to keep from prompt",
@@ -0,0 +1,28 @@
+import { detectLorebookFormat, previewLorebookImport } from "../../core/storage/lorebookTransfer";
+import { confirmBottomMenu } from "./ConfirmBottomMenu";
+
+/** Shows the detected format and the fields that would be lost; resolves true to import. */
+export async function confirmLorebookImport(
+  importJson: string,
+  fileName?: string,
+): Promise<boolean> {
+  const [format, preview] = await Promise.all([
+    detectLorebookFormat(importJson),
+    previewLorebookImport(importJson, fileName),
+  ]);
+  const count = preview.entries.length;
+  const noun = count === 1 ? "entry" : "entries";
+  const parts = [`${format.label}: "${preview.name}" with ${count} ${noun}.`];
+  if (preview.unmappedFields.length > 0) {
+    const fields = preview.unmappedFields
+      .map((field) => `${field.path} (${field.occurrences})`)
+      .join(", ");
+    parts.push(`These fields have no equivalent and won't be imported: ${fields}.`);
+  }
+  parts.push(...preview.warnings);
+  return confirmBottomMenu({
+    title: "Import lorebook?",
+    message: parts.join(" "),
+    confirmLabel: "Import",
+  });
+}
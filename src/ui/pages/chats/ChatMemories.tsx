@@ -25,6 +25,9 @@ import {
   Cpu,
   EllipsisVertical,
   PinOff,
+  Merge,
+  ArrowUpCircle,
+  Network,
 } from "lucide-react";
 import type { Character, Session, StoredMessage, Model } from "../../../core/storage/schemas";
 import {
@@ -501,6 +504,34 @@ const ACTION_STYLES: Record<
     bg: "bg-amber-400/10",
     border: "border-amber-400/20",
   },
+  merge_memories: {
+    icon: Merge,
+    color: "text-violet-300",
+    label: "Merged",
+    bg: "bg-violet-400/10",
+    border: "border-violet-400/20",
+  },
+  share_memory: {
+    icon: ArrowUpCircle,
+    color: "text-sky-300",
+    label: "Shared",
+    bg: "bg-sky-400/10",
+    border: "border-sky-400/20",
+  },
+  record_entity: {
+    icon: Network,
+    color: "text-teal-300",
+    label: "Entity",
+    bg: "bg-teal-400/10",
+    border: "border-teal-400/20",
+  },
+  record_relation: {
+    icon: Network,
+    color: "text-teal-300",
+    label: "Relation",
+    bg: "bg-teal-400/10",
+    border: "border-teal-400/20",
+  },
   done: {
     icon: Check,
     color: "text-blue-300",
@@ -42,6 +42,7 @@ import { convertFileSrc } from "@tauri-apps/api/core";
 import { type as getPlatform } from "@tauri-apps/plugin-os";
 import { impactFeedback } from "@tauri-apps/plugin-haptics";
 import { confirmBottomMenu } from "../../../components/ConfirmBottomMenu";
+import { toast } from "../../../components/toast";
 import {
   consumeThinkDelta,
   createThinkStreamState,
@@ -157,6 +158,20 @@ function isStartingSceneMessage(message: StoredMessage): boolean {
   return message.role === "scene";
 }
 
+/** Tells the user once per reply that a local model dropped its oldest context to keep going. */
+function createContextShiftNotifier() {
+  let notified = false;
+  return (discarded: number) => {
+    if (notified) return;
+    notified = true;
+    toast.info(
+      "Early context dropped",
+      `The model ran out of context and forgot its ${discarded} oldest tokens. ` +
+        "The system prompt was kept.",
+    );
+  };
+}
+
 /**
  * Creates a batched streaming updater that coalesces rapid stream events
  * into a single render per animation frame for better performance.
@@ -970,6 +985,7 @@ export function useChatController(
 
       let unlistenNormalized: UnlistenFn | null = null;
       const streamBatcher = createStreamBatcher(dispatch);
+      const notifyContextShift = createContextShiftNotifier();
       const thinkState = createThinkStreamState();
 
       try {
@@ -1006,6 +1022,8 @@ export function useChatController(
                   reasoning: String(payload.data.text),
                 },
               });
+            } else if (payload && payload.type === "contextShift") {
+              notifyContextShift(Number(payload.data?.discarded) || 0);
             } else if (payload && payload.type === "error" && payload.data?.message) {
               dispatch({ type: "SET_ERROR", payload: String(payload.data.message) });
             }
@@ -1111,6 +1129,7 @@ export function useChatController(
 
       let unlistenNormalized: UnlistenFn | null = null;
       const streamBatcher = createStreamBatcher(dispatch);
+      const notifyContextShift = createContextShiftNotifier();
       const thinkState = createThinkStreamState();
 
       try {
@@ -1144,6 +1163,8 @@ export function useChatController(
                   reasoning: String(payload.data.text),
                 },
               });
+            } else if (payload && payload.type === "contextShift") {
+              notifyContextShift(Number(payload.data?.discarded) || 0);
             } else if (payload && payload.type === "error" && payload.data?.message) {
               dispatch({ type: "SET_ERROR", payload: String(payload.data.message) });
             }
@@ -1279,6 +1300,7 @@ export function useChatController(
       });
 
       const streamBatcher = createStreamBatcher(dispatch);
+      const notifyContextShift = createContextShiftNotifier();
       const thinkState = createThinkStreamState();
 
       try {
@@ -1309,6 +1331,8 @@ export function useChatController(
                 type: "UPDATE_MESSAGE_REASONING",
                 payload: { messageId: message.id, reasoning: String(payload.data.text) },
               });
+            } else if (payload && payload.type === "contextShift") {
+              notifyContextShift(Number(payload.data?.discarded) || 0);
             } else if (payload && payload.type === "error" && payload.data?.message) {
               dispatch({ type: "SET_ERROR", payload: String(payload.data.message) });
             }
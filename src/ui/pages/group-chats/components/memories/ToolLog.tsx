@@ -10,6 +10,7 @@ import {
   Clock,
   ChevronDown,
   AlertTriangle,
+  Merge,
 } from "lucide-react";
 
 import type { GroupSession } from "../../../../../core/storage/schemas";
@@ -78,6 +79,13 @@ const ACTION_STYLES: Record<
     bg: "bg-amber-400/10",
     border: "border-amber-400/20",
   },
+  merge_memories: {
+    icon: Merge,
+    color: "text-violet-300",
+    label: "Merged",
+    bg: "bg-violet-400/10",
+    border: "border-violet-400/20",
+  },
   done: {
     icon: Check,
     color: "text-blue-300",
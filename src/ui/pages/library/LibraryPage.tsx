@@ -37,6 +37,7 @@ import {
 } from "../../../core/storage/characterTransfer";
 import { exportPersona, generateExportFilename } from "../../../core/storage/personaTransfer";
 import { importLorebook, readFileAsText } from "../../../core/storage/lorebookTransfer";
+import { confirmLorebookImport } from "../../components/confirmLorebookImport";
 import { listen } from "@tauri-apps/api/event";
 
 type FilterOption = "All" | "Characters" | "Personas" | "Lorebooks";
@@ -229,7 +230,8 @@ export function LibraryPage() {
     try {
       setImportingLorebook(true);
       const raw = await readFileAsText(file);
-      const imported = await importLorebook(raw);
+      if (!(await confirmLorebookImport(raw, file.name))) return;
+      const imported = await importLorebook(raw, file.name);
       await loadData();
       navigate(`/library/lorebooks/${imported.id}`);
     } catch (err) {
@@ -31,6 +31,7 @@ import {
   readFileAsText,
   generateLorebookExportFilename,
 } from "../../../core/storage/lorebookTransfer";
+import { confirmLorebookImport } from "../../components/confirmLorebookImport";
 import { BottomMenu, MenuButton } from "../../components";
 import { confirmBottomMenu } from "../../components/ConfirmBottomMenu";
 import { TopNav } from "../../components/App";
@@ -606,7 +607,8 @@ export function StandaloneLorebookEditor() {
     try {
       setIsImporting(true);
       const raw = await readFileAsText(file);
-      const imported = await importLorebook(raw);
+      if (!(await confirmLorebookImport(raw, file.name))) return;
+      const imported = await importLorebook(raw, file.name);
       navigate(`/library/lorebooks/${imported.id}`);
     } catch (error) {
       console.error("Failed to import lorebook:", error);
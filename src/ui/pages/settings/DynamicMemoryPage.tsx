@@ -21,7 +21,14 @@ import {
   getEmbeddingModelInfo,
 } from "../../../core/storage/repo";
 import { storageBridge } from "../../../core/storage/files";
-import type { DynamicMemorySettings, Model, Settings } from "../../../core/storage/schemas";
+import type {
+  CustomEmbeddingModel,
+  CustomEmbeddingModelConfig,
+  DynamicMemorySettings,
+  Model,
+  RemoteEmbeddingConfig,
+  Settings,
+} from "../../../core/storage/schemas";
 import { cn, typography, interactive } from "../../design-tokens";
 import { useNavigate } from "react-router-dom";
 import { EmbeddingUpgradePrompt } from "../../components/EmbeddingUpgradePrompt";
@@ -185,6 +192,83 @@ export function DynamicMemoryPage() {
   const [defaultModelId, setDefaultModelId] = useState<string | null>(null);
   const [showModelMenu, setShowModelMenu] = useState(false);
   const [modelSearchQuery, setModelSearchQuery] = useState("");
+  const [embeddingBackend, setEmbeddingBackend] = useState<"onnx" | "remote" | "custom">("onnx");
+  const [embeddingRemote, setEmbeddingRemote] = useState<RemoteEmbeddingConfig>({
+    baseUrl: "",
+    model: "",
+  });
+  const [embeddingTestStatus, setEmbeddingTestStatus] = useState<string | null>(null);
+  const [customModels, setCustomModels] = useState<CustomEmbeddingModel[]>([]);
+  const [customModelId, setCustomModelId] = useState<string | null>(null);
+  const [customModelDraft, setCustomModelDraft] = useState<CustomEmbeddingModelConfig>({
+    name: "",
+    dir: "",
+    pooling: "mean",
+    normalize: true,
+    maxSeqLength: 512,
+    dimension: 384,
+  });
+  const [customModelStatus, setCustomModelStatus] = useState<string | null>(null);
+  const [rerankerInstalled, setRerankerInstalled] = useState(false);
+  const [rerankerDownloading, setRerankerDownloading] = useState(false);
+  const [rerankerDownloadStatus, setRerankerDownloadStatus] = useState<string | null>(null);
+  const [reembedProgress, setReembedProgress] = useState<{
+    done: number;
+    total: number;
+    status: string;
+  } | null>(null);
+
+  useEffect(() => {
+    let unsubscribe: (() => void) | null = null;
+    let mounted = true;
+    storageBridge
+      .listenToEmbeddingReembedProgress((progress) => {
+        if (mounted) {
+          setReembedProgress(progress.status === "completed" ? null : progress);
+        }
+      })
+      .then((unlisten) => {
+        if (mounted) {
+          unsubscribe = unlisten;
+        } else {
+          unlisten();
+        }
+      })
+      .catch((err) => console.error("Failed to listen for re-embedding progress:", err));
+    return () => {
+      mounted = false;
+      unsubscribe?.();
+    };
+  }, []);
+  const [embeddingCacheStats, setEmbeddingCacheStats] = useState<{
+    entries: number;
+    sizeBytes: number;
+    hits: number;
+    misses: number;
+  } | null>(null);
+
+  const handleDownloadReranker = async () => {
+    setRerankerDownloading(true);
+    setRerankerDownloadStatus("Starting download...");
+    const unlisten = await storageBridge.listenToRerankerDownloadProgress((progress) => {
+      if (progress.status === "downloading" && progress.total > 0) {
+        const percent = Math.round((progress.downloaded / progress.total) * 100);
+        const file = `${progress.currentFileIndex}/${progress.totalFiles}`;
+        setRerankerDownloadStatus(`Downloading file ${file}: ${percent}%`);
+      }
+    });
+    try {
+      await storageBridge.downloadRerankerModel();
+      setRerankerInstalled(await storageBridge.checkRerankerModel());
+      setRerankerDownloadStatus(null);
+    } catch (err) {
+      console.error("Failed to download reranker model:", err);
+      setRerankerDownloadStatus(`Download failed: ${String(err)}`);
+    } finally {
+      unlisten();
+      setRerankerDownloading(false);
+    }
+  };
 
   useEffect(() => {
     const loadData = async () => {
@@ -218,7 +302,25 @@ export function DynamicMemoryPage() {
         );
         setEmbeddingMaxTokens(settings.advancedSettings?.embeddingMaxTokens ?? 2048);
         setEmbeddingKeepModelLoaded(settings.advancedSettings?.embeddingKeepModelLoaded ?? false);
+        setEmbeddingBackend(settings.advancedSettings?.embeddingBackend ?? "onnx");
+        if (settings.advancedSettings?.embeddingRemote) {
+          setEmbeddingRemote(settings.advancedSettings.embeddingRemote);
+        }
+        setCustomModelId(settings.advancedSettings?.embeddingCustomModelId ?? null);
+        void refreshReembedStatus();
+        storageBridge
+          .listCustomEmbeddingModels()
+          .then(setCustomModels)
+          .catch((err) => console.error("Failed to load custom embedding models:", err));
+        storageBridge
+          .checkRerankerModel()
+          .then(setRerankerInstalled)
+          .catch((err) => console.error("Failed to check reranker model:", err));
         setModels(settings.models);
+        storageBridge
+          .embeddingCacheStats()
+          .then(setEmbeddingCacheStats)
+          .catch((err) => console.error("Failed to load embedding cache stats:", err));
 
         if (modelInfo.installed) {
           setModelVersion(modelInfo.version);
@@ -339,6 +441,23 @@ export function DynamicMemoryPage() {
     }, "Failed to save embedding max tokens:");
   };
 
+  const refreshReembedStatus = async () => {
+    try {
+      const status = await storageBridge.getEmbeddingReembedStatus();
+      setReembedProgress(
+        status.pending > 0
+          ? {
+              done: status.total - status.pending,
+              total: status.total,
+              status: status.running ? "running" : "pending",
+            }
+          : null,
+      );
+    } catch (err) {
+      console.error("Failed to load re-embedding status:", err);
+    }
+  };
+
   const handleEmbeddingModelVersionChange = async (version: "v2" | "v3") => {
     setSelectedEmbeddingVersion(version);
     setModelSourceVersion(version);
@@ -351,6 +470,7 @@ export function DynamicMemoryPage() {
     } catch (err) {
       console.error("Failed to reinitialize embedding runtime after version switch:", err);
     }
+    await refreshReembedStatus();
   };
 
   const handleEmbeddingKeepModelLoadedChange = async (enabled: boolean) => {
@@ -360,6 +480,93 @@ export function DynamicMemoryPage() {
     }, "Failed to save keep-model-loaded setting:");
   };
 
+  const handleEmbeddingBackendChange = async (backend: "onnx" | "remote" | "custom") => {
+    setEmbeddingBackend(backend);
+    setEmbeddingTestStatus(null);
+    await updateAdvancedSettings((advanced) => {
+      advanced.embeddingBackend = backend;
+    }, "Failed to save embedding backend:");
+    await refreshReembedStatus();
+  };
+
+  const handleEmbeddingRemoteSave = async () => {
+    await updateAdvancedSettings((advanced) => {
+      advanced.embeddingRemote = {
+        ...embeddingRemote,
+        baseUrl: embeddingRemote.baseUrl.trim(),
+        model: embeddingRemote.model.trim(),
+        apiKey: embeddingRemote.apiKey?.trim() || undefined,
+      };
+    }, "Failed to save remote embedding settings:");
+    await refreshReembedStatus();
+  };
+
+  const replaceCustomModel = (model: CustomEmbeddingModel) =>
+    setCustomModels((prev) => prev.map((m) => (m.id === model.id ? model : m)));
+
+  const handleCustomModelRegister = async () => {
+    setCustomModelStatus(null);
+    try {
+      const model = await storageBridge.registerCustomEmbeddingModel(customModelDraft);
+      setCustomModels((prev) => [...prev, model]);
+      setCustomModelDraft((prev) => ({ ...prev, name: "", dir: "" }));
+    } catch (err) {
+      setCustomModelStatus(`Failed: ${String(err)}`);
+    }
+  };
+
+  const handleCustomModelValidate = async (id: string) => {
+    setCustomModelStatus("Validating...");
+    try {
+      const model = await storageBridge.validateCustomEmbeddingModel(id);
+      replaceCustomModel(model);
+      const failed = model.validation?.checks.filter((check) => !check.passed) ?? [];
+      setCustomModelStatus(
+        failed.length === 0
+          ? `${model.name} passed validation`
+          : `Failed: ${failed.map((check) => `${check.name} (${check.detail})`).join(", ")}`,
+      );
+    } catch (err) {
+      setCustomModelStatus(`Failed: ${String(err)}`);
+    }
+  };
+
+  const handleCustomModelUse = async (id: string) => {
+    setCustomModelId(id);
+    await updateAdvancedSettings((advanced) => {
+      advanced.embeddingCustomModelId = id;
+    }, "Failed to save custom embedding model:");
+    await refreshReembedStatus();
+  };
+
+  const handleCustomModelDelete = async (id: string) => {
+    try {
+      await storageBridge.deleteCustomEmbeddingModel(id);
+      setCustomModels((prev) => prev.filter((m) => m.id !== id));
+    } catch (err) {
+      console.error("Failed to delete custom embedding model:", err);
+    }
+  };
+
+  const handleEmbeddingCacheClear = async () => {
+    try {
+      await storageBridge.embeddingCacheClear();
+      setEmbeddingCacheStats(await storageBridge.embeddingCacheStats());
+    } catch (err) {
+      console.error("Failed to clear embedding cache:", err);
+    }
+  };
+
+  const handleEmbeddingBackendTest = async () => {
+    setEmbeddingTestStatus("Testing...");
+    try {
+      const dimension = await storageBridge.testEmbeddingBackend(embeddingRemote);
+      setEmbeddingTestStatus(`Connected · ${dimension} dimensions`);
+    } catch (err) {
+      setEmbeddingTestStatus(`Failed: ${String(err)}`);
+    }
+  };
+
   const handleDeleteSelectedEmbeddingModel = async () => {
     const version = selectedEmbeddingVersion === "v2" ? "v2" : "v3";
     const confirmed = await confirmBottomMenu({
@@ -699,7 +906,7 @@ export function DynamicMemoryPage() {
                           <div className="text-[11px] font-medium text-white/90">
                             Retrieval Mode
                           </div>
-                          <div className="grid grid-cols-2 gap-2">
+                          <div className="grid grid-cols-3 gap-2">
                             <button
                               onClick={() => {
                                 if (activeTab === "direct") {
@@ -734,11 +941,67 @@ export function DynamicMemoryPage() {
                             >
                               Cosine
                             </button>
+                            <button
+                              onClick={() => {
+                                if (activeTab === "direct") {
+                                  handleDirectSettingChange("retrievalStrategy", "hybrid");
+                                } else {
+                                  handleGroupSettingChange("retrievalStrategy", "hybrid");
+                                }
+                              }}
+                              className={cn(
+                                "rounded-lg border px-3 py-2 text-xs font-medium transition-colors",
+                                currentSettings.retrievalStrategy === "hybrid"
+                                  ? "border-blue-400/50 bg-blue-500/20 text-blue-100"
+                                  : "border-white/10 bg-white/5 text-white/60 hover:border-white/20",
+                              )}
+                            >
+                              Hybrid
+                            </button>
                           </div>
                           <p className="text-[11px] text-white/45">
                             Smart blends relevance with recency/frequency. Cosine uses pure top
-                            similarity.
+                            similarity. Hybrid fuses keyword (BM25) and similarity rankings.
                           </p>
+                          {currentSettings.retrievalStrategy === "hybrid" && !rerankerInstalled && (
+                            <div className="flex items-center justify-between gap-3 pt-1">
+                              <span className="text-[11px] text-white/45">
+                                {rerankerDownloadStatus ??
+                                  "Reranking needs a local cross-encoder (about 23 MB)."}
+                              </span>
+                              <button
+                                onClick={handleDownloadReranker}
+                                disabled={rerankerDownloading}
+                                className={cn(
+                                  "shrink-0 rounded-lg border px-3 py-1.5 text-xs font-medium",
+                                  "border-blue-400/25 bg-blue-500/10 text-blue-100",
+                                  interactive.transition.fast,
+                                  "hover:bg-blue-500/20 disabled:opacity-50",
+                                )}
+                              >
+                                Download
+                              </button>
+                            </div>
+                          )}
+                          {currentSettings.retrievalStrategy === "hybrid" && rerankerInstalled && (
+                            <label className="flex items-center justify-between gap-3 pt-1">
+                              <span className="text-[11px] text-white/70">
+                                Rerank with local cross-encoder
+                              </span>
+                              <input
+                                type="checkbox"
+                                checked={currentSettings.rerankerEnabled ?? false}
+                                onChange={(e) => {
+                                  if (activeTab === "direct") {
+                                    handleDirectSettingChange("rerankerEnabled", e.target.checked);
+                                  } else {
+                                    handleGroupSettingChange("rerankerEnabled", e.target.checked);
+                                  }
+                                }}
+                                className="h-4 w-4 accent-blue-500"
+                              />
+                            </label>
+                          )}
                         </div>
 
                         {/* Retrieval Limit */}
@@ -759,24 +1022,79 @@ export function DynamicMemoryPage() {
                           }}
                         />
 
-                        {/* Decay Rate */}
-                        <SettingRow
-                          label="Decay Rate"
-                          description="How fast importance fades"
-                          value={currentSettings.decayRate}
-                          unit="/ cycle"
-                          min={0.01}
-                          max={0.3}
-                          step={0.01}
-                          decimals={2}
-                          onChange={(val) => {
-                            if (activeTab === "direct") {
-                              handleDirectSettingChange("decayRate", val);
-                            } else {
-                              handleGroupSettingChange("decayRate", val);
-                            }
-                          }}
-                        />
+                        {/* Decay Model */}
+                        <div className="space-y-2">
+                          <div className="text-[11px] font-medium text-white/90">Decay Model</div>
+                          <div className="grid grid-cols-2 gap-2">
+                            {(
+                              [
+                                ["perCycle", "Per cycle"],
+                                ["halfLife", "Half-life"],
+                              ] as const
+                            ).map(([model, label]) => (
+                              <button
+                                key={model}
+                                onClick={() => {
+                                  if (activeTab === "direct") {
+                                    handleDirectSettingChange("decayModel", model);
+                                  } else {
+                                    handleGroupSettingChange("decayModel", model);
+                                  }
+                                }}
+                                className={cn(
+                                  "rounded-lg border px-3 py-2 text-xs font-medium transition-colors",
+                                  (currentSettings.decayModel ?? "perCycle") === model
+                                    ? "border-blue-400/50 bg-blue-500/20 text-blue-100"
+                                    : "border-white/10 bg-white/5 text-white/60 hover:border-white/20",
+                                )}
+                              >
+                                {label}
+                              </button>
+                            ))}
+                          </div>
+                          <p className="text-[11px] text-white/45">
+                            Per cycle fades memories each time the memory manager runs. Half-life
+                            fades them with real time since they were last recalled, so a paused
+                            chat keeps its memories until time actually passes.
+                          </p>
+                        </div>
+
+                        {(currentSettings.decayModel ?? "perCycle") === "halfLife" ? (
+                          <SettingRow
+                            label="Half-life"
+                            description="Time for an unrecalled memory to lose half its importance"
+                            value={currentSettings.decayHalfLifeHours ?? 72}
+                            unit="hours"
+                            min={1}
+                            max={2160}
+                            step={1}
+                            onChange={(val) => {
+                              if (activeTab === "direct") {
+                                handleDirectSettingChange("decayHalfLifeHours", val);
+                              } else {
+                                handleGroupSettingChange("decayHalfLifeHours", val);
+                              }
+                            }}
+                          />
+                        ) : (
+                          <SettingRow
+                            label="Decay Rate"
+                            description="How fast importance fades"
+                            value={currentSettings.decayRate}
+                            unit="/ cycle"
+                            min={0.01}
+                            max={0.3}
+                            step={0.01}
+                            decimals={2}
+                            onChange={(val) => {
+                              if (activeTab === "direct") {
+                                handleDirectSettingChange("decayRate", val);
+                              } else {
+                                handleGroupSettingChange("decayRate", val);
+                              }
+                            }}
+                          />
+                        )}
 
                         {/* Cold Threshold */}
                         <SettingRow
@@ -795,6 +1113,101 @@ export function DynamicMemoryPage() {
                             }
                           }}
                         />
+
+                        {/* Character memory */}
+                        {activeTab === "direct" && (
+                          <div className="space-y-2">
+                            <label className="flex items-center justify-between gap-3">
+                              <span className="text-sm text-white/80">Character memory</span>
+                              <input
+                                type="checkbox"
+                                checked={currentSettings.characterMemoryEnabled ?? false}
+                                onChange={(e) =>
+                                  handleDirectSettingChange(
+                                    "characterMemoryEnabled",
+                                    e.target.checked,
+                                  )
+                                }
+                                className="h-4 w-4 accent-blue-500"
+                              />
+                            </label>
+                            <p className="text-[11px] text-white/45">
+                              Lasting facts can be shared with the character and recalled in every
+                              chat with them, including new ones.
+                            </p>
+                          </div>
+                        )}
+
+                        {/* Knowledge graph */}
+                        {activeTab === "direct" && (
+                          <div className="space-y-2">
+                            <label className="flex items-center justify-between gap-3">
+                              <span className="text-sm text-white/80">Knowledge graph</span>
+                              <input
+                                type="checkbox"
+                                checked={currentSettings.knowledgeGraphEnabled ?? false}
+                                onChange={(e) =>
+                                  handleDirectSettingChange(
+                                    "knowledgeGraphEnabled",
+                                    e.target.checked,
+                                  )
+                                }
+                                className="h-4 w-4 accent-blue-500"
+                              />
+                            </label>
+                            <p className="text-[11px] text-white/45">
+                              Tracks people, places and items with their relations, and reminds the
+                              model of those mentioned in the latest messages.
+                            </p>
+                          </div>
+                        )}
+
+                        {/* Consolidation */}
+                        <div className="space-y-2">
+                          <label className="flex items-center justify-between gap-3">
+                            <span className="text-sm text-white/80">Merge near-duplicates</span>
+                            <input
+                              type="checkbox"
+                              checked={currentSettings.consolidationEnabled ?? false}
+                              onChange={(e) => {
+                                if (activeTab === "direct") {
+                                  handleDirectSettingChange(
+                                    "consolidationEnabled",
+                                    e.target.checked,
+                                  );
+                                } else {
+                                  handleGroupSettingChange(
+                                    "consolidationEnabled",
+                                    e.target.checked,
+                                  );
+                                }
+                              }}
+                              className="h-4 w-4 accent-blue-500"
+                            />
+                          </label>
+                          <p className="text-[11px] text-white/45">
+                            After each memory cycle, similar memories are merged into one. Merges
+                            can be undone from the memory log.
+                          </p>
+                        </div>
+                        {(currentSettings.consolidationEnabled ?? false) && (
+                          <SettingRow
+                            label="Merge Similarity"
+                            description="Minimum similarity to merge"
+                            value={currentSettings.consolidationThreshold ?? 0.8}
+                            min={0.5}
+                            max={0.99}
+                            step={0.01}
+                            decimals={2}
+                            onChange={(val) => {
+                              if (activeTab === "direct") {
+                                handleDirectSettingChange("consolidationThreshold", val);
+                              } else {
+                                handleGroupSettingChange("consolidationThreshold", val);
+                              }
+                            }}
+                          />
+                        )}
                       </div>
                     </motion.div>
                   )}
@@ -991,6 +1404,230 @@ export function DynamicMemoryPage() {
                     </div>
                   )}
 
+                  {/* Embedding backend */}
+                  <div className={cn("rounded-xl border border-white/10 bg-white/5 px-4 py-3")}>
+                    <div className="text-sm font-medium text-white mb-1">Embedding Backend</div>
+                    <div className="text-[11px] text-white/45 leading-relaxed mb-3">
+                      Switching backends re-embeds all stored memories in the background
+                    </div>
+                    {reembedProgress && (
+                      <div className="mb-3 text-[11px] text-amber-200/80">
+                        {reembedProgress.status === "failed"
+                          ? "Re-embedding paused, it resumes with the next embedding"
+                          : "Re-embedding memories"}{" "}
+                        · {reembedProgress.done}/{reembedProgress.total}. Memories not yet
+                        re-embedded are skipped by semantic search.
+                      </div>
+                    )}
+                    <div className="grid grid-cols-3 gap-2">
+                      {(["onnx", "remote", "custom"] as const).map((backend) => (
+                        <button
+                          key={backend}
+                          onClick={() => handleEmbeddingBackendChange(backend)}
+                          className={cn(
+                            "rounded-lg px-3 py-2 text-xs font-medium transition",
+                            embeddingBackend === backend
+                              ? "bg-blue-500 text-white"
+                              : "border border-white/10 bg-white/5 text-white/70 hover:border-white/20",
+                          )}
+                        >
+                          {backend === "onnx"
+                            ? "On-device"
+                            : backend === "remote"
+                              ? "Remote"
+                              : "Custom"}
+                        </button>
+                      ))}
+                    </div>
+                    {embeddingBackend === "remote" && (
+                      <div className="mt-3 space-y-2">
+                        {(
+                          [
+                            ["baseUrl", "Base URL (e.g. http://localhost:11434)", "text"],
+                            ["model", "Model (e.g. nomic-embed-text)", "text"],
+                            ["apiKey", "API key (optional)", "password"],
+                          ] as const
+                        ).map(([field, placeholder, type]) => (
+                          <input
+                            key={field}
+                            type={type}
+                            value={embeddingRemote[field] ?? ""}
+                            onChange={(e) =>
+                              setEmbeddingRemote((prev) => ({ ...prev, [field]: e.target.value }))
+                            }
+                            onBlur={handleEmbeddingRemoteSave}
+                            placeholder={placeholder}
+                            className="w-full rounded-lg border border-white/10 bg-black/30 px-3 py-2 text-xs text-white placeholder-white/40 focus:border-white/20 focus:outline-none"
+                          />
+                        ))}
+                        <div className="flex items-center gap-3">
+                          <button
+                            onClick={handleEmbeddingBackendTest}
+                            disabled={
+                              !embeddingRemote.baseUrl.trim() || !embeddingRemote.model.trim()
+                            }
+                            className="rounded-lg border border-white/10 bg-white/5 px-3 py-1.5 text-xs text-white/70 hover:border-white/20 disabled:opacity-40"
+                          >
+                            Test connection
+                          </button>
+                          {embeddingTestStatus && (
+                            <span className="min-w-0 truncate text-[11px] text-white/50">
+                              {embeddingTestStatus}
+                            </span>
+                          )}
+                        </div>
+                      </div>
+                    )}
+                    {embeddingBackend === "custom" && (
+                      <div className="mt-3 space-y-2">
+                        <div className="text-[11px] text-white/45 leading-relaxed">
+                          A folder with an ONNX model and tokenizer.json. Models must pass
+                          validation before use; until then the on-device model is used.
+                        </div>
+                        {customModels.map((model) => (
+                          <div
+                            key={model.id}
+                            className="flex items-center gap-2 rounded-lg border border-white/10 bg-black/20 px-3 py-2"
+                          >
+                            <div className="min-w-0 flex-1">
+                              <div className="truncate text-xs text-white">{model.name}</div>
+                              <div className="truncate text-[10px] text-white/40">
+                                {model.dimension}d · {model.pooling} ·{" "}
+                                {model.validatedAt ? "validated" : "not validated"}
+                              </div>
+                            </div>
+                            <button
+                              onClick={() => handleCustomModelValidate(model.id)}
+                              className="text-[11px] text-white/60 hover:text-white"
+                            >
+                              Validate
+                            </button>
+                            <button
+                              onClick={() => handleCustomModelUse(model.id)}
+                              disabled={!model.validatedAt || customModelId === model.id}
+                              className="text-[11px] text-blue-300 disabled:opacity-40"
+                            >
+                              {customModelId === model.id ? "In use" : "Use"}
+                            </button>
+                            <button
+                              onClick={() => handleCustomModelDelete(model.id)}
+                              className="text-[11px] text-red-300/80 hover:text-red-300"
+                            >
+                              Remove
+                            </button>
+                          </div>
+                        ))}
+                        {(
+                          [
+                            ["name", "Name (e.g. bge-small-en)"],
+                            ["dir", "Model folder path"],
+                            ["outputName", "Output tensor (optional)"],
+                          ] as const
+                        ).map(([field, placeholder]) => (
+                          <input
+                            key={field}
+                            value={customModelDraft[field] ?? ""}
+                            onChange={(e) =>
+                              setCustomModelDraft((prev) => ({ ...prev, [field]: e.target.value }))
+                            }
+                            placeholder={placeholder}
+                            className="w-full rounded-lg border border-white/10 bg-black/30 px-3 py-2 text-xs text-white placeholder-white/40 focus:border-white/20 focus:outline-none"
+                          />
+                        ))}
+                        <div className="grid grid-cols-2 gap-2">
+                          {(
+                            [
+                              ["maxSeqLength", "Max tokens"],
+                              ["dimension", "Dimension"],
+                            ] as const
+                          ).map(([field, label]) => (
+                            <label key={field} className="text-[10px] text-white/45">
+                              {label}
+                              <input
+                                type="number"
+                                min={1}
+                                value={customModelDraft[field]}
+                                onChange={(e) =>
+                                  setCustomModelDraft((prev) => ({
+                                    ...prev,
+                                    [field]: Number(e.target.value),
+                                  }))
+                                }
+                                className="mt-1 w-full rounded-lg border border-white/10 bg-black/30 px-3 py-2 text-xs text-white focus:border-white/20 focus:outline-none"
+                              />
+                            </label>
+                          ))}
+                        </div>
+                        <div className="flex items-center gap-2">
+                          {(["mean", "cls", "lastToken"] as const).map((pooling) => (
+                            <button
+                              key={pooling}
+                              onClick={() => setCustomModelDraft((prev) => ({ ...prev, pooling }))}
+                              className={cn(
+                                "rounded-lg px-2.5 py-1 text-[11px] transition",
+                                customModelDraft.pooling === pooling
+                                  ? "bg-blue-500 text-white"
+                                  : "border border-white/10 bg-white/5 text-white/70",
+                              )}
+                            >
+                              {pooling === "lastToken" ? "Last token" : pooling.toUpperCase()}
+                            </button>
+                          ))}
+                          <label className="ml-auto flex items-center gap-1.5 text-[11px] text-white/60">
+                            <input
+                              type="checkbox"
+                              checked={customModelDraft.normalize}
+                              onChange={(e) =>
+                                setCustomModelDraft((prev) => ({
+                                  ...prev,
+                                  normalize: e.target.checked,
+                                }))
+                              }
+                            />
+                            Normalize
+                          </label>
+                        </div>
+                        <button
+                          onClick={handleCustomModelRegister}
+                          disabled={!customModelDraft.name.trim() || !customModelDraft.dir.trim()}
+                          className="rounded-lg border border-white/10 bg-white/5 px-3 py-1.5 text-xs text-white/70 hover:border-white/20 disabled:opacity-40"
+                        >
+                          Add model
+                        </button>
+                        {customModelStatus && (
+                          <div className="text-[11px] text-white/50 break-words">
+                            {customModelStatus}
+                          </div>
+                        )}
+                      </div>
+                    )}
+                  </div>
+
+                  {/* Embedding cache */}
+                  {embeddingCacheStats && (
+                    <div className={cn("rounded-xl border border-white/10 bg-white/5 px-4 py-3")}>
+                      <div className="flex items-center justify-between gap-3">
+                        <div className="min-w-0 flex-1">
+                          <div className="text-sm font-medium text-white mb-1">
+                            Embedding Cache
+                          </div>
+                          <div className="text-[11px] text-white/45 leading-relaxed">
+                            {embeddingCacheStats.entries} vectors ·{" "}
+                            {(embeddingCacheStats.sizeBytes / (1024 * 1024)).toFixed(1)} MB ·{" "}
+                            {embeddingCacheStats.hits} hits this session
+                          </div>
+                        </div>
+                        <button
+                          onClick={handleEmbeddingCacheClear}
+                          disabled={embeddingCacheStats.entries === 0}
+                          className="shrink-0 rounded-lg border border-white/10 bg-white/5 px-3 py-1.5 text-xs text-white/70 hover:border-white/20 disabled:opacity-40"
+                        >
+                          Clear
+                        </button>
+                      </div>
+                    </div>
+                  )}
+
                   {/* Model info */}
                   {modelVersion && (
                     <div className="text-xs text-white/40 px-1">
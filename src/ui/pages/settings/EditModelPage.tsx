@@ -17,6 +17,20 @@ import {
   ADVANCED_LLAMA_SEED_RANGE,
   ADVANCED_LLAMA_ROPE_FREQ_BASE_RANGE,
   ADVANCED_LLAMA_ROPE_FREQ_SCALE_RANGE,
+  ADVANCED_LLAMA_MIN_P_RANGE,
+  ADVANCED_LLAMA_TYPICAL_P_RANGE,
+  ADVANCED_LLAMA_REPEAT_PENALTY_RANGE,
+  ADVANCED_LLAMA_REPEAT_LAST_N_RANGE,
+  ADVANCED_LLAMA_MIROSTAT_TAU_RANGE,
+  ADVANCED_LLAMA_MIROSTAT_ETA_RANGE,
+  ADVANCED_LLAMA_DRY_MULTIPLIER_RANGE,
+  ADVANCED_LLAMA_DRY_BASE_RANGE,
+  ADVANCED_LLAMA_DRY_ALLOWED_LENGTH_RANGE,
+  ADVANCED_LLAMA_DRY_PENALTY_LAST_N_RANGE,
+  ADVANCED_LLAMA_XTC_PROBABILITY_RANGE,
+  ADVANCED_LLAMA_XTC_THRESHOLD_RANGE,
+  ADVANCED_LLAMA_STATE_BUDGET_MB_RANGE,
+  ADVANCED_LLAMA_DRAFT_MAX_RANGE,
   ADVANCED_OLLAMA_NUM_CTX_RANGE,
   ADVANCED_OLLAMA_NUM_PREDICT_RANGE,
   ADVANCED_OLLAMA_NUM_KEEP_RANGE,
@@ -134,6 +148,25 @@ export function EditModelPage() {
     handleLlamaRopeFreqBaseChange,
     handleLlamaRopeFreqScaleChange,
     handleLlamaOffloadKqvChange,
+    handleLlamaMinPChange,
+    handleLlamaTypicalPChange,
+    handleLlamaRepeatPenaltyChange,
+    handleLlamaRepeatLastNChange,
+    handleLlamaMirostatChange,
+    handleLlamaMirostatTauChange,
+    handleLlamaMirostatEtaChange,
+    handleLlamaDryMultiplierChange,
+    handleLlamaDryBaseChange,
+    handleLlamaDryAllowedLengthChange,
+    handleLlamaDryPenaltyLastNChange,
+    handleLlamaXtcProbabilityChange,
+    handleLlamaXtcThresholdChange,
+    handleLlamaSamplerOrderChange,
+    handleLlamaLoraAdaptersChange,
+    handleLlamaStateBudgetMbChange,
+    handleLlamaDraftModelChange,
+    handleLlamaDraftMaxChange,
+    handleLlamaMmprojPathChange,
     handleOllamaNumCtxChange,
     handleOllamaNumPredictChange,
     handleOllamaNumKeepChange,
@@ -403,6 +436,10 @@ export function EditModelPage() {
   const availableRamGiB = formatGiB(llamaContextInfo?.availableMemoryBytes ?? null);
   const modelSizeGiB = formatGiB(llamaContextInfo?.modelSizeBytes ?? null);
   const ollamaStopText = (modelAdvancedDraft.ollamaStop ?? []).join("\n");
+  const llamaSamplerOrderText = (modelAdvancedDraft.llamaSamplerOrder ?? []).join(", ");
+  const llamaLoraAdaptersText = (modelAdvancedDraft.llamaLoraAdapters ?? [])
+    .map((adapter) => (adapter.scale === 1 ? adapter.path : `${adapter.path} @ ${adapter.scale}`))
+    .join("\n");
 
   // Register window globals for header save button
   useEffect(() => {
@@ -1547,6 +1584,520 @@ export function EditModelPage() {
                             </div>
                           </div>
                         </div>
+
+                        <div className="space-y-1">
+                          <span className="block text-xs font-semibold text-white/70">Sampling</span>
+                          <span className="block text-[10px] text-white/40">
+                            Leave blank to use llama.cpp defaults
+                          </span>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">Min P</span>
+                              <span className="block text-[10px] text-white/40">
+                                Min-p sampling (0-1)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_MIN_P_RANGE.min}
+                              max={ADVANCED_LLAMA_MIN_P_RANGE.max}
+                              step={0.01}
+                              value={modelAdvancedDraft.llamaMinP ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaMinPChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="Off"
+                              className={numberInputClassName}
+                            />
+                          </div>
+
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                Typical P
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Typical sampling (0-1)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_TYPICAL_P_RANGE.min}
+                              max={ADVANCED_LLAMA_TYPICAL_P_RANGE.max}
+                              step={0.01}
+                              value={modelAdvancedDraft.llamaTypicalP ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaTypicalPChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="Off"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                Repeat Penalty
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Penalize repetition (0-2)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_REPEAT_PENALTY_RANGE.min}
+                              max={ADVANCED_LLAMA_REPEAT_PENALTY_RANGE.max}
+                              step={0.01}
+                              value={modelAdvancedDraft.llamaRepeatPenalty ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaRepeatPenaltyChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="Off"
+                              className={numberInputClassName}
+                            />
+                          </div>
+
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                Repeat Window
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Tokens checked (-1 = whole context)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="numeric"
+                              min={ADVANCED_LLAMA_REPEAT_LAST_N_RANGE.min}
+                              max={ADVANCED_LLAMA_REPEAT_LAST_N_RANGE.max}
+                              step={1}
+                              value={modelAdvancedDraft.llamaRepeatLastN ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                const next = raw === "" ? null : Number(raw);
+                                handleLlamaRepeatLastNChange(
+                                  next === null || !Number.isFinite(next) ? null : Math.trunc(next),
+                                );
+                              }}
+                              placeholder="Context"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                Mirostat
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                0 = off, 1 or 2 = enabled
+                              </span>
+                            </div>
+                            <select
+                              value={
+                                modelAdvancedDraft.llamaMirostat === null ||
+                                modelAdvancedDraft.llamaMirostat === undefined
+                                  ? "auto"
+                                  : modelAdvancedDraft.llamaMirostat.toString()
+                              }
+                              onChange={(e) => {
+                                const val = e.target.value;
+                                handleLlamaMirostatChange(val === "auto" ? null : Number(val));
+                              }}
+                              className="w-full rounded-xl border border-white/10 bg-black/20 px-3 py-2.5 text-sm text-white transition focus:border-white/30 focus:outline-none"
+                            >
+                              <option value="auto" className="bg-[#16171d]">
+                                Auto
+                              </option>
+                              <option value="0" className="bg-[#16171d]">
+                                0 (Off)
+                              </option>
+                              <option value="1" className="bg-[#16171d]">
+                                1
+                              </option>
+                              <option value="2" className="bg-[#16171d]">
+                                2
+                              </option>
+                            </select>
+                          </div>
+
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                Mirostat Tau
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Target entropy
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_MIROSTAT_TAU_RANGE.min}
+                              max={ADVANCED_LLAMA_MIROSTAT_TAU_RANGE.max}
+                              step={0.1}
+                              value={modelAdvancedDraft.llamaMirostatTau ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaMirostatTauChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="5.0"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                Mirostat Eta
+                              </span>
+                              <span className="block text-[10px] text-white/40">Learning rate</span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_MIROSTAT_ETA_RANGE.min}
+                              max={ADVANCED_LLAMA_MIROSTAT_ETA_RANGE.max}
+                              step={0.01}
+                              value={modelAdvancedDraft.llamaMirostatEta ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaMirostatEtaChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="0.1"
+                              className={numberInputClassName}
+                            />
+                          </div>
+
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                DRY Multiplier
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Penalize repeated phrases (0 = off)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_DRY_MULTIPLIER_RANGE.min}
+                              max={ADVANCED_LLAMA_DRY_MULTIPLIER_RANGE.max}
+                              step={0.05}
+                              value={modelAdvancedDraft.llamaDryMultiplier ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaDryMultiplierChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="Off"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                DRY Base
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Penalty growth per extra token
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_DRY_BASE_RANGE.min}
+                              max={ADVANCED_LLAMA_DRY_BASE_RANGE.max}
+                              step={0.05}
+                              value={modelAdvancedDraft.llamaDryBase ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaDryBaseChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="1.75"
+                              className={numberInputClassName}
+                            />
+                          </div>
+
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                DRY Allowed Length
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Shorter repeats go unpenalized
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="numeric"
+                              min={ADVANCED_LLAMA_DRY_ALLOWED_LENGTH_RANGE.min}
+                              max={ADVANCED_LLAMA_DRY_ALLOWED_LENGTH_RANGE.max}
+                              step={1}
+                              value={modelAdvancedDraft.llamaDryAllowedLength ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                const next = raw === "" ? null : Number(raw);
+                                handleLlamaDryAllowedLengthChange(
+                                  next === null || !Number.isFinite(next) ? null : Math.trunc(next),
+                                );
+                              }}
+                              placeholder="2"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                DRY Window
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Tokens scanned (-1 = whole context)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="numeric"
+                              min={ADVANCED_LLAMA_DRY_PENALTY_LAST_N_RANGE.min}
+                              max={ADVANCED_LLAMA_DRY_PENALTY_LAST_N_RANGE.max}
+                              step={1}
+                              value={modelAdvancedDraft.llamaDryPenaltyLastN ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                const next = raw === "" ? null : Number(raw);
+                                handleLlamaDryPenaltyLastNChange(
+                                  next === null || !Number.isFinite(next) ? null : Math.trunc(next),
+                                );
+                              }}
+                              placeholder="Context"
+                              className={numberInputClassName}
+                            />
+                          </div>
+
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                XTC Probability
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Chance to drop top choices (0 = off)
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_XTC_PROBABILITY_RANGE.min}
+                              max={ADVANCED_LLAMA_XTC_PROBABILITY_RANGE.max}
+                              step={0.01}
+                              value={modelAdvancedDraft.llamaXtcProbability ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaXtcProbabilityChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="Off"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 gap-6 md:grid-cols-2">
+                          <div className="space-y-4">
+                            <div className="space-y-0.5">
+                              <span className="block text-xs font-medium text-white/70">
+                                XTC Threshold
+                              </span>
+                              <span className="block text-[10px] text-white/40">
+                                Min probability of dropped tokens
+                              </span>
+                            </div>
+                            <input
+                              type="number"
+                              inputMode="decimal"
+                              min={ADVANCED_LLAMA_XTC_THRESHOLD_RANGE.min}
+                              max={ADVANCED_LLAMA_XTC_THRESHOLD_RANGE.max}
+                              step={0.01}
+                              value={modelAdvancedDraft.llamaXtcThreshold ?? ""}
+                              onChange={(e) => {
+                                const raw = e.target.value;
+                                handleLlamaXtcThresholdChange(raw === "" ? null : Number(raw));
+                              }}
+                              placeholder="0.1"
+                              className={numberInputClassName}
+                            />
+                          </div>
+                        </div>
+
+                        <div className="space-y-4">
+                          <div className="space-y-0.5">
+                            <span className="block text-xs font-medium text-white/70">
+                              Sampler Order
+                            </span>
+                            <span className="block text-[10px] text-white/40">
+                              Comma-separated; samplers left out are skipped
+                            </span>
+                          </div>
+                          <input
+                            key={llamaSamplerOrderText}
+                            type="text"
+                            defaultValue={llamaSamplerOrderText}
+                            onBlur={(e) => {
+                              const next = e.target.value
+                                .split(/[\n,]+/)
+                                .map((s) => s.trim())
+                                .filter((s) => s.length > 0);
+                              handleLlamaSamplerOrderChange(next.length > 0 ? next : null);
+                            }}
+                            placeholder="e.g. penalties, top_k, min_p, temperature"
+                            className="w-full rounded-xl border border-white/10 bg-black/20 px-3 py-2.5 text-sm text-white placeholder-white/40 transition focus:border-white/30 focus:outline-none"
+                          />
+                        </div>
+
+                        <div className="space-y-4">
+                          <div className="space-y-0.5">
+                            <span className="block text-xs font-medium text-white/70">
+                              LoRA Adapters
+                            </span>
+                            <span className="block text-[10px] text-white/40">
+                              One GGUF adapter path per line, optionally followed by @ scale
+                            </span>
+                          </div>
+                          <textarea
+                            key={llamaLoraAdaptersText}
+                            rows={3}
+                            defaultValue={llamaLoraAdaptersText}
+                            onBlur={(e) => {
+                              const next = e.target.value
+                                .split("\n")
+                                .map((line) => {
+                                  const match = line.match(/^(.*?)\s*@\s*(-?[\d.]+)\s*$/);
+                                  const scale = match ? Number(match[2]) : 1;
+                                  return {
+                                    path: (match ? match[1] : line).trim(),
+                                    scale: Number.isFinite(scale) ? scale : 1,
+                                  };
+                                })
+                                .filter((adapter) => adapter.path.length > 0);
+                              handleLlamaLoraAdaptersChange(next.length > 0 ? next : null);
+                            }}
+                            placeholder="/path/to/character-lora.gguf @ 0.8"
+                            className="w-full resize-none rounded-xl border border-white/10 bg-black/20 px-3 py-2.5 font-mono text-xs text-white placeholder-white/40 transition focus:border-white/30 focus:outline-none"
+                          />
+                        </div>
+
+                        <div className="space-y-4">
+                          <div className="space-y-0.5">
+                            <span className="block text-xs font-medium text-white/70">
+                              Saved State Budget (MB)
+                            </span>
+                            <span className="block text-[10px] text-white/40">
+                              Keeps processed prompts on disk across restarts; 0 turns it off
+                            </span>
+                          </div>
+                          <input
+                            type="number"
+                            inputMode="numeric"
+                            min={ADVANCED_LLAMA_STATE_BUDGET_MB_RANGE.min}
+                            max={ADVANCED_LLAMA_STATE_BUDGET_MB_RANGE.max}
+                            step={256}
+                            value={modelAdvancedDraft.llamaStateBudgetMb ?? ""}
+                            onChange={(e) => {
+                              const raw = e.target.value;
+                              handleLlamaStateBudgetMbChange(raw === "" ? null : Number(raw));
+                            }}
+                            placeholder="2048"
+                            className={numberInputClassName}
+                          />
+                        </div>
+
+                        <div className="space-y-4">
+                          <div className="space-y-0.5">
+                            <span className="block text-xs font-medium text-white/70">
+                              Draft Model
+                            </span>
+                            <span className="block text-[10px] text-white/40">
+                              Smaller GGUF from the same family for speculative decoding
+                            </span>
+                          </div>
+                          <input
+                            type="text"
+                            key={modelAdvancedDraft.llamaDraftModel ?? ""}
+                            defaultValue={modelAdvancedDraft.llamaDraftModel ?? ""}
+                            onBlur={(e) => {
+                              const next = e.target.value.trim();
+                              handleLlamaDraftModelChange(next.length > 0 ? next : null);
+                            }}
+                            placeholder="/path/to/draft-model.gguf"
+                            className="w-full rounded-xl border border-white/10 bg-black/20 px-3 py-2.5 font-mono text-xs text-white placeholder-white/40 transition focus:border-white/30 focus:outline-none"
+                          />
+                        </div>
+
+                        <div className="space-y-4">
+                          <div className="space-y-0.5">
+                            <span className="block text-xs font-medium text-white/70">
+                              Draft Length
+                            </span>
+                            <span className="block text-[10px] text-white/40">
+                              Tokens the draft model proposes per step; 0 turns it off
+                            </span>
+                          </div>
+                          <input
+                            type="number"
+                            inputMode="numeric"
+                            min={ADVANCED_LLAMA_DRAFT_MAX_RANGE.min}
+                            max={ADVANCED_LLAMA_DRAFT_MAX_RANGE.max}
+                            step={1}
+                            value={modelAdvancedDraft.llamaDraftMax ?? ""}
+                            onChange={(e) => {
+                              const raw = e.target.value;
+                              handleLlamaDraftMaxChange(raw === "" ? null : Number(raw));
+                            }}
+                            placeholder="8"
+                            className={numberInputClassName}
+                          />
+                        </div>
+
+                        <div className="space-y-4">
+                          <div className="space-y-0.5">
+                            <span className="block text-xs font-medium text-white/70">
+                              Vision Projector (mmproj)
+                            </span>
+                            <span className="block text-[10px] text-white/40">
+                              Lets the model see attached images; also enable image input above
+                            </span>
+                          </div>
+                          <input
+                            type="text"
+                            key={modelAdvancedDraft.llamaMmprojPath ?? ""}
+                            defaultValue={modelAdvancedDraft.llamaMmprojPath ?? ""}
+                            onBlur={(e) => {
+                              const next = e.target.value.trim();
+                              handleLlamaMmprojPathChange(next.length > 0 ? next : null);
+                            }}
+                            placeholder="/path/to/mmproj-model-f16.gguf"
+                            className="w-full rounded-xl border border-white/10 bg-black/20 px-3 py-2.5 font-mono text-xs text-white placeholder-white/40 transition focus:border-white/30 focus:outline-none"
+                          />
+                        </div>
                       </div>
                     )}
 
@@ -249,6 +249,10 @@ export function EmbeddingTestPage() {
   );
   const [benchmarkResults, setBenchmarkResults] = useState<DevBenchmarkResults | null>(null);
   const [benchmarkError, setBenchmarkError] = useState<string | null>(null);
+  const [verification, setVerification] = useState<Awaited<
+    ReturnType<typeof storageBridge.verifyEmbeddingModel>
+  > | null>(null);
+  const [verifying, setVerifying] = useState(false);
   const isDevBuild = import.meta.env.DEV;
 
   const runTest = async () => {
@@ -276,6 +280,18 @@ export function EmbeddingTestPage() {
     }
   };
 
+  const verifyFiles = async () => {
+    setVerifying(true);
+    setVerification(null);
+    try {
+      setVerification(await storageBridge.verifyEmbeddingModel());
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setVerifying(false);
+    }
+  };
+
   const compareCustom = async () => {
     if (!customTextA.trim() || !customTextB.trim()) {
       setCustomError("Please enter both texts to compare");
@@ -456,6 +472,44 @@ export function EmbeddingTestPage() {
                   Run Tests Again
                 </button>
               )}
+              {testStatus !== "testing" && (
+                <button
+                  onClick={verifyFiles}
+                  disabled={verifying}
+                  className={cn(
+                    "mt-4 ml-2 inline-flex items-center gap-2 rounded-lg px-4 py-2 text-sm font-medium",
+                    "border border-white/10 bg-white/5 text-white",
+                    interactive.transition.fast,
+                    "hover:bg-white/10 disabled:opacity-50",
+                  )}
+                >
+                  {verifying ? "Verifying..." : "Verify Files"}
+                </button>
+              )}
+              {verification && (
+                <div
+                  className={cn(
+                    "mt-3 rounded-lg border p-3 text-xs",
+                    verification.ok
+                      ? "border-emerald-500/30 bg-emerald-500/10 text-emerald-200"
+                      : "border-red-500/30 bg-red-500/10 text-red-200",
+                  )}
+                >
+                  <p className="font-medium">
+                    {verification.ok
+                      ? `${verification.source.toUpperCase()} files are intact`
+                      : `${verification.source.toUpperCase()} files failed verification`}
+                    {verification.checkedAgainst === "none" && " (checksums unavailable offline)"}
+                  </p>
+                  {verification.files
+                    .filter((file) => file.status !== "ok")
+                    .map((file) => (
+                      <p key={file.name} className="mt-1 opacity-80">
+                        {file.name}: {file.status}
+                      </p>
+                    ))}
+                </div>
+              )}
             </div>
           </motion.div>
 
@@ -52,6 +52,25 @@ type ControllerReturn = {
   handleLlamaRopeFreqBaseChange: (value: number | null) => void;
   handleLlamaRopeFreqScaleChange: (value: number | null) => void;
   handleLlamaOffloadKqvChange: (value: boolean | null) => void;
+  handleLlamaMinPChange: (value: number | null) => void;
+  handleLlamaTypicalPChange: (value: number | null) => void;
+  handleLlamaRepeatPenaltyChange: (value: number | null) => void;
+  handleLlamaRepeatLastNChange: (value: number | null) => void;
+  handleLlamaMirostatChange: (value: number | null) => void;
+  handleLlamaMirostatTauChange: (value: number | null) => void;
+  handleLlamaMirostatEtaChange: (value: number | null) => void;
+  handleLlamaDryMultiplierChange: (value: number | null) => void;
+  handleLlamaDryBaseChange: (value: number | null) => void;
+  handleLlamaDryAllowedLengthChange: (value: number | null) => void;
+  handleLlamaDryPenaltyLastNChange: (value: number | null) => void;
+  handleLlamaXtcProbabilityChange: (value: number | null) => void;
+  handleLlamaXtcThresholdChange: (value: number | null) => void;
+  handleLlamaSamplerOrderChange: (value: string[] | null) => void;
+  handleLlamaLoraAdaptersChange: (value: { path: string; scale: number }[] | null) => void;
+  handleLlamaStateBudgetMbChange: (value: number | null) => void;
+  handleLlamaDraftModelChange: (value: string | null) => void;
+  handleLlamaDraftMaxChange: (value: number | null) => void;
+  handleLlamaMmprojPathChange: (value: string | null) => void;
   handleOllamaNumCtxChange: (value: number | null) => void;
   handleOllamaNumPredictChange: (value: number | null) => void;
   handleOllamaNumKeepChange: (value: number | null) => void;
@@ -491,6 +510,253 @@ export function useModelEditorController(): ControllerReturn {
     [dispatch, state.modelAdvancedDraft],
   );
 
+  const handleLlamaMinPChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaMinP: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaTypicalPChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaTypicalP: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaRepeatPenaltyChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaRepeatPenalty: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaRepeatLastNChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaRepeatLastN: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaMirostatChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaMirostat: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaMirostatTauChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaMirostatTau: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaMirostatEtaChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaMirostatEta: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaDryMultiplierChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaDryMultiplier: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaDryBaseChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaDryBase: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaDryAllowedLengthChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaDryAllowedLength: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaDryPenaltyLastNChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaDryPenaltyLastN: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaXtcProbabilityChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaXtcProbability: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaXtcThresholdChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaXtcThreshold: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaSamplerOrderChange = useCallback(
+    (value: string[] | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaSamplerOrder: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaLoraAdaptersChange = useCallback(
+    (value: { path: string; scale: number }[] | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaLoraAdapters: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaStateBudgetMbChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaStateBudgetMb: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaDraftModelChange = useCallback(
+    (value: string | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaDraftModel: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaDraftMaxChange = useCallback(
+    (value: number | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaDraftMax: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
+  const handleLlamaMmprojPathChange = useCallback(
+    (value: string | null) => {
+      dispatch({
+        type: "set_model_advanced_draft",
+        payload: {
+          ...state.modelAdvancedDraft,
+          llamaMmprojPath: value,
+        },
+      });
+    },
+    [dispatch, state.modelAdvancedDraft],
+  );
+
   const handleOllamaNumCtxChange = useCallback(
     (value: number | null) => {
       dispatch({
@@ -967,6 +1233,25 @@ export function useModelEditorController(): ControllerReturn {
     handleLlamaRopeFreqBaseChange,
     handleLlamaRopeFreqScaleChange,
     handleLlamaOffloadKqvChange,
+    handleLlamaMinPChange,
+    handleLlamaTypicalPChange,
+    handleLlamaRepeatPenaltyChange,
+    handleLlamaRepeatLastNChange,
+    handleLlamaMirostatChange,
+    handleLlamaMirostatTauChange,
+    handleLlamaMirostatEtaChange,
+    handleLlamaDryMultiplierChange,
+    handleLlamaDryBaseChange,
+    handleLlamaDryAllowedLengthChange,
+    handleLlamaDryPenaltyLastNChange,
+    handleLlamaXtcProbabilityChange,
+    handleLlamaXtcThresholdChange,
+    handleLlamaSamplerOrderChange,
+    handleLlamaLoraAdaptersChange,
+    handleLlamaStateBudgetMbChange,
+    handleLlamaDraftModelChange,
+    handleLlamaDraftMaxChange,
+    handleLlamaMmprojPathChange,
     handleOllamaNumCtxChange,
     handleOllamaNumPredictChange,
     handleOllamaNumKeepChange,